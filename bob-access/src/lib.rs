@@ -15,7 +15,7 @@ pub use authenticator::{
     basic::Basic as BasicAuthenticator, stub::Stub as StubAuthenticator, Authenticator, UsersMap,
     AuthenticationType,
 };
-pub use credentials::{DeclaredCredentials, CredentialsHolder};
+pub use credentials::{CredentialsHolder, CredentialsKind, DeclaredCredentials, RequestCredentials};
 pub use error::Error;
 pub use extractor::Extractor;
 pub use permissions::Permissions;
@@ -0,0 +1,166 @@
+//! Clients of the REST data endpoints (`/data/:key`) and the S3 API (`/s3/default/:key`).
+//! HTTP statuses are converted to gRPC codes, so errors are counted the same way for all
+//! protocols.
+
+use bytes::Bytes;
+use reqwest::{Certificate, Client, Method, StatusCode};
+use std::{net::SocketAddr, str::FromStr};
+use tonic::{Code, Status};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Protocol {
+    Grpc,
+    Rest,
+    S3,
+}
+
+impl FromStr for Protocol {
+    type Err = &'static str;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "grpc" => Ok(Protocol::Grpc),
+            "rest" => Ok(Protocol::Rest),
+            "s3" => Ok(Protocol::S3),
+            _ => Err("Failed to parse protocol, only 'grpc', 'rest' and 's3' available"),
+        }
+    }
+}
+
+pub(crate) struct TlsSettings {
+    pub(crate) ca_cert_pem: Vec<u8>,
+    pub(crate) domain_name: String,
+}
+
+#[derive(Clone)]
+pub(crate) struct HttpClient {
+    client: Client,
+    protocol: Protocol,
+    // Scheme, authority and path prefix of the data endpoint
+    base_url: String,
+    authorization: Option<String>,
+}
+
+impl HttpClient {
+    /// With TLS requests are sent to `domain_name`, which is resolved to `target:port`, so the
+    /// certificate is checked against the domain name as the gRPC client does.
+    pub(crate) async fn new(
+        protocol: Protocol,
+        target: &str,
+        port: u16,
+        tls: Option<TlsSettings>,
+        authorization: Option<String>,
+    ) -> Result<Self, String> {
+        let mut builder = Client::builder().tcp_nodelay(true);
+        let prefix = match protocol {
+            Protocol::S3 => "s3/default",
+            Protocol::Rest | Protocol::Grpc => "data",
+        };
+        let base_url = if let Some(tls) = tls {
+            let address = resolve(target, port).await?;
+            let cert = Certificate::from_pem(&tls.ca_cert_pem).map_err(|e| e.to_string())?;
+            builder = builder
+                .add_root_certificate(cert)
+                .resolve(&tls.domain_name, address);
+            format!("https://{}:{}/{}", tls.domain_name, port, prefix)
+        } else {
+            format!("http://{}:{}/{}", target, port, prefix)
+        };
+        Ok(Self {
+            client: builder.build().map_err(|e| e.to_string())?,
+            protocol,
+            base_url,
+            authorization,
+        })
+    }
+
+    async fn send(&self, method: Method, key: u64, body: Option<Bytes>) -> Result<Bytes, Status> {
+        let mut request = self
+            .client
+            .request(method, format!("{}/{}", self.base_url, key));
+        if let Some(authorization) = &self.authorization {
+            request = request.header("authorization", authorization);
+        }
+        if let Some(body) = body {
+            request = request.body(body);
+        }
+        let response = request
+            .send()
+            .await
+            .map_err(|e| Status::unavailable(e.to_string()))?;
+        let status = response.status();
+        let body = response
+            .bytes()
+            .await
+            .map_err(|e| Status::unavailable(e.to_string()))?;
+        if status.is_success() {
+            Ok(body)
+        } else {
+            Err(status_from_http(status, &body))
+        }
+    }
+
+    fn unsupported(&self, operation: &str) -> Option<Status> {
+        (self.protocol == Protocol::S3)
+            .then(|| Status::unimplemented(format!("S3 API has no {} requests", operation)))
+    }
+
+    /// Returns the size of the received data.
+    pub(crate) async fn get(&self, key: u64) -> Result<u64, Status> {
+        self.send(Method::GET, key, None)
+            .await
+            .map(|data| data.len() as u64)
+    }
+
+    pub(crate) async fn put(&self, key: u64, data: Bytes) -> Result<(), Status> {
+        let method = match self.protocol {
+            Protocol::S3 => Method::PUT,
+            Protocol::Rest | Protocol::Grpc => Method::POST,
+        };
+        self.send(method, key, Some(data)).await.map(|_| ())
+    }
+
+    pub(crate) async fn exist(&self, key: u64) -> Result<bool, Status> {
+        if let Some(status) = self.unsupported("exist") {
+            return Err(status);
+        }
+        match self.send(Method::HEAD, key, None).await {
+            Ok(_) => Ok(true),
+            Err(status) if status.code() == Code::NotFound => Ok(false),
+            Err(status) => Err(status),
+        }
+    }
+
+    pub(crate) async fn delete(&self, key: u64) -> Result<(), Status> {
+        if let Some(status) = self.unsupported("delete") {
+            return Err(status);
+        }
+        self.send(Method::DELETE, key, None).await.map(|_| ())
+    }
+}
+
+async fn resolve(target: &str, port: u16) -> Result<SocketAddr, String> {
+    tokio::net::lookup_host((target, port))
+        .await
+        .map_err(|e| e.to_string())?
+        .next()
+        .ok_or_else(|| format!("{} is not resolved", target))
+}
+
+fn status_from_http(status: StatusCode, body: &[u8]) -> Status {
+    let code = match status {
+        StatusCode::BAD_REQUEST => Code::InvalidArgument,
+        StatusCode::UNAUTHORIZED => Code::Unauthenticated,
+        StatusCode::FORBIDDEN => Code::PermissionDenied,
+        StatusCode::NOT_FOUND => Code::NotFound,
+        StatusCode::CONFLICT => Code::AlreadyExists,
+        StatusCode::PRECONDITION_FAILED => Code::FailedPrecondition,
+        StatusCode::NOT_IMPLEMENTED => Code::Unimplemented,
+        StatusCode::SERVICE_UNAVAILABLE => Code::Unavailable,
+        StatusCode::GATEWAY_TIMEOUT | StatusCode::REQUEST_TIMEOUT => Code::DeadlineExceeded,
+        _ => Code::Unknown,
+    };
+    Status::new(
+        code,
+        format!("{}: {}", status, String::from_utf8_lossy(body)),
+    )
+}
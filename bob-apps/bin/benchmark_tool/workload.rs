@@ -0,0 +1,390 @@
+//! Workload spec of bobp: operation mix, key and payload size distributions and request rate.
+//!
+//! ```yaml
+//! operations:
+//!   get: 80
+//!   put: 15
+//!   exist: 4
+//!   delete: 1
+//! keys:
+//!   distribution: zipfian # uniform | zipfian | hotspot | latest
+//!   exponent: 0.99
+//! payload:
+//!   distribution: weighted # fixed | uniform | lognormal | weighted
+//!   buckets:
+//!     - { size: 20000, weight: 70 }
+//!     - { size: 300000, weight: 30 }
+//! rate: 2000 # requests per second, closed-loop workers are used if not set
+//! ```
+
+use rand::{
+    distributions::{Distribution, WeightedIndex},
+    Rng,
+};
+use serde_derive::Deserialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+const DEFAULT_ZIPFIAN_EXPONENT: f64 = 0.99;
+const DEFAULT_MAX_IN_FLIGHT: usize = 1024;
+// Interval between requests of the open-loop run can't be shorter than a nanosecond
+const MAX_RATE: f64 = 1e9;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Operation {
+    Get,
+    Put,
+    Exist,
+    Delete,
+}
+
+/// Relative weights of operations, ratios don't have to sum up to 1 or 100.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct OperationMix {
+    #[serde(default)]
+    get: f64,
+    #[serde(default)]
+    put: f64,
+    #[serde(default)]
+    exist: f64,
+    #[serde(default)]
+    delete: f64,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(tag = "distribution", rename_all = "lowercase", deny_unknown_fields)]
+enum KeyDistribution {
+    #[default]
+    Uniform,
+    /// Key with rank `i` is requested with probability proportional to `1 / i^exponent`,
+    /// the first key of the range is the hottest one.
+    Zipfian {
+        #[serde(default = "default_zipfian_exponent")]
+        exponent: f64,
+    },
+    /// `hot_probability` of requests go to the first `hot_fraction` of keys.
+    Hotspot {
+        hot_fraction: f64,
+        hot_probability: f64,
+    },
+    /// Zipfian by recency: puts write keys one after another, reads prefer recently written.
+    Latest {
+        #[serde(default = "default_zipfian_exponent")]
+        exponent: f64,
+    },
+}
+
+fn default_zipfian_exponent() -> f64 {
+    DEFAULT_ZIPFIAN_EXPONENT
+}
+
+fn default_max_in_flight() -> usize {
+    DEFAULT_MAX_IN_FLIGHT
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SizeBucket {
+    size: u64,
+    weight: f64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "distribution", rename_all = "lowercase", deny_unknown_fields)]
+enum SizeDistribution {
+    Fixed { size: u64 },
+    Uniform { min: u64, max: u64 },
+    /// Sizes of user content are usually close to log-normal: `median * e^(sigma * N(0, 1))`.
+    Lognormal {
+        median: u64,
+        sigma: f64,
+        max: Option<u64>,
+    },
+    Weighted { buckets: Vec<SizeBucket> },
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct WorkloadSpec {
+    operations: OperationMix,
+    #[serde(default)]
+    keys: KeyDistribution,
+    payload: Option<SizeDistribution>,
+    rate: Option<f64>,
+    /// Requests of the open-loop run over this limit are skipped instead of queued.
+    #[serde(default = "default_max_in_flight")]
+    max_in_flight: usize,
+}
+
+/// YCSB zipfian generator of ranks in `0..n`, `exponent` must be in (0, 1).
+#[derive(Debug)]
+struct Zipfian {
+    n: u64,
+    theta: f64,
+    alpha: f64,
+    zeta_n: f64,
+    eta: f64,
+}
+
+impl Zipfian {
+    fn new(n: u64, theta: f64) -> Self {
+        let zeta = |n: u64| (1..=n).map(|i| 1. / (i as f64).powf(theta)).sum::<f64>();
+        let zeta_n = zeta(n);
+        let zeta_2 = zeta(n.min(2));
+        Self {
+            n,
+            theta,
+            alpha: 1. / (1. - theta),
+            zeta_n,
+            eta: (1. - (2. / n as f64).powf(1. - theta)) / (1. - zeta_2 / zeta_n),
+        }
+    }
+
+    fn sample(&self, rng: &mut impl Rng) -> u64 {
+        let u: f64 = rng.gen();
+        let uz = u * self.zeta_n;
+        if uz < 1. {
+            0
+        } else if uz < 1. + 0.5_f64.powf(self.theta) {
+            1.min(self.n - 1)
+        } else {
+            let rank = (self.n as f64 * (self.eta * u - self.eta + 1.).powf(self.alpha)) as u64;
+            rank.min(self.n - 1)
+        }
+    }
+}
+
+#[derive(Debug)]
+enum KeyGenerator {
+    Uniform,
+    Zipfian(Zipfian),
+    Hotspot { hot_count: u64, hot_probability: f64 },
+    Latest(Zipfian),
+}
+
+#[derive(Debug)]
+enum SizeGenerator {
+    Fixed(u64),
+    Uniform(u64, u64),
+    Lognormal { median: f64, sigma: f64, max: u64 },
+    Weighted {
+        sizes: Vec<u64>,
+        index: WeightedIndex<f64>,
+        mean: f64,
+    },
+}
+
+impl SizeGenerator {
+    fn sample(&self, rng: &mut impl Rng) -> u64 {
+        match self {
+            Self::Fixed(size) => *size,
+            Self::Uniform(min, max) => rng.gen_range(*min..=*max),
+            Self::Lognormal { median, sigma, max } => {
+                // Box-Muller transform of two uniform samples
+                let (u1, u2): (f64, f64) = (1. - rng.gen::<f64>(), rng.gen());
+                let normal = (-2. * u1.ln()).sqrt() * (2. * std::f64::consts::PI * u2).cos();
+                ((median * (sigma * normal).exp()) as u64).clamp(1, *max)
+            }
+            Self::Weighted { sizes, index, .. } => sizes[index.sample(rng)],
+        }
+    }
+
+    fn mean(&self) -> f64 {
+        match self {
+            Self::Fixed(size) => *size as f64,
+            Self::Uniform(min, max) => (min + max) as f64 / 2.,
+            Self::Lognormal { median, sigma, .. } => median * (sigma * sigma / 2.).exp(),
+            Self::Weighted { mean, .. } => *mean,
+        }
+    }
+}
+
+/// Generator of requests described by the workload spec.
+#[derive(Debug)]
+pub(crate) struct Workload {
+    operations: Vec<Operation>,
+    operation_index: WeightedIndex<f64>,
+    keys: KeyGenerator,
+    low_idx: u64,
+    count: u64,
+    // Keys written by puts of the `latest` distribution, keys of the range count as written
+    inserted: AtomicU64,
+    payload: SizeGenerator,
+    payload_mean: u64,
+    rate: Option<f64>,
+    max_in_flight: usize,
+}
+
+impl Workload {
+    /// Loads the spec, keys are generated in `low_idx..low_idx + count`.
+    pub(crate) fn from_file(
+        path: &str,
+        low_idx: u64,
+        count: u64,
+        default_payload_size: u64,
+    ) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let spec: WorkloadSpec = serde_yaml::from_str(&content).map_err(|e| e.to_string())?;
+        Self::from_spec(spec, low_idx, count, default_payload_size)
+    }
+
+    fn from_spec(
+        spec: WorkloadSpec,
+        low_idx: u64,
+        count: u64,
+        default_payload_size: u64,
+    ) -> Result<Self, String> {
+        if count == 0 {
+            return Err("key count must be positive".to_string());
+        }
+        let mix = &spec.operations;
+        let (operations, weights): (Vec<_>, Vec<_>) = [
+            (Operation::Get, mix.get),
+            (Operation::Put, mix.put),
+            (Operation::Exist, mix.exist),
+            (Operation::Delete, mix.delete),
+        ]
+        .into_iter()
+        .filter(|(_, weight)| *weight != 0.)
+        .unzip();
+        let operation_index = WeightedIndex::new(&weights)
+            .map_err(|e| format!("invalid operation ratios: {}", e))?;
+
+        let check_exponent = |exponent: f64| {
+            if exponent > 0. && exponent < 1. {
+                Ok(exponent)
+            } else {
+                Err(format!("zipfian exponent must be in (0, 1), got {}", exponent))
+            }
+        };
+        let keys = match spec.keys {
+            KeyDistribution::Uniform => KeyGenerator::Uniform,
+            KeyDistribution::Zipfian { exponent } => {
+                KeyGenerator::Zipfian(Zipfian::new(count, check_exponent(exponent)?))
+            }
+            KeyDistribution::Latest { exponent } => {
+                KeyGenerator::Latest(Zipfian::new(count, check_exponent(exponent)?))
+            }
+            KeyDistribution::Hotspot {
+                hot_fraction,
+                hot_probability,
+            } => {
+                if !(0. ..=1.).contains(&hot_fraction) || !(0. ..=1.).contains(&hot_probability) {
+                    return Err("hotspot fraction and probability must be in [0, 1]".to_string());
+                }
+                KeyGenerator::Hotspot {
+                    hot_count: ((count as f64 * hot_fraction) as u64).clamp(1, count),
+                    hot_probability,
+                }
+            }
+        };
+
+        let payload = match spec.payload {
+            None => SizeGenerator::Fixed(default_payload_size),
+            Some(SizeDistribution::Fixed { size }) => SizeGenerator::Fixed(size),
+            Some(SizeDistribution::Uniform { min, max }) => {
+                if min > max {
+                    return Err(format!("payload min {} is greater than max {}", min, max));
+                }
+                SizeGenerator::Uniform(min, max)
+            }
+            Some(SizeDistribution::Lognormal { median, sigma, max }) => SizeGenerator::Lognormal {
+                median: median as f64,
+                sigma,
+                max: max.unwrap_or(u64::MAX).max(1),
+            },
+            Some(SizeDistribution::Weighted { buckets }) => {
+                let index = WeightedIndex::new(buckets.iter().map(|b| b.weight))
+                    .map_err(|e| format!("invalid payload bucket weights: {}", e))?;
+                let total_weight = buckets.iter().map(|b| b.weight).sum::<f64>();
+                let mean = buckets
+                    .iter()
+                    .map(|b| b.size as f64 * b.weight / total_weight)
+                    .sum();
+                SizeGenerator::Weighted {
+                    sizes: buckets.into_iter().map(|b| b.size).collect(),
+                    index,
+                    mean,
+                }
+            }
+        };
+
+        if matches!(spec.rate, Some(rate) if !(rate > 0. && rate <= MAX_RATE)) {
+            return Err(format!("rate must be in (0, {:e}] requests per second", MAX_RATE));
+        }
+        if spec.max_in_flight == 0 {
+            return Err("max_in_flight must be positive".to_string());
+        }
+        Ok(Self {
+            operations,
+            operation_index,
+            keys,
+            low_idx,
+            count,
+            inserted: AtomicU64::new(count),
+            payload_mean: payload.mean() as u64,
+            payload,
+            rate: spec.rate,
+            max_in_flight: spec.max_in_flight,
+        })
+    }
+
+    /// Operations with non-zero ratio.
+    pub(crate) fn operations(&self) -> &[Operation] {
+        &self.operations
+    }
+
+    /// Requests per second of the open-loop run.
+    pub(crate) fn rate(&self) -> Option<f64> {
+        self.rate
+    }
+
+    pub(crate) fn max_in_flight(&self) -> usize {
+        self.max_in_flight
+    }
+
+    pub(crate) fn payload_mean(&self) -> u64 {
+        self.payload_mean
+    }
+
+    pub(crate) fn next_operation(&self, rng: &mut impl Rng) -> Operation {
+        self.operations[self.operation_index.sample(rng)]
+    }
+
+    pub(crate) fn next_payload_size(&self, rng: &mut impl Rng) -> u64 {
+        self.payload.sample(rng)
+    }
+
+    /// Key to read, check or delete.
+    pub(crate) fn next_key(&self, rng: &mut impl Rng) -> u64 {
+        let offset = match &self.keys {
+            KeyGenerator::Uniform => rng.gen_range(0..self.count),
+            KeyGenerator::Zipfian(zipfian) => zipfian.sample(rng),
+            KeyGenerator::Hotspot {
+                hot_count,
+                hot_probability,
+            } => {
+                if *hot_count == self.count || rng.gen_bool(*hot_probability) {
+                    rng.gen_range(0..*hot_count)
+                } else {
+                    rng.gen_range(*hot_count..self.count)
+                }
+            }
+            KeyGenerator::Latest(zipfian) => {
+                let latest = self.inserted.load(Ordering::Relaxed) - 1;
+                (latest - zipfian.sample(rng)) % self.count
+            }
+        };
+        self.low_idx + offset
+    }
+
+    /// Key to write, keys of the `latest` distribution are written one after another.
+    pub(crate) fn next_put_key(&self, rng: &mut impl Rng) -> u64 {
+        match &self.keys {
+            KeyGenerator::Latest(_) => {
+                self.low_idx + self.inserted.fetch_add(1, Ordering::Relaxed) % self.count
+            }
+            _ => self.next_key(rng),
+        }
+    }
+}
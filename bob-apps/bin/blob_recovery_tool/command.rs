@@ -1,4 +1,9 @@
 use super::prelude::*;
+use super::records::{
+    blob_headers, cmp_keys, find_key, format_key, parse_key, read_record_data, RecordHeader,
+};
+use super::validation::{ParallelValidation, ValidationRoot};
+use serde_derive::Serialize;
 
 const INPUT_OPT: &str = "input blob";
 const OUTPUT_OPT: &str = "output blob";
@@ -13,6 +18,20 @@ const TARGET_VERSION_OPT: &str = "target version";
 const SKIP_WRONG_OPT: &str = "skip wrong";
 const KEY_SIZE_OPT: &str = "key size";
 const FULL_BLOB_INFO_OPT: &str = "full blob info";
+const THREADS_OPT: &str = "threads";
+const STATE_OPT: &str = "state";
+const SUMMARY_OPT: &str = "summary";
+const NODE_CONFIG_OPT: &str = "node config";
+const CLUSTER_CONFIG_OPT: &str = "cluster config";
+const KEY_OPT: &str = "key";
+const MIN_KEY_OPT: &str = "min key";
+const MAX_KEY_OPT: &str = "max key";
+const SINCE_OPT: &str = "since";
+const UNTIL_OPT: &str = "until";
+const DELETED_OPT: &str = "deleted";
+const NO_DELETED_OPT: &str = "no deleted";
+
+const DUMP_MANIFEST: &str = "records.jsonl";
 
 const VALIDATE_INDEX_COMMAND: &str = "validate-index";
 const VALIDATE_BLOB_COMMAND: &str = "validate-blob";
@@ -20,6 +39,9 @@ const RECOVERY_COMMAND: &str = "recovery";
 const MIGRATE_COMMAND: &str = "migrate";
 const GET_INDEX_INFO_COMMAND: &str = "index-info";
 const GET_BLOB_INFO_COMMAND: &str = "blob-info";
+const FIND_KEY_COMMAND: &str = "find-key";
+const LIST_KEYS_COMMAND: &str = "list-keys";
+const DUMP_RECORDS_COMMAND: &str = "dump-records";
 
 pub enum MainCommand {
     Recovery(RecoveryBlobCommand),
@@ -28,6 +50,9 @@ pub enum MainCommand {
     Migrate(MigrateCommand),
     GetBlobInfo(GetBlobInfoCommand),
     GetIndexInfo(GetIndexInfoCommand),
+    FindKey(FindKeyCommand),
+    ListKeys(ListKeysCommand),
+    DumpRecords(DumpRecordsCommand),
 }
 
 pub struct RecoveryBlobCommand {
@@ -95,8 +120,104 @@ impl RecoveryBlobCommand {
     }
 }
 
+/// Files checked by validate commands: the directory or all pearl directories of the node from
+/// its configs, and params of the parallel validation.
+pub struct ValidationOptions {
+    path: Option<PathBuf>,
+    configs: Option<(String, String)>,
+    threads: usize,
+    state: Option<PathBuf>,
+    summary: Option<PathBuf>,
+}
+
+impl ValidationOptions {
+    fn single_file(&self) -> Option<&Path> {
+        self.path.as_deref().filter(|path| path.is_file())
+    }
+
+    fn run<F>(&self, suffix: &str, function: F) -> AnyResult<Vec<String>>
+    where
+        F: Fn(&Path) -> AnyResult<()> + Sync,
+    {
+        let roots = match (&self.path, &self.configs) {
+            (Some(path), _) => vec![ValidationRoot::new(path.to_string_lossy(), path)],
+            (None, Some((node, cluster))) => ValidationRoot::from_configs(node, cluster)?,
+            (None, None) => unreachable!("clap requires path or configs"),
+        };
+        ParallelValidation::new(self.threads, self.state.clone(), self.summary.clone())
+            .run(&roots, suffix, function)
+    }
+
+    fn args<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        app.arg(
+            Arg::with_name(DISK_PATH_OPT)
+                .help("disk path")
+                .short("p")
+                .long("path")
+                .takes_value(true)
+                .required_unless(NODE_CONFIG_OPT),
+        )
+        .arg(
+            Arg::with_name(NODE_CONFIG_OPT)
+                .help("node config, pearl directories of all node disks are validated")
+                .long("node-config")
+                .value_name("node.yaml")
+                .takes_value(true)
+                .requires(CLUSTER_CONFIG_OPT)
+                .conflicts_with(DISK_PATH_OPT),
+        )
+        .arg(
+            Arg::with_name(CLUSTER_CONFIG_OPT)
+                .help("cluster config with disks of the node")
+                .long("cluster-config")
+                .value_name("cluster.yaml")
+                .takes_value(true)
+                .requires(NODE_CONFIG_OPT),
+        )
+        .arg(
+            Arg::with_name(THREADS_OPT)
+                .help("count of files validated in parallel, count of CPUs by default")
+                .long("threads")
+                .value_name("N")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name(STATE_OPT)
+                .help("state file, validation continues from it after interruption")
+                .long("state")
+                .value_name("file")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name(SUMMARY_OPT)
+                .help("json file with results grouped by disk, vdisk and partition")
+                .long("summary")
+                .value_name("file")
+                .takes_value(true),
+        )
+    }
+
+    fn from_matches(matches: &ArgMatches) -> AnyResult<Self> {
+        let configs = matches
+            .value_of(NODE_CONFIG_OPT)
+            .zip(matches.value_of(CLUSTER_CONFIG_OPT))
+            .map(|(node, cluster)| (node.to_string(), cluster.to_string()));
+        let threads = match matches.value_of(THREADS_OPT) {
+            Some(threads) => threads.parse()?,
+            None => std::thread::available_parallelism().map_or(1, |n| n.get()),
+        };
+        Ok(Self {
+            path: matches.value_of(DISK_PATH_OPT).map(PathBuf::from),
+            configs,
+            threads,
+            state: matches.value_of(STATE_OPT).map(PathBuf::from),
+            summary: matches.value_of(SUMMARY_OPT).map(PathBuf::from),
+        })
+    }
+}
+
 pub struct ValidateBlobCommand {
-    path: PathBuf,
+    files: ValidationOptions,
     blob_suffix: String,
     backup_suffix: String,
     validate_every: usize,
@@ -106,12 +227,12 @@ pub struct ValidateBlobCommand {
 
 impl ValidateBlobCommand {
     fn run(&self) -> AnyResult<()> {
-        if self.path.is_file() {
-            validate_blob(&self.path)?;
-            info!("Blob {:?} is valid", self.path);
+        if let Some(path) = self.files.single_file() {
+            validate_blob(path)?;
+            info!("Blob {:?} is valid", path);
             return Ok(());
         }
-        let result = validate_files_recursive(&self.path, &self.blob_suffix, validate_blob)?;
+        let result = self.files.run(&self.blob_suffix, validate_blob)?;
         print_result(&result, "blob");
 
         if self.fix && !result.is_empty() {
@@ -135,15 +256,7 @@ impl ValidateBlobCommand {
     }
 
     fn subcommand<'a, 'b>() -> App<'a, 'b> {
-        SubCommand::with_name(VALIDATE_BLOB_COMMAND)
-            .arg(
-                Arg::with_name(DISK_PATH_OPT)
-                    .help("disk path")
-                    .short("p")
-                    .long("path")
-                    .takes_value(true)
-                    .required(true),
-            )
+        ValidationOptions::args(SubCommand::with_name(VALIDATE_BLOB_COMMAND))
             .arg(
                 Arg::with_name(SUFFIX_OPT)
                     .help("blob suffix")
@@ -186,7 +299,7 @@ impl ValidateBlobCommand {
 
     fn from_matches(matches: &ArgMatches) -> AnyResult<ValidateBlobCommand> {
         Ok(ValidateBlobCommand {
-            path: matches.value_of(DISK_PATH_OPT).expect("Required").into(),
+            files: ValidationOptions::from_matches(matches)?,
             blob_suffix: matches.value_of(SUFFIX_OPT).expect("Required").to_string(),
             backup_suffix: matches
                 .value_of(BACKUP_SUFFIX_OPT)
@@ -203,7 +316,7 @@ impl ValidateBlobCommand {
 }
 
 pub struct ValidateIndexCommand {
-    path: PathBuf,
+    files: ValidationOptions,
     index_suffix: String,
     delete: bool,
     skip_confirmation: bool,
@@ -220,12 +333,12 @@ impl ValidateIndexCommand {
             None => validate_index::<PearlKey>,
             _ => return Err(anyhow::anyhow!("Key size is not supported")),
         };
-        if self.path.is_file() {
-            validate_index_fn(&self.path)?;
-            info!("Index {:?} is valid", self.path);
+        if let Some(path) = self.files.single_file() {
+            validate_index_fn(path)?;
+            info!("Index {:?} is valid", path);
             return Ok(());
         }
-        let result = validate_files_recursive(&self.path, &self.index_suffix, validate_index_fn)?;
+        let result = self.files.run(&self.index_suffix, validate_index_fn)?;
         print_result(&result, "index");
 
         if self.delete && !result.is_empty() {
@@ -255,15 +368,7 @@ impl ValidateIndexCommand {
                             PearlKey::LEN
                         );
         }
-        SubCommand::with_name(VALIDATE_INDEX_COMMAND)
-            .arg(
-                Arg::with_name(DISK_PATH_OPT)
-                    .help("disk path")
-                    .short("p")
-                    .long("path")
-                    .takes_value(true)
-                    .required(true),
-            )
+        ValidationOptions::args(SubCommand::with_name(VALIDATE_INDEX_COMMAND))
             .arg(
                 Arg::with_name(SUFFIX_OPT)
                     .help("index suffix")
@@ -296,7 +401,7 @@ impl ValidateIndexCommand {
 
     fn from_matches(matches: &ArgMatches) -> AnyResult<Self> {
         Ok(ValidateIndexCommand {
-            path: matches.value_of(DISK_PATH_OPT).expect("Required").into(),
+            files: ValidationOptions::from_matches(matches)?,
             index_suffix: matches.value_of(SUFFIX_OPT).expect("Required").to_string(),
             delete: matches.is_present(DELETE_OPT),
             skip_confirmation: matches.is_present(NO_CONFIRM_OPT),
@@ -505,6 +610,424 @@ impl GetIndexInfoCommand {
     }
 }
 
+fn key_size_arg<'a, 'b>() -> Arg<'a, 'b> {
+    lazy_static::lazy_static! {
+        static ref KEY_SIZE_HELP: String =
+            format!("key size in bytes, {} used by default", PearlKey::LEN);
+    }
+    Arg::with_name(KEY_SIZE_OPT)
+        .takes_value(true)
+        .required(false)
+        .help(KEY_SIZE_HELP.as_str())
+        .long("key-size")
+}
+
+fn key_size_from_matches(matches: &ArgMatches) -> AnyResult<usize> {
+    Ok(matches
+        .value_of(KEY_SIZE_OPT)
+        .map(|x| x.parse())
+        .transpose()?
+        .unwrap_or(PearlKey::LEN as usize))
+}
+
+fn blob_files(path: &Path, suffix: &str) -> AnyResult<Vec<PathBuf>> {
+    if path.is_file() {
+        return Ok(vec![path.to_owned()]);
+    }
+    let mut files = vec![];
+    for_each_file_recursive(path, |entry_path, _| {
+        if entry_path.to_string_lossy().ends_with(suffix) {
+            files.push(entry_path.to_owned());
+        }
+    })?;
+    files.sort();
+    Ok(files)
+}
+
+fn record_file_name(blob: &Path, header: &RecordHeader) -> String {
+    let blob_name = blob.file_stem().unwrap_or_default().to_string_lossy();
+    format!(
+        "{}_{}_{}_{}",
+        format_key(header.key()),
+        header.timestamp(),
+        blob_name,
+        header.blob_offset()
+    )
+}
+
+fn print_record(blob: &Path, header: &RecordHeader) {
+    println!(
+        "{} timestamp: {}, deleted: {}, size: {}, blob: {:?}, offset: {}",
+        format_key(header.key()),
+        header.timestamp(),
+        header.is_deleted(),
+        header.data_size(),
+        blob,
+        header.blob_offset()
+    );
+}
+
+pub struct FindKeyCommand {
+    path: PathBuf,
+    key: Vec<u8>,
+    blob_suffix: String,
+    output: Option<PathBuf>,
+}
+
+impl FindKeyCommand {
+    fn run(&self) -> AnyResult<()> {
+        let mut found = vec![];
+        for blob in blob_files(&self.path, &self.blob_suffix)? {
+            match find_key(&blob, &self.key) {
+                Ok((source, headers)) => {
+                    debug!(
+                        "[{:?}] {} records found in {:?}",
+                        blob,
+                        headers.len(),
+                        source
+                    );
+                    found.extend(headers.into_iter().map(|header| (blob.clone(), header)));
+                }
+                Err(err) => error!("[{:?}] key lookup error: {}", blob, err),
+            }
+        }
+        let key = format_key(&self.key);
+        if found.is_empty() {
+            info!("Key {} is not found", key);
+            return Ok(());
+        }
+        found.sort_by_key(|(_, header)| std::cmp::Reverse(header.timestamp()));
+        if let Some(output) = &self.output {
+            std::fs::create_dir_all(output)?;
+        }
+        for (blob, header) in &found {
+            print_record(blob, header);
+            if let Some(output) = &self.output {
+                if !header.is_deleted() {
+                    let path = output.join(record_file_name(blob, header));
+                    let mut file = std::fs::File::open(blob)?;
+                    std::fs::write(&path, read_record_data(&mut file, header)?)?;
+                    info!("Record data is written to {:?}", path);
+                }
+            }
+        }
+        let (blob, actual) = &found[0];
+        if actual.is_deleted() {
+            info!(
+                "Key {} is deleted at {} in {:?}",
+                key,
+                actual.timestamp(),
+                blob
+            );
+        } else {
+            info!(
+                "Actual record of key {} has timestamp {} in {:?}",
+                key,
+                actual.timestamp(),
+                blob
+            );
+        }
+        Ok(())
+    }
+
+    fn subcommand<'a, 'b>() -> App<'a, 'b> {
+        SubCommand::with_name(FIND_KEY_COMMAND)
+            .arg(
+                Arg::with_name(DISK_PATH_OPT)
+                    .help("vdisk directory or blob file")
+                    .short("p")
+                    .long("path")
+                    .takes_value(true)
+                    .required(true),
+            )
+            .arg(
+                Arg::with_name(KEY_OPT)
+                    .help("key, decimal number or 0x prefixed hex bytes")
+                    .short("k")
+                    .long("key")
+                    .takes_value(true)
+                    .required(true),
+            )
+            .arg(
+                Arg::with_name(SUFFIX_OPT)
+                    .help("blob suffix")
+                    .short("s")
+                    .long("suffix")
+                    .default_value("blob")
+                    .takes_value(true),
+            )
+            .arg(
+                Arg::with_name(OUTPUT_OPT)
+                    .help("directory for data of the found records")
+                    .value_name("path")
+                    .short("o")
+                    .long("output")
+                    .takes_value(true),
+            )
+            .arg(key_size_arg())
+    }
+
+    fn from_matches(matches: &ArgMatches) -> AnyResult<Self> {
+        let key_size = key_size_from_matches(matches)?;
+        Ok(Self {
+            path: matches.value_of(DISK_PATH_OPT).expect("Required").into(),
+            key: parse_key(matches.value_of(KEY_OPT).expect("Required"), key_size)?,
+            blob_suffix: matches.value_of(SUFFIX_OPT).expect("Required").to_string(),
+            output: matches.value_of(OUTPUT_OPT).map(PathBuf::from),
+        })
+    }
+}
+
+/// Conditions on records shown by `list-keys` and `dump-records`.
+pub struct RecordFilter {
+    key_size: usize,
+    min_key: Option<Vec<u8>>,
+    max_key: Option<Vec<u8>>,
+    since: Option<u64>,
+    until: Option<u64>,
+    deleted: Option<bool>,
+}
+
+impl RecordFilter {
+    fn matches(&self, header: &RecordHeader) -> AnyResult<bool> {
+        if header.key().len() != self.key_size {
+            return Err(anyhow::anyhow!(
+                "record key size {} differs from {}",
+                header.key().len(),
+                self.key_size
+            ));
+        }
+        let key = header.key();
+        Ok(self
+            .min_key
+            .as_ref()
+            .is_none_or(|min| cmp_keys(key, min).is_ge())
+            && self
+                .max_key
+                .as_ref()
+                .is_none_or(|max| cmp_keys(key, max).is_le())
+            && self.since.is_none_or(|since| header.timestamp() >= since)
+            && self.until.is_none_or(|until| header.timestamp() <= until)
+            && self
+                .deleted
+                .is_none_or(|deleted| header.is_deleted() == deleted))
+    }
+
+    fn args<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        app.arg(
+            Arg::with_name(KEY_OPT)
+                .help("only the key, decimal number or 0x prefixed hex bytes")
+                .short("k")
+                .long("key")
+                .takes_value(true)
+                .conflicts_with_all(&[MIN_KEY_OPT, MAX_KEY_OPT]),
+        )
+        .arg(
+            Arg::with_name(MIN_KEY_OPT)
+                .help("keys greater or equal to this one")
+                .long("min-key")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name(MAX_KEY_OPT)
+                .help("keys less or equal to this one")
+                .long("max-key")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name(SINCE_OPT)
+                .help("records with timestamp greater or equal to this one")
+                .long("since")
+                .value_name("timestamp")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name(UNTIL_OPT)
+                .help("records with timestamp less or equal to this one")
+                .long("until")
+                .value_name("timestamp")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name(DELETED_OPT)
+                .help("only delete records")
+                .long("deleted")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name(NO_DELETED_OPT)
+                .help("skip delete records")
+                .long("no-deleted")
+                .takes_value(false)
+                .conflicts_with(DELETED_OPT),
+        )
+        .arg(key_size_arg())
+    }
+
+    fn from_matches(matches: &ArgMatches) -> AnyResult<Self> {
+        let key_size = key_size_from_matches(matches)?;
+        let parse = |name| {
+            matches
+                .value_of(name)
+                .map(|key| parse_key(key, key_size))
+                .transpose()
+        };
+        let key = parse(KEY_OPT)?;
+        let deleted = if matches.is_present(DELETED_OPT) {
+            Some(true)
+        } else if matches.is_present(NO_DELETED_OPT) {
+            Some(false)
+        } else {
+            None
+        };
+        Ok(Self {
+            key_size,
+            min_key: key.clone().or(parse(MIN_KEY_OPT)?),
+            max_key: key.or(parse(MAX_KEY_OPT)?),
+            since: matches.value_of(SINCE_OPT).map(|x| x.parse()).transpose()?,
+            until: matches.value_of(UNTIL_OPT).map(|x| x.parse()).transpose()?,
+            deleted,
+        })
+    }
+}
+
+pub struct ListKeysCommand {
+    path: PathBuf,
+    blob_suffix: String,
+    filter: RecordFilter,
+}
+
+impl ListKeysCommand {
+    fn run(&self) -> AnyResult<()> {
+        let mut count = 0;
+        for blob in blob_files(&self.path, &self.blob_suffix)? {
+            for header in blob_headers(&blob)? {
+                if self.filter.matches(&header)? {
+                    print_record(&blob, &header);
+                    count += 1;
+                }
+            }
+        }
+        info!("{} records listed", count);
+        Ok(())
+    }
+
+    fn subcommand<'a, 'b>() -> App<'a, 'b> {
+        let app = SubCommand::with_name(LIST_KEYS_COMMAND)
+            .arg(
+                Arg::with_name(DISK_PATH_OPT)
+                    .help("directory or blob file")
+                    .short("p")
+                    .long("path")
+                    .takes_value(true)
+                    .required(true),
+            )
+            .arg(
+                Arg::with_name(SUFFIX_OPT)
+                    .help("blob suffix")
+                    .short("s")
+                    .long("suffix")
+                    .default_value("blob")
+                    .takes_value(true),
+            );
+        RecordFilter::args(app)
+    }
+
+    fn from_matches(matches: &ArgMatches) -> AnyResult<Self> {
+        Ok(Self {
+            path: matches.value_of(DISK_PATH_OPT).expect("Required").into(),
+            blob_suffix: matches.value_of(SUFFIX_OPT).expect("Required").to_string(),
+            filter: RecordFilter::from_matches(matches)?,
+        })
+    }
+}
+
+/// Line of the `records.jsonl` written by `dump-records`.
+#[derive(Serialize)]
+struct DumpedRecord {
+    key: String,
+    timestamp: u64,
+    deleted: bool,
+    size: u64,
+    offset: u64,
+    file: Option<String>,
+}
+
+pub struct DumpRecordsCommand {
+    input: PathBuf,
+    output: PathBuf,
+    filter: RecordFilter,
+}
+
+impl DumpRecordsCommand {
+    fn run(&self) -> AnyResult<()> {
+        std::fs::create_dir_all(&self.output)?;
+        let manifest_path = self.output.join(DUMP_MANIFEST);
+        let mut manifest = std::io::BufWriter::new(std::fs::File::create(&manifest_path)?);
+        let mut blob = std::fs::File::open(&self.input)?;
+        let mut count = 0;
+        for header in blob_headers(&self.input)? {
+            if !self.filter.matches(&header)? {
+                continue;
+            }
+            let file = if header.is_deleted() {
+                None
+            } else {
+                let name = record_file_name(&self.input, &header);
+                std::fs::write(self.output.join(&name), read_record_data(&mut blob, &header)?)?;
+                Some(name)
+            };
+            let record = DumpedRecord {
+                key: format_key(header.key()),
+                timestamp: header.timestamp(),
+                deleted: header.is_deleted(),
+                size: header.data_size(),
+                offset: header.blob_offset(),
+                file,
+            };
+            serde_json::to_writer(&mut manifest, &record)?;
+            writeln!(manifest)?;
+            count += 1;
+        }
+        manifest.flush()?;
+        info!(
+            "{} records dumped, list is written to {:?}",
+            count, manifest_path
+        );
+        Ok(())
+    }
+
+    fn subcommand<'a, 'b>() -> App<'a, 'b> {
+        let app = SubCommand::with_name(DUMP_RECORDS_COMMAND)
+            .arg(
+                Arg::with_name(INPUT_OPT)
+                    .help("input blob")
+                    .takes_value(true)
+                    .required(true)
+                    .short("i")
+                    .long("input"),
+            )
+            .arg(
+                Arg::with_name(OUTPUT_OPT)
+                    .help("output directory")
+                    .value_name("path")
+                    .takes_value(true)
+                    .required(true)
+                    .short("o")
+                    .long("output"),
+            );
+        RecordFilter::args(app)
+    }
+
+    fn from_matches(matches: &ArgMatches) -> AnyResult<Self> {
+        Ok(Self {
+            input: matches.value_of(INPUT_OPT).expect("Required").into(),
+            output: matches.value_of(OUTPUT_OPT).expect("Required").into(),
+            filter: RecordFilter::from_matches(matches)?,
+        })
+    }
+}
+
 impl MainCommand {
     pub fn run() -> AnyResult<()> {
         let settings = MainCommand::from_matches()?;
@@ -515,6 +1038,9 @@ impl MainCommand {
             MainCommand::Migrate(settings) => settings.run(),
             MainCommand::GetBlobInfo(settings) => settings.run(),
             MainCommand::GetIndexInfo(settings) => settings.run(),
+            MainCommand::FindKey(settings) => settings.run(),
+            MainCommand::ListKeys(settings) => settings.run(),
+            MainCommand::DumpRecords(settings) => settings.run(),
         }
     }
 
@@ -527,6 +1053,9 @@ impl MainCommand {
             .subcommand(MigrateCommand::subcommand())
             .subcommand(GetBlobInfoCommand::subcommand())
             .subcommand(GetIndexInfoCommand::subcommand())
+            .subcommand(FindKeyCommand::subcommand())
+            .subcommand(ListKeysCommand::subcommand())
+            .subcommand(DumpRecordsCommand::subcommand())
             .get_matches()
     }
 
@@ -551,6 +1080,15 @@ impl MainCommand {
             (GET_INDEX_INFO_COMMAND, Some(matches)) => Ok(MainCommand::GetIndexInfo(
                 GetIndexInfoCommand::from_matches(matches)?,
             )),
+            (FIND_KEY_COMMAND, Some(matches)) => {
+                Ok(MainCommand::FindKey(FindKeyCommand::from_matches(matches)?))
+            }
+            (LIST_KEYS_COMMAND, Some(matches)) => Ok(MainCommand::ListKeys(
+                ListKeysCommand::from_matches(matches)?,
+            )),
+            (DUMP_RECORDS_COMMAND, Some(matches)) => Ok(MainCommand::DumpRecords(
+                DumpRecordsCommand::from_matches(matches)?,
+            )),
             _ => Err(anyhow::anyhow!("Unknown command")),
         }
     }
@@ -1,6 +1,8 @@
 pub(crate) mod command;
 pub(crate) mod key;
+pub(crate) mod records;
 pub(crate) mod utils;
+pub(crate) mod validation;
 
 pub(crate) mod prelude {
     pub(crate) use super::key::*;
@@ -0,0 +1,275 @@
+use super::prelude::*;
+use pearl::{Bloom, FilterResult};
+use serde_derive::{Deserialize, Serialize};
+use std::{
+    fs::File,
+    io::{BufReader, Read, Seek, SeekFrom},
+};
+
+const RECORD_MAGIC_BYTE: u64 = 0xacdc_bcde;
+const INDEX_MAGIC_BYTE: u64 = 0xacdc_bcde;
+const RECORD_DELETE_FLAG: u8 = 0x01;
+const INDEX_EXTENSION: &str = "index";
+
+// Layout of pearl record header: pearl tools return it from indexes, but its timestamp, flags
+// and sizes are private, so the header is converted through the same bincode layout
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct RecordHeader {
+    magic_byte: u64,
+    key: Vec<u8>,
+    meta_size: u64,
+    data_size: u64,
+    flags: u8,
+    blob_offset: u64,
+    timestamp: u64,
+    _data_checksum: u32,
+    _header_checksum: u32,
+}
+
+impl RecordHeader {
+    fn from_pearl(header: &impl serde::Serialize) -> AnyResult<Self> {
+        Ok(bincode::deserialize(&bincode::serialize(header)?)?)
+    }
+
+    pub(crate) fn key(&self) -> &[u8] {
+        &self.key
+    }
+
+    pub(crate) fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    pub(crate) fn data_size(&self) -> u64 {
+        self.data_size
+    }
+
+    pub(crate) fn blob_offset(&self) -> u64 {
+        self.blob_offset
+    }
+
+    pub(crate) fn is_deleted(&self) -> bool {
+        self.flags & RECORD_DELETE_FLAG == RECORD_DELETE_FLAG
+    }
+
+    fn data_offset(&self) -> AnyResult<u64> {
+        Ok(self.blob_offset + bincode::serialized_size(self)? + self.meta_size)
+    }
+
+    fn validate(&self, path: &Path) -> AnyResult<()> {
+        if self.magic_byte != RECORD_MAGIC_BYTE {
+            return Err(anyhow::anyhow!(
+                "[{:?}] wrong record magic byte at offset {}",
+                path,
+                self.blob_offset
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Where [`find_key`] took the result from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum LookupSource {
+    Bloom,
+    Index,
+    Blob,
+}
+
+/// Record headers of the blob in order of their offsets. Headers are read from the index with
+/// pearl tools, active blob without index is scanned.
+pub(crate) fn blob_headers(blob: &Path) -> AnyResult<Vec<RecordHeader>> {
+    let index = blob.with_extension(INDEX_EXTENSION);
+    if index.is_file() {
+        match index_headers(&index) {
+            Ok(mut headers) => {
+                headers.sort_unstable_by_key(|header| header.blob_offset());
+                return Ok(headers);
+            }
+            Err(err) => warn!("[{:?}] index is not used: {}", index, err),
+        }
+    }
+    let mut records = BlobRecords::open(blob)?;
+    let mut headers = vec![];
+    while let Some(header) = records.next_header()? {
+        headers.push(header);
+    }
+    Ok(headers)
+}
+
+fn index_headers(index: &Path) -> AnyResult<Vec<RecordHeader>> {
+    read_index_sync(index)?
+        .values()
+        .flatten()
+        .map(RecordHeader::from_pearl)
+        .collect()
+}
+
+// Sequential reader of the blob without index, pearl tools only validate such blobs
+struct BlobRecords {
+    path: PathBuf,
+    reader: BufReader<File>,
+    position: u64,
+    len: u64,
+}
+
+impl BlobRecords {
+    fn open(path: &Path) -> AnyResult<Self> {
+        // Validates magic byte and version of the blob header
+        let summary = BlobSummaryCollector::from_path(path, false)?;
+        let header_size = bincode::serialized_size(&(
+            summary.header_magic_byte(),
+            summary.header_version(),
+            summary.header_flags(),
+        ))?;
+        let file = File::open(path)?;
+        let len = file.metadata()?.len();
+        let mut reader = BufReader::new(file);
+        reader.seek(SeekFrom::Start(header_size))?;
+        Ok(Self {
+            path: path.to_owned(),
+            reader,
+            position: header_size,
+            len,
+        })
+    }
+
+    fn next_header(&mut self) -> AnyResult<Option<RecordHeader>> {
+        if self.position >= self.len {
+            return Ok(None);
+        }
+        let header: RecordHeader = bincode::deserialize_from(&mut self.reader)?;
+        header.validate(&self.path)?;
+        self.reader
+            .seek_relative((header.meta_size + header.data_size) as i64)?;
+        self.position += bincode::serialized_size(&header)? + header.meta_size + header.data_size;
+        Ok(Some(header))
+    }
+}
+
+/// Reads data of the record from the open blob.
+pub(crate) fn read_record_data(blob: &mut File, header: &RecordHeader) -> AnyResult<Vec<u8>> {
+    blob.seek(SeekFrom::Start(header.data_offset()?))?;
+    let mut data = vec![0; header.data_size as usize];
+    blob.read_exact(&mut data)?;
+    Ok(data)
+}
+
+/// Looks the key up in the blob: index with its bloom filter is used if the blob has it,
+/// active blob without index is scanned.
+pub(crate) fn find_key(blob: &Path, key: &[u8]) -> AnyResult<(LookupSource, Vec<RecordHeader>)> {
+    let index = blob.with_extension(INDEX_EXTENSION);
+    if index.is_file() {
+        match find_key_in_index(&index, key) {
+            Ok(result) => return Ok(result),
+            Err(err) => warn!("[{:?}] index is not used: {}", index, err),
+        }
+    }
+    let mut records = BlobRecords::open(blob)?;
+    let mut headers = vec![];
+    while let Some(header) = records.next_header()? {
+        if header.key() == key {
+            headers.push(header);
+        }
+    }
+    Ok((LookupSource::Blob, headers))
+}
+
+fn find_key_in_index(index: &Path, key: &[u8]) -> AnyResult<(LookupSource, Vec<RecordHeader>)> {
+    if let Some(bloom) = read_bloom(index)? {
+        if bloom.contains_in_memory(key) == Some(FilterResult::NotContains) {
+            return Ok((LookupSource::Bloom, vec![]));
+        }
+    }
+    let mut headers = read_index_sync(index)?;
+    if let Some(index_key) = headers.keys().next() {
+        if index_key.len() != key.len() {
+            return Err(anyhow::anyhow!(
+                "index key size {} differs from {}",
+                index_key.len(),
+                key.len()
+            ));
+        }
+    }
+    let headers = headers
+        .remove(key)
+        .unwrap_or_default()
+        .iter()
+        .map(RecordHeader::from_pearl)
+        .collect::<AnyResult<_>>()?;
+    Ok((LookupSource::Index, headers))
+}
+
+// Fields of pearl index header before its meta, pearl tools don't read the meta
+#[derive(Deserialize)]
+struct IndexHeader {
+    magic_byte: u64,
+    _records_count: usize,
+    _record_header_size: usize,
+    meta_size: usize,
+    _hash: Vec<u8>,
+    _version: u8,
+    _key_size: u16,
+    _blob_size: u64,
+}
+
+// Index meta is the size of the range filter, the range filter and the bloom filter. Bloom
+// filter is read before the whole index, so the lookup of the absent key is cheap.
+fn read_bloom(index: &Path) -> AnyResult<Option<Bloom>> {
+    let mut file = BufReader::new(File::open(index)?);
+    let header: IndexHeader = bincode::deserialize_from(&mut file)?;
+    if header.magic_byte != INDEX_MAGIC_BYTE {
+        return Err(anyhow::anyhow!("wrong index magic byte"));
+    }
+    let mut meta = vec![0; header.meta_size];
+    file.read_exact(&mut meta)?;
+    let size_len = std::mem::size_of::<u64>();
+    let bloom = meta
+        .get(..size_len)
+        .and_then(|size| bincode::deserialize::<u64>(size).ok())
+        .and_then(|range_size| meta.get(size_len + range_size as usize..))
+        .and_then(|bloom| Bloom::from_raw(bloom).ok());
+    Ok(bloom)
+}
+
+/// Parses key given as decimal number or as `0x` prefixed hex bytes in storage order.
+pub(crate) fn parse_key(s: &str, key_size: usize) -> AnyResult<Vec<u8>> {
+    if let Some(hex) = s.strip_prefix("0x") {
+        let key = hex::decode(hex)?;
+        if key.len() != key_size {
+            return Err(anyhow::anyhow!(
+                "key {} has {} bytes, key size is {}",
+                s,
+                key.len(),
+                key_size
+            ));
+        }
+        return Ok(key);
+    }
+    let bytes = s.parse::<u128>()?.to_le_bytes();
+    let mut key = bytes.to_vec();
+    if key_size < key.len() {
+        if key[key_size..].iter().any(|b| *b != 0) {
+            return Err(anyhow::anyhow!("key {} doesn't fit {} bytes", s, key_size));
+        }
+        key.truncate(key_size);
+    } else {
+        key.resize(key_size, 0);
+    }
+    Ok(key)
+}
+
+/// Formats key as decimal number if it fits `u128`, as hex bytes otherwise.
+pub(crate) fn format_key(key: &[u8]) -> String {
+    let mut bytes = [0; 16];
+    if key.len() <= bytes.len() {
+        bytes[..key.len()].copy_from_slice(key);
+        u128::from_le_bytes(bytes).to_string()
+    } else {
+        format!("0x{}", hex::encode(key))
+    }
+}
+
+/// Compares keys as little endian numbers, the same way bob orders them.
+pub(crate) fn cmp_keys(x: &[u8], y: &[u8]) -> std::cmp::Ordering {
+    x.iter().rev().cmp(y.iter().rev())
+}
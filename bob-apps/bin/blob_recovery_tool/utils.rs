@@ -122,36 +122,6 @@ where
     Ok(function)
 }
 
-pub(crate) fn validate_files_recursive<F>(
-    path: &Path,
-    suffix: &str,
-    mut function: F,
-) -> AnyResult<Vec<String>>
-where
-    F: FnMut(&Path) -> AnyResult<()> + Clone,
-{
-    info!("Start validation");
-    let mut result = vec![];
-    let mut count = 0;
-    let mut last_log = Instant::now();
-    for_each_file_recursive(path, |entry_path, _relative_path| {
-        let entry_path = entry_path.as_os_str().to_str().unwrap().to_string();
-        if entry_path.ends_with(&suffix) {
-            if let Err(err) = function(entry_path.as_ref()) {
-                error!("[{}] validation error: {}", entry_path, err);
-                result.push(entry_path.to_string());
-            }
-            count += 1;
-            if last_log.elapsed() >= Duration::from_secs(1) {
-                info!("{} files validated", count);
-                last_log = Instant::now();
-            }
-        }
-    })?;
-    info!("Validation completed! {} files validated", count);
-    Ok(result)
-}
-
 pub(crate) fn print_result(result: &[String], file_type: &str) {
     if result.is_empty() {
         info!("All {} files is valid", file_type);
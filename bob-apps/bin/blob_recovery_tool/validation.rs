@@ -0,0 +1,306 @@
+use super::prelude::*;
+use bob_common::configs::cluster::Cluster as ClusterConfig;
+use serde_derive::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, BufWriter},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+};
+
+/// Directory validated as a whole, files are grouped by it in the summary.
+pub(crate) struct ValidationRoot {
+    name: String,
+    path: PathBuf,
+}
+
+impl ValidationRoot {
+    pub(crate) fn new(name: impl Into<String>, path: impl Into<PathBuf>) -> Self {
+        Self {
+            name: name.into(),
+            path: path.into(),
+        }
+    }
+
+    /// Bob directories of all disks of the node and its alien directory.
+    pub(crate) fn from_configs(node_config: &str, cluster_config: &str) -> AnyResult<Vec<Self>> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+        let (cluster, node) = runtime.block_on(async {
+            let cluster = ClusterConfig::try_get(cluster_config)
+                .await
+                .map_err(|e| anyhow::anyhow!(e))?;
+            let node = cluster.get(node_config).await?;
+            Ok::<_, anyhow::Error>((cluster, node))
+        })?;
+        let disks = cluster
+            .nodes()
+            .iter()
+            .find(|n| n.name() == node.name())
+            .ok_or_else(|| anyhow::anyhow!("node {} is not in cluster config", node.name()))?
+            .disks();
+        let settings = node.pearl().settings();
+        let mut roots = disks
+            .iter()
+            .map(|disk| {
+                let path = Path::new(disk.path()).join(settings.root_dir_name());
+                Self::new(disk.name().as_str(), path)
+            })
+            .collect::<Vec<_>>();
+        let alien_path = match node.pearl().alien_disk() {
+            Some(alien_disk) => {
+                let disk = disks
+                    .iter()
+                    .find(|disk| disk.name().as_str() == alien_disk)
+                    .ok_or_else(|| anyhow::anyhow!("alien disk {} is not found", alien_disk))?;
+                Path::new(disk.path()).join(settings.alien_root_dir_name())
+            }
+            None => PathBuf::from(settings.alien_root_dir_name()),
+        };
+        roots.push(Self::new("alien", alien_path));
+        roots.retain(|root| {
+            let exists = root.path.is_dir();
+            if !exists {
+                warn!(
+                    "[{:?}] directory of {} not found, skipped",
+                    root.path, root.name
+                );
+            }
+            exists
+        });
+        Ok(roots)
+    }
+}
+
+/// Result of the file validation, written to the state file as json line.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct FileResult {
+    path: String,
+    root: String,
+    // Path of the partition directory relative to the root without the last component:
+    // vdisk id for local data, `<node>/<vdisk id>` for aliens
+    vdisk: String,
+    partition: String,
+    error: Option<String>,
+}
+
+#[derive(Default, Serialize)]
+struct Summary {
+    files: usize,
+    invalid: usize,
+    disks: BTreeMap<String, DiskSummary>,
+}
+
+#[derive(Default, Serialize)]
+struct DiskSummary {
+    files: usize,
+    invalid: usize,
+    vdisks: BTreeMap<String, VDiskSummary>,
+}
+
+#[derive(Default, Serialize)]
+struct VDiskSummary {
+    files: usize,
+    invalid: usize,
+    partitions: BTreeMap<String, PartitionSummary>,
+}
+
+#[derive(Default, Serialize)]
+struct PartitionSummary {
+    files: usize,
+    invalid: usize,
+    invalid_files: Vec<String>,
+}
+
+impl Summary {
+    fn new<'a>(results: impl IntoIterator<Item = &'a FileResult>) -> Self {
+        let mut summary = Self::default();
+        for result in results {
+            let invalid = usize::from(result.error.is_some());
+            let disk = summary.disks.entry(result.root.clone()).or_default();
+            let vdisk = disk.vdisks.entry(result.vdisk.clone()).or_default();
+            let partition = vdisk
+                .partitions
+                .entry(result.partition.clone())
+                .or_default();
+            summary.files += 1;
+            disk.files += 1;
+            vdisk.files += 1;
+            partition.files += 1;
+            summary.invalid += invalid;
+            disk.invalid += invalid;
+            vdisk.invalid += invalid;
+            partition.invalid += invalid;
+            if invalid > 0 {
+                partition.invalid_files.push(result.path.clone());
+            }
+        }
+        summary
+    }
+}
+
+/// Validates files on several threads. Every result is appended to the state file, so
+/// interrupted validation continues from the files not validated yet.
+pub(crate) struct ParallelValidation {
+    threads: usize,
+    state_path: Option<PathBuf>,
+    summary_path: Option<PathBuf>,
+}
+
+impl ParallelValidation {
+    pub(crate) fn new(
+        threads: usize,
+        state_path: Option<PathBuf>,
+        summary_path: Option<PathBuf>,
+    ) -> Self {
+        Self {
+            threads: threads.max(1),
+            state_path,
+            summary_path,
+        }
+    }
+
+    /// Returns paths of invalid files, including ones found before resume.
+    pub(crate) fn run<F>(
+        &self,
+        roots: &[ValidationRoot],
+        suffix: &str,
+        function: F,
+    ) -> AnyResult<Vec<String>>
+    where
+        F: Fn(&Path) -> AnyResult<()> + Sync,
+    {
+        let mut results = self.load_state()?;
+        let files = Self::collect_files(roots, suffix)?
+            .into_iter()
+            .filter(|file| !results.contains_key(&file.path))
+            .collect::<Vec<_>>();
+        info!(
+            "Start validation: {} files on {} threads, {} files validated before",
+            files.len(),
+            self.threads,
+            results.len()
+        );
+        let state = self.open_state()?.map(Mutex::new);
+        let next = AtomicUsize::new(0);
+        let validated = Mutex::new(Vec::with_capacity(files.len()));
+        let last_log = Mutex::new(Instant::now());
+        std::thread::scope(|scope| {
+            for _ in 0..self.threads {
+                scope.spawn(|| loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(file) = files.get(index) else {
+                        break;
+                    };
+                    let error = function(file.path.as_ref()).err().map(|err| {
+                        error!("[{}] validation error: {}", file.path, err);
+                        err.to_string()
+                    });
+                    let result = FileResult {
+                        error,
+                        ..file.clone()
+                    };
+                    if let Some(state) = &state {
+                        if let Err(err) = Self::write_state(state, &result) {
+                            error!("failed to write state file: {}", err);
+                        }
+                    }
+                    let mut validated = validated.lock().expect("mutex");
+                    validated.push(result);
+                    let mut last_log = last_log.lock().expect("mutex");
+                    if last_log.elapsed() >= Duration::from_secs(1) {
+                        info!("{}/{} files validated", validated.len(), files.len());
+                        *last_log = Instant::now();
+                    }
+                });
+            }
+        });
+        for result in validated.into_inner().expect("mutex") {
+            results.insert(result.path.clone(), result);
+        }
+        info!("Validation completed! {} files validated", results.len());
+        let summary = Summary::new(results.values());
+        if let Some(path) = &self.summary_path {
+            serde_json::to_writer_pretty(BufWriter::new(File::create(path)?), &summary)?;
+            info!("Summary is written to {:?}", path);
+        }
+        let mut invalid = results
+            .into_values()
+            .filter(|result| result.error.is_some())
+            .map(|result| result.path)
+            .collect::<Vec<_>>();
+        invalid.sort();
+        Ok(invalid)
+    }
+
+    fn collect_files(roots: &[ValidationRoot], suffix: &str) -> AnyResult<Vec<FileResult>> {
+        let mut files = vec![];
+        for root in roots {
+            for_each_file_recursive(&root.path, |entry_path, relative_path| {
+                let path = entry_path.as_os_str().to_str().unwrap().to_string();
+                if !path.ends_with(suffix) {
+                    return;
+                }
+                let mut components = relative_path
+                    .iter()
+                    .map(|c| c.to_string_lossy().into_owned())
+                    .collect::<Vec<_>>();
+                let partition = components.pop().unwrap_or_default();
+                files.push(FileResult {
+                    path,
+                    root: root.name.clone(),
+                    vdisk: components.join("/"),
+                    partition,
+                    error: None,
+                });
+            })?;
+        }
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(files)
+    }
+
+    // Broken last line of the interrupted run is skipped, that file is validated again
+    fn load_state(&self) -> AnyResult<HashMap<String, FileResult>> {
+        let mut results = HashMap::new();
+        let path = match &self.state_path {
+            Some(path) if path.exists() => path,
+            _ => return Ok(results),
+        };
+        for line in BufReader::new(File::open(path)?).lines() {
+            match serde_json::from_str::<FileResult>(&line?) {
+                Ok(result) => {
+                    results.insert(result.path.clone(), result);
+                }
+                Err(err) => warn!("[{:?}] skip broken state line: {}", path, err),
+            }
+        }
+        info!(
+            "{} results loaded from state file {:?}",
+            results.len(),
+            path
+        );
+        Ok(results)
+    }
+
+    fn open_state(&self) -> AnyResult<Option<BufWriter<File>>> {
+        self.state_path
+            .as_ref()
+            .map(|path| {
+                let file = OpenOptions::new().create(true).append(true).open(path)?;
+                Ok(BufWriter::new(file))
+            })
+            .transpose()
+    }
+
+    fn write_state(state: &Mutex<BufWriter<File>>, result: &FileResult) -> AnyResult<()> {
+        let mut state = state.lock().expect("mutex");
+        serde_json::to_writer(&mut *state, result)?;
+        writeln!(state)?;
+        state.flush()?;
+        Ok(())
+    }
+}
@@ -7,8 +7,9 @@ include!("alloc/jemalloc.rs");
 
 use bob::{
     build_info::BuildInfo, init_counters, BobApiServer, BobServer, ClusterConfig, NodeConfig, Factory, Grinder,
-    VirtualMapper, BackendType, FactoryTlsConfig,
+    VirtualMapper, BackendType, FactoryTlsConfig, HealthServer, HealthService, FILE_DESCRIPTOR_SET,
 };
+use bob_common::telemetry;
 use bob_access::{Authenticator, BasicAuthenticator, DeclaredCredentials, StubAuthenticator, UsersMap, AuthenticationType};
 use clap::{crate_version, App, Arg, ArgMatches, SubCommand};
 use std::{
@@ -18,6 +19,7 @@ use std::{
 };
 use tokio::runtime::Handle;
 use tonic::transport::Server;
+use tonic_reflection::server::Builder as ReflectionBuilder;
 use qoollo_log4rs_logstash::config::DeserializersExt; 
 use std::path::PathBuf;
 use std::fs::create_dir;
@@ -236,6 +238,9 @@ fn init_testmode_logger(loglevel: log::LevelFilter) {
 
 async fn run_server<A: Authenticator>(node: NodeConfig, authenticator: A, mapper: VirtualMapper, address: IpAddr, port: u16, addr: SocketAddr) {
     let (metrics, shared_metrics) = init_counters(&node, &addr.to_string()).await;
+    if let Err(e) = telemetry::init_tracing(node.tracing(), node.name()) {
+        error!("tracing is not initialized: {}", e);
+    }
     let handle = Handle::current();
     let factory_tls_config = node.tls_config().as_ref().and_then(|tls_config| tls_config.grpc_config())
         .map(|tls_config| {
@@ -245,7 +250,8 @@ async fn run_server<A: Authenticator>(node: NodeConfig, authenticator: A, mapper
                 tls_domain_name: tls_config.domain_name.clone(),
             }
         });
-    let factory = Factory::new(node.operation_timeout(), metrics, node.name().into(), factory_tls_config);
+    let factory = Factory::new(node.operation_timeout(), metrics, node.name().into(), factory_tls_config)
+        .with_connection_pool(node.connection_pool().clone());
 
     let mut server_builder = Server::builder();
     if let Some(node_tls_config) = node.tls_config().as_ref().and_then(|tls_config| tls_config.grpc_config()) {
@@ -265,10 +271,17 @@ async fn run_server<A: Authenticator>(node: NodeConfig, authenticator: A, mapper
     bob.run_periodic_tasks(factory);
     bob.run_api_server(address, port, node.tls_config()).await;
 
+    let health_service = HealthServer::new(HealthService::new(bob.clone()));
     let bob_service = BobApiServer::new(bob);
+    let reflection_service = ReflectionBuilder::configure()
+        .register_encoded_file_descriptor_set(FILE_DESCRIPTOR_SET)
+        .build()
+        .expect("descriptor set is generated by build script");
     server_builder
         .tcp_nodelay(true)
         .add_service(bob_service)
+        .add_service(health_service)
+        .add_service(reflection_service)
         .serve(addr)
         .await
         .unwrap();
@@ -339,6 +352,8 @@ fn spawn_signal_handler<A: Authenticator, TFut: futures::Future<Output = &'stati
         let signal_name = signal_tasks_future.await;
         info!("Got signal '{}'. Shutdown started", signal_name);
         server.shutdown().await;
+        // Batch exporter blocks the thread while sending the rest of spans
+        let _ = tokio::task::spawn_blocking(telemetry::shutdown_tracing).await;
         log::logger().flush();
         std::process::exit(0);
     });
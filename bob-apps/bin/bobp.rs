@@ -1,21 +1,32 @@
+mod benchmark_tool;
+
+use benchmark_tool::{
+    http::{HttpClient, Protocol, TlsSettings},
+    workload::{Operation, Workload},
+};
 use rand::prelude::*;
 
 use bob::{
     Blob, BlobKey, BlobMeta, BobApiClient, ExistRequest, GetOptions, GetRequest, GetSource,
-    PutOptions, PutRequest, DeleteRequest, DeleteOptions,
+    PutOptions, PutRequest, DeleteRequest, DeleteOptions, WriteCondition,
+    ConsistencyLevel,
 };
 
 use clap::{App, Arg, ArgMatches};
+use hdrhistogram::Histogram;
+use serde_derive::Serialize;
 use std::collections::HashMap;
 use std::fmt::{Debug, Formatter, Result as FmtResult};
 use std::fs;
 use std::mem::size_of;
 use std::ops::Sub;
+use std::path::PathBuf;
 use std::str::FromStr;
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::{self, Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::Semaphore;
 use tokio::task::JoinHandle;
 use tokio::time::sleep;
 use tonic::metadata::{Ascii, MetadataValue};
@@ -26,10 +37,13 @@ use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64_ENGINE};
 #[macro_use]
 extern crate log;
 
+const DEFAULT_HTTP_API_PORT: u16 = 8000;
+
 #[derive(Clone)]
 struct NetConfig {
     port: u16,
     target: String,
+    protocol: Protocol,
     ca_cert_path: Option<String>,
     tls_domain_name: Option<String>,
 }
@@ -44,15 +58,51 @@ impl NetConfig {
     }
 
     fn from_matches(matches: &ArgMatches) -> Self {
+        let protocol = matches.value_or_default("protocol");
+        if protocol != Protocol::Grpc && matches.is_present("direct") {
+            panic!("direct requests are available only with grpc protocol");
+        }
+        // REST API listens on its own port
+        let port = if protocol != Protocol::Grpc && matches.occurrences_of("port") == 0 {
+            DEFAULT_HTTP_API_PORT
+        } else {
+            matches.value_or_default("port")
+        };
         Self {
-            port: matches.value_or_default("port"),
+            port,
             target: matches.value_or_default("host"),
+            protocol,
             ca_cert_path: matches.value_of("ca_path").map(|p| p.to_string()),
             tls_domain_name: matches.value_of("domain_name").map(|n| n.to_string()),
         }
     }
 
-    async fn build_client(&self) -> BobApiClient<Channel> {
+    async fn build_client(&self, task_conf: &TaskConfig) -> BenchmarkClient {
+        match self.protocol {
+            Protocol::Grpc => BenchmarkClient::Grpc(self.build_grpc_client().await),
+            Protocol::Rest | Protocol::S3 => {
+                BenchmarkClient::Http(self.build_http_client(task_conf).await)
+            }
+        }
+    }
+
+    async fn build_http_client(&self, task_conf: &TaskConfig) -> HttpClient {
+        let tls = self.ca_cert_path.as_ref().map(|ca_cert_path| TlsSettings {
+            ca_cert_pem: fs::read(ca_cert_path).expect("can not read ca certificate from file"),
+            domain_name: self.tls_domain_name.clone().expect("domain name required"),
+        });
+        HttpClient::new(
+            self.protocol,
+            &self.target,
+            self.port,
+            tls,
+            task_conf.get_basic_authorization(),
+        )
+        .await
+        .expect("can not create http client")
+    }
+
+    async fn build_grpc_client(&self) -> BobApiClient<Channel> {
         let mut endpoint = Endpoint::from(self.get_uri()).tcp_nodelay(true);
         if let Some(ca_cert_path) = &self.ca_cert_path {
             let cert_bin = fs::read(&ca_cert_path).expect("can not read ca certificate from file");
@@ -77,7 +127,7 @@ impl NetConfig {
 
 impl Debug for NetConfig {
     fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
-        write!(f, "{}:{}", self.target, self.port)
+        write!(f, "{}:{} ({:?})", self.target, self.port, self.protocol)
     }
 }
 
@@ -153,6 +203,7 @@ impl TaskConfig {
             Some(GetOptions {
                 force_node: true,
                 source: GetSource::Normal as i32,
+                consistency: ConsistencyLevel::Default as i32,
             })
         } else {
             None
@@ -165,6 +216,9 @@ impl TaskConfig {
                 remote_nodes: vec![],
                 force_node: true,
                 overwrite: false,
+                condition: WriteCondition::None as i32,
+                condition_timestamps: vec![],
+                consistency: ConsistencyLevel::Default as i32,
             })
         } else {
             None
@@ -178,6 +232,9 @@ impl TaskConfig {
                 force_node: true,
                 is_alien: false,
                 force_alien_nodes: vec![],
+                condition: WriteCondition::None as i32,
+                condition_timestamps: vec![],
+                consistency: ConsistencyLevel::Default as i32,
             })
         } else {
             None
@@ -198,20 +255,26 @@ impl TaskConfig {
         data
     }
 
-    fn get_request_creator<T>(&self) -> impl Fn(T) -> Request<T> {
-        let do_basic_auth = self.basic_username.is_some() && self.basic_password.is_some();
-        let basic_username = self.basic_username.clone().unwrap_or_default();
-        let basic_password = self.basic_password.clone().unwrap_or_default();
+    fn get_basic_authorization(&self) -> Option<String> {
+        match (&self.basic_username, &self.basic_password) {
+            (Some(username), Some(password)) => {
+                let credentials = BASE64_ENGINE.encode(format!("{}:{}", username, password));
+                Some(format!("Basic {}", credentials))
+            }
+            _ => None,
+        }
+    }
 
-        let credentials = format!("{}:{}", basic_username, basic_password);
-        let credentials = BASE64_ENGINE.encode(credentials);
-        let authorization = format!("Basic {}", credentials)
-            .parse::<MetadataValue<Ascii>>()
-            .expect("can not parse authorization value");
+    fn get_request_creator<T>(&self) -> impl Fn(T) -> Request<T> {
+        let authorization = self.get_basic_authorization().map(|authorization| {
+            authorization
+                .parse::<MetadataValue<Ascii>>()
+                .expect("can not parse authorization value")
+        });
 
         move |a| {
             let mut request = Request::new(a);
-            if do_basic_auth {
+            if let Some(authorization) = &authorization {
                 let req_md = request.metadata_mut();
                 req_md.insert("authorization", authorization.clone());
             }
@@ -266,6 +329,7 @@ struct PeriodicStatisticsBit {
     count_spd: u64,
     error: u64,
     latency: f64,
+    percentiles: LatencyPercentiles,
 }
 
 struct PeriodicOperationStatistics<'a> {
@@ -298,6 +362,7 @@ impl<'a> PeriodicOperationStatistics<'a> {
             count_spd,
             error,
             latency,
+            percentiles: self.stat.take_period_percentiles(),
         }
     }
 }
@@ -318,8 +383,8 @@ impl<'a> PeriodicPutStatistics<'a> {
     fn process_and_print(&mut self) -> f64 {
         let bit = self.common.current_bit();
         let spd = bit.count_spd as f64 * self.request_size_kb;
-        println!("put:   {:>6} rps | err {:5} | {:>6.2} kb/s | lat {:>6.2} ms", 
-            bit.count_spd, bit.error, spd, bit.latency);
+        println!("put:   {:>6} rps | err {:5} | {:>6.2} kb/s | lat {:>6.2} ms | {}", 
+            bit.count_spd, bit.error, spd, bit.latency, bit.percentiles);
         spd
     }
 }
@@ -340,8 +405,8 @@ impl<'a> PeriodicDeleteStatistics<'a> {
     fn process_and_print(&mut self) -> f64 {
         let bit = self.common.current_bit();
         let spd = bit.count_spd as f64 * self.request_size_kb;
-        println!("delete:{:>6} rps | err {:5} | {:>6.2} kb/s | lat {:>6.2} ms", 
-            bit.count_spd, bit.error, spd, bit.latency);
+        println!("delete:{:>6} rps | err {:5} | {:>6.2} kb/s | lat {:>6.2} ms | {}", 
+            bit.count_spd, bit.error, spd, bit.latency, bit.percentiles);
         spd
     }
 }
@@ -364,8 +429,8 @@ impl<'a> PeriodicGetStatistics<'a> {
     fn process_and_print(&mut self) -> f64 {
         let bit = self.common.current_bit();
         let spd = self.size.get_diff() as f64 / 1024. / self.period_s;
-        println!("get:   {:>6} rps | err {:5} | {:>6.2} kb/s | lat {:>6.2} ms", 
-            bit.count_spd, bit.error, spd, bit.latency);
+        println!("get:   {:>6} rps | err {:5} | {:>6.2} kb/s | lat {:>6.2} ms | {}", 
+            bit.count_spd, bit.error, spd, bit.latency, bit.percentiles);
         spd
     }
 }
@@ -388,14 +453,75 @@ impl<'a> PeriodicExistStatistics<'a> {
     fn process_and_print(&mut self) -> f64 {
         let bit = self.common.current_bit();
         let spd = self.size.get_diff() as f64 / 1024.0 / self.period_s;
-        println!("exist: {:>6} rps | err {:5} | {:>6.2} kb/s | lat {:>6.2} ms", 
-            bit.count_spd, bit.error, spd, bit.latency);
+        println!("exist: {:>6} rps | err {:5} | {:>6.2} kb/s | lat {:>6.2} ms | {}", 
+            bit.count_spd, bit.error, spd, bit.latency, bit.percentiles);
         spd
     }
 }
 
 type CodeRepresentation = i32; // Because tonic::Code does not implement hash
 
+// Latencies are recorded in microseconds, longer requests are saturated to the highest value
+const LATENCY_HIGHEST_US: u64 = 60_000_000;
+const LATENCY_SIGNIFICANT_DIGITS: u8 = 3;
+
+struct LatencyHistograms {
+    total: Histogram<u64>,
+    // Reset after every periodic output
+    period: Histogram<u64>,
+}
+
+impl Default for LatencyHistograms {
+    fn default() -> Self {
+        let histogram =
+            Histogram::new_with_bounds(1, LATENCY_HIGHEST_US, LATENCY_SIGNIFICANT_DIGITS)
+                .expect("latency histogram bounds");
+        Self {
+            total: histogram.clone(),
+            period: histogram,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+struct LatencyPercentiles {
+    min: f64,
+    mean: f64,
+    p50: f64,
+    p90: f64,
+    p99: f64,
+    p999: f64,
+    max: f64,
+}
+
+impl LatencyPercentiles {
+    fn from_histogram(histogram: &Histogram<u64>) -> Self {
+        if histogram.is_empty() {
+            return Self::default();
+        }
+        let ms = |us: u64| us as f64 / 1000.;
+        Self {
+            min: ms(histogram.min()),
+            mean: histogram.mean() / 1000.,
+            p50: ms(histogram.value_at_quantile(0.5)),
+            p90: ms(histogram.value_at_quantile(0.9)),
+            p99: ms(histogram.value_at_quantile(0.99)),
+            p999: ms(histogram.value_at_quantile(0.999)),
+            max: ms(histogram.max()),
+        }
+    }
+}
+
+impl std::fmt::Display for LatencyPercentiles {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(
+            f,
+            "p50 {:>6.2} | p99 {:>6.2} | p999 {:>6.2} ms",
+            self.p50, self.p99, self.p999
+        )
+    }
+}
+
 #[derive(Default)]
 struct OperationStatistics {
     total: AtomicU64,
@@ -403,6 +529,7 @@ struct OperationStatistics {
     time_ns_st: AtomicU64,
     count_st: AtomicU64,
     errors: Mutex<HashMap<CodeRepresentation, u64>>,
+    latencies: Mutex<LatencyHistograms>,
 }
 
 impl OperationStatistics {
@@ -417,12 +544,57 @@ impl OperationStatistics {
         debug!("{}", status.message())
     }
 
+    fn save_time(&self, duration: &Duration, single_thread: bool) {
+        let us = duration.as_micros() as u64;
+        {
+            let mut latencies = self.latencies.lock().expect("mutex");
+            latencies.total.saturating_record(us);
+            latencies.period.saturating_record(us);
+        }
+        if single_thread {
+            self.save_single_thread_time(duration);
+        }
+    }
+
     fn save_single_thread_time(&self, duration: &Duration) {
         self.time_ns_st
             .fetch_add(duration.as_nanos() as u64, Ordering::Relaxed);
         self.count_st.fetch_add(1, Ordering::Relaxed);
     }
 
+    fn take_period_percentiles(&self) -> LatencyPercentiles {
+        let mut latencies = self.latencies.lock().expect("mutex");
+        let percentiles = LatencyPercentiles::from_histogram(&latencies.period);
+        latencies.period.reset();
+        percentiles
+    }
+
+    fn get_total_percentiles(&self) -> LatencyPercentiles {
+        LatencyPercentiles::from_histogram(&self.latencies.lock().expect("mutex").total)
+    }
+
+    fn print_percentiles(&self, operation_name: &str) {
+        let p = self.get_total_percentiles();
+        println!(
+            "{} latency: min {:.2} | mean {:.2} | p50 {:.2} | p90 {:.2} | p99 {:.2} | p999 {:.2} | max {:.2} ms",
+            operation_name, p.min, p.mean, p.p50, p.p90, p.p99, p.p999, p.max
+        );
+    }
+
+    fn get_report(&self, operation: &'static str, kb_per_sec: f64, elapsed: Duration) -> OperationReport {
+        let count = self.total.load(Ordering::Relaxed);
+        OperationReport {
+            operation,
+            count,
+            errors: self.error_count.load(Ordering::Relaxed),
+            rps: (count * 1000)
+                .checked_div(elapsed.as_millis() as u64)
+                .unwrap_or_default(),
+            kb_per_sec,
+            latency_ms: self.get_total_percentiles(),
+        }
+    }
+
     fn print_errors(&self, operation_name: &str) {
         let guard = self.errors.lock().expect("mutex");
         if !guard.is_empty() {
@@ -499,6 +671,8 @@ struct BenchmarkConfig {
     time: Option<Duration>,
     request_amount_bytes: u64,
     keys_count: u64,
+    report: Option<ReportConfig>,
+    workload: Option<Arc<Workload>>,
 }
 
 #[derive(Debug)]
@@ -509,6 +683,7 @@ enum Behavior {
     Delete,
     Test,
     PingPong,
+    Workload,
 }
 
 impl FromStr for Behavior {
@@ -529,23 +704,134 @@ impl FromStr for Behavior {
 
 impl BenchmarkConfig {
     fn from_matches(matches: &ArgMatches) -> Self {
-        Self {
-            workers_count: matches.value_or_default("threads"),
-            behavior: matches
+        let workload = matches.value_of("workload").map(|path| {
+            let workload = Workload::from_file(
+                path,
+                matches.value_or_default("first"),
+                matches.value_or_default("count"),
+                matches.value_or_default("payload"),
+            )
+            .unwrap_or_else(|e| panic!("can't load workload spec {}: {}", path, e));
+            Arc::new(workload)
+        });
+        let behavior = if workload.is_some() {
+            Behavior::Workload
+        } else {
+            matches
                 .value_of("behavior")
                 .unwrap()
                 .parse()
-                .expect("incorrect behavior"),
+                .expect("incorrect behavior")
+        };
+        Self {
+            workers_count: matches.value_or_default("threads"),
+            behavior,
             statistics: Arc::new(Statistics::default()),
             time: matches
                 .value_of("time")
                 .map(|t| Duration::from_secs(t.parse().expect("error parsing time"))),
-            request_amount_bytes: matches.value_or_default("payload"),
+            request_amount_bytes: workload
+                .as_ref()
+                .map_or_else(|| matches.value_or_default("payload"), |w| w.payload_mean()),
             keys_count: matches.value_or_default("count"),
+            report: ReportConfig::from_matches(matches),
+            workload,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum ReportFormat {
+    Json,
+    Csv,
+}
+
+impl FromStr for ReportFormat {
+    type Err = &'static str;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(ReportFormat::Json),
+            "csv" => Ok(ReportFormat::Csv),
+            _ => Err("Failed to parse report format, only 'json' and 'csv' available"),
         }
     }
 }
 
+#[derive(Clone, Debug)]
+struct ReportConfig {
+    path: PathBuf,
+    format: ReportFormat,
+    behavior: String,
+    workers_count: u64,
+}
+
+impl ReportConfig {
+    fn from_matches(matches: &ArgMatches) -> Option<Self> {
+        let path = matches.value_of("report")?;
+        Some(Self {
+            path: PathBuf::from(path),
+            format: matches.value_or_default("report_format"),
+            behavior: if matches.is_present("workload") {
+                "workload".to_string()
+            } else {
+                matches.value_or_default("behavior")
+            },
+            workers_count: matches.value_or_default("threads"),
+        })
+    }
+
+    fn write(&self, report: &Report) -> Result<(), Box<dyn std::error::Error>> {
+        let content = match self.format {
+            ReportFormat::Json => serde_json::to_string_pretty(report)?,
+            ReportFormat::Csv => report.to_csv(),
+        };
+        fs::write(&self.path, content)?;
+        Ok(())
+    }
+}
+
+/// Final results of the benchmark, saved with `--report` for comparison between runs.
+#[derive(Serialize)]
+struct Report {
+    behavior: String,
+    workers_count: u64,
+    payload_size: u64,
+    elapsed_ms: u64,
+    total_count: u64,
+    total_errors: u64,
+    operations: Vec<OperationReport>,
+}
+
+#[derive(Serialize)]
+struct OperationReport {
+    operation: &'static str,
+    count: u64,
+    errors: u64,
+    rps: u64,
+    kb_per_sec: f64,
+    latency_ms: LatencyPercentiles,
+}
+
+impl Report {
+    const CSV_HEADER: &'static str = "behavior,workers_count,payload_size,elapsed_ms,operation,count,errors,rps,kb_per_sec,\
+        latency_min_ms,latency_mean_ms,latency_p50_ms,latency_p90_ms,latency_p99_ms,latency_p999_ms,latency_max_ms";
+
+    // One row per operation, benchmark parameters are repeated in every row
+    fn to_csv(&self) -> String {
+        let mut csv = format!("{}\n", Self::CSV_HEADER);
+        for op in &self.operations {
+            let l = &op.latency_ms;
+            csv.push_str(&format!(
+                "{},{},{},{},{},{},{},{},{:.3},{:.3},{:.3},{:.3},{:.3},{:.3},{:.3},{:.3}\n",
+                self.behavior, self.workers_count, self.payload_size, self.elapsed_ms,
+                op.operation, op.count, op.errors, op.rps, op.kb_per_sec,
+                l.min, l.mean, l.p50, l.p90, l.p99, l.p999, l.max
+            ));
+        }
+        csv
+    }
+}
+
 impl Debug for BenchmarkConfig {
     fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
         write!(
@@ -621,10 +907,36 @@ async fn stat_worker(
     request_bytes: u64,
     keys_count: u64,
     behavior_flags: u8,
+    report_conf: Option<ReportConfig>,
 ) {
     let (put_speed_values, get_speed_values, exist_speed_values, delete_speed_values, elapsed) =
         print_periodic_stat(stop_token, period_ms, &stat, request_bytes, behavior_flags);
     print_averages(&stat, &put_speed_values, &get_speed_values, &exist_speed_values, &delete_speed_values, elapsed, keys_count, behavior_flags);
+    if let Some(report_conf) = report_conf {
+        let operations = [
+            (PUT_FLAG, "put", &stat.put.common, &put_speed_values),
+            (GET_FLAG, "get", &stat.get.common, &get_speed_values),
+            (DELETE_FLAG, "delete", &stat.delete.common, &delete_speed_values),
+            (EXIST_FLAG, "exist", &stat.exist.common, &exist_speed_values),
+        ]
+        .into_iter()
+        .filter(|(flag, ..)| (behavior_flags & flag) > 0)
+        .map(|(_, name, op_stat, speed_values)| op_stat.get_report(name, average(speed_values), elapsed))
+        .collect();
+        let report = Report {
+            behavior: report_conf.behavior.clone(),
+            workers_count: report_conf.workers_count,
+            payload_size: request_bytes,
+            elapsed_ms: elapsed.as_millis() as u64,
+            total_count: stat.get_total_op_count(),
+            total_errors: stat.get_total_error_count(),
+            operations,
+        };
+        match report_conf.write(&report) {
+            Ok(()) => println!("report is saved to {}", report_conf.path.display()),
+            Err(e) => eprintln!("error saving report to {}: {}", report_conf.path.display(), e),
+        }
+    }
     print_errors_with_codes(stat).await
 }
 
@@ -654,6 +966,7 @@ fn print_averages(
     if (behavior_flags & PUT_FLAG) > 0 {
         let put_resp_time = stat.put.common.get_average_response_time();
         println!("put: {:>6.2} kb/s | resp time {:>6.2} ms", average(put_speed_values), put_resp_time);
+        stat.put.common.print_percentiles("put");
 
         if verify {
             println!(
@@ -665,10 +978,12 @@ fn print_averages(
     if (behavior_flags & GET_FLAG) > 0 {
         let get_resp_time = stat.get.common.get_average_response_time();
         println!("get: {:>6.2} kb/s | resp time {:>6.2} ms", average(get_speed_values), get_resp_time);
+        stat.get.common.print_percentiles("get");
     }
     if (behavior_flags & DELETE_FLAG) > 0 {
         let delete_resp_time = stat.delete.common.get_average_response_time();
         println!("delete: {:>6.2} kb/s | resp time {:>6.2} ms", average(delete_speed_values), delete_resp_time);
+        stat.delete.common.print_percentiles("delete");
 
         if verify {
             println!(
@@ -682,6 +997,7 @@ fn print_averages(
         let present_keys = stat.exist.presented_keys.load(Ordering::Relaxed);
         println!("exist: {:>6.2} kb/s | resp time {:>6.2} ms | {} of {} keys present", 
             average(exist_speed_values), exist_resp_time, present_keys, keys_count);
+        stat.exist.common.print_percentiles("exist");
     }
 }
 
@@ -764,10 +1080,93 @@ fn finite_or_default(value: f64) -> f64 {
     }
 }
 
+/// Client of the benchmarked API, gRPC requests are built from the task config.
+#[derive(Clone)]
+enum BenchmarkClient {
+    Grpc(BobApiClient<Channel>),
+    Http(HttpClient),
+}
+
+impl BenchmarkClient {
+    /// Returns the size of the received data.
+    async fn get(&mut self, task_conf: &TaskConfig, key: u64) -> Result<u64, Status> {
+        match self {
+            Self::Grpc(client) => {
+                let request = task_conf.get_request_creator()(GetRequest {
+                    key: Some(BlobKey {
+                        key: task_conf.get_proper_key(key),
+                    }),
+                    options: task_conf.find_get_options(),
+                });
+                let response = client.get(request).await?;
+                Ok(response.into_inner().data.len() as u64)
+            }
+            Self::Http(client) => client.get(key).await,
+        }
+    }
+
+    async fn put(&mut self, task_conf: &TaskConfig, key: u64, blob: Blob) -> Result<(), Status> {
+        match self {
+            Self::Grpc(client) => {
+                let request = task_conf.get_request_creator()(PutRequest {
+                    key: Some(BlobKey {
+                        key: task_conf.get_proper_key(key),
+                    }),
+                    data: Some(blob),
+                    options: task_conf.find_put_options(),
+                });
+                client.put(request).await.map(|_| ())
+            }
+            Self::Http(client) => client.put(key, blob.data).await,
+        }
+    }
+
+    /// HTTP APIs check one key per request, so keys are checked one by one.
+    async fn exist(&mut self, task_conf: &TaskConfig, keys: &[u64]) -> Result<Vec<bool>, Status> {
+        match self {
+            Self::Grpc(client) => {
+                let request = task_conf.get_request_creator()(ExistRequest {
+                    keys: keys
+                        .iter()
+                        .map(|key| BlobKey {
+                            key: task_conf.get_proper_key(*key),
+                        })
+                        .collect(),
+                    options: task_conf.find_get_options(),
+                });
+                let response = client.exist(request).await?;
+                Ok(response.into_inner().exist)
+            }
+            Self::Http(client) => {
+                let mut exist = Vec::with_capacity(keys.len());
+                for key in keys {
+                    exist.push(client.exist(*key).await?);
+                }
+                Ok(exist)
+            }
+        }
+    }
+
+    async fn delete(&mut self, task_conf: &TaskConfig, key: u64) -> Result<(), Status> {
+        match self {
+            Self::Grpc(client) => {
+                let request = task_conf.get_request_creator()(DeleteRequest {
+                    key: Some(BlobKey {
+                        key: task_conf.get_proper_key(key),
+                    }),
+                    options: task_conf.find_delete_options(),
+                    meta: Some(create_current_blobmeta()),
+                });
+                client.delete(request).await.map(|_| ())
+            }
+            Self::Http(client) => client.delete(key).await,
+        }
+    }
+}
+
 async fn get_worker(net_conf: NetConfig, task_conf: TaskConfig, stat: Arc<GetStatistics>) {
-    let mut client = net_conf.build_client().await;
+    let mut client = net_conf.build_client(&task_conf).await;
 
-    let options = task_conf.find_get_options();
     let upper_idx = task_conf.low_idx + task_conf.count;
     let measure_time = task_conf.is_time_measurement_thread();
     let iterator: Box<dyn Send + Iterator<Item = u64>> = if task_conf.is_random() {
@@ -778,29 +1177,15 @@ async fn get_worker(net_conf: NetConfig, task_conf: TaskConfig, stat: Arc<GetSta
         Box::new(task_conf.low_idx..upper_idx)
     };
 
-    let request_creator = task_conf.get_request_creator::<GetRequest>();
     for key in iterator {
-        let request = request_creator(GetRequest {
-            key: Some(BlobKey {
-                key: task_conf.get_proper_key(key),
-            }),
-            options: options.clone(),
-        });
-
-        let res = if measure_time {
-            let start = Instant::now();
-            let res = client.get(request).await;
-            stat.common.save_single_thread_time(&start.elapsed());
-            res
-        } else {
-            client.get(request).await
-        };
+        let start = Instant::now();
+        let res = client.get(&task_conf, key).await;
+        stat.common.save_time(&start.elapsed(), measure_time);
         match res {
             Err(status) => stat.common.save_error(status).await,
-            Ok(payload) => {
-                let res = payload.into_inner().data;
+            Ok(size) => {
                 stat.size_bytes
-                    .fetch_add(res.len() as u64, Ordering::SeqCst);
+                    .fetch_add(size, Ordering::SeqCst);
             }
         }
         stat.common.total.fetch_add(1, Ordering::SeqCst);
@@ -808,51 +1193,25 @@ async fn get_worker(net_conf: NetConfig, task_conf: TaskConfig, stat: Arc<GetSta
 }
 
 async fn put_worker(net_conf: NetConfig, task_conf: TaskConfig, stat: Arc<PutStatistics>) {
-    let mut client = net_conf.build_client().await;
-
-    let request_creator = task_conf.get_request_creator::<PutRequest>();
+    let mut client = net_conf.build_client(&task_conf).await;
 
-    let options: Option<PutOptions> = task_conf.find_put_options();
     let measure_time = task_conf.is_time_measurement_thread();
     let upper_idx = task_conf.low_idx + task_conf.count;
     for i in task_conf.low_idx..upper_idx {
         let blob = create_blob(&task_conf);
-        let key = BlobKey {
-            key: task_conf.get_proper_key(i),
-        };
-        let request = request_creator(PutRequest {
-            key: Some(key),
-            data: Some(blob),
-            options: options.clone(),
-        });
-
-        let res = if measure_time {
-            let start = Instant::now();
-            let res = client.put(request).await;
-            stat.common.save_single_thread_time(&start.elapsed());
-            res
-        } else {
-            client.put(request).await
-        };
+        let start = Instant::now();
+        let res = client.put(&task_conf, i, blob).await;
+        stat.common.save_time(&start.elapsed(), measure_time);
         if let Err(status) = res {
             stat.common.save_error(status).await;
         }
         stat.common.total.fetch_add(1, Ordering::SeqCst);
     }
     if get_matches().is_present("verify") {
-        let request_creator = task_conf.get_request_creator::<ExistRequest>();
-        let req = request_creator(
-            ExistRequest {
-                keys: (task_conf.low_idx..upper_idx)
-                    .map(|i| BlobKey {
-                        key: task_conf.get_proper_key(i),
-                    })
-                    .collect(),
-                options: task_conf.find_get_options(),
-            });
-        let res = client.exist(req).await;
+        let keys: Vec<_> = (task_conf.low_idx..upper_idx).collect();
+        let res = client.exist(&task_conf, &keys).await;
         if let Ok(res) = res {
-            if res.into_inner().exist.iter().all(|b| *b) {
+            if res.iter().all(|b| *b) {
                 stat.verified.fetch_add(1, Ordering::SeqCst);
             }
         }
@@ -860,50 +1219,24 @@ async fn put_worker(net_conf: NetConfig, task_conf: TaskConfig, stat: Arc<PutSta
 }
 
 async fn delete_worker(net_conf: NetConfig, task_conf: TaskConfig, stat: Arc<DeleteStatistics>) {
-    let mut client = net_conf.build_client().await;
+    let mut client = net_conf.build_client(&task_conf).await;
 
-    let request_creator = task_conf.get_request_creator::<DeleteRequest>();
-
-    let options = task_conf.find_delete_options();
     let measure_time = task_conf.is_time_measurement_thread();
     let upper_idx = task_conf.low_idx + task_conf.count;
     for i in task_conf.low_idx..upper_idx {
-        let key = BlobKey {
-            key: task_conf.get_proper_key(i),
-        };
-        let request = request_creator(DeleteRequest {
-            key: Some(key),
-            options: options.clone(),
-            meta: Some(create_current_blobmeta()),
-        });
-
-        let res = if measure_time {
-            let start = Instant::now();
-            let res = client.delete(request).await;
-            stat.common.save_single_thread_time(&start.elapsed());
-            res
-        } else {
-            client.delete(request).await
-        };
+        let start = Instant::now();
+        let res = client.delete(&task_conf, i).await;
+        stat.common.save_time(&start.elapsed(), measure_time);
         if let Err(status) = res {
             stat.common.save_error(status).await;
         }
         stat.common.total.fetch_add(1, Ordering::SeqCst);
     }
     if get_matches().is_present("verify") {
-        let request_creator = task_conf.get_request_creator::<ExistRequest>();
-        let req = request_creator(
-            ExistRequest {
-                keys: (task_conf.low_idx..upper_idx)
-                    .map(|i| BlobKey {
-                        key: task_conf.get_proper_key(i),
-                    })
-                    .collect(),
-                options: task_conf.find_get_options(),
-            });
-        let res = client.exist(req).await;
+        let keys: Vec<_> = (task_conf.low_idx..upper_idx).collect();
+        let res = client.exist(&task_conf, &keys).await;
         if let Ok(res) = res {
-            if res.into_inner().exist.iter().all(|b| !(*b)) {
+            if res.iter().all(|b| !(*b)) {
                 stat.verified.fetch_add(1, Ordering::SeqCst);
             }
         }
@@ -911,11 +1244,8 @@ async fn delete_worker(net_conf: NetConfig, task_conf: TaskConfig, stat: Arc<Del
 }
 
 async fn exist_worker(net_conf: NetConfig, task_conf: TaskConfig, stat: Arc<ExistStatistics>) {
-    let mut client = net_conf.build_client().await;
-
-    let request_creator = task_conf.get_request_creator::<ExistRequest>();
+    let mut client = net_conf.build_client(&task_conf).await;
 
-    let options = task_conf.find_get_options();
     let send_size_bytes = task_conf.packet_size * (task_conf.key_size as u64);
     let measure_time = task_conf.is_time_measurement_thread();
 
@@ -923,30 +1253,14 @@ async fn exist_worker(net_conf: NetConfig, task_conf: TaskConfig, stat: Arc<Exis
     let iterator = Box::new(keys.into_iter());
 
     for portion in iterator {
-        let keys = portion
-            .iter()
-            .map(|key| BlobKey {
-                key: task_conf.get_proper_key(*key),
-            })
-            .collect();
-        let request = request_creator(ExistRequest {
-            keys,
-            options: options.clone(),
-        });
-        let res = if measure_time {
-            let start = Instant::now();
-            let res = client.exist(request).await;
-            stat.common.save_single_thread_time(&start.elapsed());
-            res
-        } else {
-            client.exist(request).await
-        };
+        let start = Instant::now();
+        let res = client.exist(&task_conf, &portion).await;
+        stat.common.save_time(&start.elapsed(), measure_time);
         stat.size_bytes
                     .fetch_add(send_size_bytes, Ordering::SeqCst);
         match res {
             Err(status) => stat.common.save_error(status).await,
-            Ok(payload) => {
-                let res = payload.into_inner().exist;
+            Ok(res) => {
                 stat.size_bytes
                     .fetch_add((res.len() * size_of::<bool>()) as u64, Ordering::SeqCst);
 
@@ -967,49 +1281,22 @@ async fn test_worker(net_conf: NetConfig, task_conf: TaskConfig, stat: Arc<Stati
 }
 
 async fn ping_pong_worker(net_conf: NetConfig, task_conf: TaskConfig, stat: Arc<Statistics>) {
-    let mut client = net_conf.build_client().await;
+    let mut client = net_conf.build_client(&task_conf).await;
 
-    let get_request_creator = task_conf.get_request_creator::<GetRequest>();
-    let put_request_creator = task_conf.get_request_creator::<PutRequest>();
-
-    let get_options = task_conf.find_get_options();
-    let put_options = task_conf.find_put_options();
     let measure_time = task_conf.is_time_measurement_thread();
     let upper_idx = task_conf.low_idx + task_conf.count;
     for i in task_conf.low_idx..upper_idx {
         let blob = create_blob(&task_conf);
-        let key = BlobKey {
-            key: task_conf.get_proper_key(i),
-        };
-        let put_request = put_request_creator(PutRequest {
-            key: Some(key.clone()),
-            data: Some(blob),
-            options: put_options.clone(),
-        });
-        let put_res = if measure_time {
-            let start = Instant::now();
-            let res = client.put(put_request).await;
-            stat.put.common.save_single_thread_time(&start.elapsed());
-            res
-        } else {
-            client.put(put_request).await
-        };
+        let start = Instant::now();
+        let put_res = client.put(&task_conf, i, blob).await;
+        stat.put.common.save_time(&start.elapsed(), measure_time);
         if let Err(status) = put_res {
             stat.put.common.save_error(status).await;
         }
         stat.put.common.total.fetch_add(1, Ordering::SeqCst);
-        let get_request = get_request_creator(GetRequest {
-            key: Some(key),
-            options: get_options.clone(),
-        });
-        let get_res = if measure_time {
-            let start = Instant::now();
-            let res = client.get(get_request).await;
-            stat.get.common.save_single_thread_time(&start.elapsed());
-            res
-        } else {
-            client.get(get_request).await
-        };
+        let start = Instant::now();
+        let get_res = client.get(&task_conf, i).await;
+        stat.get.common.save_time(&start.elapsed(), measure_time);
         if let Err(status) = get_res {
             stat.get.common.save_error(status).await;
         }
@@ -1017,11 +1304,157 @@ async fn ping_pong_worker(net_conf: NetConfig, task_conf: TaskConfig, stat: Arc<
     }
 }
 
+/// Sends one request of the workload, latency is measured from `start`.
+async fn send_workload_request(
+    client: &mut BenchmarkClient,
+    task_conf: &TaskConfig,
+    workload: &Workload,
+    stat: &Statistics,
+    start: Instant,
+    measure_time: bool,
+) {
+    let (operation, key, payload_size) = {
+        let mut rng = thread_rng();
+        let operation = workload.next_operation(&mut rng);
+        let key = if operation == Operation::Put {
+            workload.next_put_key(&mut rng)
+        } else {
+            workload.next_key(&mut rng)
+        };
+        (operation, key, workload.next_payload_size(&mut rng))
+    };
+    match operation {
+        Operation::Get => {
+            let res = client.get(task_conf, key).await;
+            stat.get.common.save_time(&start.elapsed(), measure_time);
+            match res {
+                Err(status) => stat.get.common.save_error(status).await,
+                Ok(size) => {
+                    stat.get.size_bytes.fetch_add(size, Ordering::SeqCst);
+                }
+            }
+            stat.get.common.total.fetch_add(1, Ordering::SeqCst);
+        }
+        Operation::Put => {
+            let res = client.put(task_conf, key, create_blob_of_size(payload_size)).await;
+            stat.put.common.save_time(&start.elapsed(), measure_time);
+            if let Err(status) = res {
+                stat.put.common.save_error(status).await;
+            }
+            stat.put.common.total.fetch_add(1, Ordering::SeqCst);
+        }
+        Operation::Exist => {
+            let res = client.exist(task_conf, &[key]).await;
+            stat.exist.common.save_time(&start.elapsed(), measure_time);
+            stat.exist.size_bytes
+                .fetch_add(task_conf.key_size as u64, Ordering::SeqCst);
+            match res {
+                Err(status) => stat.exist.common.save_error(status).await,
+                Ok(res) => {
+                    stat.exist.size_bytes
+                        .fetch_add((res.len() * size_of::<bool>()) as u64, Ordering::SeqCst);
+                    let present = res.iter().fold(0, |acc, flag| acc + u64::from(*flag));
+                    stat.exist.presented_keys
+                        .fetch_add(present, Ordering::SeqCst);
+                }
+            }
+            stat.exist.common.total.fetch_add(1, Ordering::SeqCst);
+        }
+        Operation::Delete => {
+            let res = client.delete(task_conf, key).await;
+            stat.delete.common.save_time(&start.elapsed(), measure_time);
+            if let Err(status) = res {
+                stat.delete.common.save_error(status).await;
+            }
+            stat.delete.common.total.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+}
+
+/// Closed-loop worker: the next request of the workload is sent after the previous one is done.
+async fn workload_worker(
+    net_conf: NetConfig,
+    task_conf: TaskConfig,
+    workload: Arc<Workload>,
+    stat: Arc<Statistics>,
+) {
+    let mut client = net_conf.build_client(&task_conf).await;
+    let measure_time = task_conf.is_time_measurement_thread();
+    for _ in 0..task_conf.count {
+        send_workload_request(&mut client, &task_conf, &workload, &stat, Instant::now(), measure_time).await;
+    }
+}
+
+/// Skipped request never completes: it is counted as `ResourceExhausted` error with the highest
+/// latency, so overload shows up in error count and percentiles instead of being hidden.
+async fn save_skipped_request(workload: &Workload, stat: &Statistics) {
+    let operation = workload.next_operation(&mut thread_rng());
+    let common = match operation {
+        Operation::Get => &stat.get.common,
+        Operation::Put => &stat.put.common,
+        Operation::Exist => &stat.exist.common,
+        Operation::Delete => &stat.delete.common,
+    };
+    common.save_time(&Duration::from_micros(LATENCY_HIGHEST_US), false);
+    common
+        .save_error(Status::resource_exhausted("max_in_flight requests are in flight"))
+        .await;
+    common.total.fetch_add(1, Ordering::SeqCst);
+}
+
+/// Open-loop worker: requests are sent at the workload rate regardless of responses.
+async fn open_loop_workload_worker(
+    net_conf: NetConfig,
+    task_conf: TaskConfig,
+    workload: Arc<Workload>,
+    stat: Arc<Statistics>,
+    rate: f64,
+) {
+    let client = net_conf.build_client(&task_conf).await;
+    let max_in_flight = workload.max_in_flight();
+    let in_flight = Arc::new(Semaphore::new(max_in_flight));
+    let mut interval = tokio::time::interval(Duration::from_secs_f64(1. / rate));
+    let mut skipped = 0;
+    for _ in 0..task_conf.count {
+        // Latency is measured from the scheduled time, so queueing on the client is included
+        let scheduled = interval.tick().await.into_std();
+        let permit = match in_flight.clone().try_acquire_owned() {
+            Ok(permit) => permit,
+            Err(_) => {
+                skipped += 1;
+                save_skipped_request(&workload, &stat).await;
+                continue;
+            }
+        };
+        let mut client = client.clone();
+        let (task_conf, workload, stat) = (task_conf.clone(), workload.clone(), stat.clone());
+        tokio::spawn(async move {
+            send_workload_request(&mut client, &task_conf, &workload, &stat, scheduled, true).await;
+            drop(permit);
+        });
+    }
+    let _ = in_flight.acquire_many(max_in_flight as u32).await;
+    if skipped > 0 {
+        println!("{} requests skipped, {} requests were in flight", skipped, max_in_flight);
+    }
+}
+
 fn spawn_workers(
     net_conf: &NetConfig,
     task_conf: &TaskConfig,
     benchmark_conf: &BenchmarkConfig,
 ) -> Vec<tokio::task::JoinHandle<()>> {
+    if let Some(workload) = &benchmark_conf.workload {
+        if let Some(rate) = workload.rate() {
+            return vec![tokio::spawn(open_loop_workload_worker(
+                net_conf.clone(),
+                task_conf.clone(),
+                workload.clone(),
+                benchmark_conf.statistics.clone(),
+                rate,
+            ))];
+        }
+    }
     let task_size = task_conf.count / benchmark_conf.workers_count;
     (0..benchmark_conf.workers_count)
         .map(|i| {
@@ -1052,6 +1485,10 @@ fn spawn_workers(
                 Behavior::Delete => tokio::spawn(delete_worker(nc, tc, stat_inner.delete.clone())),
                 Behavior::Test => tokio::spawn(test_worker(nc, tc, stat_inner)),
                 Behavior::PingPong => tokio::spawn(ping_pong_worker(nc, tc, stat_inner)),
+                Behavior::Workload => {
+                    let workload = benchmark_conf.workload.clone().expect("workload spec");
+                    tokio::spawn(workload_worker(nc, tc, workload, stat_inner))
+                }
             }
         })
         .collect()
@@ -1093,6 +1530,17 @@ fn spawn_statistics_thread(
             behavior_flags |= EXIST_FLAG;
             behavior_flags |= DELETE_FLAG;
         }
+        Behavior::Workload => {
+            let workload = benchmark_conf.workload.as_ref().expect("workload spec");
+            for operation in workload.operations() {
+                behavior_flags |= match operation {
+                    Operation::Get => GET_FLAG,
+                    Operation::Put => PUT_FLAG,
+                    Operation::Exist => EXIST_FLAG,
+                    Operation::Delete => DELETE_FLAG,
+                };
+            }
+        }
     }
     tokio::spawn(stat_worker(
         stop_token,
@@ -1101,6 +1549,7 @@ fn spawn_statistics_thread(
         bytes_amount,
         keys_count,
         behavior_flags,
+        benchmark_conf.report.clone(),
     ))
 }
 
@@ -1114,9 +1563,13 @@ fn create_current_blobmeta() -> BlobMeta {
 }
 
 fn create_blob(task_conf: &TaskConfig) -> Blob {
+    create_blob_of_size(task_conf.payload_size)
+}
+
+fn create_blob_of_size(size: u64) -> Blob {
     let meta = create_current_blobmeta();
     Blob {
-        data: vec![0_u8; task_conf.payload_size as usize].into(),
+        data: vec![0_u8; size as usize].into(),
         meta: Some(meta),
     }
 }
@@ -1251,6 +1704,36 @@ fn get_matches() -> ArgMatches<'static> {
                 .takes_value(true)
                 .long("domain_name"),
         )
+        .arg(
+            Arg::with_name("protocol")
+                .help("api to benchmark: grpc, rest (/data/:key) or s3 (/s3/default/:key), \
+                    port defaults to 8000 for rest and s3")
+                .takes_value(true)
+                .possible_values(&["grpc", "rest", "s3"])
+                .default_value("grpc")
+                .long("protocol"),
+        )
+        .arg(
+            Arg::with_name("workload")
+                .help("workload spec file with operation mix, key and payload size distributions \
+                    and request rate, replaces behavior")
+                .takes_value(true)
+                .long("workload"),
+        )
+        .arg(
+            Arg::with_name("report")
+                .help("file to save final statistics and latency percentiles")
+                .takes_value(true)
+                .long("report"),
+        )
+        .arg(
+            Arg::with_name("report_format")
+                .help("json or csv")
+                .takes_value(true)
+                .possible_values(&["json", "csv"])
+                .default_value("json")
+                .long("report_format"),
+        )
         .get_matches()
 }
 
@@ -1,14 +1,24 @@
+mod consistency_tool;
+
 use clap::{App, Arg, ArgMatches};
+use consistency_tool::{
+    checker::{self, CheckerSettings, Model},
+    history::{decode_value, encode_value, History, OperationRecord, OperationResult, Request},
+    nemesis::Nemesis,
+};
 use env_logger::{Env, Target};
 use http::{StatusCode, Uri};
 use lazy_static::lazy_static;
 use rand::distributions::Uniform;
 use rand::prelude::*;
 use reqwest::RequestBuilder;
-use std::collections::BTreeMap;
+use std::path::PathBuf;
 use std::process::ExitCode;
-use std::time::Duration;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use bob_common::stopwatch::Stopwatch;
+use tokio::{sync::watch, time::Instant};
 
 const START_ID_ARG_NAME: &str = "start-id";
 const END_ID_ARG_NAME: &str = "end-id";
@@ -17,20 +27,124 @@ const COUNT_ARG_NAME: &str = "key";
 const API_ADDRESS_ARG_NAME: &str = "api-address";
 const USERNAME_ARG_NAME: &str = "username";
 const PASSWORD_ARG_NAME: &str = "password";
+const CLIENTS_ARG_NAME: &str = "clients";
+const MODEL_ARG_NAME: &str = "model";
+const CLOCK_SKEW_ARG_NAME: &str = "clock-skew";
+const CONSISTENCY_ARG_NAME: &str = "consistency";
+const HISTORY_ARG_NAME: &str = "history";
+const FAULTS_ARG_NAME: &str = "faults";
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+// Max count of states visited by the checker for one key
+const SEARCH_LIMIT: usize = 1_000_000;
+const PROGRESS_LOG_INTERVAL: u64 = 10000;
 
 #[tokio::main]
 async fn main() -> ExitCode {
     let env = Env::default().filter_or("RUST_LOG", "info");
     env_logger::Builder::from_env(env).target(Target::Stdout).init();
     let settings = Settings::new();
-    let mut tester = Tester::new(settings).await;
-    if tester.run_test().await {
+    if run_test(settings).await {
         ExitCode::SUCCESS
     } else {
         ExitCode::FAILURE
     }
 }
 
+/// Runs concurrent clients and the nemesis, then checks the recorded history.
+async fn run_test(settings: Settings) -> bool {
+    let started = Instant::now();
+    let started_at_ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("system time is after unix epoch")
+        .as_millis() as u64;
+    let (stop_nemesis, stop_receiver) = watch::channel(false);
+    let nemesis = match &settings.faults_path {
+        Some(path) => match Nemesis::from_file(path, settings.credentials()) {
+            Ok(nemesis) => Some(tokio::spawn(nemesis.run(started, stop_receiver))),
+            Err(e) => {
+                log::error!("Failed to load faults from {}: {}", path.display(), e);
+                return false;
+            }
+        },
+        None => None,
+    };
+    let counter = Arc::new(AtomicU64::new(0));
+    let testers = (0..settings.clients)
+        .map(|id| {
+            let tester = Tester::new(id, settings.clone(), counter.clone(), started);
+            tokio::spawn(tester.run())
+        })
+        .collect::<Vec<_>>();
+    let mut operations = vec![];
+    let mut metrics = Metrics::default();
+    for tester in testers {
+        let (tester_operations, tester_metrics) = tester.await.expect("tester panicked");
+        operations.extend(tester_operations);
+        metrics.add(&tester_metrics);
+    }
+    let _ = stop_nemesis.send(true);
+    let faults = match nemesis {
+        Some(nemesis) => nemesis.await.expect("nemesis panicked"),
+        None => vec![],
+    };
+    metrics.print_summary();
+    let history = History {
+        started_at_ms,
+        operations,
+        faults,
+    };
+    if let Some(path) = &settings.history_path {
+        match history.save(path) {
+            Ok(()) => log::info!("History is saved to {}", path.display()),
+            Err(e) => log::error!("Failed to save history to {}: {}", path.display(), e),
+        }
+    }
+    check_history(&history, &settings)
+}
+
+fn check_history(history: &History, settings: &Settings) -> bool {
+    let checker_settings = CheckerSettings {
+        model: settings.model,
+        started_at_ms: history.started_at_ms,
+        clock_skew_ms: settings.clock_skew_ms,
+        search_limit: SEARCH_LIMIT,
+    };
+    let report = checker::check(&history.operations, &checker_settings);
+    for key in &report.skipped_keys {
+        log::warn!("Key {}: history is too large to check", key);
+    }
+    for violation in &report.violations {
+        let mut message = format!(
+            "Key {}: history violates {} model, minimal violating history:",
+            violation.key, settings.model
+        );
+        for op in &violation.operations {
+            message.push_str(&format!("\n  {}", op));
+        }
+        let from = violation.operations.iter().map(|op| op.invoke_us).min();
+        let to = violation.operations.iter().map(|op| op.complete_us).max();
+        if let (Some(from), Some(to)) = (from, to) {
+            let faults = history.faults.iter().filter(|f| f.overlaps(from, to));
+            for (i, fault) in faults.enumerate() {
+                if i == 0 {
+                    message.push_str("\n  faults:");
+                }
+                message.push_str(&format!("\n  {}", fault));
+            }
+        }
+        log::error!("{}", message);
+    }
+    log::info!(
+        "Final summary: {} operations, {} keys checked, {} skipped, {} violations",
+        history.operations.len(),
+        report.checked_keys,
+        report.skipped_keys.len(),
+        report.violations.len()
+    );
+    report.violations.is_empty()
+}
+
 enum Operation {
     Get,
     Put,
@@ -53,24 +167,32 @@ impl Operation {
     }
 }
 
+/// One of the concurrent clients, records results of its operations.
 struct Tester {
-    storage: BTreeMap<u64, usize>,
+    id: usize,
     client: Client,
     settings: Settings,
-    rng: rand::rngs::ThreadRng,
+    rng: StdRng,
     id_distribution: rand::distributions::Uniform<u64>,
     size_distribution: rand::distributions::Uniform<usize>,
+    // Count of operations started by all testers
+    counter: Arc<AtomicU64>,
+    started: Instant,
+    operations: Vec<OperationRecord>,
 }
 
 impl Tester {
-    async fn new(settings: Settings) -> Tester {
+    fn new(id: usize, settings: Settings, counter: Arc<AtomicU64>, started: Instant) -> Tester {
         Self {
-            storage: Default::default(),
-            client: Client::new(settings.clone()).await,
-            rng: rand::thread_rng(),
+            id,
+            client: Client::new(settings.clone()),
+            rng: StdRng::from_entropy(),
             id_distribution: Uniform::new(settings.start_id, settings.end_id),
             size_distribution: Uniform::new(0, settings.max_size),
             settings,
+            counter,
+            started,
+            operations: vec![],
         }
     }
 
@@ -82,130 +204,47 @@ impl Tester {
         self.size_distribution.sample(&mut self.rng)
     }
 
-    async fn get(&mut self) -> bool {
-        let key = self.rand_id();
-        let res = self.client.get(key).await;
-        match res {
-            Ok(size) => {
-                if let Some(expected) = self.storage.get(&key) {
-                    if *expected == size {
-                        true
-                    } else {
-                        log::warn!("Get size error: expected {} != actual {}", expected, size);
-                        false
-                    }
-                } else {
-                    log::warn!("Get unexpected of size {}", size);
-                    false
-                }
-            }
-            Err(e) => {
-                if self.storage.contains_key(&key) {
-                    log::warn!("Get unexpected error: {}", e);
-                    false
-                } else {
-                    true
-                }
-            }
-        }
-    }
-
-    async fn exists(&mut self) -> bool {
-        let key = self.rand_id();
-        let res = self.client.exists(key).await;
-        match res {
-            Ok(found) => {
-                let contains_key = self.storage.contains_key(&key);
-                if contains_key == found {
-                    true
-                } else {
-                    log::warn!("Exists unexpected result: expected '{}' != actual '{}'", contains_key, found);
-                    false
-                }
-            }
-            Err(e) => {
-                log::warn!("Exists unexpected error: {}", e);
-                false
-            }
-        }
+    fn offset_us(&self) -> u64 {
+        self.started.elapsed().as_micros() as u64
     }
 
-    async fn put(&mut self) -> bool {
-        let key = self.rand_id();
-        let size = self.rand_size();
-        let res = self.client.put(key, size).await;
-        match res {
-            Ok(_) => {
-                self.storage.insert(key, size);
-                true
-            }
-            Err(e) => {
-                log::warn!("Put error: {}", e);
-                false
+    async fn run(mut self) -> (Vec<OperationRecord>, Metrics) {
+        loop {
+            let i = self.counter.fetch_add(1, Ordering::SeqCst);
+            if i >= self.settings.count {
+                break;
             }
-        }
-    }
-
-    async fn delete(&mut self) -> bool {
-        let key = self.rand_id();
-        let res = self.client.delete(key).await;
-        let value = self.storage.remove(&key);
-        match res {
-            Ok(size) => {
-                if let Some(expected) = value {
-                    if expected == size {
-                        true
-                    } else {
-                        log::warn!(
-                            "Deletion size error: expected {} != actual {}",
-                            expected,
-                            size
-                        );
-                        false
-                    }
-                } else {
-                    log::warn!("Unexpected deletion of size {}", size);
-                    false
-                }
+            if i.is_multiple_of(PROGRESS_LOG_INTERVAL) {
+                log::info!("Progress: {}/{}", i, self.settings.count);
             }
-            Err(e) => {
-                if value.is_none() {
-                    true
-                } else {
-                    log::warn!("Unexpected deletion error: {}", e);
-                    false
+            let key = self.rand_id();
+            let invoke_us = self.offset_us();
+            let (request, result) = match Operation::gen(&mut self.rng) {
+                Operation::Get => (Request::Get, self.client.get(key).await),
+                Operation::Put => {
+                    // Values are random to differ from values of previous runs
+                    let value = self.rng.gen();
+                    let data = encode_value(value, self.rand_size());
+                    (Request::Put { value }, self.client.put(key, data).await)
                 }
-            }
-        }
-    }
-
-    async fn run_test(&mut self) -> bool {
-        let mut total_succ: u64 = 0;
-        for i in 0..self.settings.count {
-            if i % 10000 == 0 || i == self.settings.count - 1 {
-                log::info!("Summary: {}/{}", total_succ, i);
-                self.client.print_summary();
-                self.client.reset_metrics();
-            }
-            let op = Operation::gen(&mut self.rng);
-            let success = match op {
-                Operation::Get => self.get().await,
-                Operation::Put => self.put().await,
-                Operation::Delete => self.delete().await,
-                Operation::Exists => self.exists().await
+                Operation::Delete => (Request::Delete, self.client.delete(key).await),
+                Operation::Exists => (Request::Exists, self.client.exists(key).await),
             };
-            if success {
-                total_succ += 1;
-            }
+            self.operations.push(OperationRecord {
+                client: self.id,
+                key,
+                request,
+                invoke_us,
+                complete_us: self.offset_us(),
+                result,
+            });
         }
-        log::info!("Final summary: {}/{}", total_succ, self.settings.count);
-        total_succ == self.settings.count
+        (self.operations, self.client.metrics)
     }
 }
 
-struct Client {
-    http_client: reqwest::Client,
-    settings: Settings,
+#[derive(Default)]
+struct Metrics {
     put_time: Duration,
     put_count: u64,
     get_count: u64,
@@ -213,34 +252,21 @@ struct Client {
     delete_count: u64,
     delete_time: Duration,
     exists_count: u64,
-    exists_time: Duration
+    exists_time: Duration,
+    errors_count: u64,
 }
 
-impl Client {
-    async fn new(settings: Settings) -> Self {
-        Self {
-            http_client: reqwest::ClientBuilder::default().build().unwrap(),
-            settings,
-            put_count: 0,
-            put_time: Duration::ZERO,
-            get_count: 0,
-            get_time: Duration::ZERO,
-            delete_count: 0,
-            delete_time: Duration::ZERO,
-            exists_count: 0,
-            exists_time: Duration::ZERO
-        }
-    }
-
-    fn reset_metrics(&mut self) {
-        self.put_time = Duration::ZERO;
-        self.put_count = 0;
-        self.get_time = Duration::ZERO;
-        self.get_count = 0;
-        self.delete_time = Duration::ZERO;
-        self.delete_count = 0;
-        self.exists_time = Duration::ZERO;
-        self.exists_count = 0;
+impl Metrics {
+    fn add(&mut self, other: &Metrics) {
+        self.put_time += other.put_time;
+        self.put_count += other.put_count;
+        self.get_time += other.get_time;
+        self.get_count += other.get_count;
+        self.delete_time += other.delete_time;
+        self.delete_count += other.delete_count;
+        self.exists_time += other.exists_time;
+        self.exists_count += other.exists_count;
+        self.errors_count += other.errors_count;
     }
 
     fn print_summary(&self) {
@@ -252,93 +278,140 @@ impl Client {
         log::info!("DELETE: count: {}, latency: {}", self.delete_count, latency);
         let latency = self.exists_time.as_secs_f64() / self.exists_count as f64 * 1000.0;
         log::info!("EXISTS: count: {}, latency: {}", self.exists_count, latency);
+        log::info!("ERRORS: count: {}", self.errors_count);
     }
+}
 
-    async fn put(&mut self, key: u64, size: usize) -> Result<(), String> {
-        let data = vec![1; size];
-        let sw = Stopwatch::new();
-        let req = self
-            .settings
-            .request(key, |a| self.http_client.post(a).body(data))?;
-        let res = self
-            .http_client
-            .execute(req)
-            .await
-            .map_err(|e| e.to_string())?;
-        self.put_time += sw.elapsed();
-        self.put_count += 1;
-        if !res.status().is_success() {
-            log::warn!("Put resulted in error code: {:?}", res.status());
+struct Client {
+    http_client: reqwest::Client,
+    settings: Settings,
+    metrics: Metrics,
+}
+
+impl Client {
+    fn new(settings: Settings) -> Self {
+        Self {
+            http_client: reqwest::ClientBuilder::default()
+                .timeout(REQUEST_TIMEOUT)
+                .build()
+                .unwrap(),
+            settings,
+            metrics: Metrics::default(),
         }
-        log::debug!("Put {} with size {}, result: {:?}", key, size, res);
-        Ok(())
     }
 
-    async fn get(&mut self, key: u64) -> Result<usize, String> {
-        let req = self.settings.request(key, |a| self.http_client.get(a))?;
-        let sw = Stopwatch::new();
+    async fn execute(
+        &mut self,
+        request: Result<reqwest::Request, String>,
+    ) -> Result<reqwest::Response, String> {
         let res = self
             .http_client
-            .execute(req)
+            .execute(request?)
             .await
-            .map_err(|e| e.to_string())?;
-        let status = res.status();
-        self.get_time += sw.elapsed();
-        self.get_count += 1;
-        if !status.is_success() {
-            if status != StatusCode::NOT_FOUND {
-                log::warn!("Get resulted in error code: {:?}", res.status());
+            .map_err(|e| e.to_string());
+        let failed = match &res {
+            Ok(res) => !res.status().is_success() && res.status() != StatusCode::NOT_FOUND,
+            Err(_) => true,
+        };
+        if failed {
+            self.metrics.errors_count += 1;
+        }
+        res
+    }
+
+    /// Rejected requests are not applied, write may be applied if the response is lost or
+    /// the node failed to process it.
+    fn write_result(status: StatusCode) -> OperationResult {
+        match status {
+            s if s.is_success() => OperationResult::Done,
+            StatusCode::BAD_REQUEST
+            | StatusCode::UNAUTHORIZED
+            | StatusCode::FORBIDDEN
+            | StatusCode::PRECONDITION_FAILED => OperationResult::Failed(status.to_string()),
+            _ => OperationResult::Unknown(status.to_string()),
+        }
+    }
+
+    async fn put(&mut self, key: u64, data: Vec<u8>) -> OperationResult {
+        let size = data.len();
+        let req = self
+            .settings
+            .request(key, |a| self.http_client.post(a).body(data));
+        let sw = Stopwatch::start_new();
+        let res = self.execute(req).await;
+        self.metrics.put_time += sw.elapsed();
+        self.metrics.put_count += 1;
+        log::debug!("Put {} with size {}, result: {:?}", key, size, res);
+        match res {
+            Ok(res) => {
+                if !res.status().is_success() {
+                    log::warn!("Put resulted in error code: {:?}", res.status());
+                }
+                Self::write_result(res.status())
             }
-            return Err(status.to_string());
+            Err(e) => OperationResult::Unknown(e),
         }
-        log::debug!("Get {}, result: {:?}", key, res);
-        let bytes = res.bytes().await;
-        bytes.map(|b| b.len()).map_err(|e| e.to_string())
     }
 
-    async fn exists(&mut self, key: u64) -> Result<bool, String> {
-        let req = self.settings.request(key, |a| self.http_client.head(a))?;
-        let sw = Stopwatch::new();
-        let res = self
-            .http_client
-            .execute(req)
-            .await
-            .map_err(|e| e.to_string())?;
-        let status = res.status();
-        self.exists_time += sw.elapsed();
-        self.exists_count += 1;
-
-        if status == StatusCode::OK {
-            log::debug!("Exists {}, result: {:?}", key, status);
-            return Ok(true);
+    async fn get(&mut self, key: u64) -> OperationResult {
+        let req = self.settings.request(key, |a| self.http_client.get(a));
+        let sw = Stopwatch::start_new();
+        let res = self.execute(req).await;
+        self.metrics.get_time += sw.elapsed();
+        self.metrics.get_count += 1;
+        log::debug!("Get {}, result: {:?}", key, res);
+        let res = match res {
+            Ok(res) => res,
+            Err(e) => return OperationResult::Unknown(e),
+        };
+        match res.status() {
+            StatusCode::OK => match res.bytes().await {
+                Ok(data) => decode_value(&data).map_or(OperationResult::Corrupted, |value| {
+                    OperationResult::Read(Some(value))
+                }),
+                Err(e) => OperationResult::Unknown(e.to_string()),
+            },
+            StatusCode::NOT_FOUND => OperationResult::Read(None),
+            status => {
+                log::warn!("Get resulted in error code: {:?}", status);
+                OperationResult::Unknown(status.to_string())
+            }
         }
-        else if status == StatusCode::NOT_FOUND {
-            log::debug!("Exists {}, result: {:?}", key, status);
-            return Ok(false);
-        } 
-        else {
-            log::warn!("Exists resulted in error code: {:?}", res.status());
-            return Err(status.to_string());
+    }
+
+    async fn exists(&mut self, key: u64) -> OperationResult {
+        let req = self.settings.request(key, |a| self.http_client.head(a));
+        let sw = Stopwatch::start_new();
+        let res = self.execute(req).await;
+        self.metrics.exists_time += sw.elapsed();
+        self.metrics.exists_count += 1;
+        log::debug!("Exists {}, result: {:?}", key, res);
+        match res.map(|res| res.status()) {
+            Ok(StatusCode::OK) => OperationResult::Exists(true),
+            Ok(StatusCode::NOT_FOUND) => OperationResult::Exists(false),
+            Ok(status) => {
+                log::warn!("Exists resulted in error code: {:?}", status);
+                OperationResult::Unknown(status.to_string())
+            }
+            Err(e) => OperationResult::Unknown(e),
         }
     }
 
-    async fn delete(&mut self, key: u64) -> Result<usize, String> {
-        let size = self.get(key).await?;
-        log::debug!("Delete {} with size {}", key, size);
-        let req = self.settings.request(key, |a| self.http_client.delete(a))?;
-        let sw = Stopwatch::new();
-        let _ = self
-            .http_client
-            .execute(req)
-            .await
-            .map_err(|e| e.to_string());
-        self.delete_time += sw.elapsed();
-        self.delete_count += 1;
-        let res = self.get(key).await;
+    async fn delete(&mut self, key: u64) -> OperationResult {
+        let req = self.settings.request(key, |a| self.http_client.delete(a));
+        let sw = Stopwatch::start_new();
+        let res = self.execute(req).await;
+        self.metrics.delete_time += sw.elapsed();
+        self.metrics.delete_count += 1;
         log::debug!("Delete {}, result: {:?}", key, res);
         match res {
-            Ok(new_size) => Err(format!("{} => {}", size, new_size)),
-            Err(_) => Ok(size),
+            Ok(res) => {
+                if !res.status().is_success() {
+                    log::warn!("Delete resulted in error code: {:?}", res.status());
+                }
+                Self::write_result(res.status())
+            }
+            Err(e) => OperationResult::Unknown(e),
         }
     }
 }
@@ -352,6 +425,12 @@ struct Settings {
     api_uri: Uri,
     username: Option<String>,
     password: Option<String>,
+    clients: usize,
+    model: Model,
+    clock_skew_ms: u64,
+    consistency: Option<String>,
+    history_path: Option<PathBuf>,
+    faults_path: Option<PathBuf>,
 }
 
 impl Settings {
@@ -365,9 +444,45 @@ impl Settings {
             api_uri: Self::get_api_uri(&matches),
             username: Self::get_username(&matches),
             password: Self::get_password(&matches),
+            clients: Self::get_clients(&matches),
+            model: Self::get_model(&matches),
+            clock_skew_ms: Self::get_clock_skew_ms(&matches),
+            consistency: matches
+                .value_of(CONSISTENCY_ARG_NAME)
+                .map(|s| s.to_string()),
+            history_path: matches.value_of(HISTORY_ARG_NAME).map(PathBuf::from),
+            faults_path: matches.value_of(FAULTS_ARG_NAME).map(PathBuf::from),
         }
     }
 
+    fn get_clients(matches: &ArgMatches) -> usize {
+        matches
+            .value_of(CLIENTS_ARG_NAME)
+            .expect("has default")
+            .parse()
+            .expect("should be usize")
+    }
+
+    fn get_model(matches: &ArgMatches) -> Model {
+        matches
+            .value_of(MODEL_ARG_NAME)
+            .expect("has default")
+            .parse()
+            .expect("should be consistency model")
+    }
+
+    fn get_clock_skew_ms(matches: &ArgMatches) -> u64 {
+        matches
+            .value_of(CLOCK_SKEW_ARG_NAME)
+            .expect("has default")
+            .parse()
+            .expect("should be u64")
+    }
+
+    fn credentials(&self) -> Option<(String, String)> {
+        self.username.clone().zip(self.password.clone())
+    }
+
     fn get_count(matches: &ArgMatches) -> u64 {
         matches
             .value_of(COUNT_ARG_NAME)
@@ -435,6 +550,9 @@ impl Settings {
         if let (Some(username), Some(password)) = (self.username.as_ref(), self.password.as_ref()) {
             b = b.basic_auth(username, Some(password));
         }
+        if let Some(consistency) = &self.consistency {
+            b = b.header("X-Bob-Consistency-Level", consistency);
+        }
         b
     }
 }
@@ -473,7 +591,40 @@ fn get_matches<'a>() -> ArgMatches<'a> {
         .short("p")
         .long("password")
         .takes_value(true);
+    let clients_arg = Arg::with_name(CLIENTS_ARG_NAME)
+        .long("clients")
+        .help("count of concurrent clients")
+        .takes_value(true)
+        .default_value("8");
+    let model_arg = Arg::with_name(MODEL_ARG_NAME)
+        .long("model")
+        .help("consistency model the history is checked against")
+        .takes_value(true)
+        .possible_values(&["last-write-wins", "linearizable"])
+        .default_value("last-write-wins");
+    let clock_skew_arg = Arg::with_name(CLOCK_SKEW_ARG_NAME)
+        .long("clock-skew")
+        .help("max difference between clocks of bobt and nodes in milliseconds")
+        .takes_value(true)
+        .default_value("0");
+    let consistency_arg = Arg::with_name(CONSISTENCY_ARG_NAME)
+        .long("consistency")
+        .help("consistency level of requests")
+        .takes_value(true)
+        .possible_values(&["one", "quorum", "all"]);
+    let history_arg = Arg::with_name(HISTORY_ARG_NAME)
+        .long("history")
+        .help("file to save history of operations and faults as JSON lines")
+        .takes_value(true);
+    let faults_arg = Arg::with_name(FAULTS_ARG_NAME)
+        .long("faults")
+        .help("yaml file with faults injected during the test")
+        .takes_value(true);
     App::new("bobt")
+        .about(
+            "Checks consistency of bob under concurrent requests and faults. \
+            Keys from the range must be absent or written by bobt",
+        )
         .arg(count_arg)
         .arg(size_arg)
         .arg(start_id_arg)
@@ -481,5 +632,11 @@ fn get_matches<'a>() -> ArgMatches<'a> {
         .arg(api_uri_arg)
         .arg(username_arg)
         .arg(password_arg)
+        .arg(clients_arg)
+        .arg(model_arg)
+        .arg(clock_skew_arg)
+        .arg(consistency_arg)
+        .arg(history_arg)
+        .arg(faults_arg)
         .get_matches()
 }
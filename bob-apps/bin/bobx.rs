@@ -0,0 +1,14 @@
+#[macro_use]
+extern crate log;
+
+mod data_transfer_tool;
+use data_transfer_tool as dtt;
+
+#[tokio::main]
+async fn main() {
+    dtt::utils::init_logger().unwrap();
+    if let Err(e) = dtt::command::MainCommand::run().await {
+        error!("{:#}", e);
+        std::process::exit(1);
+    }
+}
@@ -9,6 +9,7 @@ use clap::{App, Arg, ArgMatches, SubCommand};
 use config_cluster_generator::{
     center::{check_expand_configs, get_new_disks, get_new_racks, Center},
     utils::{init_logger, ceil, read_config_from_file, write_to_file},
+    weights::{read_disk_weights_from_file, DiskWeights},
 };
 
 #[tokio::main]
@@ -36,7 +37,9 @@ fn subcommand_new(matches: &ArgMatches) -> AnyResult<()> {
     debug!("start new config generation");
     debug!("arguments: {:?}", matches);
     let config = read_config_from_file(&get_input_config_name(matches))?;
-    let output = generate_config(matches, config)?;
+    let weights =
+        read_disk_weights_from_file(&get_input_config_name(matches), get_probe_capacity(matches))?;
+    let output = generate_config(matches, config, &weights)?;
     let output = serde_yaml::to_string(&output).expect("config serialization error");
     debug!("config cluster generation: OK");
     if let Some(name) = matches.value_of("output") {
@@ -54,7 +57,9 @@ fn subcommand_expand(matches: &ArgMatches) -> AnyResult<()> {
     debug!("arguments: {:?}", matches);
     let config = read_config_from_file(&get_input_config_name(matches))?;
     let hardware_config = read_config_from_file(&get_hardware_config_name(matches))?;
-    let output = expand_config(matches, config, hardware_config)?;
+    let weights =
+        read_disk_weights_from_file(&get_hardware_config_name(matches), get_probe_capacity(matches))?;
+    let output = expand_config(matches, config, hardware_config, &weights)?;
     let output = serde_yaml::to_string(&output).expect("config serialization error");
     debug!("config cluster extending: OK");
     if let Some(name) = matches.value_of("output") {
@@ -67,7 +72,11 @@ fn subcommand_expand(matches: &ArgMatches) -> AnyResult<()> {
     Ok(())
 }
 
-fn generate_config(matches: &ArgMatches, input: ClusterConfig) -> AnyResult<ClusterConfig> {
+fn generate_config(
+    matches: &ArgMatches,
+    input: ClusterConfig,
+    weights: &DiskWeights,
+) -> AnyResult<ClusterConfig> {
     let replicas_count = get_replicas_count(matches)?;
     let (total_vdisks, vdisks_per_disk) = get_vdisks_total_and_per_disk(matches)?;
     let use_racks = get_use_racks(matches);
@@ -77,6 +86,7 @@ fn generate_config(matches: &ArgMatches, input: ClusterConfig) -> AnyResult<Clus
         total_vdisks,
         vdisks_per_disk,
         use_racks,
+        weights,
     )?;
     debug!("generate config: OK");
     Ok(res)
@@ -86,9 +96,10 @@ fn expand_config(
     matches: &ArgMatches,
     config: ClusterConfig,
     hardware_config: ClusterConfig,
+    weights: &DiskWeights,
 ) -> AnyResult<ClusterConfig> {
     let use_racks = get_use_racks(matches);
-    let res = simple_expand(config, hardware_config, use_racks)?;
+    let res = simple_expand(config, hardware_config, use_racks, weights)?;
     debug!("expand config: OK");
     Ok(res)
 }
@@ -97,9 +108,11 @@ fn simple_expand(
     config: ClusterConfig,
     mut hardware_config: ClusterConfig,
     use_racks: bool,
+    weights: &DiskWeights,
 ) -> AnyResult<ClusterConfig> {
     let mut center = Center::from_cluster_config(&hardware_config, use_racks)?;
     center.validate()?;
+    center.set_weights(weights)?;
     let old_center = Center::from_cluster_config(&config, use_racks)?;
     old_center.validate()?;
     check_expand_configs(&old_center, &center, use_racks)?;
@@ -122,6 +135,7 @@ fn simple_expand(
     }
     hardware_config.vdisks_extend(vdisks);
     debug!("extend config: OK [\n{:#?}\n]", center);
+    info!("balance report:\n{}", center.balance_report());
     Ok(hardware_config)
 }
 
@@ -131,9 +145,11 @@ fn simple_gen(
     total_vdisks: Option<usize>,
     vdisks_per_disk: Option<usize>,
     use_racks: bool,
+    weights: &DiskWeights,
 ) -> AnyResult<ClusterConfig> {
     let mut center = Center::from_cluster_config(&config, use_racks)?;
     center.validate()?;
+    center.set_weights(weights)?;
     let vdisks_count;
     if let Some(vdpd) = vdisks_per_disk {
         vdisks_count = ceil(vdpd * center.disks_count(), replicas_count);
@@ -150,6 +166,7 @@ fn simple_gen(
     }
     config.vdisks_extend(vdisks);
     debug!("simple gen: OK [\n{:#?}\n]", center);
+    info!("balance report:\n{}", center.balance_report());
     Ok(config)
 }
 
@@ -175,6 +192,12 @@ fn get_use_racks(matches: &ArgMatches) -> bool {
     res
 }
 
+fn get_probe_capacity(matches: &ArgMatches) -> bool {
+    let res = matches.is_present("probe_capacity");
+    debug!("get_probe_capacity: OK [{}]", res);
+    res
+}
+
 fn get_replicas_count(matches: &ArgMatches) -> AnyResult<usize> {
     matches
         .value_of("replicas")
@@ -231,11 +254,16 @@ fn get_matches() -> ArgMatches<'static> {
         .long("use-racks")
         .help("Use racks field in config")
         .takes_value(false);
+    let probe_capacity = Arg::with_name("probe_capacity")
+        .long("probe-capacity")
+        .help("Use capacity of disk paths for disks without weight or capacity in config")
+        .takes_value(false);
     debug!("input arg: OK");
     let subcommand_expand = SubCommand::with_name("expand")
         .arg(input.clone())
         .arg(output.clone())
         .arg(use_racks.clone())
+        .arg(probe_capacity.clone())
         .arg(hardware_config);
 
     let subcommand_new = SubCommand::with_name("new")
@@ -244,6 +272,7 @@ fn get_matches() -> ArgMatches<'static> {
         .arg(vdisks_per_disk)
         .arg(vdisks_count)
         .arg(use_racks)
+        .arg(probe_capacity)
         .arg(replicas);
 
     App::new("Config Cluster Generator")
@@ -1,15 +1,21 @@
 #![allow(clippy::ptr_arg)]
+use super::weights::DiskWeights;
 use anyhow::{anyhow, Result as AnyResult};
 use bob::{
     ClusterConfig, ClusterNodeConfig as ClusterNode, ClusterRackConfig as ClusterRack,
     ReplicaConfig as Replica, VDiskConfig as VDisk,
 };
 use std::{
+    cmp::Ordering,
     collections::{HashMap, HashSet},
+    fmt::Write,
     ops::AddAssign,
 };
 
 const REPLICA_IN_FIRST_RACK: usize = 2;
+// Share of the least load by which the disk or rack of the existing replica may be loaded over
+// it and still keep the replica, loads of weighted disks are rarely exactly equal
+const KEEP_REPLICA_TOLERANCE: f64 = 0.05;
 type RackName = String;
 type NodeName = bob_common::node::NodeName;
 type DiskName = bob_common::core_types::DiskName;
@@ -73,12 +79,27 @@ impl Counter {
             .get(disk)
             .expect("added by constructor")
     }
+
+    // Load of the disk after one more replica, equals to the used count plus one for
+    // disks without weights, so unweighted disks are balanced by count
+    fn disk_load(&self, node: &Node, disk: &Disk) -> f64 {
+        (self.disk_used_count(&node.name, &disk.name) + 1) as f64 / disk.weight
+    }
+}
+
+fn cmp_load(a: f64, b: f64) -> Ordering {
+    a.partial_cmp(&b).expect("weights are positive")
+}
+
+fn is_near_min_load(load: f64, min_load: f64) -> bool {
+    load <= min_load * (1.0 + KEEP_REPLICA_TOLERANCE)
 }
 
 #[derive(Debug, Default)]
 pub struct Center {
     racks: Vec<Rack>,
     counter: Counter,
+    weighted: bool,
 }
 
 impl Center {
@@ -180,6 +201,32 @@ impl Center {
         });
     }
 
+    /// Sets weights of disks, replicas are placed proportionally to them. Every disk of the
+    /// center must have a weight, empty weights keep balancing by count.
+    pub fn set_weights(&mut self, weights: &DiskWeights) -> AnyResult<()> {
+        if weights.is_empty() {
+            return Ok(());
+        }
+        let mean = weights.values().sum::<f64>() / weights.len() as f64;
+        for node in self.racks.iter_mut().flat_map(|r| r.nodes.iter_mut()) {
+            for disk in node.disks.iter_mut() {
+                let weight = weights
+                    .get(&(node.name.clone(), disk.name.clone()))
+                    .ok_or_else(|| {
+                        anyhow!(
+                            "Center::set_weights: weight not found [node: {}, disk: {}]",
+                            node.name,
+                            disk.name
+                        )
+                    })?;
+                disk.weight = weight / mean;
+            }
+        }
+        self.weighted = true;
+        debug!("Center::set_weights: OK");
+        Ok(())
+    }
+
     pub fn push(&mut self, item: Rack) {
         self.counter.add_rack(&item.name);
         for node in item.nodes.iter() {
@@ -224,22 +271,33 @@ impl Center {
 
     fn next_disk(&self) -> (&Rack, &Node, &Disk) {
         self.iter_disks()
-            .min_by_key(|(_, node, disk)| self.counter.disk_used_count(&node.name, &disk.name))
+            .min_by(|(_, a_node, a_disk), (_, b_node, b_disk)| {
+                cmp_load(
+                    self.counter.disk_load(a_node, a_disk),
+                    self.counter.disk_load(b_node, b_disk),
+                )
+            })
             .expect("checked by constructor")
     }
 
     fn next_disk_from_list(&self, list: &[Replica]) -> AnyResult<(&Rack, &Node, &Disk)> {
         let min_key = self
             .iter_disks()
-            .map(|(_, node, disk)| self.counter.disk_used_count(&node.name, &disk.name))
-            .min()
+            .map(|(_, node, disk)| self.counter.disk_load(node, disk))
+            .min_by(|a, b| cmp_load(*a, *b))
             .expect("checked by constructor");
 
         self.iter_disks()
-            .find(|(_, node, disk)| {
-                self.counter.disk_used_count(&node.name, &disk.name) == min_key
+            .filter(|(_, node, disk)| {
+                is_near_min_load(self.counter.disk_load(node, disk), min_key)
                     && list.contains(&Replica::new(node.name.to_string(), disk.name.to_string()))
             })
+            .min_by(|(_, a_node, a_disk), (_, b_node, b_disk)| {
+                cmp_load(
+                    self.counter.disk_load(a_node, a_disk),
+                    self.counter.disk_load(b_node, b_disk),
+                )
+            })
             .ok_or_else(|| anyhow!("Can't find disk"))
     }
 
@@ -249,11 +307,21 @@ impl Center {
             .ok_or_else(|| anyhow!("Rack with name {} not found", name))
     }
 
+    // Racks are balanced by count without weights and by their total weight with them
+    fn rack_load(&self, rack: &Rack) -> f64 {
+        let used = self.counter.rack_used_count(&rack.name) as f64;
+        if self.weighted {
+            (used + 1.0) / rack.weight()
+        } else {
+            used
+        }
+    }
+
     fn next_rack(&self) -> &Rack {
         let rack = self
             .racks
             .iter()
-            .min_by_key(|rack| self.counter.rack_used_count(&rack.name))
+            .min_by(|a, b| cmp_load(self.rack_load(a), self.rack_load(b)))
             .expect("checked by constructor");
         rack
     }
@@ -265,16 +333,17 @@ impl Center {
         let min_key = self
             .racks
             .iter()
-            .map(|rack| self.counter.rack_used_count(&rack.name))
-            .min()
+            .map(|rack| self.rack_load(rack))
+            .min_by(|a, b| cmp_load(*a, *b))
             .expect("checked by constructor");
 
         let rack = self
             .racks
             .iter()
-            .find(|&rack| {
-                self.counter.rack_used_count(&rack.name) == min_key && list.contains(&rack.name)
+            .filter(|&rack| {
+                is_near_min_load(self.rack_load(rack), min_key) && list.contains(&rack.name)
             })
+            .min_by(|a, b| cmp_load(self.rack_load(a), self.rack_load(b)))
             .ok_or_else(|| anyhow!("Can't find rack"))?;
 
         Ok(rack)
@@ -381,6 +450,71 @@ impl Center {
         debug!("Center::validate: OK");
         Ok(())
     }
+
+    /// Expected utilization of disks and nodes: share of replicas divided by share of weight,
+    /// 100% means the disk is filled at the average rate of the cluster.
+    pub fn balance_report(&self) -> String {
+        let disks: Vec<_> = self.iter_disks().map(|(_, node, disk)| (node, disk)).collect();
+        let replicas = disks.iter().fold(0, |acc, (node, disk)| {
+            acc + self.counter.disk_used_count(&node.name, &disk.name)
+        });
+        let weight: f64 = disks.iter().map(|(_, disk)| disk.weight).sum();
+        let utilization = |used: usize, disk_weight: f64| {
+            if replicas == 0 {
+                0.0
+            } else {
+                100.0 * (used as f64 / replicas as f64) / (disk_weight / weight)
+            }
+        };
+        let mut report = String::new();
+        writeln!(
+            report,
+            "{:<20} {:<20} {:>8} {:>8} {:>12}",
+            "node", "disk", "weight", "replicas", "utilization"
+        )
+        .expect("write to string");
+        let (mut min, mut max) = (f64::MAX, 0.0_f64);
+        for node in self.racks.iter().flat_map(|r| r.nodes.iter()) {
+            let mut node_used = 0;
+            for disk in &node.disks {
+                let used = self.counter.disk_used_count(&node.name, &disk.name);
+                let disk_utilization = utilization(used, disk.weight);
+                min = min.min(disk_utilization);
+                max = max.max(disk_utilization);
+                node_used += used;
+                writeln!(
+                    report,
+                    "{:<20} {:<20} {:>8.3} {:>8} {:>11.1}%",
+                    node.name.as_str(),
+                    disk.name.as_str(),
+                    disk.weight,
+                    used,
+                    disk_utilization
+                )
+                .expect("write to string");
+            }
+            writeln!(
+                report,
+                "{:<20} {:<20} {:>8.3} {:>8} {:>11.1}%",
+                node.name.as_str(),
+                "total",
+                node.weight(),
+                node_used,
+                utilization(node_used, node.weight())
+            )
+            .expect("write to string");
+        }
+        write!(
+            report,
+            "{} replicas on {} disks, disk utilization from {:.1}% to {:.1}%",
+            replicas,
+            disks.len(),
+            min,
+            max
+        )
+        .expect("write to string");
+        report
+    }
 }
 
 #[derive(Debug)]
@@ -419,7 +553,12 @@ impl Rack {
             .iter()
             .filter(|node| !replicas.contains(&node.name))
             .flat_map(|n| n.disks.iter().map(move |d| (n, d)))
-            .min_by_key(|(node, disk)| counter.disk_used_count(&node.name, &disk.name))
+            .min_by(|(a_node, a_disk), (b_node, b_disk)| {
+                cmp_load(
+                    counter.disk_load(a_node, a_disk),
+                    counter.disk_load(b_node, b_disk),
+                )
+            })
             .unwrap_or(self.min_used_disk(counter)?);
         Ok((node, disk))
     }
@@ -430,7 +569,7 @@ impl Rack {
         replicas: &[NodeName],
         list: &[Replica],
     ) -> AnyResult<(&Node, &Disk)> {
-        let mut disks = self
+        let disks = self
             .nodes
             .iter()
             .filter(|node| !replicas.contains(&node.name))
@@ -438,15 +577,21 @@ impl Rack {
 
         let min_key = disks
             .clone()
-            .map(|(node, disk)| counter.disk_used_count(&node.name, &disk.name))
-            .min()
+            .map(|(node, disk)| counter.disk_load(node, disk))
+            .min_by(|a, b| cmp_load(*a, *b))
             .ok_or_else(|| anyhow!("Empty disks list"))?;
 
         let (node, disk) = disks
-            .find(|(node, disk)| {
-                min_key == counter.disk_used_count(&node.name, &disk.name)
+            .filter(|(node, disk)| {
+                is_near_min_load(counter.disk_load(node, disk), min_key)
                     && list.contains(&Replica::new(node.name.to_string(), disk.name.to_string()))
             })
+            .min_by(|(a_node, a_disk), (b_node, b_disk)| {
+                cmp_load(
+                    counter.disk_load(a_node, a_disk),
+                    counter.disk_load(b_node, b_disk),
+                )
+            })
             .ok_or_else(|| anyhow!("Can't find disk"))?;
 
         Ok((node, disk))
@@ -456,9 +601,18 @@ impl Rack {
         self.nodes
             .iter()
             .flat_map(|n| n.disks.iter().map(move |d| (n, d)))
-            .min_by_key(|(node, disk)| counter.disk_used_count(&node.name, &disk.name))
+            .min_by(|(a_node, a_disk), (b_node, b_disk)| {
+                cmp_load(
+                    counter.disk_load(a_node, a_disk),
+                    counter.disk_load(b_node, b_disk),
+                )
+            })
             .ok_or_else(|| anyhow!("Empty disks list"))
     }
+
+    fn weight(&self) -> f64 {
+        self.nodes.iter().map(|n| n.weight()).sum()
+    }
 }
 
 #[derive(Debug)]
@@ -503,12 +657,18 @@ impl Node {
     fn disks_count(&self) -> usize {
         self.disks.len()
     }
+
+    fn weight(&self) -> f64 {
+        self.disks.iter().map(|d| d.weight).sum()
+    }
 }
 
 #[derive(Debug)]
 pub struct Disk {
     name: DiskName,
     is_old: bool,
+    // Relative to the mean weight of all disks
+    weight: f64,
 }
 
 impl Disk {
@@ -516,6 +676,7 @@ impl Disk {
         Self {
             name: name.into(),
             is_old,
+            weight: 1.0,
         }
     }
 }
@@ -602,3 +763,47 @@ pub fn check_expand_configs(old: &Center, new: &Center, use_racks: bool) -> AnyR
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn center(weights: &[(&str, f64)]) -> Center {
+        let nodes = weights
+            .iter()
+            .map(|(node, _)| Node::new(*node, vec![Disk::new("disk", true)], true))
+            .collect();
+        let mut center = Center::new();
+        center.push(Rack::new("rack".to_string(), nodes, true));
+        let weights = weights
+            .iter()
+            .map(|(node, weight)| ((NodeName::from(*node), DiskName::from("disk")), *weight))
+            .collect();
+        center.set_weights(&weights).unwrap();
+        center
+    }
+
+    #[test]
+    fn expand_moves_only_replicas_of_new_disk_share() {
+        let old_weights = [("a", 1.1), ("b", 2.2), ("c", 3.3)];
+        let mut old = center(&old_weights);
+        let vdisks = (0..120)
+            .map(|id| old.create_vdisk(id, 2).unwrap())
+            .collect::<Vec<_>>();
+
+        let mut new = center(&[("a", 1.1), ("b", 2.2), ("c", 3.3), ("d", 3.3)]);
+        let moved: usize = vdisks
+            .iter()
+            .map(|vdisk| {
+                let expanded = new.create_vdisk_from_another(vdisk).unwrap();
+                expanded
+                    .replicas()
+                    .iter()
+                    .filter(|r| !vdisk.replicas().contains(r))
+                    .count()
+            })
+            .sum();
+        // New disk takes 3.3 / 9.9 of 240 replicas, the rest should stay in place
+        assert!(moved <= 85, "{} replicas moved", moved);
+    }
+}
@@ -1,2 +1,3 @@
 pub mod center;
 pub mod utils;
+pub mod weights;
@@ -0,0 +1,130 @@
+use super::utils::read_file;
+use anyhow::{anyhow, Result as AnyResult};
+use serde_derive::Deserialize;
+use std::{collections::HashMap, fs::File, os::unix::ffi::OsStrExt, path::Path};
+use ubyte::ByteUnit;
+
+type NodeName = bob_common::node::NodeName;
+type DiskName = bob_common::core_types::DiskName;
+
+/// Relative weights of disks, vdisk replicas are placed proportionally to them.
+pub type DiskWeights = HashMap<(NodeName, DiskName), f64>;
+
+// Only the fields ccg needs, cluster config itself doesn't know about capacity and weight
+#[derive(Debug, Deserialize)]
+struct HardwareConfig {
+    nodes: Vec<HardwareNode>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HardwareNode {
+    name: String,
+    #[serde(default)]
+    disks: Vec<HardwareDisk>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HardwareDisk {
+    name: String,
+    path: String,
+    capacity: Option<ByteUnit>,
+    weight: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Source {
+    Weight,
+    Capacity,
+}
+
+/// Reads `weight` or `capacity` of every disk from the config, capacity of the disk mount
+/// point is used if `probe` is set and the disk has neither of them. Returns empty map if no
+/// disk has a weight, all disks must have the same kind of weight otherwise.
+pub fn read_disk_weights_from_file(name: &str, probe: bool) -> AnyResult<DiskWeights> {
+    let file = File::open(name).map_err(|e| anyhow!("open file: ERR [{}]", e))?;
+    let config: HardwareConfig = serde_yaml::from_str(&read_file(file)?)
+        .map_err(|e| anyhow!("deserialize disk weights: ERR [{}]", e))?;
+    let mut weights = DiskWeights::new();
+    let mut sources = vec![];
+    let mut missing = vec![];
+    for node in &config.nodes {
+        for disk in &node.disks {
+            let weight = match (disk.weight, disk.capacity) {
+                (Some(_), Some(_)) => {
+                    return Err(anyhow!(
+                        "disk weights: ERR [both weight and capacity are set for {}/{}]",
+                        node.name,
+                        disk.name
+                    ))
+                }
+                (Some(weight), None) => Some((Source::Weight, weight)),
+                (None, Some(capacity)) => Some((Source::Capacity, capacity.as_u64() as f64)),
+                (None, None) if probe => {
+                    let capacity = probe_capacity(Path::new(&disk.path)).ok_or_else(|| {
+                        anyhow!(
+                            "disk weights: ERR [can't probe capacity of {}/{} at {}]",
+                            node.name,
+                            disk.name,
+                            disk.path
+                        )
+                    })?;
+                    debug!(
+                        "probed capacity of {}/{}: {}",
+                        node.name, disk.name, capacity
+                    );
+                    Some((Source::Capacity, capacity as f64))
+                }
+                (None, None) => None,
+            };
+            let key = (
+                NodeName::from(node.name.as_str()),
+                DiskName::from(disk.name.as_str()),
+            );
+            match weight {
+                Some((_, weight)) if !weight.is_finite() || weight <= 0.0 => {
+                    return Err(anyhow!(
+                        "disk weights: ERR [weight of {}/{} must be positive]",
+                        node.name,
+                        disk.name
+                    ))
+                }
+                Some((source, weight)) => {
+                    sources.push(source);
+                    weights.insert(key, weight);
+                }
+                None => missing.push(format!("{}/{}", node.name, disk.name)),
+            }
+        }
+    }
+    if weights.is_empty() {
+        debug!("disk weights are not set, vdisks are balanced by count");
+        return Ok(weights);
+    }
+    if !missing.is_empty() {
+        return Err(anyhow!(
+            "disk weights: ERR [weight or capacity is not set for {}]",
+            missing.join(", ")
+        ));
+    }
+    if sources.windows(2).any(|pair| pair[0] != pair[1]) {
+        return Err(anyhow!(
+            "disk weights: ERR [weight and capacity can't be mixed in one config]"
+        ));
+    }
+    debug!("disk weights: OK [{} disks]", weights.len());
+    Ok(weights)
+}
+
+fn probe_capacity(path: &Path) -> Option<u64> {
+    let mut cpath = path.as_os_str().as_bytes().to_vec();
+    cpath.push(0);
+    // SAFETY: path is nul terminated and stat is a plain C struct filled by the call
+    unsafe {
+        let mut stat: libc::statvfs = std::mem::zeroed();
+        if libc::statvfs(cpath.as_ptr() as *const _, &mut stat) == 0 {
+            Some(stat.f_bsize as u64 * stat.f_blocks as u64)
+        } else {
+            None
+        }
+    }
+}
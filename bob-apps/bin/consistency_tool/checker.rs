@@ -0,0 +1,363 @@
+//! Linearizability checker of per-key register histories.
+//!
+//! Keys are independent, so the history of every key is checked separately by searching for a
+//! linearization (Wing & Gong search with memoization of visited states). Acknowledged
+//! operations must take effect between request and response, writes with unknown result may
+//! take effect at any moment after request or never. Failed writes and reads without result
+//! are not used. Keys may exist before the test, so reads may observe a value which is not
+//! written by the test until the first write of the key is applied.
+
+use super::history::{OperationRecord, OperationResult, Request};
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt,
+    str::FromStr,
+};
+
+const MS_IN_SEC: u64 = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Model {
+    /// Every key behaves as a single register
+    Linearizable,
+    /// Records are versioned by timestamp in seconds set by the node. Writes which may get equal
+    /// timestamps can be applied in any order, write with older timestamp is ignored
+    LastWriteWins,
+}
+
+impl FromStr for Model {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "linearizable" => Ok(Self::Linearizable),
+            "last-write-wins" => Ok(Self::LastWriteWins),
+            _ => Err(format!("unknown consistency model: {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for Model {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Linearizable => write!(f, "linearizable"),
+            Self::LastWriteWins => write!(f, "last-write-wins"),
+        }
+    }
+}
+
+pub(crate) struct CheckerSettings {
+    pub(crate) model: Model,
+    /// Unix time of the test start in milliseconds
+    pub(crate) started_at_ms: u64,
+    /// Max difference between clocks of tester and nodes
+    pub(crate) clock_skew_ms: u64,
+    /// Max count of visited states of one key, the key is skipped after it
+    pub(crate) search_limit: usize,
+}
+
+pub(crate) struct Violation {
+    pub(crate) key: u64,
+    /// Minimal subset of the key history which can't be linearized
+    pub(crate) operations: Vec<OperationRecord>,
+}
+
+#[derive(Default)]
+pub(crate) struct CheckReport {
+    pub(crate) checked_keys: usize,
+    /// Keys with too large histories to check
+    pub(crate) skipped_keys: Vec<u64>,
+    pub(crate) violations: Vec<Violation>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+enum Value {
+    /// Value before the test, it is unknown until read
+    #[default]
+    Initial,
+    Absent,
+    Written(u64),
+    /// Value of one of puts which are never read, reads can't tell them apart
+    Unobserved,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Effect {
+    Write(Value),
+    Read(Option<u64>),
+    Exists(bool),
+    Corrupted,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Event {
+    // Index in the key history
+    index: usize,
+    invoke: u64,
+    // Write with unknown result may take effect at any time after invoke
+    complete: Option<u64>,
+    effect: Effect,
+    // Range of timestamps (seconds) the node could assign to the write
+    timestamps: (u64, u64),
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+struct Register {
+    value: Value,
+    // Timestamps range of the write which has set the value
+    timestamps: Option<(u64, u64)>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum SearchResult {
+    Linearizable,
+    NotLinearizable,
+    LimitExceeded,
+}
+
+impl Model {
+    fn step(self, state: Register, event: &Event, written_values: &HashSet<u64>) -> Vec<Register> {
+        match event.effect {
+            Effect::Write(value) => {
+                let written = Register {
+                    value,
+                    timestamps: Some(event.timestamps),
+                };
+                match (self, state.timestamps) {
+                    (Model::LastWriteWins, Some((from, to))) => {
+                        let (write_from, write_to) = event.timestamps;
+                        let mut states = Vec::with_capacity(2);
+                        if write_to >= from {
+                            states.push(written);
+                        }
+                        if write_from <= to {
+                            states.push(state);
+                        }
+                        states
+                    }
+                    _ => vec![written],
+                }
+            }
+            Effect::Read(read) => match (state.value, read) {
+                (Value::Written(value), Some(read)) if value == read => vec![state],
+                (Value::Absent, None) => vec![state],
+                (Value::Initial, None) => vec![Register {
+                    value: Value::Absent,
+                    ..state
+                }],
+                // Value written before the test
+                (Value::Initial, Some(read)) if !written_values.contains(&read) => {
+                    vec![Register {
+                        value: Value::Written(read),
+                        ..state
+                    }]
+                }
+                _ => vec![],
+            },
+            Effect::Exists(false) => match state.value {
+                Value::Absent => vec![state],
+                Value::Initial => vec![Register {
+                    value: Value::Absent,
+                    ..state
+                }],
+                _ => vec![],
+            },
+            Effect::Exists(true) if state.value != Value::Absent => vec![state],
+            Effect::Exists(true) | Effect::Corrupted => vec![],
+        }
+    }
+}
+
+impl CheckerSettings {
+    fn timestamp(&self, offset_us: u64, skew: i64) -> u64 {
+        let ms = (self.started_at_ms + offset_us / 1000) as i64 + skew;
+        ms.max(0) as u64 / MS_IN_SEC
+    }
+
+    fn event(
+        &self,
+        index: usize,
+        op: &OperationRecord,
+        read_values: &HashSet<u64>,
+    ) -> Option<Event> {
+        let effect = match (&op.request, &op.result) {
+            (Request::Put { value }, OperationResult::Done | OperationResult::Unknown(_)) => {
+                if read_values.contains(value) {
+                    Effect::Write(Value::Written(*value))
+                } else {
+                    Effect::Write(Value::Unobserved)
+                }
+            }
+            (Request::Delete, OperationResult::Done | OperationResult::Unknown(_)) => {
+                Effect::Write(Value::Absent)
+            }
+            (_, OperationResult::Read(value)) => Effect::Read(*value),
+            (_, OperationResult::Exists(exists)) => Effect::Exists(*exists),
+            (_, OperationResult::Corrupted) => Effect::Corrupted,
+            _ => return None,
+        };
+        let skew = self.clock_skew_ms as i64;
+        let (complete, latest_timestamp) = if op.is_indeterminate() {
+            (None, u64::MAX)
+        } else {
+            (Some(op.complete_us), self.timestamp(op.complete_us, skew))
+        };
+        Some(Event {
+            index,
+            invoke: op.invoke_us,
+            complete,
+            effect,
+            timestamps: (self.timestamp(op.invoke_us, -skew), latest_timestamp),
+        })
+    }
+
+    /// Values of all puts of the key, including failed ones, are `written_values`.
+    fn search(&self, events: &[Event], written_values: &HashSet<u64>) -> SearchResult {
+        let required = events.iter().filter(|e| e.complete.is_some()).count();
+        let mut visited = HashSet::new();
+        let mut stack = vec![(Bits::new(events.len()), 0, Register::default())];
+        while let Some((done, done_required, state)) = stack.pop() {
+            if done_required == required {
+                return SearchResult::Linearizable;
+            }
+            if !visited.insert((done.clone(), state)) {
+                continue;
+            }
+            if visited.len() > self.search_limit {
+                return SearchResult::LimitExceeded;
+            }
+            // Operation can't be linearized after the response of another pending one
+            let horizon = events
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| !done.get(*i))
+                .filter_map(|(_, e)| e.complete)
+                .min()
+                .unwrap_or(u64::MAX);
+            let candidates = events
+                .iter()
+                .enumerate()
+                .take_while(|(_, e)| e.invoke <= horizon)
+                .filter(|(i, _)| !done.get(*i));
+            // Read which observes the current state can be linearized right away, it doesn't
+            // change the state and only releases other operations
+            let mut next_states = vec![];
+            let mut tried_unknown = vec![];
+            for (i, event) in candidates {
+                let states = self.model.step(state, event, written_values);
+                if !matches!(event.effect, Effect::Write(_)) && states == [state] {
+                    next_states = vec![(i, state)];
+                    break;
+                }
+                // Writes with unknown result are not required, so the earliest one of the same
+                // effect covers the rest
+                if event.complete.is_none() {
+                    if tried_unknown.contains(&event.effect) {
+                        continue;
+                    }
+                    tried_unknown.push(event.effect);
+                }
+                next_states.extend(states.into_iter().map(|next| (i, next)));
+            }
+            // Writes with unknown result are tried last
+            next_states.sort_by_key(|(i, _)| events[*i].complete.is_some());
+            for (i, next) in next_states {
+                let mut done = done.clone();
+                done.set(i);
+                let done_required = done_required + usize::from(events[i].complete.is_some());
+                stack.push((done, done_required, next));
+            }
+        }
+        SearchResult::NotLinearizable
+    }
+
+    /// Removes events while the rest still can't be linearized. Writes observed by remaining
+    /// reads are kept, so the reported history explains where the values came from.
+    fn minimize(&self, mut events: Vec<Event>, written_values: &HashSet<u64>) -> Vec<Event> {
+        // Reads usually follow writes, so removal goes from the end and repeats while the
+        // removed reads release their writes
+        let mut removed = true;
+        while removed {
+            removed = false;
+            for i in (0..events.len()).rev() {
+                let observed = match events[i].effect {
+                    Effect::Write(Value::Written(value)) => {
+                        events.iter().any(|e| e.effect == Effect::Read(Some(value)))
+                    }
+                    _ => false,
+                };
+                if observed {
+                    continue;
+                }
+                let mut candidate = events.clone();
+                candidate.remove(i);
+                if self.search(&candidate, written_values) == SearchResult::NotLinearizable {
+                    events = candidate;
+                    removed = true;
+                }
+            }
+        }
+        events
+    }
+}
+
+pub(crate) fn check(operations: &[OperationRecord], settings: &CheckerSettings) -> CheckReport {
+    let mut keys = BTreeMap::<u64, Vec<&OperationRecord>>::new();
+    for op in operations {
+        keys.entry(op.key).or_default().push(op);
+    }
+    let mut report = CheckReport::default();
+    for (key, mut ops) in keys {
+        ops.sort_by_key(|op| op.invoke_us);
+        let read_values = ops
+            .iter()
+            .filter_map(|op| match op.result {
+                OperationResult::Read(value) => value,
+                _ => None,
+            })
+            .collect::<HashSet<_>>();
+        let events = ops
+            .iter()
+            .enumerate()
+            .filter_map(|(i, op)| settings.event(i, op, &read_values))
+            .collect::<Vec<_>>();
+        let written_values = ops
+            .iter()
+            .filter_map(|op| match op.request {
+                Request::Put { value } => Some(value),
+                _ => None,
+            })
+            .collect::<HashSet<_>>();
+        report.checked_keys += 1;
+        match settings.search(&events, &written_values) {
+            SearchResult::Linearizable => {}
+            SearchResult::LimitExceeded => report.skipped_keys.push(key),
+            SearchResult::NotLinearizable => {
+                let operations = settings
+                    .minimize(events, &written_values)
+                    .into_iter()
+                    .map(|e| ops[e.index].clone())
+                    .collect();
+                report.violations.push(Violation { key, operations });
+            }
+        }
+    }
+    report
+}
+
+/// Set of linearized events.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Bits(Vec<u64>);
+
+impl Bits {
+    fn new(len: usize) -> Self {
+        Self(vec![0; len.div_ceil(64)])
+    }
+
+    fn get(&self, i: usize) -> bool {
+        self.0[i / 64] & (1 << (i % 64)) != 0
+    }
+
+    fn set(&mut self, i: usize) {
+        self.0[i / 64] |= 1 << (i % 64);
+    }
+}
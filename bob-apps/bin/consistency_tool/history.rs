@@ -0,0 +1,175 @@
+//! History of operations executed by the test clients and faults injected during the test.
+//!
+//! Every put writes a unique value, so a read tells which write it has observed:
+//!
+//! ```text
+//! value: u64 | payload len: u32 | filler bytes derived from value
+//! ```
+
+use serde_derive::Serialize;
+use std::{fmt, io::Write, path::Path};
+
+const VALUE_HEADER_LEN: usize = 12;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub(crate) enum Request {
+    Get,
+    Put { value: u64 },
+    Delete,
+    Exists,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case", tag = "type", content = "details")]
+pub(crate) enum OperationResult {
+    /// Write is acknowledged
+    Done,
+    /// Get returned the written value or `None` if the key is not found
+    Read(Option<u64>),
+    /// Get returned data which was never written
+    Corrupted,
+    Exists(bool),
+    /// Request is rejected, write is not applied
+    Failed(String),
+    /// Response is lost or has unexpected status, write may be applied
+    Unknown(String),
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct OperationRecord {
+    pub(crate) client: usize,
+    pub(crate) key: u64,
+    pub(crate) request: Request,
+    /// Microseconds since the start of the test
+    pub(crate) invoke_us: u64,
+    pub(crate) complete_us: u64,
+    pub(crate) result: OperationResult,
+}
+
+impl OperationRecord {
+    /// Write which may be applied even though it is not acknowledged.
+    pub(crate) fn is_indeterminate(&self) -> bool {
+        matches!(self.result, OperationResult::Unknown(_))
+    }
+}
+
+impl fmt::Display for OperationRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "[{:>10.6}s - {:>10.6}s] client {:<3} ",
+            self.invoke_us as f64 / 1e6,
+            self.complete_us as f64 / 1e6,
+            self.client
+        )?;
+        match self.request {
+            Request::Get => write!(f, "get    ")?,
+            Request::Put { value } => write!(f, "put    {:#018x} ", value)?,
+            Request::Delete => write!(f, "delete ")?,
+            Request::Exists => write!(f, "exists ")?,
+        }
+        match &self.result {
+            OperationResult::Done => write!(f, "-> ok"),
+            OperationResult::Read(Some(value)) => write!(f, "-> {:#018x}", value),
+            OperationResult::Read(None) => write!(f, "-> not found"),
+            OperationResult::Corrupted => write!(f, "-> corrupted data"),
+            OperationResult::Exists(exists) => write!(f, "-> {}", exists),
+            OperationResult::Failed(e) => write!(f, "-> failed: {}", e),
+            OperationResult::Unknown(e) => write!(f, "-> unknown: {}", e),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct FaultRecord {
+    pub(crate) fault: String,
+    pub(crate) injected_us: u64,
+    pub(crate) healed_us: u64,
+    pub(crate) error: Option<String>,
+}
+
+impl FaultRecord {
+    pub(crate) fn overlaps(&self, from_us: u64, to_us: u64) -> bool {
+        self.injected_us <= to_us && self.healed_us >= from_us
+    }
+}
+
+impl fmt::Display for FaultRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "[{:>10.6}s - {:>10.6}s] {}",
+            self.injected_us as f64 / 1e6,
+            self.healed_us as f64 / 1e6,
+            self.fault
+        )?;
+        if let Some(error) = &self.error {
+            write!(f, " ({})", error)?;
+        }
+        Ok(())
+    }
+}
+
+pub(crate) struct History {
+    /// Unix time of the test start in milliseconds
+    pub(crate) started_at_ms: u64,
+    pub(crate) operations: Vec<OperationRecord>,
+    pub(crate) faults: Vec<FaultRecord>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case", tag = "entry")]
+enum HistoryEntry<'a> {
+    Start { unix_time_ms: u64 },
+    Operation(&'a OperationRecord),
+    Fault(&'a FaultRecord),
+}
+
+impl History {
+    /// Saves history as JSON lines.
+    pub(crate) fn save(&self, path: &Path) -> Result<(), String> {
+        let file = std::fs::File::create(path).map_err(|e| e.to_string())?;
+        let mut writer = std::io::BufWriter::new(file);
+        let start = HistoryEntry::Start {
+            unix_time_ms: self.started_at_ms,
+        };
+        let entries = std::iter::once(start)
+            .chain(self.operations.iter().map(HistoryEntry::Operation))
+            .chain(self.faults.iter().map(HistoryEntry::Fault));
+        for entry in entries {
+            serde_json::to_writer(&mut writer, &entry).map_err(|e| e.to_string())?;
+            writer.write_all(b"\n").map_err(|e| e.to_string())?;
+        }
+        writer.flush().map_err(|e| e.to_string())
+    }
+}
+
+fn filler(value: u64, index: usize) -> u8 {
+    (value as u8).wrapping_add(index as u8)
+}
+
+/// Data of the write with given value, `size` filler bytes follow the header.
+pub(crate) fn encode_value(value: u64, size: usize) -> Vec<u8> {
+    let len = VALUE_HEADER_LEN + size;
+    let mut data = Vec::with_capacity(len);
+    data.extend_from_slice(&value.to_le_bytes());
+    data.extend_from_slice(&(len as u32).to_le_bytes());
+    data.extend((0..size).map(|i| filler(value, i)));
+    data
+}
+
+/// Returns `None` if data is not produced by [`encode_value`].
+pub(crate) fn decode_value(data: &[u8]) -> Option<u64> {
+    if data.len() < VALUE_HEADER_LEN {
+        return None;
+    }
+    let value = u64::from_le_bytes(data[..8].try_into().expect("8 bytes"));
+    let len = u32::from_le_bytes(data[8..VALUE_HEADER_LEN].try_into().expect("4 bytes"));
+    let valid = len as usize == data.len()
+        && data[VALUE_HEADER_LEN..]
+            .iter()
+            .enumerate()
+            .all(|(i, b)| *b == filler(value, i));
+    valid.then_some(value)
+}
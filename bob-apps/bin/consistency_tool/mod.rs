@@ -0,0 +1,3 @@
+pub(crate) mod checker;
+pub(crate) mod history;
+pub(crate) mod nemesis;
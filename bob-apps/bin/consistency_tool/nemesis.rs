@@ -0,0 +1,201 @@
+//! Fault injection during the test. Faults are taken from the config file:
+//!
+//! ```yaml
+//! interval: 10s # pause between faults
+//! duration: 5s  # time before the fault is healed
+//! disks:        # stopped and started with REST API of the node
+//!   - api: http://node1:8000
+//!     disk: disk1
+//! nodes:        # killed and started with shell commands
+//!   - name: node1
+//!     kill: docker kill bob_node1
+//!     start: docker start bob_node1
+//! ```
+
+use super::history::FaultRecord;
+use humantime::Duration as HumanDuration;
+use rand::seq::SliceRandom;
+use reqwest::{Client, RequestBuilder};
+use serde_derive::Deserialize;
+use std::{fmt, path::Path, time::Duration};
+use tokio::{process::Command, sync::watch, time::Instant};
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct DiskFault {
+    api: String,
+    disk: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct NodeFault {
+    name: String,
+    kill: String,
+    start: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FaultsConfig {
+    interval: String,
+    duration: String,
+    #[serde(default)]
+    disks: Vec<DiskFault>,
+    #[serde(default)]
+    nodes: Vec<NodeFault>,
+}
+
+#[derive(Debug, Clone)]
+enum Fault {
+    Disk(DiskFault),
+    Node(NodeFault),
+}
+
+impl fmt::Display for Fault {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Fault::Disk(disk) => write!(f, "disk {} stopped on {}", disk.disk, disk.api),
+            Fault::Node(node) => write!(f, "node {} killed", node.name),
+        }
+    }
+}
+
+pub(crate) struct Nemesis {
+    interval: Duration,
+    duration: Duration,
+    faults: Vec<Fault>,
+    http_client: Client,
+    credentials: Option<(String, String)>,
+}
+
+impl Nemesis {
+    pub(crate) fn from_file(
+        path: &Path,
+        credentials: Option<(String, String)>,
+    ) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let config: FaultsConfig = serde_yaml::from_str(&content).map_err(|e| e.to_string())?;
+        let parse = |s: &str| {
+            s.parse::<HumanDuration>()
+                .map(Into::into)
+                .map_err(|e| format!("wrong duration '{}': {}", s, e))
+        };
+        let faults = config
+            .disks
+            .into_iter()
+            .map(Fault::Disk)
+            .chain(config.nodes.into_iter().map(Fault::Node))
+            .collect::<Vec<_>>();
+        if faults.is_empty() {
+            return Err("no faults in config".to_string());
+        }
+        Ok(Self {
+            interval: parse(&config.interval)?,
+            duration: parse(&config.duration)?,
+            faults,
+            http_client: Client::new(),
+            credentials,
+        })
+    }
+
+    fn authorize(&self, request: RequestBuilder) -> RequestBuilder {
+        match &self.credentials {
+            Some((username, password)) => request.basic_auth(username, Some(password)),
+            None => request,
+        }
+    }
+
+    async fn post_disk(&self, disk: &DiskFault, action: &str) -> Result<(), String> {
+        let url = format!(
+            "{}/disks/{}/{}",
+            disk.api.trim_end_matches('/'),
+            disk.disk,
+            action
+        );
+        let response = self
+            .authorize(self.http_client.post(url))
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(format!("{} {}", action, response.status()))
+        }
+    }
+
+    async fn inject(&self, fault: &Fault) -> Result<(), String> {
+        match fault {
+            Fault::Disk(disk) => self.post_disk(disk, "stop").await,
+            Fault::Node(node) => run_command(&node.kill).await,
+        }
+    }
+
+    async fn heal(&self, fault: &Fault) -> Result<(), String> {
+        match fault {
+            Fault::Disk(disk) => self.post_disk(disk, "start").await,
+            Fault::Node(node) => run_command(&node.start).await,
+        }
+    }
+
+    /// Injects random faults until `stop` is set, active fault is healed before return.
+    pub(crate) async fn run(
+        self,
+        started: Instant,
+        mut stop: watch::Receiver<bool>,
+    ) -> Vec<FaultRecord> {
+        let mut records = vec![];
+        let offset_us = || started.elapsed().as_micros() as u64;
+        loop {
+            tokio::select! {
+                _ = tokio::time::sleep(self.interval) => {}
+                _ = stop.changed() => break,
+            }
+            let fault = self
+                .faults
+                .choose(&mut rand::thread_rng())
+                .expect("faults are not empty")
+                .clone();
+            log::info!("inject fault: {}", fault);
+            let injected_us = offset_us();
+            let mut error = self.inject(&fault).await.err();
+            if let Some(e) = &error {
+                log::warn!("failed to inject fault '{}': {}", fault, e);
+            }
+            let stopped = tokio::select! {
+                _ = tokio::time::sleep(self.duration) => false,
+                _ = stop.changed() => true,
+            };
+            log::info!("heal fault: {}", fault);
+            if let Err(e) = self.heal(&fault).await {
+                log::warn!("failed to heal fault '{}': {}", fault, e);
+                error.get_or_insert(e);
+            }
+            records.push(FaultRecord {
+                fault: fault.to_string(),
+                injected_us,
+                healed_us: offset_us(),
+                error,
+            });
+            if stopped {
+                break;
+            }
+        }
+        records
+    }
+}
+
+async fn run_command(command: &str) -> Result<(), String> {
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .status()
+        .await
+        .map_err(|e| e.to_string())?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("'{}' exited with {}", command, status))
+    }
+}
@@ -0,0 +1,254 @@
+//! Portable archive of bob records.
+//!
+//! ```text
+//! header:    b"BOBARCH1" | key size: u16
+//! record:    1 | key | timestamp: u64 | data len: u32 | data | crc32: u32
+//! tombstone: 2 | key | timestamp: u64 | 0: u32 | crc32: u32
+//! end:       0 | entries count: u64 | crc32: u32
+//! ```
+//!
+//! Numbers are little-endian, crc32 covers all previous bytes of the entry. Archive without the
+//! end entry is truncated.
+
+use super::prelude::*;
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter, Read, Seek, SeekFrom},
+};
+
+const MAGIC: &[u8; 8] = b"BOBARCH1";
+const HEADER_LEN: u64 = MAGIC.len() as u64 + 2;
+const END_TAG: u8 = 0;
+const RECORD_TAG: u8 = 1;
+const TOMBSTONE_TAG: u8 = 2;
+
+const CRC_TABLE: [u32; 256] = crc_table();
+
+const fn crc_table() -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+/// CRC-32 (IEEE) of the concatenated parts.
+fn crc32(parts: &[&[u8]]) -> u32 {
+    let mut crc = !0_u32;
+    for byte in parts.iter().flat_map(|part| part.iter()) {
+        crc = CRC_TABLE[((crc ^ u32::from(*byte)) & 0xFF) as usize] ^ (crc >> 8);
+    }
+    !crc
+}
+
+/// Record or tombstone of the key, tombstone has no data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Entry {
+    pub(crate) key: Vec<u8>,
+    pub(crate) timestamp: u64,
+    pub(crate) data: Option<Vec<u8>>,
+}
+
+pub(crate) struct ArchiveWriter {
+    inner: BufWriter<File>,
+    key_size: u16,
+    count: u64,
+}
+
+impl ArchiveWriter {
+    pub(crate) fn create(path: &Path, key_size: u16) -> AnyResult<Self> {
+        let mut inner = BufWriter::new(File::create(path)?);
+        inner.write_all(MAGIC)?;
+        inner.write_all(&key_size.to_le_bytes())?;
+        Ok(Self {
+            inner,
+            key_size,
+            count: 0,
+        })
+    }
+
+    pub(crate) fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub(crate) fn write(&mut self, entry: &Entry) -> AnyResult<()> {
+        if entry.key.len() != self.key_size as usize {
+            anyhow::bail!(
+                "key {} has size {}, archive key size is {}",
+                hex::encode(&entry.key),
+                entry.key.len(),
+                self.key_size
+            );
+        }
+        let (tag, data) = match &entry.data {
+            Some(data) => (RECORD_TAG, data.as_slice()),
+            None => (TOMBSTONE_TAG, &[][..]),
+        };
+        let len = u32::try_from(data.len())?;
+        let parts: [&[u8]; 5] = [
+            &[tag],
+            &entry.key,
+            &entry.timestamp.to_le_bytes(),
+            &len.to_le_bytes(),
+            data,
+        ];
+        for part in parts {
+            self.inner.write_all(part)?;
+        }
+        self.inner.write_all(&crc32(&parts).to_le_bytes())?;
+        self.count += 1;
+        Ok(())
+    }
+
+    pub(crate) fn finish(mut self) -> AnyResult<u64> {
+        let count = self.count.to_le_bytes();
+        let crc = crc32(&[&[END_TAG], &count]);
+        self.inner.write_all(&[END_TAG])?;
+        self.inner.write_all(&count)?;
+        self.inner.write_all(&crc.to_le_bytes())?;
+        self.inner.flush()?;
+        Ok(self.count)
+    }
+}
+
+pub(crate) struct ArchiveReader {
+    inner: BufReader<File>,
+    len: u64,
+    key_size: u16,
+    offset: u64,
+    count: u64,
+    finished: bool,
+}
+
+impl ArchiveReader {
+    pub(crate) fn open(path: &Path) -> AnyResult<Self> {
+        let file = File::open(path)?;
+        let len = file.metadata()?.len();
+        let mut inner = BufReader::new(file);
+        let mut magic = [0; MAGIC.len()];
+        inner.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            anyhow::bail!("{} is not a bob archive", path.display());
+        }
+        let mut key_size = [0; 2];
+        inner.read_exact(&mut key_size)?;
+        Ok(Self {
+            inner,
+            len,
+            key_size: u16::from_le_bytes(key_size),
+            offset: HEADER_LEN,
+            count: 0,
+            finished: false,
+        })
+    }
+
+    pub(crate) fn key_size(&self) -> u16 {
+        self.key_size
+    }
+
+    /// Offset of the next entry and count of entries before it.
+    pub(crate) fn position(&self) -> (u64, u64) {
+        (self.offset, self.count)
+    }
+
+    /// Continues reading from the position returned by [`Self::position`].
+    pub(crate) fn seek(&mut self, offset: u64, count: u64) -> AnyResult<()> {
+        self.inner.seek(SeekFrom::Start(offset))?;
+        self.offset = offset;
+        self.count = count;
+        Ok(())
+    }
+
+    fn read_array<const N: usize>(&mut self) -> AnyResult<[u8; N]> {
+        let mut buf = [0; N];
+        self.inner.read_exact(&mut buf).map_err(|e| {
+            anyhow::anyhow!("archive is truncated at offset {}: {}", self.offset, e)
+        })?;
+        Ok(buf)
+    }
+
+    fn check_crc(&mut self, parts: &[&[u8]]) -> AnyResult<()> {
+        let crc = u32::from_le_bytes(self.read_array()?);
+        if crc != crc32(parts) {
+            anyhow::bail!("checksum mismatch of the entry at offset {}", self.offset);
+        }
+        Ok(())
+    }
+
+    /// Returns the next entry with verified checksum, `None` after the end entry.
+    pub(crate) fn next_entry(&mut self) -> AnyResult<Option<Entry>> {
+        if self.finished {
+            return Ok(None);
+        }
+        let [tag] = self.read_array()?;
+        if tag == END_TAG {
+            let count = self.read_array::<8>()?;
+            self.check_crc(&[&[tag], &count])?;
+            if u64::from_le_bytes(count) != self.count {
+                anyhow::bail!(
+                    "archive has {} entries, {} are read",
+                    u64::from_le_bytes(count),
+                    self.count
+                );
+            }
+            self.finished = true;
+            return Ok(None);
+        }
+        if tag != RECORD_TAG && tag != TOMBSTONE_TAG {
+            anyhow::bail!("unknown entry {} at offset {}", tag, self.offset);
+        }
+        let mut key = vec![0; self.key_size as usize];
+        self.inner.read_exact(&mut key)?;
+        let timestamp = self.read_array::<8>()?;
+        let len = self.read_array::<4>()?;
+        let data_len = u64::from(u32::from_le_bytes(len));
+        // Damaged length must not allocate more than the archive can hold
+        let data_offset = self.offset + 1 + key.len() as u64 + 8 + 4;
+        if data_offset + data_len > self.len {
+            anyhow::bail!(
+                "entry at offset {} has data length {} beyond the end of the archive",
+                self.offset,
+                data_len
+            );
+        }
+        if tag == TOMBSTONE_TAG && data_len != 0 {
+            anyhow::bail!("tombstone at offset {} has data", self.offset);
+        }
+        let mut data = vec![0; data_len as usize];
+        self.inner.read_exact(&mut data)?;
+        self.check_crc(&[&[tag], &key, &timestamp, &len, &data])?;
+        self.offset += 1 + key.len() as u64 + 8 + 4 + data.len() as u64 + 4;
+        self.count += 1;
+        Ok(Some(Entry {
+            key,
+            timestamp: u64::from_le_bytes(timestamp),
+            data: (tag == RECORD_TAG).then_some(data),
+        }))
+    }
+}
+
+/// Converts the key to another key size. Keys are little-endian, so high zero bytes are added
+/// or removed.
+pub(crate) fn convert_key(mut key: Vec<u8>, key_size: usize) -> AnyResult<Vec<u8>> {
+    if key.len() > key_size && key[key_size..].iter().any(|b| *b != 0) {
+        anyhow::bail!(
+            "key {} doesn't fit into {} bytes",
+            hex::encode(&key),
+            key_size
+        );
+    }
+    key.resize(key_size, 0);
+    Ok(key)
+}
@@ -0,0 +1,330 @@
+use super::{archive::ArchiveReader, export, import, prelude::*};
+
+const CLUSTER_OPT: &str = "cluster config";
+const PEARL_OPT: &str = "pearl path";
+const INPUT_OPT: &str = "input archive";
+const OUTPUT_OPT: &str = "output archive";
+const ADDRESS_OPT: &str = "address";
+const KEY_SIZE_OPT: &str = "key size";
+const PARALLELISM_OPT: &str = "parallelism";
+const USER_OPT: &str = "user";
+const PASSWORD_OPT: &str = "password";
+const CA_PATH_OPT: &str = "ca path";
+const DOMAIN_NAME_OPT: &str = "domain name";
+
+const EXPORT_COMMAND: &str = "export";
+const IMPORT_COMMAND: &str = "import";
+const VERIFY_COMMAND: &str = "verify";
+
+pub enum MainCommand {
+    Export(ExportCommand),
+    Import(ImportCommand),
+    Verify(VerifyCommand),
+}
+
+fn auth_args<'a, 'b>(command: App<'a, 'b>) -> App<'a, 'b> {
+    command
+        .arg(
+            Arg::with_name(USER_OPT)
+                .help("username for auth")
+                .takes_value(true)
+                .requires(PASSWORD_OPT)
+                .long("user"),
+        )
+        .arg(
+            Arg::with_name(PASSWORD_OPT)
+                .help("password for auth")
+                .takes_value(true)
+                .requires(USER_OPT)
+                .long("password"),
+        )
+}
+
+fn auth_from_matches(matches: &ArgMatches) -> AnyResult<Auth> {
+    Auth::new(matches.value_of(USER_OPT), matches.value_of(PASSWORD_OPT))
+}
+
+fn tls_args<'a, 'b>(command: App<'a, 'b>) -> App<'a, 'b> {
+    command
+        .arg(
+            Arg::with_name(CA_PATH_OPT)
+                .help("path to tls ca certificate, nodes are connected with tls if it's set")
+                .takes_value(true)
+                .requires(DOMAIN_NAME_OPT)
+                .long("ca-path"),
+        )
+        .arg(
+            Arg::with_name(DOMAIN_NAME_OPT)
+                .help("tls domain name")
+                .takes_value(true)
+                .requires(CA_PATH_OPT)
+                .long("domain-name"),
+        )
+}
+
+fn tls_from_matches(matches: &ArgMatches) -> AnyResult<Tls> {
+    Tls::new(
+        matches.value_of(CA_PATH_OPT),
+        matches.value_of(DOMAIN_NAME_OPT),
+    )
+}
+
+fn key_size_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name(KEY_SIZE_OPT)
+        .help("size of the binary key")
+        .takes_value(true)
+        .default_value("8")
+        .long("key-size")
+}
+
+pub struct ExportCommand {
+    source: ExportSource,
+    output: PathBuf,
+    key_size: u16,
+    auth: Auth,
+    tls: Tls,
+}
+
+enum ExportSource {
+    Cluster(PathBuf),
+    Pearl(PathBuf),
+}
+
+impl ExportCommand {
+    async fn run(&self) -> AnyResult<()> {
+        let result = match &self.source {
+            ExportSource::Cluster(config) => {
+                export::export_cluster(
+                    config,
+                    &self.output,
+                    self.key_size,
+                    &self.auth,
+                    &self.tls,
+                )
+                .await
+            }
+            ExportSource::Pearl(path) => export::export_pearl(path, &self.output).await,
+        };
+        if result.is_err() && self.output.exists() {
+            // Archive without the end entry can't be imported anyway
+            std::fs::remove_file(&self.output)?;
+        }
+        let count = result?;
+        info!("{} entries exported to {}", count, self.output.display());
+        Ok(())
+    }
+
+    fn subcommand<'a, 'b>() -> App<'a, 'b> {
+        let command = SubCommand::with_name(EXPORT_COMMAND)
+            .about(
+                "export records of the running cluster or of pearl directories of the stopped node",
+            )
+            .arg(
+                Arg::with_name(CLUSTER_OPT)
+                    .help("cluster config, records are read from the running nodes")
+                    .takes_value(true)
+                    .required_unless(PEARL_OPT)
+                    .conflicts_with(PEARL_OPT)
+                    .short("c")
+                    .long("cluster"),
+            )
+            .arg(
+                Arg::with_name(PEARL_OPT)
+                    .help(
+                        "directory with pearl storages, records and tombstones are read from blobs",
+                    )
+                    .takes_value(true)
+                    .short("p")
+                    .long("pearl"),
+            )
+            .arg(
+                Arg::with_name(OUTPUT_OPT)
+                    .help("output archive")
+                    .takes_value(true)
+                    .required(true)
+                    .short("o")
+                    .long("output"),
+            )
+            .arg(key_size_arg());
+        tls_args(auth_args(command))
+    }
+
+    fn from_matches(matches: &ArgMatches) -> AnyResult<ExportCommand> {
+        let source = match matches.value_of(CLUSTER_OPT) {
+            Some(config) => ExportSource::Cluster(config.into()),
+            None => ExportSource::Pearl(matches.value_of(PEARL_OPT).expect("Required").into()),
+        };
+        Ok(ExportCommand {
+            source,
+            output: matches.value_of(OUTPUT_OPT).expect("Required").into(),
+            key_size: matches
+                .value_of(KEY_SIZE_OPT)
+                .expect("Has default")
+                .parse()?,
+            auth: auth_from_matches(matches)?,
+            tls: tls_from_matches(matches)?,
+        })
+    }
+}
+
+pub struct ImportCommand {
+    input: PathBuf,
+    address: String,
+    key_size: u16,
+    parallelism: usize,
+    auth: Auth,
+    tls: Tls,
+}
+
+impl ImportCommand {
+    async fn run(&self) -> AnyResult<()> {
+        let count = import::import(
+            &self.input,
+            &self.address,
+            self.key_size,
+            self.parallelism,
+            &self.auth,
+            &self.tls,
+        )
+        .await?;
+        info!("{} entries imported to {}", count, self.address);
+        Ok(())
+    }
+
+    fn subcommand<'a, 'b>() -> App<'a, 'b> {
+        let command = SubCommand::with_name(IMPORT_COMMAND)
+            .about(
+                "put records and delete tombstones of the archive, interrupted import is resumed",
+            )
+            .arg(
+                Arg::with_name(INPUT_OPT)
+                    .help("input archive")
+                    .takes_value(true)
+                    .required(true)
+                    .short("i")
+                    .long("input"),
+            )
+            .arg(
+                Arg::with_name(ADDRESS_OPT)
+                    .help("grpc address of the node, host:port or uri with scheme")
+                    .takes_value(true)
+                    .default_value("localhost:20000")
+                    .short("a")
+                    .long("address"),
+            )
+            .arg(key_size_arg())
+            .arg(
+                Arg::with_name(PARALLELISM_OPT)
+                    .help("count of concurrent requests")
+                    .takes_value(true)
+                    .default_value("16")
+                    .long("parallelism"),
+            );
+        tls_args(auth_args(command))
+    }
+
+    fn from_matches(matches: &ArgMatches) -> AnyResult<ImportCommand> {
+        let parallelism = matches
+            .value_of(PARALLELISM_OPT)
+            .expect("Has default")
+            .parse()?;
+        if parallelism == 0 {
+            anyhow::bail!("parallelism must be positive");
+        }
+        Ok(ImportCommand {
+            input: matches.value_of(INPUT_OPT).expect("Required").into(),
+            address: matches
+                .value_of(ADDRESS_OPT)
+                .expect("Has default")
+                .to_string(),
+            key_size: matches
+                .value_of(KEY_SIZE_OPT)
+                .expect("Has default")
+                .parse()?,
+            parallelism,
+            auth: auth_from_matches(matches)?,
+            tls: tls_from_matches(matches)?,
+        })
+    }
+}
+
+pub struct VerifyCommand {
+    input: PathBuf,
+}
+
+impl VerifyCommand {
+    fn run(&self) -> AnyResult<()> {
+        let mut reader = ArchiveReader::open(&self.input)?;
+        let (mut records, mut tombstones) = (0, 0);
+        while let Some(entry) = reader.next_entry()? {
+            if entry.data.is_some() {
+                records += 1;
+            } else {
+                tombstones += 1;
+            }
+        }
+        info!(
+            "archive {} is valid: key size {}, {} records, {} tombstones",
+            self.input.display(),
+            reader.key_size(),
+            records,
+            tombstones
+        );
+        Ok(())
+    }
+
+    fn subcommand<'a, 'b>() -> App<'a, 'b> {
+        SubCommand::with_name(VERIFY_COMMAND)
+            .about("verify checksums of the archive")
+            .arg(
+                Arg::with_name(INPUT_OPT)
+                    .help("input archive")
+                    .takes_value(true)
+                    .required(true)
+                    .short("i")
+                    .long("input"),
+            )
+    }
+
+    fn from_matches(matches: &ArgMatches) -> AnyResult<VerifyCommand> {
+        Ok(VerifyCommand {
+            input: matches.value_of(INPUT_OPT).expect("Required").into(),
+        })
+    }
+}
+
+impl MainCommand {
+    pub async fn run() -> AnyResult<()> {
+        let settings = MainCommand::from_matches()?;
+        match settings {
+            MainCommand::Export(settings) => settings.run().await,
+            MainCommand::Import(settings) => settings.run().await,
+            MainCommand::Verify(settings) => settings.run(),
+        }
+    }
+
+    fn get_matches<'a>() -> ArgMatches<'a> {
+        App::new(format!("Data transfer tool, {}", env!("CARGO_PKG_NAME")))
+            .version(env!("CARGO_PKG_VERSION"))
+            .subcommand(ExportCommand::subcommand())
+            .subcommand(ImportCommand::subcommand())
+            .subcommand(VerifyCommand::subcommand())
+            .get_matches()
+    }
+
+    fn from_matches() -> AnyResult<MainCommand> {
+        let matches = Self::get_matches();
+        match matches.subcommand() {
+            (EXPORT_COMMAND, Some(matches)) => {
+                Ok(MainCommand::Export(ExportCommand::from_matches(matches)?))
+            }
+            (IMPORT_COMMAND, Some(matches)) => {
+                Ok(MainCommand::Import(ImportCommand::from_matches(matches)?))
+            }
+            (VERIFY_COMMAND, Some(matches)) => {
+                Ok(MainCommand::Verify(VerifyCommand::from_matches(matches)?))
+            }
+            _ => Err(anyhow::anyhow!("Unknown command")),
+        }
+    }
+}
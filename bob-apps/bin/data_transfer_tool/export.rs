@@ -0,0 +1,235 @@
+use super::{
+    archive::{ArchiveWriter, Entry},
+    prelude::*,
+};
+use bob::{BobApiClient, ClusterConfig, PearlKey, StreamVDiskRequest, BOB_KEY_SIZE};
+use bob_common::data::BobData;
+use futures::StreamExt;
+use pearl::{Builder, Storage};
+use std::collections::{HashMap, HashSet};
+
+// Pearl requires blob limits even if nothing is written
+const READ_ONLY_MAX_BLOB_SIZE: u64 = 1 << 40;
+const READ_ONLY_MAX_DATA_IN_BLOB: u64 = 1 << 30;
+// Directories of quarantined blobs and vdisk snapshots hold copies of records
+const SKIPPED_DIRS: [&str; 2] = ["corrupted", "snapshots"];
+
+/// Exports records and tombstones of all vdisks of the cluster with `StreamVDisk` RPC. Every
+/// replica of the vdisk is read, so records missing on some of them are still exported. Only
+/// versions newer than the already exported one are written, the newest version wins on import.
+/// Vdisk fails if none of its replicas is read to the end.
+pub(crate) async fn export_cluster(
+    cluster: &Path,
+    output: &Path,
+    key_size: u16,
+    auth: &Auth,
+    tls: &Tls,
+) -> AnyResult<u64> {
+    let cluster = ClusterConfig::try_get(&cluster.to_string_lossy())
+        .await
+        .map_err(|e| anyhow::anyhow!(e))?;
+    let addresses = cluster
+        .nodes()
+        .iter()
+        .map(|node| (node.name(), node.address()))
+        .collect::<HashMap<_, _>>();
+    let mut writer = ArchiveWriter::create(output, key_size)?;
+    let mut clients = HashMap::new();
+    for vdisk in cluster.vdisks() {
+        let mut nodes = vdisk
+            .replicas()
+            .iter()
+            .map(|r| r.node())
+            .collect::<Vec<_>>();
+        nodes.sort_unstable();
+        nodes.dedup();
+        // Keys of different vdisks don't intersect
+        let mut exported = HashMap::new();
+        let mut completed = false;
+        for node in nodes {
+            let address = addresses
+                .get(node)
+                .ok_or_else(|| anyhow::anyhow!("node {} is not in cluster config", node))?;
+            let before = writer.count();
+            let result = export_vdisk(
+                &mut clients,
+                address,
+                vdisk.id(),
+                &mut writer,
+                &mut exported,
+                auth,
+                tls,
+            )
+            .await;
+            match result {
+                Ok(()) => {
+                    info!(
+                        "vdisk {}: {} entries exported from {}",
+                        vdisk.id(),
+                        writer.count() - before,
+                        node
+                    );
+                    completed = true;
+                }
+                Err(e) => warn!("vdisk {}: export from {} failed: {:#}", vdisk.id(), node, e),
+            }
+        }
+        if !completed {
+            anyhow::bail!("vdisk {} can't be exported from any replica", vdisk.id());
+        }
+    }
+    writer.finish()
+}
+
+async fn export_vdisk(
+    clients: &mut HashMap<String, BobApiClient<Channel>>,
+    address: &str,
+    vdisk_id: u32,
+    writer: &mut ArchiveWriter,
+    exported: &mut HashMap<Vec<u8>, u64>,
+    auth: &Auth,
+    tls: &Tls,
+) -> AnyResult<()> {
+    if !clients.contains_key(address) {
+        let client = tls.connect(address).await?;
+        clients.insert(address.to_owned(), client);
+    }
+    let client = clients.get_mut(address).expect("inserted");
+    let message = StreamVDiskRequest {
+        vdisk_id,
+        start_after: None,
+        start_partition: 0,
+    };
+    let mut records = client
+        .stream_v_disk(auth.request(message))
+        .await?
+        .into_inner();
+    while let Some(record) = records.next().await {
+        let record = record?;
+        let key = record.key.map(|k| k.key).unwrap_or_default();
+        let blob = record
+            .data
+            .ok_or_else(|| anyhow::anyhow!("record {} has no data", hex::encode(&key)))?;
+        let timestamp = blob.meta.map_or(0, |m| m.timestamp);
+        if matches!(exported.get(&key), Some(ts) if *ts >= timestamp) {
+            continue;
+        }
+        writer.write(&Entry {
+            key: key.clone(),
+            timestamp,
+            data: (!record.deleted).then(|| blob.data.to_vec()),
+        })?;
+        exported.insert(key, timestamp);
+    }
+    Ok(())
+}
+
+/// Exports records and tombstones from pearl directories found under `path`. Node must be
+/// stopped, storages are opened without active blobs. If the key is found in several
+/// directories, only versions newer than the already exported one are written.
+pub(crate) async fn export_pearl(path: &Path, output: &Path) -> AnyResult<u64> {
+    let mut dirs = vec![];
+    find_pearl_dirs(path, &mut dirs)?;
+    dirs.sort();
+    let mut writer = ArchiveWriter::create(output, BOB_KEY_SIZE as u16)?;
+    let mut exported = HashMap::<Vec<u8>, u64>::new();
+    for (dir, prefix) in dirs {
+        let before = writer.count();
+        export_pearl_dir(&dir, &prefix, &mut writer, &mut exported)
+            .await
+            .with_context(|| format!("export of {} failed", dir.display()))?;
+        info!(
+            "{}: {} entries exported",
+            dir.display(),
+            writer.count() - before
+        );
+    }
+    writer.finish()
+}
+
+// Directories with blobs and prefixes of their blob file names
+fn find_pearl_dirs(path: &Path, dirs: &mut Vec<(PathBuf, String)>) -> AnyResult<()> {
+    let mut prefix = None;
+    for entry in std::fs::read_dir(path)? {
+        let entry_path = entry?.path();
+        let name = entry_path.file_name().unwrap_or_default().to_string_lossy();
+        if entry_path.is_dir() {
+            if !SKIPPED_DIRS.contains(&name.as_ref()) {
+                find_pearl_dirs(&entry_path, dirs)?;
+            }
+        } else if entry_path.extension() == Some("blob".as_ref()) {
+            prefix = name.split('.').next().map(String::from);
+        }
+    }
+    if let Some(prefix) = prefix {
+        dirs.push((path.to_owned(), prefix));
+    }
+    Ok(())
+}
+
+async fn export_pearl_dir(
+    dir: &Path,
+    prefix: &str,
+    writer: &mut ArchiveWriter,
+    exported: &mut HashMap<Vec<u8>, u64>,
+) -> AnyResult<()> {
+    let mut storage: Storage<PearlKey> = Builder::new()
+        .work_dir(dir)
+        .blob_file_name_prefix(prefix)
+        .max_blob_size(READ_ONLY_MAX_BLOB_SIZE)
+        .max_data_in_blob(READ_ONLY_MAX_DATA_IN_BLOB)
+        .allow_duplicates()
+        .build()?;
+    storage.init_lazy().await?;
+    let result = export_storage(&storage, dir, writer, exported).await;
+    storage.close().await?;
+    result
+}
+
+async fn export_storage(
+    storage: &Storage<PearlKey>,
+    dir: &Path,
+    writer: &mut ArchiveWriter,
+    exported: &mut HashMap<Vec<u8>, u64>,
+) -> AnyResult<()> {
+    // Indexes of blobs are regenerated by init if they are missing
+    let mut keys = HashSet::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension() == Some("index".as_ref()) {
+            keys.extend(read_index(&path).await?.into_keys());
+        }
+    }
+    let mut keys = keys.into_iter().collect::<Vec<_>>();
+    keys.sort();
+    for key in keys {
+        let entries = storage
+            .read_all_with_deletion_marker(&PearlKey::from(key.clone()))
+            .await?;
+        let newest = match entries
+            .into_iter()
+            .max_by_key(|e| -> u64 { e.timestamp().into() })
+        {
+            Some(entry) => entry,
+            None => continue,
+        };
+        let timestamp: u64 = newest.timestamp().into();
+        if matches!(exported.get(&key), Some(ts) if *ts >= timestamp) {
+            continue;
+        }
+        let data = if newest.is_deleted() {
+            None
+        } else {
+            let data = BobData::from_serialized_bytes(newest.load_data().await?.freeze())
+                .map_err(|e| anyhow::anyhow!("record {}: {}", hex::encode(&key), e))?;
+            Some(data.into_inner().to_vec())
+        };
+        writer.write(&Entry {
+            key: key.clone(),
+            timestamp,
+            data,
+        })?;
+        exported.insert(key, timestamp);
+    }
+    Ok(())
+}
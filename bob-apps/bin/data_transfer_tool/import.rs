@@ -0,0 +1,125 @@
+use super::{
+    archive::{convert_key, ArchiveReader, Entry},
+    prelude::*,
+};
+use bob::{Blob, BlobMeta, BobApiClient, DeleteOptions, DeleteRequest, PutOptions, PutRequest};
+use futures::{stream::FuturesOrdered, StreamExt};
+
+// Position is saved after this count of imported entries
+const PROGRESS_SAVE_INTERVAL: u64 = 1000;
+const PROGRESS_LOG_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Position of the first not imported entry, saved next to the archive.
+fn progress_path(archive: &Path) -> PathBuf {
+    let mut name = archive.file_name().unwrap_or_default().to_owned();
+    name.push(".progress");
+    archive.with_file_name(name)
+}
+
+fn load_progress(path: &Path) -> AnyResult<Option<(u64, u64)>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = std::fs::read_to_string(path)?;
+    let mut numbers = content.split_whitespace().map(u64::from_str);
+    match (numbers.next(), numbers.next()) {
+        (Some(offset), Some(count)) => Ok(Some((offset?, count?))),
+        _ => anyhow::bail!("progress file {} is corrupted", path.display()),
+    }
+}
+
+fn save_progress(path: &Path, (offset, count): (u64, u64)) -> AnyResult<()> {
+    std::fs::write(path, format!("{} {}\n", offset, count))?;
+    Ok(())
+}
+
+async fn send(mut client: BobApiClient<Channel>, entry: Entry, auth: Auth) -> AnyResult<()> {
+    let key = Some(BlobKey { key: entry.key });
+    let meta = Some(BlobMeta {
+        timestamp: entry.timestamp,
+    });
+    let status = match entry.data {
+        Some(data) => {
+            let message = PutRequest {
+                key,
+                data: Some(Blob {
+                    data: data.into(),
+                    meta,
+                }),
+                options: Some(PutOptions::default()),
+            };
+            client.put(auth.request(message)).await
+        }
+        None => {
+            let message = DeleteRequest {
+                key,
+                meta,
+                options: Some(DeleteOptions::default()),
+            };
+            client.delete(auth.request(message)).await
+        }
+    };
+    status?;
+    Ok(())
+}
+
+/// Puts records and deletes tombstones of the archive to the cluster, `parallelism` requests
+/// are sent at once. Checksums of entries are verified before sending. Position is saved to
+/// `<archive>.progress`, so the interrupted import is resumed by the next run.
+pub(crate) async fn import(
+    archive: &Path,
+    address: &str,
+    key_size: u16,
+    parallelism: usize,
+    auth: &Auth,
+    tls: &Tls,
+) -> AnyResult<u64> {
+    let mut reader = ArchiveReader::open(archive)?;
+    let progress_path = progress_path(archive);
+    if let Some((offset, count)) = load_progress(&progress_path)? {
+        info!("import is resumed after {} entries", count);
+        reader.seek(offset, count)?;
+    }
+    if reader.key_size() != key_size {
+        info!(
+            "keys are converted from {} to {} bytes",
+            reader.key_size(),
+            key_size
+        );
+    }
+    let client = tls.connect(address).await?;
+    let mut in_flight = FuturesOrdered::new();
+    let mut saved = reader.position().1;
+    let mut last_log = Instant::now();
+    loop {
+        while in_flight.len() < parallelism {
+            let mut entry = match reader.next_entry()? {
+                Some(entry) => entry,
+                None => break,
+            };
+            entry.key = convert_key(entry.key, key_size as usize)?;
+            // Requests are completed in order, so the import can be resumed from the position
+            // after the completed entry
+            let next = reader.position();
+            let sent = send(client.clone(), entry, auth.clone());
+            in_flight.push_back(async move { sent.await.map(|_| next) });
+        }
+        let next = match in_flight.next().await {
+            Some(result) => result?,
+            None => break,
+        };
+        if next.1 - saved >= PROGRESS_SAVE_INTERVAL {
+            save_progress(&progress_path, next)?;
+            saved = next.1;
+        }
+        if last_log.elapsed() >= PROGRESS_LOG_INTERVAL {
+            info!("{} entries imported", next.1);
+            last_log = Instant::now();
+        }
+    }
+    let (_, count) = reader.position();
+    if progress_path.exists() {
+        std::fs::remove_file(&progress_path)?;
+    }
+    Ok(count)
+}
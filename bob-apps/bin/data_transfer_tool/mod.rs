@@ -0,0 +1,20 @@
+pub(crate) mod archive;
+pub(crate) mod command;
+pub(crate) mod export;
+pub(crate) mod import;
+pub(crate) mod utils;
+
+pub(crate) mod prelude {
+    pub(crate) use super::utils::{Auth, Tls};
+    pub(crate) use anyhow::{Context, Result as AnyResult};
+    pub(crate) use bob::BlobKey;
+    pub(crate) use clap::{App, Arg, ArgMatches, SubCommand};
+    pub(crate) use pearl::tools::read_index;
+    pub(crate) use std::{
+        io::Write,
+        path::{Path, PathBuf},
+        str::FromStr,
+        time::{Duration, Instant},
+    };
+    pub(crate) use tonic::{transport::Channel, Request};
+}
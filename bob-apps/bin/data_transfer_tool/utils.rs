@@ -0,0 +1,87 @@
+use super::prelude::*;
+use base64::{engine::general_purpose::STANDARD as BASE64_ENGINE, Engine as _};
+use bob::BobApiClient;
+use tonic::{
+    metadata::{Ascii, MetadataValue},
+    transport::{Certificate, ClientTlsConfig, Endpoint},
+};
+
+/// Basic credentials added to every gRPC request.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Auth {
+    authorization: Option<MetadataValue<Ascii>>,
+}
+
+impl Auth {
+    pub(crate) fn new(user: Option<&str>, password: Option<&str>) -> AnyResult<Self> {
+        let authorization = match (user, password) {
+            (Some(user), Some(password)) => {
+                let credentials = BASE64_ENGINE.encode(format!("{}:{}", user, password));
+                Some(format!("Basic {}", credentials).parse()?)
+            }
+            _ => None,
+        };
+        Ok(Self { authorization })
+    }
+
+    pub(crate) fn request<T>(&self, message: T) -> Request<T> {
+        let mut request = Request::new(message);
+        if let Some(authorization) = &self.authorization {
+            request
+                .metadata_mut()
+                .insert("authorization", authorization.clone());
+        }
+        request
+    }
+}
+
+/// TLS settings of gRPC connections, connections are plain without them.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Tls {
+    config: Option<ClientTlsConfig>,
+}
+
+impl Tls {
+    pub(crate) fn new(ca_path: Option<&str>, domain_name: Option<&str>) -> AnyResult<Self> {
+        let config = match (ca_path, domain_name) {
+            (Some(ca_path), Some(domain_name)) => {
+                let ca_cert = std::fs::read(ca_path)
+                    .with_context(|| format!("can't read ca certificate {}", ca_path))?;
+                Some(
+                    ClientTlsConfig::new()
+                        .domain_name(domain_name)
+                        .ca_certificate(Certificate::from_pem(ca_cert)),
+                )
+            }
+            _ => None,
+        };
+        Ok(Self { config })
+    }
+
+    /// Connects to `host:port` or to the full uri if the address has a scheme.
+    pub(crate) async fn connect(&self, address: &str) -> AnyResult<BobApiClient<Channel>> {
+        let uri = if address.contains("://") {
+            address.to_owned()
+        } else if self.config.is_some() {
+            format!("https://{}", address)
+        } else {
+            format!("http://{}", address)
+        };
+        let mut endpoint = Endpoint::from_shared(uri)?;
+        if let Some(config) = &self.config {
+            endpoint = endpoint.tls_config(config.clone())?;
+        }
+        Ok(BobApiClient::connect(endpoint).await?)
+    }
+}
+
+pub(crate) fn init_logger() -> AnyResult<()> {
+    if std::env::var_os("RUST_LOG").is_none() {
+        env_logger::builder()
+            .filter(None, log::LevelFilter::Info)
+            .try_init()?;
+    } else {
+        env_logger::try_init()?;
+    }
+    Ok(())
+}
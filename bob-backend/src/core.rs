@@ -7,6 +7,7 @@ use std::{
 use smallvec::SmallVec;
 
 use crate::{
+    faulty_backend::{FaultyBackend, Faults},
     mem_backend::MemBackend,
     pearl::{DiskController, Pearl},
     stub_backend::StubBackend,
@@ -101,6 +102,38 @@ pub trait BackendStorage: Debug + MetricsProducer + Send + Sync + 'static {
     async fn delete(&self, op: Operation, key: BobKey, meta: &BobMeta) -> Result<u64, Error>;
     async fn delete_alien(&self, op: Operation, key: BobKey, meta: &BobMeta, force_delete: bool) -> Result<u64, Error>;
 
+    // Default implementations check condition with separate read, so they are not atomic.
+    // Backends that can serialize check and write should override them
+    async fn put_conditional(
+        &self,
+        op: Operation,
+        key: BobKey,
+        data: &BobData,
+        condition: BobWriteCondition,
+    ) -> Result<(), Error> {
+        condition.check(self.actual_timestamp(op.clone(), key).await?)?;
+        self.put(op, key, data).await
+    }
+
+    async fn delete_conditional(
+        &self,
+        op: Operation,
+        key: BobKey,
+        meta: &BobMeta,
+        condition: BobWriteCondition,
+    ) -> Result<u64, Error> {
+        condition.check(self.actual_timestamp(op.clone(), key).await?)?;
+        self.delete(op, key, meta).await
+    }
+
+    async fn actual_timestamp(&self, op: Operation, key: BobKey) -> Result<Option<u64>, Error> {
+        match self.get(op, key).await {
+            Ok(data) => Ok(Some(data.meta().timestamp())),
+            Err(e) if e.is_key_not_found() => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
     async fn shutdown(&self);
 
     // Should return pair: slice of normal disks and disk with aliens (because some method require
@@ -182,26 +215,41 @@ const ERROR_LOG_INTERVAL: u64 = 5000;
 #[derive(Debug)]
 pub struct Backend {
     inner: Arc<dyn BackendStorage>,
+    faults: Option<Arc<Faults>>,
     mapper: Arc<Virtual>,
     error_logger: IntervalLoggerSafe<BackendErrorAction>,
 }
 
 impl Backend {
     pub async fn new(mapper: Arc<Virtual>, config: &NodeConfig) -> Self {
+        let faults = config
+            .fault_injection()
+            .map(|fault_injection| Arc::new(Faults::new(fault_injection.rules())));
+        if faults.is_some() {
+            warn!("fault injection is enabled, backend operations may fail on purpose");
+        }
         let inner: Arc<dyn BackendStorage> = match config.backend_type() {
-            BackendType::InMemory => Arc::new(MemBackend::new(&mapper)),
+            BackendType::InMemory => Arc::new(MemBackend::new(&mapper, config)),
             BackendType::Stub => Arc::new(StubBackend {}),
             BackendType::Pearl => {
-                let pearl = Pearl::new(mapper.clone(), config)
+                let pearl = Pearl::new(mapper.clone(), config, faults.clone())
                     .await
                     .expect("pearl initialization failed");
                 Arc::new(pearl)
             }
         };
+        // Pearl injects faults in disk controllers, so that they are processed as disk errors
+        let inner: Arc<dyn BackendStorage> = match &faults {
+            Some(faults) if inner.disk_controllers().is_none() => {
+                Arc::new(FaultyBackend::new(inner, faults.clone()))
+            }
+            _ => inner,
+        };
         let error_logger = IntervalLoggerSafe::new(ERROR_LOG_INTERVAL, Level::Error);
 
         Self {
             inner,
+            faults,
             mapper,
             error_logger,
         }
@@ -235,6 +283,11 @@ impl Backend {
         self.inner.as_ref()
     }
 
+    /// Faults injected into the backend, `None` if fault injection is disabled in node config.
+    pub fn faults(&self) -> Option<&Faults> {
+        self.faults.as_deref()
+    }
+
     #[inline]
     pub async fn run_backend(&self) -> AnyResult<()> {
         self.inner.run().await
@@ -257,7 +310,7 @@ impl Backend {
                 op.set_remote_node_name(node_name.clone());
 
                 //TODO make it parallel?
-                self.put_single(key, data, op).await?;
+                self.put_single(key, data, op, None).await?;
             }
             Ok(())
         } else if let Some(paths) = disk_paths {
@@ -267,7 +320,8 @@ impl Backend {
             );
             let mut result = Ok(());
             for path in paths {
-                if let Err(e) = self.put_single(key, data, Operation::new_local(vdisk_id, path.clone())).await {
+                let op = Operation::new_local(vdisk_id, path.clone());
+                if let Err(e) = self.put_single(key, data, op, options.condition()).await {
                     warn!("PUT[{}] error put to {:?}: {:?}", key, path, e);
                     result = Err(e);
                 }
@@ -292,8 +346,9 @@ impl Backend {
         key: BobKey,
         data: &BobData,
         operation: Operation,
+        condition: Option<BobWriteCondition>,
     ) -> Result<(), Error> {
-        self.put_single(key, data, operation).await
+        self.put_single(key, data, operation, condition).await
     }
 
     async fn update_bloom_filter_metrics(&self) {
@@ -306,15 +361,21 @@ impl Backend {
         key: BobKey,
         data: &BobData,
         operation: Operation,
+        condition: Option<BobWriteCondition>,
     ) -> Result<(), Error> {
         if operation.is_data_alien() {
             debug!("PUT[{}] to backend, alien data: {:?}", key, operation);
             self.inner.put_alien(operation, key, data).await
         } else {
-            debug!("PUT[{}] to backend: {:?}", key, operation);
-            let result = self.inner.put(operation.clone(), key, data).await;
+            debug!("PUT[{}] to backend: {:?}, condition: {:?}", key, operation, condition);
+            let result = if let Some(condition) = condition {
+                self.inner.put_conditional(operation.clone(), key, data, condition).await
+            } else {
+                self.inner.put(operation.clone(), key, data).await
+            };
             match result {
-                Err(local_err) if !local_err.is_duplicate() => {
+                // rejected by condition data must not get into alien
+                Err(local_err) if !local_err.is_duplicate() && !local_err.is_precondition_failed() => {
                     debug!(
                         "PUT[{}][{}] local failed: {:?}",
                         key,
@@ -480,7 +541,7 @@ impl Backend {
                 let force_delete = options.is_force_delete(node.name());
                 let mut op = Operation::new_alien(vdisk_id);
                 op.set_remote_node_name(node.name().clone());
-                let delete_res = self.delete_single(key, meta, op, force_delete).await;
+                let delete_res = self.delete_single(key, meta, op, force_delete, None).await;
                 if let Err(err) = delete_res {
                     error!("DELETE[{}] Error deleting from aliens (node: {}, force_delete: {}): {:?}", key, node.name(), force_delete, err);
                     errors.push(err);
@@ -497,7 +558,7 @@ impl Backend {
             let mut result = Ok(());
             for path in paths {
                 let op = Operation::new_local(vdisk_id, path.clone());
-                if let Err(e) = self.delete_single(key, meta, op, true).await.map(|_| ()) {
+                if let Err(e) = self.delete_single(key, meta, op, true, options.condition()).await.map(|_| ()) {
                     warn!("DELETE[{}] failed on path {:?}: {:?}", key, path, e);
                     result = Err(e);
                 }
@@ -518,8 +579,9 @@ impl Backend {
         meta: &BobMeta,
         operation: Operation,
         force_delete: bool,
+        condition: Option<BobWriteCondition>,
     ) -> Result<u64, Error> {
-        self.delete_single(key, meta, operation, force_delete).await
+        self.delete_single(key, meta, operation, force_delete, condition).await
     }
 
     async fn delete_single(
@@ -528,17 +590,23 @@ impl Backend {
         meta: &BobMeta,
         operation: Operation,
         force_delete: bool,
+        condition: Option<BobWriteCondition>,
     ) -> Result<u64, Error> {
         if operation.is_data_alien() {
             debug!("DELETE[{}] from backend, foreign data", key);
             self.inner.delete_alien(operation, key, meta, force_delete).await
         } else {
             debug!(
-                "DELETE[{}][{}] from backend",
+                "DELETE[{}][{}] from backend, condition: {:?}",
                 key,
-                operation.disk_name_local()
+                operation.disk_name_local(),
+                condition
             );
-            self.inner.delete(operation, key, meta).await
+            if let Some(condition) = condition {
+                self.inner.delete_conditional(operation, key, meta, condition).await
+            } else {
+                self.inner.delete(operation, key, meta).await
+            }
         }
     }
 
@@ -0,0 +1,288 @@
+use crate::prelude::*;
+
+use crate::{
+    core::{BackendStorage, MetricsProducer, Operation},
+    pearl::DiskController,
+};
+use bob_common::configs::node::{BackendOperation, FaultRule};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+// Alien operations have no local disk, rules with disk don't match them
+pub(crate) const ALIEN_DISK: &str = "";
+
+/// Fault rule set at runtime.
+#[derive(Debug, Clone)]
+pub struct Fault {
+    id: u64,
+    rule: FaultRule,
+    active_from: Instant,
+    active_until: Option<Instant>,
+}
+
+impl Fault {
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn rule(&self) -> &FaultRule {
+        &self.rule
+    }
+
+    pub fn is_active(&self, now: Instant) -> bool {
+        self.active_from <= now && !self.is_expired(now)
+    }
+
+    fn is_expired(&self, now: Instant) -> bool {
+        self.active_until.is_some_and(|until| until <= now)
+    }
+}
+
+/// Faults injected into backend operations, shared between the backend and REST API. Pearl
+/// backend checks them in [`DiskController`] operations, other backends are wrapped into
+/// [`FaultyBackend`].
+#[derive(Debug, Default)]
+pub struct Faults {
+    next_id: AtomicU64,
+    faults: SyncRwLock<Vec<Fault>>,
+}
+
+impl Faults {
+    pub fn new(rules: &[FaultRule]) -> Self {
+        let faults = Self::default();
+        for rule in rules {
+            faults.add(rule.clone());
+        }
+        faults
+    }
+
+    /// Adds the rule, returns the fault with the assigned id.
+    pub fn add(&self, rule: FaultRule) -> Fault {
+        let active_from = Instant::now() + rule.delay();
+        let fault = Fault {
+            id: self.next_id.fetch_add(1, Ordering::Relaxed),
+            active_until: rule.duration().map(|duration| active_from + duration),
+            active_from,
+            rule,
+        };
+        info!("fault {} is set: {:?}", fault.id, fault.rule);
+        self.faults.write().expect("rwlock").push(fault.clone());
+        fault
+    }
+
+    /// Returns false if fault with the id is not found.
+    pub fn remove(&self, id: u64) -> bool {
+        let mut faults = self.faults.write().expect("rwlock");
+        let len = faults.len();
+        faults.retain(|fault| fault.id != id);
+        faults.len() != len
+    }
+
+    pub fn clear(&self) {
+        self.faults.write().expect("rwlock").clear();
+    }
+
+    /// Faults which are active or wait for their delay, expired ones are removed.
+    pub fn list(&self) -> Vec<Fault> {
+        let now = Instant::now();
+        let mut faults = self.faults.write().expect("rwlock");
+        faults.retain(|fault| !fault.is_expired(now));
+        faults.clone()
+    }
+
+    async fn inject(&self, op: &Operation, operation: BackendOperation) -> Result<(), Error> {
+        let disk = if op.is_data_alien() {
+            ALIEN_DISK
+        } else {
+            op.disk_name_local().as_str()
+        };
+        self.inject_into_disk(disk, operation).await
+    }
+
+    /// Applies active faults matching the disk and operation: waits for the largest latency,
+    /// then returns error of the first rule which fires.
+    pub(crate) async fn inject_into_disk(
+        &self,
+        disk: &str,
+        operation: BackendOperation,
+    ) -> Result<(), Error> {
+        let now = Instant::now();
+        let (latency, error) = {
+            let faults = self.faults.read().expect("rwlock");
+            let mut latency = Duration::ZERO;
+            let mut error = None;
+            for fault in faults
+                .iter()
+                .filter(|fault| fault.is_active(now) && fault.rule.matches(disk, operation))
+            {
+                latency = latency.max(fault.rule.latency());
+                if error.is_none() && rand::random::<f64>() < fault.rule.probability() {
+                    error = fault.rule.error();
+                }
+            }
+            (latency, error)
+        };
+        if !latency.is_zero() {
+            tokio::time::sleep(latency).await;
+        }
+        match error {
+            Some(error) => {
+                debug!(
+                    "injected {:?} error into {:?} on disk {:?}",
+                    error, operation, disk
+                );
+                Err(error.to_error())
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+/// Wraps backend without disk controllers and injects faults into its data operations.
+#[derive(Debug)]
+pub struct FaultyBackend {
+    inner: Arc<dyn BackendStorage>,
+    faults: Arc<Faults>,
+}
+
+impl FaultyBackend {
+    pub fn new(inner: Arc<dyn BackendStorage>, faults: Arc<Faults>) -> Self {
+        Self { inner, faults }
+    }
+}
+
+#[async_trait]
+impl MetricsProducer for FaultyBackend {
+    async fn blobs_count(&self) -> (usize, usize) {
+        self.inner.blobs_count().await
+    }
+
+    async fn active_disks_count(&self) -> usize {
+        self.inner.active_disks_count().await
+    }
+
+    async fn index_memory(&self) -> usize {
+        self.inner.index_memory().await
+    }
+
+    async fn disk_used_by_disk(&self) -> HashMap<DiskPath, u64> {
+        self.inner.disk_used_by_disk().await
+    }
+
+    async fn corrupted_blobs_count(&self) -> usize {
+        self.inner.corrupted_blobs_count().await
+    }
+}
+
+#[async_trait]
+impl BackendStorage for FaultyBackend {
+    async fn run_backend(&self) -> AnyResult<()> {
+        self.inner.run_backend().await
+    }
+
+    async fn put(&self, op: Operation, key: BobKey, data: &BobData) -> Result<(), Error> {
+        self.faults.inject(&op, BackendOperation::Put).await?;
+        self.inner.put(op, key, data).await
+    }
+
+    async fn put_alien(&self, op: Operation, key: BobKey, data: &BobData) -> Result<(), Error> {
+        self.faults.inject(&op, BackendOperation::Put).await?;
+        self.inner.put_alien(op, key, data).await
+    }
+
+    async fn get(&self, op: Operation, key: BobKey) -> Result<BobData, Error> {
+        self.faults.inject(&op, BackendOperation::Get).await?;
+        self.inner.get(op, key).await
+    }
+
+    async fn get_alien(&self, op: Operation, key: BobKey) -> Result<BobData, Error> {
+        self.faults.inject(&op, BackendOperation::Get).await?;
+        self.inner.get_alien(op, key).await
+    }
+
+    async fn exist(&self, op: Operation, keys: &[BobKey]) -> Result<Vec<bool>, Error> {
+        self.faults.inject(&op, BackendOperation::Exist).await?;
+        self.inner.exist(op, keys).await
+    }
+
+    async fn exist_alien(&self, op: Operation, keys: &[BobKey]) -> Result<Vec<bool>, Error> {
+        self.faults.inject(&op, BackendOperation::Exist).await?;
+        self.inner.exist_alien(op, keys).await
+    }
+
+    async fn delete(&self, op: Operation, key: BobKey, meta: &BobMeta) -> Result<u64, Error> {
+        self.faults.inject(&op, BackendOperation::Delete).await?;
+        self.inner.delete(op, key, meta).await
+    }
+
+    async fn delete_alien(
+        &self,
+        op: Operation,
+        key: BobKey,
+        meta: &BobMeta,
+        force_delete: bool,
+    ) -> Result<u64, Error> {
+        self.faults.inject(&op, BackendOperation::Delete).await?;
+        self.inner.delete_alien(op, key, meta, force_delete).await
+    }
+
+    async fn put_conditional(
+        &self,
+        op: Operation,
+        key: BobKey,
+        data: &BobData,
+        condition: BobWriteCondition,
+    ) -> Result<(), Error> {
+        self.faults.inject(&op, BackendOperation::Put).await?;
+        self.inner.put_conditional(op, key, data, condition).await
+    }
+
+    async fn delete_conditional(
+        &self,
+        op: Operation,
+        key: BobKey,
+        meta: &BobMeta,
+        condition: BobWriteCondition,
+    ) -> Result<u64, Error> {
+        self.faults.inject(&op, BackendOperation::Delete).await?;
+        self.inner
+            .delete_conditional(op, key, meta, condition)
+            .await
+    }
+
+    async fn actual_timestamp(&self, op: Operation, key: BobKey) -> Result<Option<u64>, Error> {
+        self.faults.inject(&op, BackendOperation::Get).await?;
+        self.inner.actual_timestamp(op, key).await
+    }
+
+    async fn shutdown(&self) {
+        self.inner.shutdown().await
+    }
+
+    fn disk_controllers(&self) -> Option<(&[Arc<DiskController>], Arc<DiskController>)> {
+        self.inner.disk_controllers()
+    }
+
+    async fn close_unneeded_active_blobs(&self, soft: usize, hard: usize) {
+        self.inner.close_unneeded_active_blobs(soft, hard).await
+    }
+
+    async fn close_oldest_active_blob(&self) -> Option<usize> {
+        self.inner.close_oldest_active_blob().await
+    }
+
+    async fn free_least_used_resources(&self) -> Option<usize> {
+        self.inner.free_least_used_resources().await
+    }
+
+    async fn offload_old_filters(&self, limit: usize) {
+        self.inner.offload_old_filters(limit).await
+    }
+
+    async fn filter_memory_allocated(&self) -> usize {
+        self.inner.filter_memory_allocated().await
+    }
+
+    async fn remount_vdisk(&self, vdisk_id: u32) -> AnyResult<()> {
+        self.inner.remount_vdisk(vdisk_id).await
+    }
+}
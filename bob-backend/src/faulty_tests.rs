@@ -0,0 +1,124 @@
+use crate::prelude::*;
+
+use crate::{
+    core::{BackendStorage, Operation},
+    faulty_backend::{Faults, FaultyBackend},
+    mem_tests::new_direct,
+};
+use bob_common::{configs::node::FaultRule, error::Kind};
+
+const VDISKS_COUNT: u32 = 10;
+
+fn rule(yaml: &str) -> FaultRule {
+    serde_yaml::from_str(yaml).unwrap()
+}
+
+fn backend(rules: &[FaultRule]) -> (FaultyBackend, Arc<Faults>) {
+    let inner = new_direct(&["d1".to_owned(), "d2".to_owned()], VDISKS_COUNT);
+    let faults = Arc::new(Faults::new(rules));
+    (FaultyBackend::new(Arc::new(inner), faults.clone()), faults)
+}
+
+fn local(disk: &str) -> Operation {
+    Operation::new_local(0, DiskPath::new(disk.into(), ""))
+}
+
+fn data() -> BobData {
+    BobData::new(vec![1].into(), BobMeta::stub())
+}
+
+#[tokio::test]
+async fn test_faulty_without_rules_passes_operations() {
+    let (backend, _) = backend(&[]);
+
+    backend.put(local("d1"), 1.into(), &data()).await.unwrap();
+    let retval = backend.get(local("d1"), 1.into()).await.unwrap();
+    assert_eq!(retval.into_inner(), vec![1]);
+}
+
+#[tokio::test]
+async fn test_faulty_error_on_matching_disk_and_operation() {
+    let (backend, _) = backend(&[rule(
+        "{disk: d1, operations: [put], error: possible_disk_disconnection}",
+    )]);
+
+    let err = backend
+        .put(local("d1"), 1.into(), &data())
+        .await
+        .unwrap_err();
+    assert!(err.is_possible_disk_disconnection());
+    backend.put(local("d2"), 1.into(), &data()).await.unwrap();
+    assert!(backend
+        .get(local("d1"), 1.into())
+        .await
+        .unwrap_err()
+        .is_key_not_found());
+}
+
+#[tokio::test]
+async fn test_faulty_rule_with_disk_skips_alien() {
+    let (backend, _) = backend(&[rule("{disk: d1, error: internal}")]);
+
+    let mut op = Operation::new_alien(0);
+    op.set_remote_node_name("node".into());
+    backend.put_alien(op, 1.into(), &data()).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_faulty_latency_delays_operation() {
+    let (backend, _) = backend(&[rule("{operations: [exist], latency: 100ms}")]);
+
+    let started = Instant::now();
+    backend.exist(local("d1"), &[1.into()]).await.unwrap();
+    assert!(started.elapsed() >= Duration::from_millis(100));
+    let started = Instant::now();
+    backend.get(local("d1"), 1.into()).await.unwrap_err();
+    assert!(started.elapsed() < Duration::from_millis(100));
+}
+
+#[tokio::test]
+async fn test_faulty_zero_probability_never_fails() {
+    let (backend, _) = backend(&[rule("{error: timeout, probability: 0.0}")]);
+
+    for key in 0..100u64 {
+        backend.put(local("d1"), key.into(), &data()).await.unwrap();
+    }
+}
+
+#[tokio::test]
+async fn test_faulty_unavailability_window() {
+    let (backend, faults) = backend(&[]);
+    faults.add(rule(
+        "{disk: d2, error: holder_temporary_unavailable, delay: 50ms, duration: 100ms}",
+    ));
+
+    backend.put(local("d2"), 1.into(), &data()).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(75)).await;
+    let err = backend
+        .put(local("d2"), 2.into(), &data())
+        .await
+        .unwrap_err();
+    assert_eq!(err.kind(), &Kind::HolderTemporaryUnavailable);
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    backend.put(local("d2"), 3.into(), &data()).await.unwrap();
+    assert!(faults.list().is_empty());
+}
+
+#[tokio::test]
+async fn test_faulty_removed_fault_stops_injection() {
+    let (backend, faults) = backend(&[]);
+    let fault = faults.add(rule("{error: internal}"));
+
+    assert!(backend
+        .get(local("d1"), 1.into())
+        .await
+        .unwrap_err()
+        .is_internal());
+    assert!(faults.remove(fault.id()));
+    assert!(!faults.remove(fault.id()));
+    assert!(backend
+        .get(local("d1"), 1.into())
+        .await
+        .unwrap_err()
+        .is_key_not_found());
+}
@@ -1,10 +1,13 @@
 pub mod core;
+pub mod faulty_backend;
 pub mod mem_backend;
 pub mod pearl;
 pub mod stub_backend;
 
 #[cfg(test)]
 pub mod mem_tests;
+#[cfg(test)]
+pub mod faulty_tests;
 
 #[macro_use]
 extern crate log;
@@ -14,13 +17,15 @@ extern crate async_trait;
 extern crate metrics;
 #[macro_use]
 extern crate lazy_static;
+#[macro_use]
+extern crate serde_derive;
 
 pub(crate) mod prelude {
     pub use anyhow::{Context, Result as AnyResult};
     pub use bob_common::{
         configs::node::{BackendType, Node as NodeConfig, Pearl as PearlConfig},
         data::{BobData, BobKey, BobMeta},
-        operation_options::{BobPutOptions, BobGetOptions, BobDeleteOptions},
+        operation_options::{BobPutOptions, BobGetOptions, BobDeleteOptions, BobWriteCondition},
         core_types::{DiskName, DiskPath, VDiskId},
         node::NodeName,
         error::Error,
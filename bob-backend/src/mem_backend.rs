@@ -2,44 +2,134 @@ use crate::prelude::*;
 
 use crate::core::{BackendStorage, MetricsProducer, Operation};
 
+/// Newest version of the key: data or delete marker. Like pearl, the record with the greater
+/// timestamp wins, the later one wins if timestamps are equal.
+#[derive(Clone, Debug)]
+pub enum Record {
+    Data(BobData),
+    Deleted(u64),
+}
+
+impl Record {
+    pub fn timestamp(&self) -> u64 {
+        match self {
+            Self::Data(data) => data.meta().timestamp(),
+            Self::Deleted(timestamp) => *timestamp,
+        }
+    }
+
+    fn data(&self) -> Option<&BobData> {
+        match self {
+            Self::Data(data) => Some(data),
+            Self::Deleted(_) => None,
+        }
+    }
+}
+
 #[derive(Clone, Debug, Default)]
 pub struct VDisk {
-    inner: Arc<SyncRwLock<HashMap<BobKey, BobData>>>,
+    inner: Arc<SyncRwLock<HashMap<BobKey, Record>>>,
 }
 
 impl VDisk {
+    // Older record is shadowed by the stored one, so it is dropped without error
+    fn write(repo: &mut HashMap<BobKey, Record>, key: BobKey, record: Record) {
+        match repo.get(&key) {
+            Some(actual) if actual.timestamp() > record.timestamp() => {
+                trace!("{} has newer record {:?}", key, actual);
+            }
+            _ => {
+                repo.insert(key, record);
+            }
+        }
+    }
+
+    fn actual_timestamp(repo: &HashMap<BobKey, Record>, key: BobKey) -> Option<u64> {
+        repo.get(&key).and_then(Record::data).map(|d| d.meta().timestamp())
+    }
+
     async fn put(&self, key: BobKey, data: &BobData) -> Result<(), Error> {
         debug!("PUT[{}] to vdisk", key);
-        self.inner
-            .write()
-            .expect("rwlock")
-            .insert(key, data.clone());
+        let mut repo = self.inner.write().expect("rwlock");
+        Self::write(&mut repo, key, Record::Data(data.clone()));
+        Ok(())
+    }
+
+    async fn put_conditional(&self, key: BobKey, data: &BobData, condition: BobWriteCondition) -> Result<(), Error> {
+        let mut repo = self.inner.write().expect("rwlock");
+        condition.check(Self::actual_timestamp(&repo, key))?;
+        debug!("PUT[{}] to vdisk with condition {:?}", key, condition);
+        Self::write(&mut repo, key, Record::Data(data.clone()));
         Ok(())
     }
 
     async fn get(&self, key: BobKey) -> Result<BobData, Error> {
-        if let Some(data) = self.inner.read().expect("rwlock").get(&key) {
-            debug!("GET[{}] from vdisk", key);
-            Ok(data.clone())
-        } else {
-            debug!("GET[{}] from vdisk failed. Cannot find key", key);
-            Err(Error::key_not_found(key))
+        match self.inner.read().expect("rwlock").get(&key) {
+            Some(Record::Data(data)) => {
+                debug!("GET[{}] from vdisk", key);
+                Ok(data.clone())
+            }
+            Some(Record::Deleted(timestamp)) => {
+                debug!("GET[{}] from vdisk failed. Key is deleted at {}", key, timestamp);
+                Err(Error::key_not_found(key))
+            }
+            None => {
+                debug!("GET[{}] from vdisk failed. Cannot find key", key);
+                Err(Error::key_not_found(key))
+            }
         }
     }
 
     async fn exist(&self, keys: &[BobKey]) -> Result<Vec<bool>, Error> {
         let repo = self.inner.read().expect("rwlock");
-        let result = keys.iter().map(|k| repo.get(k).is_some()).collect();
+        let result = keys
+            .iter()
+            .map(|k| repo.get(k).and_then(Record::data).is_some())
+            .collect();
         Ok(result)
     }
 
-    async fn delete(&self, key: BobKey) -> Result<u64, Error> {
-        if self.inner.write().expect("rwlock").remove(&key).is_some() {
-            debug!("DELETE[{}] from vdisk", key);
-            Ok(1)
-        } else {
-            debug!("DELETE[{}] from vdisk failed. Cannot find key", key);
-            Err(Error::key_not_found(key))
+    // Without `force_delete` marker is written only if vdisk has any record of the key.
+    // Returns count of records shadowed by the marker
+    async fn delete(&self, key: BobKey, meta: &BobMeta, force_delete: bool) -> Result<u64, Error> {
+        let mut repo = self.inner.write().expect("rwlock");
+        Ok(Self::delete_record(&mut repo, key, meta, force_delete))
+    }
+
+    fn delete_record(repo: &mut HashMap<BobKey, Record>, key: BobKey, meta: &BobMeta, force_delete: bool) -> u64 {
+        if !force_delete && !repo.contains_key(&key) {
+            return 0;
+        }
+        let deleted = match repo.get(&key) {
+            Some(Record::Data(data)) if data.meta().timestamp() <= meta.timestamp() => 1,
+            _ => 0,
+        };
+        debug!("DELETE[{}] from vdisk, {} record deleted", key, deleted);
+        Self::write(repo, key, Record::Deleted(meta.timestamp()));
+        deleted
+    }
+
+    async fn delete_conditional(&self, key: BobKey, meta: &BobMeta, condition: BobWriteCondition) -> Result<u64, Error> {
+        let mut repo = self.inner.write().expect("rwlock");
+        condition.check(Self::actual_timestamp(&repo, key))?;
+        debug!("DELETE[{}] from vdisk with condition {:?}", key, condition);
+        Ok(Self::delete_record(&mut repo, key, meta, true))
+    }
+
+    /// Copy of all records including delete markers.
+    pub fn records(&self) -> Vec<(BobKey, Record)> {
+        self.inner
+            .read()
+            .expect("rwlock")
+            .iter()
+            .map(|(key, record)| (*key, record.clone()))
+            .collect()
+    }
+
+    fn restore(&self, records: impl IntoIterator<Item = (BobKey, Record)>) {
+        let mut repo = self.inner.write().expect("rwlock");
+        for (key, record) in records {
+            Self::write(&mut repo, key, record);
         }
     }
 }
@@ -68,7 +158,6 @@ impl MemDisk {
     pub async fn get(&self, vdisk_id: VDiskId, key: BobKey) -> Result<BobData, Error> {
         if let Some(vdisk) = self.vdisks.get(&vdisk_id) {
             debug!("GET[{}] from: {} for disk: {}", key, vdisk_id, self.name);
-            debug!("{:?}", *vdisk.inner.read().expect("rwlock"));
             vdisk.get(key).await
         } else {
             debug!("GET[{}] Cannot find vdisk for disk: {}", key, self.name);
@@ -86,6 +175,22 @@ impl MemDisk {
         }
     }
 
+    pub async fn put_conditional(
+        &self,
+        vdisk_id: VDiskId,
+        key: BobKey,
+        data: &BobData,
+        condition: BobWriteCondition,
+    ) -> Result<(), Error> {
+        if let Some(vdisk) = self.vdisks.get(&vdisk_id) {
+            debug!("PUT[{}] to vdisk: {} for: {}", key, vdisk_id, self.name);
+            vdisk.put_conditional(key, data, condition).await
+        } else {
+            debug!("PUT[{}] Cannot find vdisk for disk: {}", key, self.name);
+            Err(Error::internal())
+        }
+    }
+
     pub async fn exist(&self, vdisk_id: VDiskId, keys: &[BobKey]) -> Result<Vec<bool>, Error> {
         if let Some(vdisk) = self.vdisks.get(&vdisk_id) {
             trace!("EXIST from vdisk: {} for disk: {}", vdisk_id, self.name);
@@ -96,25 +201,129 @@ impl MemDisk {
         }
     }
 
-    pub async fn delete(&self, vdisk_id: VDiskId, key: BobKey) -> Result<u64, Error> {
+    // Local delete always writes the marker, like pearl
+    pub async fn delete(&self, vdisk_id: VDiskId, key: BobKey, meta: &BobMeta) -> Result<u64, Error> {
         if let Some(vdisk) = self.vdisks.get(&vdisk_id) {
             debug!("DELETE[{}] from: {} for disk: {}", key, vdisk_id, self.name);
-            vdisk.delete(key).await
+            vdisk.delete(key, meta, true).await
         } else {
             debug!("DELETE[{}] Cannot find vdisk for disk: {}", key, self.name);
             Err(Error::internal())
         }
     }
+
+    pub async fn delete_conditional(
+        &self,
+        vdisk_id: VDiskId,
+        key: BobKey,
+        meta: &BobMeta,
+        condition: BobWriteCondition,
+    ) -> Result<u64, Error> {
+        if let Some(vdisk) = self.vdisks.get(&vdisk_id) {
+            debug!("DELETE[{}] from: {} for disk: {}", key, vdisk_id, self.name);
+            vdisk.delete_conditional(key, meta, condition).await
+        } else {
+            debug!("DELETE[{}] Cannot find vdisk for disk: {}", key, self.name);
+            Err(Error::internal())
+        }
+    }
+}
+
+/// Alien data, grouped by vdisk and the node it is addressed to, like alien pearl groups.
+#[derive(Clone, Debug, Default)]
+pub struct AlienDisk {
+    groups: Arc<SyncRwLock<HashMap<(NodeName, VDiskId), VDisk>>>,
+}
+
+impl AlienDisk {
+    // Operation without node name matches groups of all nodes
+    fn find_all(&self, op: &Operation) -> Vec<VDisk> {
+        self.groups
+            .read()
+            .expect("rwlock")
+            .iter()
+            .filter(|((node_name, vdisk_id), _)| {
+                *vdisk_id == op.vdisk_id() && op.remote_node_name().is_none_or(|n| n == node_name)
+            })
+            .map(|(_, vdisk)| vdisk.clone())
+            .collect()
+    }
+
+    fn get_or_create(&self, op: &Operation) -> Result<VDisk, Error> {
+        let node_name = op.remote_node_name().ok_or_else(|| {
+            error!("alien operation without node name: {:?}", op);
+            Error::internal()
+        })?;
+        let vdisk = self
+            .groups
+            .write()
+            .expect("rwlock")
+            .entry((node_name.clone(), op.vdisk_id()))
+            .or_default()
+            .clone();
+        Ok(vdisk)
+    }
+
+    pub async fn put(&self, op: &Operation, key: BobKey, data: &BobData) -> Result<(), Error> {
+        self.get_or_create(op)?.put(key, data).await
+    }
+
+    /// Newest data among groups of all nodes.
+    pub async fn get(&self, op: &Operation, key: BobKey) -> Result<BobData, Error> {
+        let mut result: Option<BobData> = None;
+        for vdisk in self.find_all(op) {
+            if let Ok(data) = vdisk.get(key).await {
+                if result.as_ref().is_none_or(|r| data.meta().timestamp() > r.meta().timestamp()) {
+                    result = Some(data);
+                }
+            }
+        }
+        result.ok_or_else(|| Error::key_not_found(key))
+    }
+
+    pub async fn exist(&self, op: &Operation, keys: &[BobKey]) -> Result<Vec<bool>, Error> {
+        let mut result = vec![false; keys.len()];
+        for vdisk in self.find_all(op) {
+            for (exist, found) in result.iter_mut().zip(vdisk.exist(keys).await?) {
+                *exist |= found;
+            }
+        }
+        Ok(result)
+    }
+
+    // Forced delete creates the group to keep the marker, so data put to alien later with the
+    // older timestamp stays deleted
+    pub async fn delete(&self, op: &Operation, key: BobKey, meta: &BobMeta, force_delete: bool) -> Result<u64, Error> {
+        let groups = self.find_all(op);
+        if force_delete && groups.is_empty() {
+            return self.get_or_create(op)?.delete(key, meta, true).await;
+        }
+        let mut result = 0;
+        for vdisk in groups {
+            result += vdisk.delete(key, meta, force_delete).await?;
+        }
+        Ok(result)
+    }
+
+    pub fn groups(&self) -> Vec<(NodeName, VDiskId, VDisk)> {
+        self.groups
+            .read()
+            .expect("rwlock")
+            .iter()
+            .map(|((node_name, vdisk_id), vdisk)| (node_name.clone(), *vdisk_id, vdisk.clone()))
+            .collect()
+    }
 }
 
 #[derive(Clone, Debug)]
 pub struct MemBackend {
     pub disks: HashMap<DiskName, MemDisk>,
-    pub foreign_data: MemDisk,
+    pub aliens: AlienDisk,
+    pub snapshot_path: Option<PathBuf>,
 }
 
 impl MemBackend {
-    pub fn new(mapper: &Virtual) -> Self {
+    pub fn new(mapper: &Virtual, config: &NodeConfig) -> Self {
         let disks = mapper
             .local_disks()
             .iter()
@@ -123,9 +332,75 @@ impl MemBackend {
             .collect();
         Self {
             disks,
-            foreign_data: MemDisk::new_direct("foreign".into(), mapper.vdisks_count()),
+            aliens: AlienDisk::default(),
+            snapshot_path: config.in_memory().snapshot_path().map(PathBuf::from),
         }
     }
+
+    fn snapshot(&self) -> snapshot::Snapshot {
+        let disks = self
+            .disks
+            .values()
+            .flat_map(|disk| {
+                disk.vdisks.iter().map(move |(vdisk_id, vdisk)| {
+                    snapshot::VDisk::new(disk.name.as_str(), *vdisk_id, vdisk.records())
+                })
+            })
+            .collect();
+        let aliens = self
+            .aliens
+            .groups()
+            .into_iter()
+            .map(|(node_name, vdisk_id, vdisk)| snapshot::VDisk::new(node_name.as_str(), vdisk_id, vdisk.records()))
+            .collect();
+        snapshot::Snapshot { disks, aliens }
+    }
+
+    // Vdisks missing in the current cluster config are skipped
+    fn restore(&self, snapshot: snapshot::Snapshot) -> AnyResult<()> {
+        for vdisk in snapshot.disks {
+            let target = self
+                .disks
+                .get(&DiskName::from(vdisk.owner.as_str()))
+                .and_then(|disk| disk.vdisks.get(&vdisk.vdisk_id));
+            if let Some(target) = target {
+                target.restore(vdisk.into_records()?);
+            } else {
+                warn!("vdisk {} on disk {} from snapshot is not local, skipped", vdisk.vdisk_id, vdisk.owner);
+            }
+        }
+        for vdisk in snapshot.aliens {
+            let mut op = Operation::new_alien(vdisk.vdisk_id);
+            op.set_remote_node_name(vdisk.owner.as_str().into());
+            self.aliens.get_or_create(&op)?.restore(vdisk.into_records()?);
+        }
+        Ok(())
+    }
+
+    async fn load_snapshot(&self, path: &Path) -> AnyResult<()> {
+        let bytes = match tokio::fs::read(path).await {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == IOErrorKind::NotFound => {
+                info!("mem backend snapshot {} not found, start empty", path.display());
+                return Ok(());
+            }
+            Err(e) => return Err(e).with_context(|| format!("read snapshot {}", path.display())),
+        };
+        let snapshot = bincode::deserialize(&bytes).with_context(|| format!("parse snapshot {}", path.display()))?;
+        self.restore(snapshot)?;
+        info!("mem backend is restored from snapshot {}", path.display());
+        Ok(())
+    }
+
+    // Written through temporary file, so crash during save doesn't damage previous snapshot
+    async fn save_snapshot(&self, path: &Path) -> AnyResult<()> {
+        let bytes = bincode::serialize(&self.snapshot())?;
+        let tmp_path = path.with_extension("tmp");
+        tokio::fs::write(&tmp_path, bytes).await?;
+        tokio::fs::rename(&tmp_path, path).await?;
+        info!("mem backend snapshot is saved to {}", path.display());
+        Ok(())
+    }
 }
 
 #[async_trait]
@@ -135,6 +410,9 @@ impl MetricsProducer for MemBackend {}
 impl BackendStorage for MemBackend {
     async fn run_backend(&self) -> AnyResult<()> {
         debug!("run mem backend");
+        if let Some(path) = &self.snapshot_path {
+            self.load_snapshot(path).await?;
+        }
         Ok(())
     }
 
@@ -151,8 +429,8 @@ impl BackendStorage for MemBackend {
     }
 
     async fn put_alien(&self, op: Operation, key: BobKey, data: &BobData) -> Result<(), Error> {
-        debug!("PUT[{}] to backend, foreign data", key);
-        self.foreign_data.put(op.vdisk_id(), key, data).await
+        debug!("PUT[{}] to backend, alien data: {:?}", key, op);
+        self.aliens.put(&op, key, data).await
     }
 
     async fn get(&self, op: Operation, key: BobKey) -> Result<BobData, Error> {
@@ -166,9 +444,8 @@ impl BackendStorage for MemBackend {
     }
 
     async fn get_alien(&self, op: Operation, key: BobKey) -> Result<BobData, Error> {
-        debug!("GET[{}] to backend, foreign data", key);
-        debug!("{:?}", self.foreign_data);
-        self.foreign_data.get(op.vdisk_id(), key).await
+        debug!("GET[{}] to backend, alien data: {:?}", key, op);
+        self.aliens.get(&op, key).await
     }
 
     async fn exist(&self, op: Operation, keys: &[BobKey]) -> Result<Vec<bool>, Error> {
@@ -183,25 +460,125 @@ impl BackendStorage for MemBackend {
     }
 
     async fn exist_alien(&self, operation: Operation, keys: &[BobKey]) -> Result<Vec<bool>, Error> {
-        debug!("EXIST to backend, foreign data");
-        self.foreign_data.exist(operation.vdisk_id(), keys).await
+        debug!("EXIST to backend, alien data: {:?}", operation);
+        self.aliens.exist(&operation, keys).await
     }
 
-    async fn delete(&self, op: Operation, key: BobKey, _meta: &BobMeta) -> Result<u64, Error> {
+    async fn delete(&self, op: Operation, key: BobKey, meta: &BobMeta) -> Result<u64, Error> {
         debug!("DELETE[{}][{}] from backend", key, op.disk_name_local());
         if let Some(mem_disk) = self.disks.get(&op.disk_name_local()) {
-            mem_disk.delete(op.vdisk_id(), key).await
+            mem_disk.delete(op.vdisk_id(), key, meta).await
         } else {
             error!("DELETE[{}] Can't find disk {}", key, op.disk_name_local());
             Err(Error::internal())
         }
     }
 
-    async fn delete_alien(&self, op: Operation, key: BobKey, _meta: &BobMeta, _force_delete: bool) -> Result<u64, Error> {
-        debug!("DELETE[{}] from backend, foreign data", key);
-        debug!("{:?}", self.foreign_data);
-        self.foreign_data.delete(op.vdisk_id(), key).await
+    async fn delete_alien(&self, op: Operation, key: BobKey, meta: &BobMeta, force_delete: bool) -> Result<u64, Error> {
+        debug!("DELETE[{}] from backend, alien data: {:?}", key, op);
+        self.aliens.delete(&op, key, meta, force_delete).await
+    }
+
+    async fn put_conditional(
+        &self,
+        op: Operation,
+        key: BobKey,
+        data: &BobData,
+        condition: BobWriteCondition,
+    ) -> Result<(), Error> {
+        debug!("PUT[{}][{}] to backend with condition", key, op.disk_name_local());
+        if let Some(mem_disk) = self.disks.get(op.disk_name_local()) {
+            mem_disk.put_conditional(op.vdisk_id(), key, data, condition).await
+        } else {
+            error!("PUT[{}] Can't find disk {}", key, op.disk_name_local());
+            Err(Error::internal())
+        }
+    }
+
+    async fn delete_conditional(
+        &self,
+        op: Operation,
+        key: BobKey,
+        meta: &BobMeta,
+        condition: BobWriteCondition,
+    ) -> Result<u64, Error> {
+        debug!("DELETE[{}][{}] from backend with condition", key, op.disk_name_local());
+        if let Some(mem_disk) = self.disks.get(op.disk_name_local()) {
+            mem_disk.delete_conditional(op.vdisk_id(), key, meta, condition).await
+        } else {
+            error!("DELETE[{}] Can't find disk {}", key, op.disk_name_local());
+            Err(Error::internal())
+        }
     }
 
-    async fn shutdown(&self) {}
+    async fn shutdown(&self) {
+        if let Some(path) = &self.snapshot_path {
+            if let Err(e) = self.save_snapshot(path).await {
+                error!("failed to save mem backend snapshot to {}: {:?}", path.display(), e);
+            }
+        }
+    }
+}
+
+mod snapshot {
+    use super::Record;
+    use crate::prelude::*;
+    use bob_common::data::BOB_KEY_SIZE;
+
+    /// Content of the mem backend saved to file.
+    #[derive(Serialize, Deserialize)]
+    pub(super) struct Snapshot {
+        pub(super) disks: Vec<VDisk>,
+        pub(super) aliens: Vec<VDisk>,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    pub(super) struct VDisk {
+        // Disk name for local vdisks, node name for aliens
+        pub(super) owner: String,
+        pub(super) vdisk_id: VDiskId,
+        records: Vec<SnapshotRecord>,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct SnapshotRecord {
+        key: Vec<u8>,
+        timestamp: u64,
+        // `None` for delete marker
+        data: Option<Vec<u8>>,
+    }
+
+    impl VDisk {
+        pub(super) fn new(owner: &str, vdisk_id: VDiskId, records: Vec<(BobKey, Record)>) -> Self {
+            let records = records
+                .into_iter()
+                .map(|(key, record)| SnapshotRecord {
+                    key: key.into(),
+                    timestamp: record.timestamp(),
+                    data: record.data().map(|data| data.inner().to_vec()),
+                })
+                .collect();
+            Self {
+                owner: owner.to_owned(),
+                vdisk_id,
+                records,
+            }
+        }
+
+        pub(super) fn into_records(self) -> AnyResult<Vec<(BobKey, Record)>> {
+            self.records
+                .into_iter()
+                .map(|record| {
+                    if record.key.len() != BOB_KEY_SIZE {
+                        anyhow::bail!("key size {} doesn't match {}", record.key.len(), BOB_KEY_SIZE);
+                    }
+                    let value = match record.data {
+                        Some(data) => Record::Data(BobData::new(data.into(), BobMeta::new(record.timestamp))),
+                        None => Record::Deleted(record.timestamp),
+                    };
+                    Ok((BobKey::from(record.key), value))
+                })
+                .collect()
+        }
+    }
 }
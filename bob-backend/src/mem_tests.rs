@@ -2,7 +2,7 @@ use crate::prelude::*;
 
 use crate::{
     core::{BackendStorage, Operation},
-    mem_backend::{MemBackend, MemDisk},
+    mem_backend::{AlienDisk, MemBackend, MemDisk},
 };
 
 const VDISKS_COUNT: u32 = 10;
@@ -14,7 +14,8 @@ pub(crate) fn new_direct(paths: &[String], vdisks_count: u32) -> MemBackend {
         .collect();
     MemBackend {
         disks,
-        foreign_data: MemDisk::new_direct(DiskName::from("foreign"), vdisks_count),
+        aliens: AlienDisk::default(),
+        snapshot_path: None,
     }
 }
 
@@ -88,3 +89,178 @@ async fn test_mem_get_no_data() {
         .await;
     assert!(retval.err().unwrap().is_key_not_found())
 }
+
+#[tokio::test]
+async fn test_mem_put_conditional() {
+    let backend = new_direct(&["name".to_owned()], VDISKS_COUNT);
+    let op = || Operation::new_local(0, DiskPath::new("name".into(), ""));
+    let key = BobKey::from(1u64);
+
+    backend
+        .put_conditional(op(), key, &BobData::new(vec![1].into(), BobMeta::new(10)), BobWriteCondition::IfAbsent)
+        .await
+        .unwrap();
+    let retval = backend
+        .put_conditional(op(), key, &BobData::new(vec![2].into(), BobMeta::new(11)), BobWriteCondition::IfAbsent)
+        .await;
+    assert!(retval.err().unwrap().is_precondition_failed());
+
+    let retval = backend
+        .put_conditional(
+            op(),
+            key,
+            &BobData::new(vec![3].into(), BobMeta::new(12)),
+            BobWriteCondition::IfTimestampMatches(vec![11]),
+        )
+        .await;
+    assert!(retval.err().unwrap().is_precondition_failed());
+
+    backend
+        .put_conditional(
+            op(),
+            key,
+            &BobData::new(vec![4].into(), BobMeta::new(13)),
+            BobWriteCondition::IfTimestampMatches(vec![9, 10]),
+        )
+        .await
+        .unwrap();
+    let retval = backend.get(op(), key).await.unwrap();
+    assert_eq!(retval.into_inner(), vec![4]);
+}
+
+#[tokio::test]
+async fn test_mem_delete_conditional() {
+    let backend = new_direct(&["name".to_owned()], VDISKS_COUNT);
+    let op = || Operation::new_local(0, DiskPath::new("name".into(), ""));
+    let key = BobKey::from(1u64);
+
+    backend
+        .put(op(), key, &BobData::new(vec![1].into(), BobMeta::new(10)))
+        .await
+        .unwrap();
+    let retval = backend
+        .delete_conditional(op(), key, &BobMeta::new(20), BobWriteCondition::IfTimestampMatches(vec![9]))
+        .await;
+    assert!(retval.err().unwrap().is_precondition_failed());
+
+    backend
+        .delete_conditional(op(), key, &BobMeta::new(20), BobWriteCondition::IfNewerThan(11))
+        .await
+        .unwrap();
+    let retval = backend.get(op(), key).await;
+    assert!(retval.err().unwrap().is_key_not_found());
+
+    let retval = backend
+        .delete_conditional(op(), key, &BobMeta::new(21), BobWriteCondition::IfExists)
+        .await;
+    assert!(retval.err().unwrap().is_precondition_failed());
+}
+
+fn alien(node_name: &str) -> Operation {
+    let mut op = Operation::new_alien(0);
+    op.set_remote_node_name(node_name.into());
+    op
+}
+
+fn data(value: u8, timestamp: u64) -> BobData {
+    BobData::new(vec![value].into(), BobMeta::new(timestamp))
+}
+
+#[tokio::test]
+async fn test_mem_newest_record_wins() {
+    let backend = new_direct(&["name".to_owned()], VDISKS_COUNT);
+    let op = || Operation::new_local(0, DiskPath::new("name".into(), ""));
+    let key = BobKey::from(1u64);
+
+    backend.put(op(), key, &data(1, 10)).await.unwrap();
+    backend.put(op(), key, &data(2, 5)).await.unwrap();
+    assert_eq!(backend.get(op(), key).await.unwrap().into_inner(), vec![1]);
+
+    backend.put(op(), key, &data(3, 10)).await.unwrap();
+    assert_eq!(backend.get(op(), key).await.unwrap().into_inner(), vec![3]);
+}
+
+#[tokio::test]
+async fn test_mem_delete_marker_shadows_older_data() {
+    let backend = new_direct(&["name".to_owned()], VDISKS_COUNT);
+    let op = || Operation::new_local(0, DiskPath::new("name".into(), ""));
+    let key = BobKey::from(1u64);
+
+    assert_eq!(backend.delete(op(), key, &BobMeta::new(10)).await.unwrap(), 0);
+    backend.put(op(), key, &data(1, 9)).await.unwrap();
+    assert!(backend.get(op(), key).await.err().unwrap().is_key_not_found());
+    assert_eq!(backend.exist(op(), &[key]).await.unwrap(), vec![false]);
+
+    backend.put(op(), key, &data(2, 11)).await.unwrap();
+    assert_eq!(backend.exist(op(), &[key]).await.unwrap(), vec![true]);
+    assert_eq!(backend.delete(op(), key, &BobMeta::new(12)).await.unwrap(), 1);
+    assert!(backend.get(op(), key).await.err().unwrap().is_key_not_found());
+}
+
+#[tokio::test]
+async fn test_mem_aliens_are_separated_by_node() {
+    let backend = new_direct(&["name".to_owned()], VDISKS_COUNT);
+    let key = BobKey::from(1u64);
+
+    backend.put_alien(alien("a"), key, &data(1, 10)).await.unwrap();
+    backend.put_alien(alien("b"), key, &data(2, 20)).await.unwrap();
+
+    let retval = backend.get_alien(alien("a"), key).await.unwrap();
+    assert_eq!(retval.into_inner(), vec![1]);
+    let retval = backend.get_alien(Operation::new_alien(0), key).await.unwrap();
+    assert_eq!(retval.into_inner(), vec![2]);
+    let retval = backend.exist_alien(alien("c"), &[key]).await.unwrap();
+    assert_eq!(retval, vec![false]);
+    assert!(backend.get_alien(Operation::new_alien(1), key).await.err().unwrap().is_key_not_found());
+}
+
+#[tokio::test]
+async fn test_mem_alien_delete() {
+    let backend = new_direct(&["name".to_owned()], VDISKS_COUNT);
+    let key = BobKey::from(1u64);
+    backend.put_alien(alien("a"), key, &data(1, 10)).await.unwrap();
+
+    // not forced delete doesn't leave marker where key is absent
+    let retval = backend.delete_alien(alien("b"), key, &BobMeta::new(20), false).await.unwrap();
+    assert_eq!(retval, 0);
+    backend.put_alien(alien("b"), key, &data(2, 15)).await.unwrap();
+    assert!(backend.get_alien(alien("b"), key).await.is_ok());
+
+    let retval = backend.delete_alien(alien("c"), key, &BobMeta::new(20), true).await.unwrap();
+    assert_eq!(retval, 0);
+    backend.put_alien(alien("c"), key, &data(3, 15)).await.unwrap();
+    assert!(backend.get_alien(alien("c"), key).await.err().unwrap().is_key_not_found());
+
+    let retval = backend.delete_alien(Operation::new_alien(0), key, &BobMeta::new(20), false).await.unwrap();
+    assert_eq!(retval, 2);
+    assert!(backend.get_alien(Operation::new_alien(0), key).await.err().unwrap().is_key_not_found());
+}
+
+#[tokio::test]
+async fn test_mem_snapshot_restores_data_on_restart() {
+    let path = std::env::temp_dir().join(format!("bob_mem_snapshot_{}", std::process::id()));
+    let op = || Operation::new_local(0, DiskPath::new("name".into(), ""));
+    let backend = MemBackend {
+        snapshot_path: Some(path.clone()),
+        ..new_direct(&["name".to_owned()], VDISKS_COUNT)
+    };
+    backend.put(op(), 1.into(), &data(1, 10)).await.unwrap();
+    backend.delete(op(), 2.into(), &BobMeta::new(10)).await.unwrap();
+    backend.put_alien(alien("a"), 3.into(), &data(3, 10)).await.unwrap();
+    backend.shutdown().await;
+
+    let backend = MemBackend {
+        snapshot_path: Some(path.clone()),
+        ..new_direct(&["name".to_owned()], VDISKS_COUNT)
+    };
+    backend.run_backend().await.unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let retval = backend.get(op(), 1.into()).await.unwrap();
+    assert_eq!(retval.meta().timestamp(), 10);
+    assert_eq!(retval.into_inner(), vec![1]);
+    backend.put(op(), 2.into(), &data(2, 9)).await.unwrap();
+    assert!(backend.get(op(), 2.into()).await.err().unwrap().is_key_not_found());
+    let retval = backend.get_alien(alien("a"), 3.into()).await.unwrap();
+    assert_eq!(retval.into_inner(), vec![3]);
+}
@@ -10,7 +10,10 @@ use super::{
     data::Key, disk_controller::logger::DisksEventsLogger, disk_controller::DiskController,
     hooks::SimpleHolder, settings::Settings, Holder,
 };
-use crate::core::{BackendStorage, MetricsProducer, Operation};
+use crate::{
+    core::{BackendStorage, MetricsProducer, Operation},
+    faulty_backend::Faults,
+};
 
 pub type BackendResult<T> = std::result::Result<T, Error>;
 pub type PearlStorage = Storage<Key>;
@@ -23,9 +26,13 @@ pub struct Pearl {
 }
 
 impl Pearl {
-    pub async fn new(mapper: Arc<Virtual>, config: &NodeConfig) -> BackendResult<Self> {
+    pub async fn new(
+        mapper: Arc<Virtual>,
+        config: &NodeConfig,
+        faults: Option<Arc<Faults>>,
+    ) -> BackendResult<Self> {
         debug!("initializing pearl backend");
-        let settings = Arc::new(Settings::new(config, mapper));
+        let settings = Arc::new(Settings::new(config, mapper, faults));
         let logfile = config.pearl().disks_events_logfile();
         let logger = DisksEventsLogger::new(logfile).await.map_err(|e| {
             Error::disk_events_logger("disk events logger initialization failed", e)
@@ -168,7 +175,7 @@ impl BackendStorage for Pearl {
             .find(|dc| dc.can_process_operation(&op));
         if let Some(disk_controller) = dc_option {
             disk_controller
-                .put(op, key, data)
+                .put(op, key, data, None)
                 .await
         } else {
             debug!(
@@ -237,7 +244,7 @@ impl BackendStorage for Pearl {
             .find(|dc| dc.can_process_operation(&op));
 
         if let Some(disk_controller) = dc_option {
-            disk_controller.delete(op, key, meta).await
+            disk_controller.delete(op, key, meta, None).await
         } else {
             Err(Error::dc_is_not_available())
         }
@@ -252,6 +259,44 @@ impl BackendStorage for Pearl {
         }
     }
 
+    async fn put_conditional(
+        &self,
+        op: Operation,
+        key: BobKey,
+        data: &BobData,
+        condition: BobWriteCondition,
+    ) -> BackendResult<()> {
+        debug!("PUT[{}] to pearl backend with condition {:?}. operation: {:?}", key, condition, op);
+        let dc_option = self
+            .disk_controllers
+            .iter()
+            .find(|dc| dc.can_process_operation(&op));
+        if let Some(disk_controller) = dc_option {
+            disk_controller.put(op, key, data, Some(condition)).await
+        } else {
+            Err(Error::dc_is_not_available())
+        }
+    }
+
+    async fn delete_conditional(
+        &self,
+        op: Operation,
+        key: BobKey,
+        meta: &BobMeta,
+        condition: BobWriteCondition,
+    ) -> Result<u64, Error> {
+        debug!("DELETE[{}] from pearl backend with condition {:?}. operation: {:?}", key, condition, op);
+        let dc_option = self
+            .disk_controllers
+            .iter()
+            .find(|dc| dc.can_process_operation(&op));
+        if let Some(disk_controller) = dc_option {
+            disk_controller.delete(op, key, meta, Some(condition)).await
+        } else {
+            Err(Error::dc_is_not_available())
+        }
+    }
+
     async fn shutdown(&self) {
         use futures::stream::FuturesUnordered;
         info!("begin shutdown");
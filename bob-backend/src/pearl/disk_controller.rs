@@ -5,14 +5,19 @@ use futures::Future;
 use tokio::time::{interval, Interval};
 
 pub(crate) mod logger;
-use crate::{core::Operation, pearl::hooks::Hooks, prelude::*};
+use crate::{
+    core::Operation,
+    faulty_backend::ALIEN_DISK,
+    pearl::hooks::Hooks,
+    prelude::*,
+};
 use logger::DisksEventsLogger;
 
 use super::holder::PearlCreationContext;
 use super::Holder;
 use super::{core::BackendResult, settings::Settings, utils::StartTimestampConfig, Group};
 
-use bob_common::metrics::DISKS_FOLDER;
+use bob_common::{configs::node::BackendOperation, metrics::DISKS_FOLDER};
 
 const CHECK_INTERVAL: Duration = Duration::from_millis(5000);
 
@@ -185,6 +190,21 @@ impl DiskController {
         e
     }
 
+    // Injected errors are processed as errors of pearl, so disconnection makes disk not ready
+    async fn inject_fault(&self, operation: BackendOperation) -> Result<(), Error> {
+        if let Some(faults) = self.settings.faults() {
+            let disk = if self.is_alien {
+                ALIEN_DISK
+            } else {
+                self.disk.name().as_str()
+            };
+            if let Err(e) = faults.inject_into_disk(disk, operation).await {
+                return Err(self.process_error(e).await);
+            }
+        }
+        Ok(())
+    }
+
     async fn log_state_change(&self, new_state: &GroupsState) {
         match new_state {
             GroupsState::NotReady => {
@@ -386,9 +406,10 @@ impl DiskController {
         data: &BobData,
     ) -> Result<(), Error> {
         if *self.state.read().await == GroupsState::Ready {
+            self.inject_fault(BackendOperation::Put).await?;
             let vdisk_group = self.get_or_create_pearl(&op).await;
             match vdisk_group {
-                Ok(group) => match group.put(key, data, StartTimestampConfig::new(false)).await {
+                Ok(group) => match group.put(key, data, StartTimestampConfig::new(false), None).await {
                     Err(e) => Err(self.process_error(e).await),
                     Ok(()) => Ok(()),
                 },
@@ -410,8 +431,10 @@ impl DiskController {
         op: Operation,
         key: BobKey,
         data: &BobData,
+        condition: Option<BobWriteCondition>,
     ) -> BackendResult<()> {
         if *self.state.read().await == GroupsState::Ready {
+            self.inject_fault(BackendOperation::Put).await?;
             let vdisk_group = {
                 let groups = self.groups.read().await;
                 groups
@@ -420,7 +443,7 @@ impl DiskController {
                     .cloned()
             };
             if let Some(group) = vdisk_group {
-                match group.put(key, data, StartTimestampConfig::default()).await {
+                match group.put(key, data, StartTimestampConfig::default(), condition).await {
                     Err(e) => {
                         debug!("PUT[{}], error: {:?}", key, e);
                         Err(self.process_error(e).await)
@@ -447,6 +470,7 @@ impl DiskController {
     pub(crate) async fn get(&self, op: Operation, key: BobKey) -> Result<BobData, Error> {
         if *self.state.read().await == GroupsState::Ready {
             debug!("Get[{}] from pearl backend. operation: {:?}", key, op);
+            self.inject_fault(BackendOperation::Get).await?;
             let vdisk_group = self
                 .groups
                 .read()
@@ -467,6 +491,7 @@ impl DiskController {
 
     pub(crate) async fn get_alien(&self, op: Operation, key: BobKey) -> Result<BobData, Error> {
         if *self.state.read().await == GroupsState::Ready {
+            self.inject_fault(BackendOperation::Get).await?;
             let mut result: Option<BobData> = None;
             for g in self.find_all_groups(&op).await {
                 match g.get(key).await {
@@ -496,6 +521,7 @@ impl DiskController {
         keys: &[BobKey],
     ) -> Result<Vec<bool>, Error> {
         if *self.state.read().await == GroupsState::Ready {
+            self.inject_fault(BackendOperation::Exist).await?;
             let group_option = self
                 .groups
                 .read()
@@ -519,6 +545,7 @@ impl DiskController {
         keys: &[BobKey],
     ) -> Result<Vec<bool>, Error> {
         if *self.state.read().await == GroupsState::Ready {
+            self.inject_fault(BackendOperation::Exist).await?;
             let mut result: Option<Vec<bool>> = None;
             for g in self.find_all_groups(&op).await {
                 match g.exist(keys).await {
@@ -543,10 +570,12 @@ impl DiskController {
         &self,
         op: Operation,
         key: BobKey,
-        meta: &BobMeta
+        meta: &BobMeta,
+        condition: Option<BobWriteCondition>,
     ) -> Result<u64, Error> {
         if *self.state.read().await == GroupsState::Ready {
             debug!("DELETE[{}] from pearl backend. operation: {:?}", key, op);
+            self.inject_fault(BackendOperation::Delete).await?;
             let vdisk_group = self
                 .groups
                 .read()
@@ -556,7 +585,7 @@ impl DiskController {
                 .cloned();
             if let Some(group) = vdisk_group {
                 group
-                    .delete(key, meta, StartTimestampConfig::default(), true) // Local delete should always have force_delete = true
+                    .delete(key, meta, StartTimestampConfig::default(), true, condition) // Local delete should always have force_delete = true
                     .await
             } else {
                 error!("DELETE[{}] Cannot find storage, operation: {:?}", key, op);
@@ -575,11 +604,12 @@ impl DiskController {
         force_delete: bool,
     ) -> Result<u64, Error> {
         if *self.state.read().await == GroupsState::Ready {
+            self.inject_fault(BackendOperation::Delete).await?;
             let groups = self.find_all_groups(&op).await;
             if force_delete && groups.is_empty() {
                 // If delete is forced we need to create at least one group
                 match self.get_or_create_pearl(&op).await {
-                    Ok(group) => match group.delete(key, meta, StartTimestampConfig::new(false), force_delete).await {
+                    Ok(group) => match group.delete(key, meta, StartTimestampConfig::new(false), force_delete, None).await {
                         Ok(r) => Ok(r),
                         Err(e) => {
                             debug!("DELETE[alien][{}] Error delete in one of the groups, op: {:?}, err: {}", key, op, e);
@@ -598,7 +628,7 @@ impl DiskController {
                 let mut result = 0;
                 let mut err = None;
                 for g in groups {
-                    match g.delete(key, meta, StartTimestampConfig::new(false), force_delete).await {
+                    match g.delete(key, meta, StartTimestampConfig::new(false), force_delete, None).await {
                         Ok(r) => result += r,
                         Err(e) => {
                             debug!("DELETE[alien][{}] Error delete in one of the groups, op: {:?}, err: {}", key, op, e);
@@ -724,7 +754,7 @@ impl DiskController {
             .await
     }
 
-    pub(crate) fn groups(&self) -> Arc<RwLock<Vec<Group>>> {
+    pub fn groups(&self) -> Arc<RwLock<Vec<Group>>> {
         self.groups.clone()
     }
 
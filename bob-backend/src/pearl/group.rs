@@ -9,14 +9,33 @@ use futures::Future;
 use pearl::{BloomProvider, ReadResult};
 use sha2::{Sha256, Digest};
 use async_lock::{RwLock as UgradableRwLock, RwLockUpgradableReadGuard};
+use std::{
+    collections::{hash_map::DefaultHasher, HashSet},
+    hash::{Hash, Hasher},
+};
+use tokio::sync::{Mutex, MutexGuard};
 
 pub type HoldersContainer =
     HierarchicalFilters<Key, <Holder as BloomProvider<Key>>::Filter, Holder>;
 
+/// Files of the partition linked to the vdisk snapshot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PartitionSnapshot {
+    /// Name of the partition directory in the snapshot.
+    pub name: String,
+    pub start_timestamp: u64,
+    /// Blob files are renamed to the prefix of the restoring node.
+    pub blob_prefix: String,
+    pub files: Vec<String>,
+}
+
+const WRITE_LOCK_STRIPES: usize = 64;
+
 #[derive(Clone, Debug)]
 pub struct Group {
     holders: Arc<UgradableRwLock<HoldersContainer>>,
     reinit_lock: Arc<RwLock<()>>,
+    write_locks: Arc<[Mutex<()>]>,
     settings: Arc<Settings>,
     directory_path: PathBuf,
     vdisk_id: VDiskId,
@@ -43,6 +62,7 @@ impl Group {
                 2,
             ))),
             reinit_lock: Arc::new(RwLock::new(())),
+            write_locks: (0..WRITE_LOCK_STRIPES).map(|_| Mutex::new(())).collect(),
             settings,
             vdisk_id,
             node_name,
@@ -206,13 +226,16 @@ impl Group {
         })
     }
 
+    #[tracing::instrument(name = "group.put", skip_all, fields(key = %key, vdisk = self.vdisk_id, disk = %self.disk_name))]
     pub async fn put(
         &self,
         key: BobKey,
         data: &BobData,
         timestamp_config: StartTimestampConfig,
+        condition: Option<BobWriteCondition>,
     ) -> Result<(), Error> {
         let _reinit_lock = self.reinit_lock.try_read().map_err(|_| Error::holder_temporary_unavailable())?;
+        let _write_lock = self.lock_and_check_condition(key, condition).await?;
         let holder = self
             .get_or_create_actual_holder(data.meta().timestamp(), timestamp_config)
             .await?;
@@ -224,6 +247,45 @@ impl Group {
         Ok(res)
     }
 
+    // All writes of the key within group are serialized by one of the striped locks, so
+    // condition check and write are atomic relative to any other put or delete of the key
+    async fn lock_and_check_condition(
+        &self,
+        key: BobKey,
+        condition: Option<BobWriteCondition>,
+    ) -> Result<MutexGuard<'_, ()>, Error> {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        let guard = self.write_locks[hasher.finish() as usize % self.write_locks.len()].lock().await;
+        if let Some(condition) = condition {
+            let actual_timestamp = self.actual_timestamp(key).await?;
+            trace!("{} actual timestamp: {:?}, condition: {:?}", key, actual_timestamp, condition);
+            condition.check(actual_timestamp)?;
+        }
+        Ok(guard)
+    }
+
+    // Returns timestamp of the newest record for key, or `None` if key is absent or deleted
+    async fn actual_timestamp(&self, key: BobKey) -> Result<Option<u64>, Error> {
+        let holders = self.holders.read().await;
+        let mut max_timestamp = None;
+        let mut result = None;
+        for (_, Leaf { data: holder, .. }) in holders.iter_possible_childs_rev(&Key::from(key)) {
+            if self.should_check_holder(holder, max_timestamp) {
+                let (ts, found) = match holder.exist(key).await? {
+                    ReadResult::Found(ts) => (ts.into(), true),
+                    ReadResult::Deleted(ts) => (ts.into(), false),
+                    ReadResult::NotFound => continue,
+                };
+                if !matches!(max_timestamp, Some(max) if max >= ts) {
+                    max_timestamp = Some(ts);
+                    result = if found { Some(ts) } else { None };
+                }
+            }
+        }
+        Ok(result)
+    }
+
     async fn put_common(holder: &Holder, key: BobKey, data: &BobData) -> Result<(), Error> {
         let result = holder.write(key, data).await;
         if let Err(e) = result {
@@ -266,6 +328,7 @@ impl Group {
         }
     }
 
+    #[tracing::instrument(name = "group.get", skip_all, fields(key = %key, vdisk = self.vdisk_id, disk = %self.disk_name))]
     pub async fn get(&self, key: BobKey) -> Result<BobData, Error> {
         let _reinit_lock = self.reinit_lock.try_read().map_err(|_| Error::holder_temporary_unavailable())?;
         let holders = self.holders.read().await;
@@ -379,8 +442,10 @@ impl Group {
         meta: &BobMeta,
         timestamp_config: StartTimestampConfig,
         force_delete: bool,
+        condition: Option<BobWriteCondition>,
     ) -> Result<u64, Error> {
         let _reinit_lock = self.reinit_lock.try_read().map_err(|_| Error::holder_temporary_unavailable())?;
+        let _write_lock = self.lock_and_check_condition(key, condition).await?;
         let mut reference_timestamp = meta.timestamp();
         let mut total_deletion_count = 0;
 
@@ -652,6 +717,118 @@ impl Group {
         corrupted_blobs
     }
 
+    /// Returns unique keys of all holders, deleted keys included.
+    pub async fn keys(&self, dump_timeout: Duration) -> BackendResult<Vec<BobKey>> {
+        let holders = self.holders.read().await;
+        let mut keys = HashSet::new();
+        for holder in holders.iter() {
+            keys.extend(holder.keys(dump_timeout).await?);
+        }
+        Ok(keys.into_iter().collect())
+    }
+
+    /// Closes active blobs of all partitions and links their files into `dir`, every partition
+    /// in its own subdirectory. Files are copied if `dir` is on another filesystem.
+    pub async fn snapshot(
+        &self,
+        dir: &Path,
+        dump_timeout: Duration,
+    ) -> BackendResult<Vec<PartitionSnapshot>> {
+        let holders: Vec<Holder> = self.holders.read().await.iter().cloned().collect();
+        let mut partitions = vec![];
+        for holder in holders {
+            let files = holder.snapshot_files(dump_timeout).await?;
+            if files.is_empty() {
+                continue;
+            }
+            let name = holder
+                .disk_path()
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_else(|| holder.start_timestamp().to_string());
+            let partition_dir = dir.join(&name);
+            let mut file_names = vec![];
+            let linked = async {
+                tokio::fs::create_dir_all(&partition_dir).await?;
+                for file in files {
+                    let file_name = file.file_name().unwrap_or_default().to_owned();
+                    let target = partition_dir.join(&file_name);
+                    if tokio::fs::hard_link(&file, &target).await.is_err() {
+                        tokio::fs::copy(&file, &target).await?;
+                    }
+                    file_names.push(file_name.to_string_lossy().into_owned());
+                }
+                Ok::<_, std::io::Error>(())
+            }
+            .await;
+            linked.map_err(|e| {
+                Error::storage(format!("can't snapshot partition {}: {}", holder.get_id(), e))
+            })?;
+            partitions.push(PartitionSnapshot {
+                name,
+                start_timestamp: holder.start_timestamp(),
+                blob_prefix: holder.blob_file_name_prefix().to_owned(),
+                files: file_names,
+            });
+        }
+        Ok(partitions)
+    }
+
+    /// Copies files of partitions from the snapshot `dir` to the vdisk directory and attaches
+    /// them. Partitions with the same start timestamps must be detached and deleted before.
+    pub async fn restore(&self, dir: &Path, partitions: &[PartitionSnapshot]) -> BackendResult<()> {
+        {
+            let holders = self.holders.read().await;
+            if let Some(partition) = partitions
+                .iter()
+                .find(|p| holders.iter().any(|h| h.start_timestamp() == p.start_timestamp))
+            {
+                let msg = format!("pearl:{} is attached, delete it before restore", partition.start_timestamp);
+                return Err(Error::pearl_change_state(msg));
+            }
+        }
+        for partition in partitions {
+            let holder = self.create_pearl_by_timestamp(
+                partition.start_timestamp,
+                &StartTimestampConfig::default(),
+            );
+            let target = holder.disk_path();
+            if target.exists() {
+                let msg = format!("directory {} already exists, delete it before restore", target.display());
+                return Err(Error::pearl_change_state(msg));
+            }
+            let copied = async {
+                tokio::fs::create_dir_all(target).await?;
+                for file in &partition.files {
+                    // pearl opens only blobs with the prefix of the holder
+                    let target_name = match file.strip_prefix(&partition.blob_prefix) {
+                        Some(rest) => format!("{}{}", holder.blob_file_name_prefix(), rest),
+                        None => file.clone(),
+                    };
+                    tokio::fs::copy(dir.join(&partition.name).join(file), target.join(target_name))
+                        .await?;
+                }
+                Ok::<_, std::io::Error>(())
+            }
+            .await;
+            copied.map_err(|e| {
+                let msg = format!("can't restore partition {}: {}", partition.name, e);
+                Error::storage(msg)
+            })?;
+            self.attach(partition.start_timestamp).await?;
+        }
+        Ok(())
+    }
+
+    pub async fn records_count(&self) -> usize {
+        let holders = self.holders.read().await;
+        let mut records = 0;
+        for holder in holders.iter() {
+            records += holder.records_count().await;
+        }
+        records
+    }
+
     pub(crate) async fn blobs_count(&self) -> usize {
         let mut blobs = 0;
         let holders = self.holders.read().await;
@@ -18,6 +18,17 @@ use pearl::{BlobRecordTimestamp, ReadResult, BloomProvider, FilterResult};
 
 const MAX_TIME_SINCE_LAST_WRITE_SEC: u64 = 10;
 const SMALL_RECORDS_COUNT_MUL: u64 = 10;
+// Default pearl directory for corrupted blobs, they are counted in `corrupted_blobs_count`
+const CORRUPTED_DIR_NAME: &str = "corrupted";
+
+/// Closed blob with records that failed checksum validation.
+#[derive(Debug, Clone)]
+pub struct CorruptedBlob {
+    pub path: PathBuf,
+    pub error: String,
+    /// Keys of records from the blob index, empty if the index is corrupted too.
+    pub keys: Vec<BobKey>,
+}
 
 /// Struct hold pearl and add put/get/restart api
 #[derive(Clone, Debug)]
@@ -228,6 +239,138 @@ impl Holder {
         }
     }
 
+    /// Returns keys of all records in holder, deletion markers included. Pearl can't iterate
+    /// over the keys, so the active blob is closed and keys are read from index files, which are
+    /// dumped in background. Fails if some index is not dumped within `dump_timeout`.
+    pub async fn keys(&self, dump_timeout: Duration) -> BackendResult<Vec<BobKey>> {
+        let blobs = self.dumped_blobs(dump_timeout).await?;
+        Ok(blobs.into_iter().flat_map(|(_, keys)| keys).collect())
+    }
+
+    /// Closes the active blob and returns paths of blob and index files of all non-empty blobs.
+    /// Files of closed blobs aren't modified, so they can be linked or copied consistently.
+    pub async fn snapshot_files(&self, dump_timeout: Duration) -> BackendResult<Vec<PathBuf>> {
+        let blobs = self.dumped_blobs(dump_timeout).await?;
+        Ok(blobs
+            .into_iter()
+            .flat_map(|(blob, _)| [blob.with_extension("index"), blob])
+            .collect())
+    }
+
+    // Closes the active blob and waits for indexes of all non-empty blobs to be dumped,
+    // returns paths of the blobs with their keys
+    async fn dumped_blobs(&self, dump_timeout: Duration) -> BackendResult<Vec<(PathBuf, Vec<BobKey>)>> {
+        let blobs = {
+            let storage = self.storage.write().await;
+            let storage = storage.get().ok_or_else(Error::vdisk_is_not_ready)?;
+            if storage.has_active_blob().await {
+                storage
+                    .try_close_active_blob()
+                    .await
+                    .map_err(|e| Error::storage(format!("pearl error: {:?}", e)))?;
+            }
+            storage.records_count_detailed().await
+        };
+        let prefix = self.inner.config.blob_file_name_prefix();
+        let deadline = Instant::now() + dump_timeout;
+        let mut dumped = Vec::new();
+        // empty blobs have no index
+        for (id, _) in blobs.into_iter().filter(|(_, count)| *count > 0) {
+            let blob = self.inner.disk_path.join(format!("{}.{}.blob", prefix, id));
+            let path = blob.with_extension("index");
+            let headers = loop {
+                match pearl::tools::read_index(&path).await {
+                    Ok(headers) => break headers,
+                    Err(e) if Instant::now() >= deadline => {
+                        let msg = format!("index {} is not dumped: {}", path.display(), e);
+                        return Err(Error::storage(msg));
+                    }
+                    Err(_) => tokio::time::sleep(Duration::from_millis(100)).await,
+                }
+            };
+            dumped.push((blob, headers.into_keys().map(BobKey::from).collect()));
+        }
+        Ok(dumped)
+    }
+
+    pub(crate) fn disk_path(&self) -> &Path {
+        &self.inner.disk_path
+    }
+
+    pub(crate) fn blob_file_name_prefix(&self) -> &str {
+        self.inner.config.blob_file_name_prefix()
+    }
+
+    /// Paths of closed blobs. The active blob and blobs which indexes are not dumped yet
+    /// are skipped.
+    pub async fn closed_blobs(&self) -> Vec<PathBuf> {
+        let mut blobs = {
+            let storage = self.storage.read().await;
+            if let Some(storage) = storage.get() {
+                let mut blobs = storage.records_count_detailed().await;
+                if storage.has_active_blob().await {
+                    blobs.pop();
+                }
+                blobs
+            } else {
+                return vec![];
+            }
+        };
+        let prefix = self.inner.config.blob_file_name_prefix();
+        blobs.retain(|(_, count)| *count > 0);
+        blobs
+            .into_iter()
+            .map(|(id, _)| self.inner.disk_path.join(format!("{}.{}.blob", prefix, id)))
+            .filter(|path| path.with_extension("index").exists())
+            .collect()
+    }
+
+    /// Validates checksums of all records of the closed blob. Returns keys of the corrupted
+    /// blob from its index, so the records can be restored.
+    pub async fn check_blob(path: &Path) -> Option<CorruptedBlob> {
+        let blob = path.to_owned();
+        let result = tokio::task::spawn_blocking(move || pearl::tools::validate_blob(&blob))
+            .await
+            .unwrap_or_else(|e| Err(e.into()));
+        let error = format!("{:#}", result.err()?);
+        let keys = match pearl::tools::read_index(&path.with_extension("index")).await {
+            Ok(headers) => headers.into_keys().map(BobKey::from).collect(),
+            Err(e) => {
+                error!("can't read keys of corrupted blob {}: {:#}", path.display(), e);
+                vec![]
+            }
+        };
+        Some(CorruptedBlob {
+            path: path.to_owned(),
+            error,
+            keys,
+        })
+    }
+
+    /// Moves blobs with their indexes to the directory for corrupted blobs. Pearl can't drop
+    /// a blob from the open storage, so the storage is reopened without them and the other
+    /// blobs of the holder are unavailable meanwhile.
+    pub async fn quarantine(&self, blobs: &[PathBuf]) -> BackendResult<()> {
+        let dir = self.inner.disk_path.join(CORRUPTED_DIR_NAME);
+        self.close_storage().await;
+        let moved = async {
+            tokio::fs::create_dir_all(&dir).await?;
+            for blob in blobs {
+                for path in [blob.clone(), blob.with_extension("index")] {
+                    if let Some(name) = path.file_name().filter(|_| path.exists()) {
+                        tokio::fs::rename(&path, dir.join(name)).await?;
+                    }
+                }
+            }
+            Ok::<_, IOError>(())
+        }
+        .await;
+        let prepared = self.prepare_storage().await;
+        moved.map_err(|e| Error::storage(format!("can't quarantine blobs of {}: {}", self.get_id(), e)))?;
+        warn!("blobs {:?} of {} are moved to {}", blobs, self.get_id(), dir.display());
+        prepared
+    }
+
     pub async fn free_excess_resources(&self) -> usize {
         let storage = self.storage.read().await;
         if let Some(storage) = storage.get() {
@@ -14,7 +14,7 @@ pub use self::{
     core::Pearl,
     data::{le_cmp_keys, Key},
     disk_controller::DiskController,
-    group::Group,
-    holder::Holder,
+    group::{Group, PartitionSnapshot},
+    holder::{CorruptedBlob, Holder},
     hooks::{BloomFilterMemoryLimitHooks, Hooks, NoopHooks},
 };
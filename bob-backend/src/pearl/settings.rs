@@ -1,4 +1,4 @@
-use crate::prelude::*;
+use crate::{faulty_backend::Faults, prelude::*};
 
 use super::{
     core::BackendResult,
@@ -18,10 +18,11 @@ pub struct Settings {
     config: PearlConfig,
     mapper: Arc<Virtual>,
     holder_group_size: usize,
+    faults: Option<Arc<Faults>>,
 }
 
 impl Settings {
-    pub fn new(config: &NodeConfig, mapper: Arc<Virtual>) -> Self {
+    pub fn new(config: &NodeConfig, mapper: Arc<Virtual>, faults: Option<Arc<Faults>>) -> Self {
         let holder_group_size = config.holder_group_size();
         let config = config.pearl().clone();
         let alien_folder = if let Some(alien_disk) = config.alien_disk() {
@@ -41,6 +42,7 @@ impl Settings {
             mapper,
             config,
             holder_group_size,
+            faults,
         }
     }
 
@@ -52,6 +54,10 @@ impl Settings {
         self.holder_group_size
     }
 
+    pub(crate) fn faults(&self) -> Option<&Faults> {
+        self.faults.as_deref()
+    }
+
     pub(crate) async fn read_group_from_disk(
         self: Arc<Self>,
         config: &NodeConfig,
@@ -1,32 +1,50 @@
 use crate::prelude::*;
 
 use super::{core::BackendResult, Pearl as PearlBackend};
-use crate::core::{BackendStorage, Operation};
-use bob_common::configs::cluster::Cluster as ClusterConfig;
+use crate::{
+    core::{BackendStorage, MetricsProducer, Operation},
+    faulty_backend::Faults,
+};
+use bob_common::{
+    configs::{cluster::Cluster as ClusterConfig, node::FaultRule},
+    error::Kind,
+};
 
 static DISK_NAME: &str = "disk1";
 static PEARL_PATH: &str = "/tmp/d1/";
+static KEYS_PEARL_PATH: &str = "/tmp/d_keys/";
+static SCRUB_PEARL_PATH: &str = "/tmp/d_scrub/";
+static SNAPSHOT_PEARL_PATH: &str = "/tmp/d_snapshot/";
+static FAULTS_PEARL_PATH: &str = "/tmp/d_faults/";
 const KEY_ID: u64 = 1;
 const TIMESTAMP: u64 = 1;
 
-async fn drop_pearl() {
-    let path = PathBuf::from(PEARL_PATH);
+async fn drop_pearl(path: &str) {
+    let path = PathBuf::from(path);
     if path.exists() {
         remove_dir_all(path).await.unwrap();
     }
 }
 
-async fn create_backend(node_config: &str, cluster_config: &str) -> BackendResult<PearlBackend> {
+async fn create_backend(
+    node_config: &str,
+    cluster_config: &str,
+    faults: Option<Arc<Faults>>,
+) -> BackendResult<PearlBackend> {
     let cluster = ClusterConfig::get_from_string(cluster_config).unwrap();
     let node = NodeConfig::get_from_string(node_config, &cluster).unwrap();
     debug!("node: {:?}", node);
 
     let mapper = Arc::new(Virtual::new(&node, &cluster));
     debug!("mapper: {:?}", mapper);
-    PearlBackend::new(mapper, &node).await
+    PearlBackend::new(mapper, &node, faults).await
 }
 
-async fn backend() -> PearlBackend {
+async fn backend(path: &str) -> PearlBackend {
+    backend_with_faults(path, None).await
+}
+
+async fn backend_with_faults(path: &str, faults: Option<Arc<Faults>>) -> PearlBackend {
     let node_config = "
 log_config: logger.yaml
 users_config: users.yaml
@@ -49,29 +67,29 @@ pearl:                             # used only for 'backend_type: pearl'
     timestamp_period: 1d           # period when new pearl directory created. required for 'pearl'
     create_pearl_wait_delay: 100ms
 ";
-    let cluster_config = "
+    let cluster_config = format!("
 nodes:
     - name: local_node
       address: 127.0.0.1:20000
       disks:
         - name: disk1
-          path: /tmp/d1
+          path: {}
 vdisks:
     - id: 0
       replicas:
         - node: local_node
           disk: disk1
-";
+", path);
     debug!("node_config: {}", node_config);
     debug!("cluster_config: {}", cluster_config);
-    create_backend(node_config, cluster_config).await.unwrap()
+    create_backend(node_config, &cluster_config, faults).await.unwrap()
 }
 
 #[tokio::test(flavor = "multi_thread")]
 async fn test_write_multiple_read() {
-    drop_pearl().await;
+    drop_pearl(PEARL_PATH).await;
     let vdisk_id = 0;
-    let backend = backend().await;
+    let backend = backend(PEARL_PATH).await;
     backend.run().await.unwrap();
     let path = DiskPath::new(DISK_NAME.into(), "");
     let operation = Operation::new_local(vdisk_id, path);
@@ -90,5 +108,128 @@ async fn test_write_multiple_read() {
     assert_eq!(TIMESTAMP, res.unwrap().meta().timestamp());
     let res = backend.get(operation, BobKey::from(KEY_ID)).await;
     assert_eq!(TIMESTAMP, res.unwrap().meta().timestamp());
-    drop_pearl().await;
+    drop_pearl(PEARL_PATH).await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_group_keys() {
+    drop_pearl(KEYS_PEARL_PATH).await;
+    let backend = backend(KEYS_PEARL_PATH).await;
+    backend.run().await.unwrap();
+    let operation = Operation::new_local(0, DiskPath::new(DISK_NAME.into(), ""));
+    for id in 0..10 {
+        let data = BobData::new(vec![].into(), BobMeta::new(TIMESTAMP));
+        backend.put(operation.clone(), BobKey::from(id), &data).await.unwrap();
+    }
+    let meta = BobMeta::new(TIMESTAMP + 1);
+    backend.delete(operation, BobKey::from(0), &meta).await.unwrap();
+    let (dcs, _) = backend.disk_controllers().unwrap();
+    let group = dcs[0].vdisk_group(0).await.unwrap();
+
+    let mut keys = group.keys(Duration::from_secs(10)).await.unwrap();
+
+    keys.sort_by_key(|key| key.to_string());
+    let expected = (0..10).map(BobKey::from).collect::<Vec<_>>();
+    assert_eq!(expected, keys);
+    drop_pearl(KEYS_PEARL_PATH).await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_corrupted_blob_quarantine() {
+    drop_pearl(SCRUB_PEARL_PATH).await;
+    let backend = backend(SCRUB_PEARL_PATH).await;
+    backend.run().await.unwrap();
+    let operation = Operation::new_local(0, DiskPath::new(DISK_NAME.into(), ""));
+    for id in 0..10 {
+        let data = BobData::new(vec![id as u8; 64].into(), BobMeta::new(TIMESTAMP));
+        backend.put(operation.clone(), BobKey::from(id), &data).await.unwrap();
+    }
+    let (dcs, _) = backend.disk_controllers().unwrap();
+    let group = dcs[0].vdisk_group(0).await.unwrap();
+    // Closes active blob and waits for index dump
+    group.keys(Duration::from_secs(10)).await.unwrap();
+    let holder = group.holders().read().await.iter().next().cloned().unwrap();
+    let blobs = holder.closed_blobs().await;
+    assert_eq!(1, blobs.len());
+    assert!(super::Holder::check_blob(&blobs[0]).await.is_none());
+
+    let mut content = std::fs::read(&blobs[0]).unwrap();
+    let last = content.len() - 1;
+    content[last] ^= 0xFF;
+    std::fs::write(&blobs[0], content).unwrap();
+    let corrupted = super::Holder::check_blob(&blobs[0]).await.unwrap();
+    holder.quarantine(std::slice::from_ref(&corrupted.path)).await.unwrap();
+
+    assert_eq!(10, corrupted.keys.len());
+    assert!(holder.closed_blobs().await.is_empty());
+    assert_eq!(1, holder.corrupted_blobs_count().await);
+    drop_pearl(SCRUB_PEARL_PATH).await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_snapshot_restore() {
+    drop_pearl(SNAPSHOT_PEARL_PATH).await;
+    let backend = backend(SNAPSHOT_PEARL_PATH).await;
+    backend.run().await.unwrap();
+    let operation = Operation::new_local(0, DiskPath::new(DISK_NAME.into(), ""));
+    for id in 0..10 {
+        let data = BobData::new(vec![id as u8; 64].into(), BobMeta::new(TIMESTAMP));
+        backend.put(operation.clone(), BobKey::from(id), &data).await.unwrap();
+    }
+    let (dcs, _) = backend.disk_controllers().unwrap();
+    let group = dcs[0].vdisk_group(0).await.unwrap();
+    let snapshot_dir = PathBuf::from(SNAPSHOT_PEARL_PATH).join("snapshot");
+
+    let partitions = group.snapshot(&snapshot_dir, Duration::from_secs(10)).await.unwrap();
+
+    assert_eq!(1, partitions.len());
+    assert_eq!(2, partitions[0].files.len());
+    let restored = group.restore(&snapshot_dir, &partitions).await;
+    assert!(restored.is_err(), "attached partition is overwritten");
+    let start_timestamp = partitions[0].start_timestamp;
+    for holder in group.detach(start_timestamp).await.unwrap() {
+        holder.drop_directory().await.unwrap();
+    }
+    assert!(backend.get(operation.clone(), BobKey::from(1)).await.is_err());
+
+    group.restore(&snapshot_dir, &partitions).await.unwrap();
+
+    for id in 0..10 {
+        let data = backend.get(operation.clone(), BobKey::from(id)).await.unwrap();
+        assert_eq!(vec![id as u8; 64], data.inner().to_vec());
+    }
+    drop_pearl(SNAPSHOT_PEARL_PATH).await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_injected_disk_disconnection_stops_disk() {
+    drop_pearl(FAULTS_PEARL_PATH).await;
+    let faults = Arc::new(Faults::default());
+    let backend = backend_with_faults(FAULTS_PEARL_PATH, Some(faults.clone())).await;
+    backend.run().await.unwrap();
+    let operation = Operation::new_local(0, DiskPath::new(DISK_NAME.into(), ""));
+    let data = BobData::new(vec![].into(), BobMeta::new(TIMESTAMP));
+    backend.put(operation.clone(), BobKey::from(KEY_ID), &data).await.unwrap();
+    assert_eq!(1, backend.active_disks_count().await);
+    let rule: FaultRule =
+        serde_yaml::from_str("{disk: disk1, operations: [put], error: possible_disk_disconnection}")
+            .unwrap();
+    faults.add(rule);
+
+    let err = backend
+        .put(operation.clone(), BobKey::from(KEY_ID + 1), &data)
+        .await
+        .unwrap_err();
+
+    assert!(err.is_possible_disk_disconnection());
+    let (dcs, _) = backend.disk_controllers().unwrap();
+    assert!(!dcs[0].is_ready().await);
+    assert_eq!(0, backend.active_disks_count().await);
+    faults.clear();
+    let err = backend
+        .get(operation, BobKey::from(KEY_ID))
+        .await
+        .unwrap_err();
+    assert_eq!(&Kind::DCIsNotAvailable, err.kind());
+    drop_pearl(FAULTS_PEARL_PATH).await;
 }
@@ -0,0 +1,280 @@
+use bob_grpc::{
+    bob_api_client::BobApiClient, Blob, BlobKey, BlobMeta, DeleteOptions, DeleteRequest,
+    ExistRequest, GetOptions, GetRequest, PutOptions, PutRequest,
+};
+use bytes::Bytes;
+use futures::future::try_join_all;
+use std::{
+    collections::BTreeMap,
+    future::Future,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, RwLock,
+    },
+};
+use tonic::{transport::Channel, Code, Request, Status};
+
+use crate::{
+    credentials::Header,
+    pool::ChannelPool,
+    topology::{RestDistrFunc, RestNode},
+    ClientConfig, Error, Key, NodeInfo, Topology,
+};
+
+/// Record returned by [`Client::get`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Data {
+    data: Bytes,
+    timestamp: u64,
+}
+
+impl Data {
+    pub fn data(&self) -> &Bytes {
+        &self.data
+    }
+
+    pub fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    pub fn into_inner(self) -> Bytes {
+        self.data
+    }
+}
+
+/// Cluster-aware client. Requests are sent to the nodes that hold replicas of the key, the node
+/// that receives request coordinates quorum and aliens as usual.
+#[derive(Debug)]
+pub struct Client {
+    config: ClientConfig,
+    header: Option<Header>,
+    http: reqwest::Client,
+    topology: RwLock<Arc<Topology>>,
+    pool: ChannelPool,
+    replica_offset: AtomicUsize,
+}
+
+impl Client {
+    /// Loads cluster topology from the first available seed.
+    /// # Errors
+    /// Fails if no seed answered or credentials can't be sent in headers.
+    pub async fn connect(config: ClientConfig) -> Result<Self, Error> {
+        let header = config.credentials().grpc_header()?;
+        let http = reqwest::Client::builder()
+            .timeout(config.operation_timeout())
+            .build()
+            .map_err(|e| Error::Bootstrap(e.to_string()))?;
+        let client = Self {
+            pool: ChannelPool::new(&config),
+            topology: RwLock::new(Arc::new(Self::load_topology(&config, &http).await?)),
+            config,
+            header,
+            http,
+            replica_offset: AtomicUsize::new(0),
+        };
+        Ok(client)
+    }
+
+    pub fn topology(&self) -> Arc<Topology> {
+        self.topology.read().expect("rwlock").clone()
+    }
+
+    /// Reloads topology from seeds, e.g. after nodes were added to the cluster.
+    pub async fn refresh_topology(&self) -> Result<(), Error> {
+        let topology = Self::load_topology(&self.config, &self.http).await?;
+        *self.topology.write().expect("rwlock") = Arc::new(topology);
+        Ok(())
+    }
+
+    async fn load_topology(config: &ClientConfig, http: &reqwest::Client) -> Result<Topology, Error> {
+        let mut errors = vec![];
+        for seed in config.seeds() {
+            match Self::load_topology_from_seed(config, http, seed.trim_end_matches('/')).await {
+                Ok(topology) => return Ok(topology),
+                Err(e) => {
+                    warn!("can't load topology from {}: {}", seed, e);
+                    errors.push(format!("{}: {}", seed, e));
+                }
+            }
+        }
+        Err(Error::Bootstrap(format!("no seed available [{}]", errors.join(", "))))
+    }
+
+    async fn load_topology_from_seed(
+        config: &ClientConfig,
+        http: &reqwest::Client,
+        seed: &str,
+    ) -> Result<Topology, Error> {
+        let get = |path: &str| {
+            let request = http.get(format!("{}{}", seed, path));
+            match config.credentials().header() {
+                Some((name, value)) => request.header(name, value),
+                None => request,
+            }
+        };
+        let load = async {
+            let nodes: Vec<RestNode> =
+                get("/nodes").send().await?.error_for_status()?.json().await?;
+            let func: RestDistrFunc = get("/metadata/distrfunc")
+                .send()
+                .await?
+                .error_for_status()?
+                .json()
+                .await?;
+            Ok::<_, reqwest::Error>((nodes, func))
+        };
+        let (nodes, func) = load.await.map_err(|e| Error::Bootstrap(e.to_string()))?;
+        Topology::from_rest(nodes, func)
+    }
+
+    pub async fn put(&self, key: impl Into<Key>, data: Vec<u8>, timestamp: u64) -> Result<(), Error> {
+        let key = key.into().into_bytes(self.config.key_size())?;
+        let message = PutRequest {
+            key: Some(BlobKey { key: key.clone() }),
+            data: Some(Blob {
+                data: data.into(),
+                meta: Some(BlobMeta { timestamp }),
+            }),
+            options: Some(PutOptions::default()),
+        };
+        self.call(&key, |mut client| {
+            let req = self.request(message.clone());
+            async move { client.put(req).await.map(|_| ()) }
+        })
+        .await
+    }
+
+    pub async fn get(&self, key: impl Into<Key>) -> Result<Data, Error> {
+        let key = key.into().into_bytes(self.config.key_size())?;
+        let message = GetRequest {
+            key: Some(BlobKey { key: key.clone() }),
+            options: Some(GetOptions::default()),
+        };
+        self.call(&key, |mut client| {
+            let req = self.request(message.clone());
+            async move {
+                let blob = client.get(req).await?.into_inner();
+                Ok(Data {
+                    timestamp: blob.meta.map_or(0, |m| m.timestamp),
+                    data: blob.data,
+                })
+            }
+        })
+        .await
+    }
+
+    /// Keys are grouped by vdisk, every group is checked on its replicas.
+    pub async fn exist(&self, keys: Vec<Key>) -> Result<Vec<bool>, Error> {
+        let topology = self.topology();
+        let mut groups: BTreeMap<u32, Vec<(usize, Vec<u8>)>> = BTreeMap::new();
+        for (i, key) in keys.into_iter().enumerate() {
+            let key = key.into_bytes(self.config.key_size())?;
+            groups.entry(topology.vdisk_id(&key)).or_default().push((i, key));
+        }
+        let futures = groups.values().map(|group| {
+            let message = ExistRequest {
+                keys: group.iter().map(|(_, key)| BlobKey { key: key.clone() }).collect(),
+                options: Some(GetOptions::default()),
+            };
+            self.call(&group[0].1, move |mut client| {
+                let req = self.request(message.clone());
+                async move { Ok(client.exist(req).await?.into_inner().exist) }
+            })
+        });
+        let answers = try_join_all(futures).await?;
+        let mut result = vec![false; groups.values().map(Vec::len).sum()];
+        for (group, answer) in groups.values().zip(answers) {
+            for ((i, _), exist) in group.iter().zip(answer) {
+                result[*i] = exist;
+            }
+        }
+        Ok(result)
+    }
+
+    pub async fn delete(&self, key: impl Into<Key>, timestamp: u64) -> Result<(), Error> {
+        let key = key.into().into_bytes(self.config.key_size())?;
+        let message = DeleteRequest {
+            key: Some(BlobKey { key: key.clone() }),
+            meta: Some(BlobMeta { timestamp }),
+            options: Some(DeleteOptions::default()),
+        };
+        self.call(&key, |mut client| {
+            let req = self.request(message.clone());
+            async move { client.delete(req).await.map(|_| ()) }
+        })
+        .await
+    }
+
+    fn request<T>(&self, message: T) -> Request<T> {
+        let mut req = Request::new(message);
+        if let Some((name, value)) = &self.header {
+            req.metadata_mut().insert(*name, value.clone());
+        }
+        req.set_timeout(self.config.operation_timeout());
+        req
+    }
+
+    /// Replicas of the key are tried one by one, starting from the next replica on every call
+    /// to spread the load. If all of them failed, request is repeated after backoff with fresh
+    /// topology, the other nodes are used as the last resort.
+    async fn call<T, F, Fut>(&self, key: &[u8], f: F) -> Result<T, Error>
+    where
+        F: Fn(BobApiClient<Channel>) -> Fut,
+        Fut: Future<Output = Result<T, Status>>,
+    {
+        let retry = self.config.retry();
+        let mut last_error = None;
+        for attempt in 0..retry.max_attempts() {
+            if attempt > 0 {
+                tokio::time::sleep(retry.delay(attempt)).await;
+                if let Err(e) = self.refresh_topology().await {
+                    debug!("topology is not refreshed before retry: {}", e);
+                }
+            }
+            for node in self.candidates(key) {
+                match self.call_node(&node, &f).await {
+                    Ok(result) => return Ok(result),
+                    Err(e) if e.is_retryable() => {
+                        debug!("request to {} failed: {}", node.name(), e);
+                        last_error = Some(e);
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+        let topology = self.topology();
+        Err(last_error.unwrap_or_else(|| Error::NoNodes(topology.vdisk_id(key))))
+    }
+
+    async fn call_node<T, F, Fut>(&self, node: &NodeInfo, f: &F) -> Result<T, Error>
+    where
+        F: Fn(BobApiClient<Channel>) -> Fut,
+        Fut: Future<Output = Result<T, Status>>,
+    {
+        let channel = self.pool.get(node.address()).await?;
+        let result = f(BobApiClient::new(channel)).await;
+        if let Err(status) = &result {
+            if matches!(status.code(), Code::Unavailable | Code::Unknown) {
+                self.pool.invalidate(node.address()).await;
+            }
+        }
+        result.map_err(Error::from)
+    }
+
+    fn candidates(&self, key: &[u8]) -> Vec<NodeInfo> {
+        let topology = self.topology();
+        let mut replicas: Vec<_> = topology.replicas(key).cloned().collect();
+        if !replicas.is_empty() {
+            let offset = self.replica_offset.fetch_add(1, Ordering::Relaxed) % replicas.len();
+            replicas.rotate_left(offset);
+        }
+        let others = topology
+            .nodes()
+            .iter()
+            .filter(|n| !replicas.contains(n))
+            .cloned()
+            .collect::<Vec<_>>();
+        replicas.extend(others);
+        replicas
+    }
+}
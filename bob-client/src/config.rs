@@ -0,0 +1,130 @@
+use std::time::Duration;
+
+use crate::{Credentials, RetryPolicy};
+
+const DEFAULT_KEY_SIZE: usize = 8;
+const DEFAULT_OPERATION_TIMEOUT: Duration = Duration::from_secs(3);
+const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+const DEFAULT_CONNECTIONS_PER_NODE: usize = 2;
+
+/// Certificate used to verify gRPC endpoints of the nodes.
+#[derive(Debug, Clone)]
+pub struct TlsConfig {
+    ca_cert: Vec<u8>,
+    domain_name: String,
+}
+
+impl TlsConfig {
+    pub fn new(ca_cert_pem: Vec<u8>, domain_name: impl Into<String>) -> Self {
+        Self {
+            ca_cert: ca_cert_pem,
+            domain_name: domain_name.into(),
+        }
+    }
+
+    pub fn ca_cert(&self) -> &[u8] {
+        &self.ca_cert
+    }
+
+    pub fn domain_name(&self) -> &str {
+        &self.domain_name
+    }
+}
+
+/// Client settings. Seeds are urls of REST API of the nodes (e.g. `http://10.0.0.1:8000`),
+/// the first seed that answers is used to get cluster topology.
+#[derive(Debug, Clone)]
+pub struct ClientConfig {
+    seeds: Vec<String>,
+    credentials: Credentials,
+    tls: Option<TlsConfig>,
+    key_size: usize,
+    operation_timeout: Duration,
+    connect_timeout: Duration,
+    connections_per_node: usize,
+    retry: RetryPolicy,
+}
+
+impl ClientConfig {
+    pub fn new(seeds: Vec<String>) -> Self {
+        Self {
+            seeds,
+            credentials: Credentials::None,
+            tls: None,
+            key_size: DEFAULT_KEY_SIZE,
+            operation_timeout: DEFAULT_OPERATION_TIMEOUT,
+            connect_timeout: DEFAULT_CONNECT_TIMEOUT,
+            connections_per_node: DEFAULT_CONNECTIONS_PER_NODE,
+            retry: RetryPolicy::default(),
+        }
+    }
+
+    pub fn with_credentials(mut self, credentials: Credentials) -> Self {
+        self.credentials = credentials;
+        self
+    }
+
+    pub fn with_tls(mut self, tls: TlsConfig) -> Self {
+        self.tls = Some(tls);
+        self
+    }
+
+    /// Must be equal to `BOB_KEY_SIZE` the cluster was built with.
+    pub fn with_key_size(mut self, key_size: usize) -> Self {
+        self.key_size = key_size;
+        self
+    }
+
+    pub fn with_operation_timeout(mut self, timeout: Duration) -> Self {
+        self.operation_timeout = timeout;
+        self
+    }
+
+    pub fn with_connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = timeout;
+        self
+    }
+
+    /// Count of HTTP/2 connections opened to each node, requests are spread between them.
+    pub fn with_connections_per_node(mut self, count: usize) -> Self {
+        self.connections_per_node = count.max(1);
+        self
+    }
+
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    pub fn seeds(&self) -> &[String] {
+        &self.seeds
+    }
+
+    pub fn credentials(&self) -> &Credentials {
+        &self.credentials
+    }
+
+    pub fn tls(&self) -> Option<&TlsConfig> {
+        self.tls.as_ref()
+    }
+
+    pub fn key_size(&self) -> usize {
+        self.key_size
+    }
+
+    pub fn operation_timeout(&self) -> Duration {
+        self.operation_timeout
+    }
+
+    pub fn connect_timeout(&self) -> Duration {
+        self.connect_timeout
+    }
+
+    pub fn connections_per_node(&self) -> usize {
+        self.connections_per_node
+    }
+
+    pub fn retry(&self) -> &RetryPolicy {
+        &self.retry
+    }
+}
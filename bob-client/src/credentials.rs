@@ -0,0 +1,52 @@
+use base64::{engine::general_purpose::STANDARD as BASE64_ENGINE, Engine as _};
+use tonic::metadata::{Ascii, MetadataValue};
+
+use crate::Error;
+
+pub(crate) type Header = (&'static str, MetadataValue<Ascii>);
+
+/// Credentials that are sent with every request, they must match `authentication_type` of
+/// the cluster.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum Credentials {
+    #[default]
+    None,
+    Basic { username: String, password: String },
+    Token(String),
+}
+
+impl Credentials {
+    pub fn basic(username: impl Into<String>, password: impl Into<String>) -> Self {
+        Self::Basic {
+            username: username.into(),
+            password: password.into(),
+        }
+    }
+
+    pub fn token(token: impl Into<String>) -> Self {
+        Self::Token(token.into())
+    }
+
+    /// Header name and value, the same for REST and gRPC.
+    pub fn header(&self) -> Option<(&'static str, String)> {
+        match self {
+            Self::None => None,
+            Self::Basic { username, password } => {
+                let encoded = BASE64_ENGINE.encode(format!("{}:{}", username, password));
+                Some(("authorization", format!("Basic {}", encoded)))
+            }
+            Self::Token(token) => Some(("token", token.clone())),
+        }
+    }
+
+    pub(crate) fn grpc_header(&self) -> Result<Option<Header>, Error> {
+        self.header()
+            .map(|(name, value)| {
+                value
+                    .parse()
+                    .map(|value| (name, value))
+                    .map_err(|_| Error::Bootstrap("credentials are not valid header value".into()))
+            })
+            .transpose()
+    }
+}
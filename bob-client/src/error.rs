@@ -0,0 +1,49 @@
+use thiserror::Error as ThisError;
+use tonic::{Code, Status};
+
+#[derive(Debug, ThisError)]
+pub enum Error {
+    #[error("key not found")]
+    KeyNotFound,
+    #[error("key is longer than {0} bytes")]
+    InvalidKey(usize),
+    #[error("condition of the write operation is not met")]
+    PreconditionFailed,
+    #[error("bootstrap failed: {0}")]
+    Bootstrap(String),
+    #[error("no nodes available for vdisk {0}")]
+    NoNodes(u32),
+    #[error("connection to {address} failed: {message}")]
+    Connection { address: String, message: String },
+    #[error("request failed with {}: {}", .0.code(), .0.message())]
+    Status(Box<Status>),
+}
+
+impl Error {
+    /// Errors that may disappear if the request is sent again or sent to another replica.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::Connection { .. } => true,
+            Self::Status(status) => matches!(
+                status.code(),
+                Code::Unavailable
+                    | Code::DeadlineExceeded
+                    | Code::Internal
+                    | Code::Unknown
+                    | Code::ResourceExhausted
+            ),
+            _ => false,
+        }
+    }
+}
+
+impl From<Status> for Error {
+    fn from(status: Status) -> Self {
+        match status.code() {
+            // Node also answers `NotFound` for missing vdisk, it's not a key miss
+            Code::NotFound if status.message().starts_with("KeyNotFound") => Self::KeyNotFound,
+            Code::FailedPrecondition => Self::PreconditionFailed,
+            _ => Self::Status(Box::new(status)),
+        }
+    }
+}
@@ -0,0 +1,44 @@
+#![warn(missing_debug_implementations)]
+
+//! Cluster-aware client for Bob.
+//!
+//! Client bootstraps from one of the seed nodes through REST API (`/nodes` and
+//! `/metadata/distrfunc`), computes vdisk of the key locally, the same way as the node does,
+//! and sends gRPC requests directly to the nodes that hold replicas of this vdisk.
+//! Library requires tokio runtime.
+//!
+//! ```no_run
+//! # async fn run() -> Result<(), bob_client::Error> {
+//! use bob_client::{Client, ClientConfig, Credentials};
+//!
+//! let config = ClientConfig::new(vec!["http://127.0.0.1:8000".to_string()])
+//!     .with_credentials(Credentials::basic("admin", "password"));
+//! let client = Client::connect(config).await?;
+//! client.put(1, b"data".to_vec(), 1).await?;
+//! let data = client.get(1).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+#[macro_use]
+extern crate log;
+#[macro_use]
+extern crate serde_derive;
+
+mod client;
+mod config;
+mod credentials;
+mod error;
+mod pool;
+mod retry;
+mod topology;
+
+pub use client::{Client, Data};
+pub use config::{ClientConfig, TlsConfig};
+pub use credentials::Credentials;
+pub use error::Error;
+pub use retry::RetryPolicy;
+pub use topology::{Key, NodeInfo, Topology};
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,81 @@
+use std::{
+    collections::HashMap,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
+use tokio::sync::RwLock;
+use tonic::transport::{Certificate, Channel, ClientTlsConfig, Endpoint};
+
+use crate::{ClientConfig, Error, TlsConfig};
+
+/// Channels to the nodes, created on first use. Every channel is a separate HTTP/2 connection,
+/// requests to the node are spread between its channels in round robin.
+#[derive(Debug)]
+pub(crate) struct ChannelPool {
+    connections_per_node: usize,
+    operation_timeout: Duration,
+    connect_timeout: Duration,
+    tls: Option<TlsConfig>,
+    channels: RwLock<HashMap<String, Vec<Channel>>>,
+    next: AtomicUsize,
+}
+
+impl ChannelPool {
+    pub(crate) fn new(config: &ClientConfig) -> Self {
+        Self {
+            connections_per_node: config.connections_per_node(),
+            operation_timeout: config.operation_timeout(),
+            connect_timeout: config.connect_timeout(),
+            tls: config.tls().cloned(),
+            channels: RwLock::new(HashMap::new()),
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    pub(crate) async fn get(&self, address: &str) -> Result<Channel, Error> {
+        let next = self.next.fetch_add(1, Ordering::Relaxed);
+        if let Some(channels) = self.channels.read().await.get(address) {
+            return Ok(channels[next % channels.len()].clone());
+        }
+        let mut channels = Vec::with_capacity(self.connections_per_node);
+        for _ in 0..self.connections_per_node {
+            channels.push(self.connect(address).await?);
+        }
+        let mut pool = self.channels.write().await;
+        // Another request could connect to the same node meanwhile, its channels are kept
+        let channels = pool.entry(address.to_string()).or_insert(channels);
+        Ok(channels[next % channels.len()].clone())
+    }
+
+    /// Drops channels to the node, next request will reconnect.
+    pub(crate) async fn invalidate(&self, address: &str) {
+        if self.channels.write().await.remove(address).is_some() {
+            debug!("channels to {} are dropped", address);
+        }
+    }
+
+    async fn connect(&self, address: &str) -> Result<Channel, Error> {
+        let connection_error = |message: String| Error::Connection {
+            address: address.to_string(),
+            message,
+        };
+        let scheme = if self.tls.is_some() { "https" } else { "http" };
+        let mut endpoint = Endpoint::from_shared(format!("{}://{}", scheme, address))
+            .map_err(|e| connection_error(e.to_string()))?
+            .connect_timeout(self.connect_timeout)
+            .timeout(self.operation_timeout)
+            .tcp_nodelay(true);
+        if let Some(tls) = &self.tls {
+            let tls_config = ClientTlsConfig::new()
+                .domain_name(tls.domain_name())
+                .ca_certificate(Certificate::from_pem(tls.ca_cert()));
+            endpoint = endpoint
+                .tls_config(tls_config)
+                .map_err(|e| connection_error(e.to_string()))?;
+        }
+        endpoint
+            .connect()
+            .await
+            .map_err(|e| connection_error(e.to_string()))
+    }
+}
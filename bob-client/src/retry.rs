@@ -0,0 +1,53 @@
+use rand::Rng;
+use std::time::Duration;
+
+/// Exponential backoff between attempts. Every attempt goes through all replicas of the key,
+/// so `max_attempts` bounds the count of full rounds, not the count of requests.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    max_attempts: usize,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+}
+
+impl RetryPolicy {
+    pub fn new(max_attempts: usize, initial_backoff: Duration, max_backoff: Duration) -> Self {
+        Self {
+            max_attempts: max_attempts.max(1),
+            initial_backoff,
+            max_backoff: max_backoff.max(initial_backoff),
+        }
+    }
+
+    /// Single attempt without backoff.
+    pub fn no_retry() -> Self {
+        Self::new(1, Duration::ZERO, Duration::ZERO)
+    }
+
+    pub fn max_attempts(&self) -> usize {
+        self.max_attempts
+    }
+
+    /// Upper bound of the delay before attempt with the given number (starting from 1 for the
+    /// first retry).
+    pub fn max_delay(&self, attempt: usize) -> Duration {
+        let shift = attempt.saturating_sub(1).min(31) as u32;
+        self.initial_backoff
+            .checked_mul(1 << shift)
+            .map_or(self.max_backoff, |delay| delay.min(self.max_backoff))
+    }
+
+    /// Delay with jitter in `[max_delay / 2, max_delay]`, so that clients that failed together
+    /// don't retry together.
+    pub fn delay(&self, attempt: usize) -> Duration {
+        let max_delay = self.max_delay(attempt);
+        let half = max_delay / 2;
+        half + (max_delay - half).mul_f64(rand::thread_rng().gen::<f64>())
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::new(3, Duration::from_millis(50), Duration::from_secs(2))
+    }
+}
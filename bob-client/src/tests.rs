@@ -0,0 +1,112 @@
+use std::{collections::BTreeMap, time::Duration};
+use tonic::Status;
+
+use crate::{
+    topology::{RestDistrFunc, RestNode},
+    Credentials, Error, Key, NodeInfo, RetryPolicy, Topology,
+};
+
+fn topology(vdisks_count: u32) -> Topology {
+    let nodes = vec![NodeInfo::new("a", "10.0.0.1:20000"), NodeInfo::new("b", "10.0.0.2:20000")];
+    let vdisks = (0..vdisks_count)
+        .map(|id| (id, vec!["a".to_string(), "b".to_string()]))
+        .collect();
+    Topology::new(nodes, vdisks).unwrap()
+}
+
+#[test]
+fn vdisk_id_matches_mod_of_integer_key() {
+    for vdisks_count in [1, 3, 7, 64] {
+        let topology = topology(vdisks_count);
+        for n in [0, 1, 255, 256, 65_537, u64::MAX] {
+            let key = Key::from(n).into_bytes(8).unwrap();
+            assert_eq!((n % vdisks_count as u64) as u32, topology.vdisk_id(&key));
+        }
+    }
+}
+
+#[test]
+fn key_is_padded_to_key_size() {
+    assert_eq!(vec![1, 0, 0, 0], Key::from(vec![1]).into_bytes(4).unwrap());
+    assert_eq!(vec![1, 0, 0, 0], Key::from(1).into_bytes(4).unwrap());
+    assert!(matches!(
+        Key::from(u64::MAX).into_bytes(4),
+        Err(Error::InvalidKey(4))
+    ));
+}
+
+#[test]
+fn topology_from_rest_api() {
+    let nodes: Vec<RestNode> = serde_json::from_str(
+        r#"[
+            {"name": "a", "address": "10.0.0.1:20000", "vdisks": [
+                {"id": 0, "replicas": [{"node": "a", "disk": "d1", "path": "/d1"}]},
+                {"id": 1, "replicas": [{"node": "a", "disk": "d2", "path": "/d2"}]}
+            ], "suspicion": 0.5},
+            {"name": "b", "address": "10.0.0.2:20000", "vdisks": [
+                {"id": 1, "replicas": [{"node": "b", "disk": "d1", "path": "/d1"}]}
+            ]}
+        ]"#,
+    )
+    .unwrap();
+    let func: RestDistrFunc = serde_json::from_str(r#"{"func": "Mod"}"#).unwrap();
+
+    let topology = Topology::from_rest(nodes, func).unwrap();
+
+    assert_eq!(2, topology.vdisks_count());
+    let replicas = |n: u64| {
+        let key = Key::from(n).into_bytes(8).unwrap();
+        topology.replicas(&key).map(|n| n.name().to_string()).collect::<Vec<_>>()
+    };
+    assert_eq!(vec!["a"], replicas(0));
+    assert_eq!(vec!["a", "b"], replicas(1));
+}
+
+#[test]
+fn topology_with_unknown_replica_node_is_rejected() {
+    let nodes = vec![NodeInfo::new("a", "10.0.0.1:20000")];
+    let vdisks = BTreeMap::from([(0, vec!["c".to_string()])]);
+
+    assert!(matches!(Topology::new(nodes, vdisks), Err(Error::Bootstrap(_))));
+}
+
+#[test]
+fn retry_backoff_is_exponential_and_bounded() {
+    let retry = RetryPolicy::new(5, Duration::from_millis(10), Duration::from_millis(50));
+
+    assert_eq!(Duration::from_millis(10), retry.max_delay(1));
+    assert_eq!(Duration::from_millis(20), retry.max_delay(2));
+    assert_eq!(Duration::from_millis(40), retry.max_delay(3));
+    assert_eq!(Duration::from_millis(50), retry.max_delay(4));
+    assert_eq!(Duration::from_millis(50), retry.max_delay(100));
+    for attempt in 1..5 {
+        let delay = retry.delay(attempt);
+        assert!(delay >= retry.max_delay(attempt) / 2 && delay <= retry.max_delay(attempt));
+    }
+}
+
+#[test]
+fn credentials_headers() {
+    assert_eq!(None, Credentials::None.header());
+    assert_eq!(
+        Some(("authorization", "Basic YWRtaW46cGFzcw==".to_string())),
+        Credentials::basic("admin", "pass").header()
+    );
+    assert_eq!(
+        Some(("token", "abc".to_string())),
+        Credentials::token("abc").header()
+    );
+}
+
+#[test]
+fn status_errors_mapping() {
+    assert!(matches!(
+        Error::from(Status::not_found("KeyNotFound 0100")),
+        Error::KeyNotFound
+    ));
+    let vdisk_not_found = Error::from(Status::not_found("VDiskNotFound 1"));
+    assert!(matches!(vdisk_not_found, Error::Status(_)));
+    assert!(!vdisk_not_found.is_retryable());
+    assert!(Error::from(Status::unavailable("HolderTemporaryUnavailable")).is_retryable());
+    assert!(!Error::from(Status::unauthenticated("Unauthorized")).is_retryable());
+}
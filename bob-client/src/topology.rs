@@ -0,0 +1,166 @@
+use std::collections::BTreeMap;
+
+use crate::Error;
+
+/// Key of the record. Integer keys are stored in little-endian order, as `BobKey` does.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Key(Vec<u8>);
+
+impl Key {
+    /// Pads the key with zeros up to `key_size` bytes, fails if significant bytes don't fit.
+    pub(crate) fn into_bytes(self, key_size: usize) -> Result<Vec<u8>, Error> {
+        let mut bytes = self.0;
+        if bytes.len() > key_size {
+            if bytes[key_size..].iter().any(|b| *b != 0) {
+                return Err(Error::InvalidKey(key_size));
+            }
+            bytes.truncate(key_size);
+        } else {
+            bytes.resize(key_size, 0);
+        }
+        Ok(bytes)
+    }
+}
+
+impl From<u64> for Key {
+    fn from(n: u64) -> Self {
+        Self(n.to_le_bytes().to_vec())
+    }
+}
+
+impl From<Vec<u8>> for Key {
+    fn from(bytes: Vec<u8>) -> Self {
+        Self(bytes)
+    }
+}
+
+impl From<&[u8]> for Key {
+    fn from(bytes: &[u8]) -> Self {
+        Self(bytes.to_vec())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeInfo {
+    name: String,
+    address: String,
+}
+
+impl NodeInfo {
+    pub fn new(name: impl Into<String>, address: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            address: address.into(),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Address of gRPC API of the node.
+    pub fn address(&self) -> &str {
+        &self.address
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct RestNode {
+    name: String,
+    address: String,
+    vdisks: Vec<RestVDisk>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RestVDisk {
+    id: u32,
+    replicas: Vec<RestReplica>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RestReplica {
+    node: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct RestDistrFunc {
+    func: String,
+}
+
+/// Cluster layout: nodes and the nodes that hold replicas of every vdisk.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Topology {
+    nodes: Vec<NodeInfo>,
+    vdisks: BTreeMap<u32, Vec<usize>>,
+}
+
+impl Topology {
+    /// `vdisks` contains names of the nodes for every vdisk id.
+    pub fn new(nodes: Vec<NodeInfo>, vdisks: BTreeMap<u32, Vec<String>>) -> Result<Self, Error> {
+        let vdisks = vdisks
+            .into_iter()
+            .map(|(id, names)| {
+                let mut indexes = names
+                    .iter()
+                    .map(|name| {
+                        nodes.iter().position(|n| n.name == *name).ok_or_else(|| {
+                            Error::Bootstrap(format!("unknown node {} of vdisk {}", name, id))
+                        })
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                indexes.sort_unstable();
+                indexes.dedup();
+                Ok((id, indexes))
+            })
+            .collect::<Result<BTreeMap<_, _>, Error>>()?;
+        if vdisks.is_empty() {
+            return Err(Error::Bootstrap("cluster has no vdisks".into()));
+        }
+        Ok(Self { nodes, vdisks })
+    }
+
+    pub(crate) fn from_rest(nodes: Vec<RestNode>, func: RestDistrFunc) -> Result<Self, Error> {
+        if func.func != "Mod" {
+            return Err(Error::Bootstrap(format!(
+                "unsupported distribution function {}",
+                func.func
+            )));
+        }
+        let mut vdisks: BTreeMap<u32, Vec<String>> = BTreeMap::new();
+        for vdisk in nodes.iter().flat_map(|n| n.vdisks.iter()) {
+            let replicas = vdisk.replicas.iter().map(|r| r.node.clone());
+            vdisks.entry(vdisk.id).or_default().extend(replicas);
+        }
+        let nodes = nodes
+            .into_iter()
+            .map(|n| NodeInfo::new(n.name, n.address))
+            .collect();
+        Self::new(nodes, vdisks)
+    }
+
+    pub fn nodes(&self) -> &[NodeInfo] {
+        &self.nodes
+    }
+
+    pub fn vdisks_count(&self) -> usize {
+        self.vdisks.len()
+    }
+
+    /// Same as `Virtual::vdisk_id_from_key` with `Mod` distribution function.
+    pub fn vdisk_id(&self, key: &[u8]) -> u32 {
+        let len = self.vdisks.len();
+        let id = key.iter().fold([0, 1], |[rem, bmult], &byte| {
+            [(rem + bmult * byte as usize) % len, (bmult << 8) % len]
+        })[0];
+        id as u32
+    }
+
+    /// Nodes with replicas of the key vdisk.
+    pub fn replicas(&self, key: &[u8]) -> impl Iterator<Item = &NodeInfo> {
+        self.vdisks
+            .get(&self.vdisk_id(key))
+            .into_iter()
+            .flatten()
+            .map(move |i| &self.nodes[*i])
+    }
+}
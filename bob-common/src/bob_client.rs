@@ -1,32 +1,45 @@
+mod pool;
+mod pool_tests;
+#[cfg(feature = "testing")]
+mod test_client;
+
 pub mod b_client {
-    use super::{DeleteResult, ExistResult, FactoryTlsConfig, GetResult, PingResult, PutResult};
+    use super::{
+        pool::{is_transport_error, ChannelPool, PoolStats},
+        DeleteResult, ExistResult, FactoryTlsConfig, GetResult, PingResult, PutResult,
+        StreamVDiskResult,
+    };
     use crate::{
-        data::{BobData, BobKey, BobMeta},
+        configs::node::ConnectionPoolConfig,
+        data::{BobData, BobKey, BobMeta, StreamedRecord, VDiskPosition},
         error::Error,
         metrics::BobClient as BobClientMetrics,
         node::{Node, NodeName, Output as NodeOutput},
+        telemetry,
     };
     use bob_grpc::{
         bob_api_client::BobApiClient, Blob, BlobKey, BlobMeta, DeleteOptions, DeleteRequest,
-        ExistRequest, GetOptions, GetRequest, Null, PutOptions, PutRequest,
+        ExistRequest, GetOptions, GetRequest, Null, PutOptions, PutRequest, StreamVDiskRequest,
     };
+    use futures::{StreamExt, TryStreamExt};
     use mockall::mock;
     use std::{
         fmt::{Debug, Formatter, Result as FmtResult},
+        future::Future,
         time::Duration,
     };
     use tonic::{
         metadata::MetadataValue,
         transport::{Certificate, Channel, ClientTlsConfig, Endpoint},
-        Request,
+        Request, Status,
     };
     use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64_ENGINE};
 
     /// Client for interaction with bob backend.
     /// Clone implementation was removed, because struct is large. Use Arc to store copies
     pub struct BobClient {
-        client: BobApiClient<Channel>,
-        
+        pool: ChannelPool,
+
         target_node_name: NodeName,
         target_node_address: String,
         local_node_name: NodeName,
@@ -46,6 +59,7 @@ pub mod b_client {
             metrics: BobClientMetrics,
             local_node_name: NodeName,
             tls_config: Option<&FactoryTlsConfig>,
+            pool_config: &ConnectionPoolConfig,
         ) -> Result<Self, String> 
         {
             let mut endpoint = Endpoint::from(node.get_uri());
@@ -58,14 +72,15 @@ pub mod b_client {
             }
             endpoint = endpoint.tcp_nodelay(true);
 
-            let client = BobApiClient::connect(endpoint)
-                .await
-                .map_err(|e| e.to_string())?;
+            let channel = endpoint.connect().await.map_err(|e| e.to_string())?;
+            let pool_metrics = metrics.channel_pool(node.name().as_str());
+            let pool =
+                ChannelPool::new(endpoint, channel, pool_config, operation_timeout, pool_metrics);
 
             let auth_header = format!("InterNode {}", BASE64_ENGINE.encode(local_node_name.as_str()));
 
             Ok(Self {
-                client,
+                pool,
                 target_node_name: node.name().clone(),
                 target_node_address: node.address().to_owned(), 
                 local_node_name: local_node_name, 
@@ -82,6 +97,9 @@ pub mod b_client {
         pub fn target_node_address(&self) -> &str {
             &self.target_node_address
         }
+        pub fn pool_stats(&self) -> PoolStats {
+            self.pool.stats()
+        }
 
         pub async fn put(&self, key: BobKey, d: BobData, options: PutOptions) -> PutResult {
             debug!("real client put called");
@@ -99,15 +117,16 @@ pub mod b_client {
             };
             let mut req = Request::new(message);
             self.set_credentials(&mut req);
+            self.set_node_name(&mut req);
+            self.set_trace_context(&mut req);
             self.set_timeout(&mut req);
 
             let node_name = self.target_node_name.to_owned();
-            let mut client = self.client.clone();
-            
+
             self.metrics.put_count();
             let timer = BobClientMetrics::start_timer();
 
-            match client.put(req).await {
+            match self.call(|mut client| async move { client.put(req).await }).await {
                 Ok(_) => {
                     self.metrics.put_timer_stop(timer);
                     Ok(NodeOutput::new(node_name, ()))
@@ -115,7 +134,7 @@ pub mod b_client {
                 Err(e) => {
                     self.metrics.put_error_count();
                     self.metrics.put_timer_stop(timer);
-                    Err(NodeOutput::new(node_name, e.into()))
+                    Err(NodeOutput::new(node_name, e))
                 }
             }
         }
@@ -127,15 +146,16 @@ pub mod b_client {
             };
             let mut req = Request::new(message);
             self.set_credentials(&mut req);
+            self.set_node_name(&mut req);
+            self.set_trace_context(&mut req);
             self.set_timeout(&mut req);
 
             let node_name = self.target_node_name.to_owned();
-            let mut client = self.client.clone();
 
             self.metrics.get_count();
             let timer = BobClientMetrics::start_timer();
 
-            match client.get(req).await {
+            match self.call(|mut client| async move { client.get(req).await }).await {
                 Ok(data) => {
                     self.metrics.get_timer_stop(timer);
                     let ans = data.into_inner();
@@ -146,7 +166,7 @@ pub mod b_client {
                 Err(e) => {
                     self.metrics.get_error_count();
                     self.metrics.get_timer_stop(timer);
-                    Err(NodeOutput::new(node_name, e.into()))
+                    Err(NodeOutput::new(node_name, e))
                 }
             }
         }
@@ -158,11 +178,10 @@ pub mod b_client {
             self.set_timeout(&mut req);
 
             let node_name = self.target_node_name.to_owned();
-            let mut client = self.client.clone();
 
-            match client.ping(req).await {
+            match self.call(|mut client| async move { client.ping(req).await }).await {
                 Ok(_) => Ok(NodeOutput::new(node_name, ())),
-                Err(e) => Err(NodeOutput::new(node_name, Error::from(e))),
+                Err(e) => Err(NodeOutput::new(node_name, e)),
             }
         }
 
@@ -177,15 +196,16 @@ pub mod b_client {
             };
             let mut req = Request::new(message);
             self.set_credentials(&mut req);
+            self.set_node_name(&mut req);
+            self.set_trace_context(&mut req);
             self.set_timeout(&mut req);
 
             let node_name = self.target_node_name.to_owned();
-            let mut client = self.client.clone();
 
             self.metrics.exist_count();
             let timer = BobClientMetrics::start_timer();
 
-            match client.exist(req).await {
+            match self.call(|mut client| async move { client.exist(req).await }).await {
                 Ok(response) => {
                     self.metrics.exist_timer_stop(timer);
                     Ok(NodeOutput::new(node_name, response.into_inner().exist))
@@ -193,7 +213,7 @@ pub mod b_client {
                 Err(error) => {
                     self.metrics.exist_timer_stop(timer);
                     self.metrics.exist_error_count();
-                    Err(NodeOutput::new(node_name, error.into()))
+                    Err(NodeOutput::new(node_name, error))
                 }
             }
         }
@@ -207,15 +227,16 @@ pub mod b_client {
             };
             let mut req = Request::new(message);
             self.set_credentials(&mut req);
+            self.set_node_name(&mut req);
+            self.set_trace_context(&mut req);
             self.set_timeout(&mut req);
 
             let node_name = self.target_node_name.to_owned();
-            let mut client = self.client.clone();
 
             self.metrics.delete_count();
             let timer = BobClientMetrics::start_timer();
 
-            match client.delete(req).await {
+            match self.call(|mut client| async move { client.delete(req).await }).await {
                 Ok(_) => {
                     self.metrics.delete_timer_stop(timer);
                     Ok(NodeOutput::new(node_name, ()))
@@ -223,11 +244,65 @@ pub mod b_client {
                 Err(error) => {
                     self.metrics.delete_timer_stop(timer);
                     self.metrics.delete_error_count();
-                    Err(NodeOutput::new(node_name, error.into()))
+                    Err(NodeOutput::new(node_name, error))
                 }
             }
         }
 
+        /// Streams records of the vdisk ordered by key bytes, deleted records are skipped.
+        /// The stream is long, so operation timeout isn't applied.
+        pub async fn stream_vdisk(&self, vdisk_id: u32, start_after: Option<VDiskPosition>) -> StreamVDiskResult {
+            let message = StreamVDiskRequest {
+                vdisk_id,
+                start_after: start_after.map(|position| BlobKey { key: position.key.into() }),
+                start_partition: start_after.map_or(0, |position| position.partition),
+            };
+            let mut req = Request::new(message);
+            self.set_credentials(&mut req);
+            self.set_node_name(&mut req);
+            self.set_trace_context(&mut req);
+
+            let node_name = self.target_node_name.to_owned();
+
+            match self.call(|mut client| async move { client.stream_v_disk(req).await }).await {
+                Ok(response) => {
+                    let records = response
+                        .into_inner()
+                        .map_err(Error::from)
+                        .map_ok(|record| {
+                            let key = record.key.expect("vdisk record key").key.into();
+                            let blob = record.data.expect("vdisk record data");
+                            let meta = BobMeta::new(blob.meta.expect("vdisk record meta").timestamp);
+                            StreamedRecord {
+                                position: VDiskPosition { partition: record.partition, key },
+                                data: BobData::new(blob.data, meta),
+                                deleted: record.deleted,
+                            }
+                        })
+                        .boxed();
+                    Ok(NodeOutput::new(node_name, records))
+                }
+                Err(e) => Err(NodeOutput::new(node_name, e)),
+            }
+        }
+
+        /// Sends request through the least loaded channel of the pool.
+        async fn call<T, F, Fut>(&self, f: F) -> Result<T, Error>
+        where
+            F: FnOnce(BobApiClient<Channel>) -> Fut,
+            Fut: Future<Output = Result<T, Status>>,
+        {
+            let pooled = self.pool.acquire().await?;
+            f(BobApiClient::new(pooled.channel())).await.map_err(|status| {
+                pooled.check_status(&status);
+                if is_transport_error(&status) {
+                    Error::transport(status.message())
+                } else {
+                    status.into()
+                }
+            })
+        }
+
         fn set_credentials<T>(&self, req: &mut Request<T>) {
             let val = MetadataValue::from_str(&self.auth_header)
                 .expect("failed to create metadata value from authorization");
@@ -240,6 +315,10 @@ pub mod b_client {
             r.metadata_mut().insert("node_name", val);
         }
 
+        fn set_trace_context<T>(&self, r: &mut Request<T>) {
+            telemetry::inject_context(r.metadata_mut());
+        }
+
         fn set_timeout<T>(&self, r: &mut Request<T>) {
             r.set_timeout(self.operation_timeout);
         }
@@ -247,13 +326,14 @@ pub mod b_client {
 
     mock! {
         pub BobClient {
-            pub async fn create<'a>(node: &Node, operation_timeout: Duration, metrics: BobClientMetrics, local_node_name: NodeName, tls_config: Option<&'a FactoryTlsConfig>) -> Result<Self, String>;
+            pub async fn create<'a>(node: &Node, operation_timeout: Duration, metrics: BobClientMetrics, local_node_name: NodeName, tls_config: Option<&'a FactoryTlsConfig>, pool_config: &ConnectionPoolConfig) -> Result<Self, String>;
             pub async fn put(&self, key: BobKey, d: BobData, options: PutOptions) -> PutResult;
             pub async fn get(&self, key: BobKey, options: GetOptions) -> GetResult;
             pub async fn ping(&self) -> PingResult;
             pub fn target_node_name(&self) -> &NodeName;
             pub async fn exist(&self, keys: Vec<BobKey>, options: GetOptions) -> ExistResult;
             pub async fn delete(&self, key: BobKey, meta: BobMeta, options: DeleteOptions) -> DeleteResult;
+            pub async fn stream_vdisk(&self, vdisk_id: u32, start_after: Option<VDiskPosition>) -> StreamVDiskResult;
         }
         impl Clone for BobClient {
             fn clone(&self) -> Self;
@@ -263,7 +343,7 @@ pub mod b_client {
     impl Debug for BobClient {
         fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
             f.debug_struct("RealBobClient")
-                .field("client", &"BobApiClient<Channel>")
+                .field("pool", &self.pool_stats())
                 .field("target_node_name", &self.target_node_name())
                 .field("target_node_address", &self.target_node_address())
                 .field("local_node_name", &self.local_node_name)
@@ -274,11 +354,13 @@ pub mod b_client {
 }
 
 use crate::{
-    data::BobData,
+    configs::node::ConnectionPoolConfig,
+    data::{BobData, StreamedRecord},
     error::Error,
     metrics::ContainerBuilder as MetricsContainerBuilder,
     node::{Node, NodeName, Output as NodeOutput},
 };
+use futures::stream::BoxStream;
 use std::{
     fmt::{Debug, Formatter, Result as FmtResult},
     sync::Arc,
@@ -287,7 +369,7 @@ use std::{
 
 cfg_if::cfg_if! {
     if #[cfg(feature = "testing")] {
-        pub use self::b_client::MockBobClient as BobClient;
+        pub use self::test_client::{TestBobClient as BobClient, Transport};
     } else {
         pub use self::b_client::BobClient;
     }
@@ -305,6 +387,10 @@ pub type ExistResult = NodeResult<Vec<bool>>;
 
 pub type DeleteResult = NodeResult<()>;
 
+pub type StreamVDiskResult = NodeResult<BoxStream<'static, Result<StreamedRecord, Error>>>;
+
+pub use self::pool::PoolStats;
+
 #[derive(Clone)]
 pub struct FactoryTlsConfig {
     pub tls_domain_name: String,
@@ -318,6 +404,7 @@ pub struct Factory {
     metrics: Arc<dyn MetricsContainerBuilder + Send + Sync>,
     local_node_name: NodeName,
     tls_config: Option<FactoryTlsConfig>,
+    pool_config: ConnectionPoolConfig,
 }
 
 impl Factory {
@@ -334,8 +421,15 @@ impl Factory {
             metrics,
             local_node_name,
             tls_config,
+            pool_config: ConnectionPoolConfig::default(),
         }
     }
+
+    pub fn with_connection_pool(mut self, pool_config: ConnectionPoolConfig) -> Self {
+        self.pool_config = pool_config;
+        self
+    }
+
     pub async fn produce(&self, node: &Node) -> Result<BobClient, String> {
         let metrics = self.metrics.clone().get_metrics();
         BobClient::create(
@@ -344,6 +438,7 @@ impl Factory {
             metrics,
             self.local_node_name.clone(),
             self.tls_config.as_ref(),
+            &self.pool_config,
         )
         .await
     }
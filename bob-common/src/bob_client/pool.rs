@@ -0,0 +1,185 @@
+use crate::{
+    configs::node::ConnectionPoolConfig, error::Error, metrics::ChannelPool as PoolMetrics,
+};
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, RwLock,
+    },
+    time::Duration,
+};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use std::error::Error as StdError;
+use tonic::{
+    transport::{Channel, Endpoint, TimeoutExpired},
+    Status,
+};
+
+/// Status created on the client side from the error of the channel, the request didn't get
+/// an answer from the node. Statuses sent by the node have no source. Expired deadline is a
+/// timeout, not a transport error.
+pub(crate) fn is_transport_error(status: &Status) -> bool {
+    let mut source = status.source();
+    if source.is_none() {
+        return false;
+    }
+    while let Some(err) = source {
+        if err.is::<TimeoutExpired>() {
+            return false;
+        }
+        source = err.source();
+    }
+    true
+}
+
+/// Snapshot of the pool state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolStats {
+    pub channels: usize,
+    pub in_flight: usize,
+    pub reconnects: usize,
+}
+
+struct Slot {
+    channel: RwLock<Channel>,
+    in_flight: AtomicUsize,
+}
+
+/// Several channels (HTTP/2 connections) to one node. Request takes the least loaded channel,
+/// channel that failed with transport error is replaced without touching the others.
+pub(crate) struct ChannelPool {
+    endpoint: Endpoint,
+    slots: Vec<Slot>,
+    next: AtomicUsize,
+    in_flight: AtomicUsize,
+    reconnects: AtomicUsize,
+    limit: Option<Arc<Semaphore>>,
+    operation_timeout: Duration,
+    metrics: PoolMetrics,
+}
+
+/// Channel taken from the pool, it is counted as busy until dropped.
+pub(crate) struct PooledChannel<'a> {
+    pool: &'a ChannelPool,
+    slot: usize,
+    channel: Channel,
+    _permit: Option<OwnedSemaphorePermit>,
+}
+
+impl ChannelPool {
+    /// `connected` is used as the first channel, the others connect on first request.
+    pub(crate) fn new(
+        endpoint: Endpoint,
+        connected: Channel,
+        config: &ConnectionPoolConfig,
+        operation_timeout: Duration,
+        metrics: PoolMetrics,
+    ) -> Self {
+        let channels_count = config.channels_per_node().max(1);
+        let slots = std::iter::once(connected)
+            .chain((1..channels_count).map(|_| endpoint.connect_lazy()))
+            .map(|channel| Slot {
+                channel: RwLock::new(channel),
+                in_flight: AtomicUsize::new(0),
+            })
+            .collect();
+        metrics.channels(channels_count);
+        Self {
+            endpoint,
+            slots,
+            next: AtomicUsize::new(0),
+            in_flight: AtomicUsize::new(0),
+            reconnects: AtomicUsize::new(0),
+            limit: config
+                .max_concurrent_requests()
+                .map(|limit| Arc::new(Semaphore::new(limit))),
+            operation_timeout,
+            metrics,
+        }
+    }
+
+    /// Waits for `operation_timeout` if the node already has max count of requests in progress.
+    pub(crate) async fn acquire(&self) -> Result<PooledChannel<'_>, Error> {
+        let permit = match &self.limit {
+            Some(limit) => Some(self.acquire_permit(limit.clone()).await?),
+            None => None,
+        };
+        let slot = self.least_loaded();
+        self.slots[slot].in_flight.fetch_add(1, Ordering::AcqRel);
+        let in_flight = self.in_flight.fetch_add(1, Ordering::AcqRel) + 1;
+        self.metrics.in_flight(in_flight);
+        let channel = self.slots[slot].channel.read().expect("rwlock").clone();
+        Ok(PooledChannel {
+            pool: self,
+            slot,
+            channel,
+            _permit: permit,
+        })
+    }
+
+    async fn acquire_permit(&self, limit: Arc<Semaphore>) -> Result<OwnedSemaphorePermit, Error> {
+        if let Ok(permit) = limit.clone().try_acquire_owned() {
+            return Ok(permit);
+        }
+        self.metrics.limit_wait_count();
+        match tokio::time::timeout(self.operation_timeout, limit.acquire_owned()).await {
+            Ok(permit) => Ok(permit.expect("pool semaphore is never closed")),
+            Err(_) => {
+                self.metrics.limit_timeout_count();
+                Err(Error::timeout())
+            }
+        }
+    }
+
+    /// Round robin among the channels with the least count of requests in progress.
+    fn least_loaded(&self) -> usize {
+        let start = self.next.fetch_add(1, Ordering::Relaxed);
+        (0..self.slots.len())
+            .map(|i| (start + i) % self.slots.len())
+            .min_by_key(|i| self.slots[*i].in_flight.load(Ordering::Acquire))
+            .expect("pool is not empty")
+    }
+
+    fn reconnect(&self, slot: usize) {
+        *self.slots[slot].channel.write().expect("rwlock") = self.endpoint.connect_lazy();
+        self.reconnects.fetch_add(1, Ordering::Relaxed);
+        self.metrics.reconnect_count();
+    }
+
+    pub(crate) fn stats(&self) -> PoolStats {
+        PoolStats {
+            channels: self.slots.len(),
+            in_flight: self.in_flight.load(Ordering::Acquire),
+            reconnects: self.reconnects.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl PooledChannel<'_> {
+    pub(crate) fn channel(&self) -> Channel {
+        self.channel.clone()
+    }
+
+    pub(crate) fn slot(&self) -> usize {
+        self.slot
+    }
+
+    /// Replaces the channel if request failed with transport error. Answers of the node, even
+    /// `Unavailable` ones, mean that the channel works.
+    pub(crate) fn check_status(&self, status: &Status) {
+        if is_transport_error(status) {
+            debug!("reconnect channel {} after error: {}", self.slot(), status);
+            self.pool.reconnect(self.slot());
+        }
+    }
+}
+
+impl Drop for PooledChannel<'_> {
+    fn drop(&mut self) {
+        self.pool.slots[self.slot]
+            .in_flight
+            .fetch_sub(1, Ordering::AcqRel);
+        let in_flight = self.pool.in_flight.fetch_sub(1, Ordering::AcqRel) - 1;
+        self.pool.metrics.in_flight(in_flight);
+    }
+}
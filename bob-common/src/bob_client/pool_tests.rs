@@ -0,0 +1,77 @@
+#[cfg(test)]
+mod tests {
+    use crate::{
+        bob_client::pool::{is_transport_error, ChannelPool},
+        configs::node::tests::connection_pool_config,
+        error::Kind,
+        metrics::ChannelPool as PoolMetrics,
+    };
+    use bob_grpc::{bob_api_client::BobApiClient, Null};
+    use std::time::Duration;
+    use tonic::{transport::Endpoint, Status};
+
+    fn pool(channels_per_node: usize, max_concurrent_requests: Option<usize>) -> ChannelPool {
+        let endpoint = Endpoint::from_static("http://127.0.0.1:1");
+        let connected = endpoint.connect_lazy();
+        ChannelPool::new(
+            endpoint,
+            connected,
+            &connection_pool_config(channels_per_node, max_concurrent_requests),
+            Duration::from_millis(50),
+            PoolMetrics::new("test"),
+        )
+    }
+
+    #[tokio::test]
+    async fn requests_go_to_least_loaded_channel() {
+        let pool = pool(3, None);
+
+        let first = pool.acquire().await.unwrap();
+        let second = pool.acquire().await.unwrap();
+        let third = pool.acquire().await.unwrap();
+        let mut slots = vec![first.slot(), second.slot(), third.slot()];
+        slots.sort_unstable();
+        assert_eq!(vec![0, 1, 2], slots);
+        assert_eq!(3, pool.stats().in_flight);
+
+        let freed = second.slot();
+        drop(second);
+        assert_eq!(freed, pool.acquire().await.unwrap().slot());
+        drop((first, third));
+        assert_eq!(0, pool.stats().in_flight);
+    }
+
+    #[tokio::test]
+    async fn requests_over_limit_wait_for_timeout() {
+        let pool = pool(2, Some(1));
+
+        let busy = pool.acquire().await.unwrap();
+        let err = pool.acquire().await.err().expect("limit reached");
+        assert_eq!(&Kind::Timeout, err.kind());
+
+        drop(busy);
+        assert!(pool.acquire().await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn only_failed_channel_is_reconnected() {
+        let pool = pool(2, None);
+
+        let failed = pool.acquire().await.unwrap();
+        let alive = pool.acquire().await.unwrap();
+        // Nothing listens on the port, connection is refused
+        let status = BobApiClient::new(failed.channel())
+            .ping(Null {})
+            .await
+            .unwrap_err();
+        assert!(is_transport_error(&status));
+        failed.check_status(&status);
+        alive.check_status(&Status::not_found("KeyNotFound"));
+        alive.check_status(&Status::unavailable("NodeIsDraining"));
+
+        let stats = pool.stats();
+        assert_eq!(1, stats.reconnects);
+        assert_eq!(2, stats.channels);
+        assert_eq!(2, stats.in_flight);
+    }
+}
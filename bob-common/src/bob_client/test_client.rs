@@ -0,0 +1,172 @@
+use super::{
+    b_client::MockBobClient, DeleteResult, ExistResult, FactoryTlsConfig, GetResult, PingResult,
+    PutResult, StreamVDiskResult,
+};
+use crate::{
+    configs::node::ConnectionPoolConfig,
+    data::{BobData, BobKey, BobMeta, StreamedRecord, VDiskPosition},
+    error::Error,
+    metrics::BobClient as BobClientMetrics,
+    node::{Node, NodeName, Output as NodeOutput},
+};
+use bob_grpc::{DeleteOptions, GetOptions, PutOptions};
+use futures::stream::BoxStream;
+use std::{
+    fmt::{Debug, Formatter, Result as FmtResult},
+    ops::{Deref, DerefMut},
+    sync::Arc,
+    time::Duration,
+};
+
+/// In-memory connection to the node, replaces gRPC channel in tests.
+#[async_trait::async_trait]
+pub trait Transport: Send + Sync {
+    async fn put(&self, key: BobKey, data: BobData, options: PutOptions) -> Result<(), Error>;
+    async fn get(&self, key: BobKey, options: GetOptions) -> Result<BobData, Error>;
+    async fn ping(&self) -> Result<(), Error>;
+    async fn exist(&self, keys: Vec<BobKey>, options: GetOptions) -> Result<Vec<bool>, Error>;
+    async fn delete(&self, key: BobKey, meta: BobMeta, options: DeleteOptions)
+        -> Result<(), Error>;
+    async fn stream_vdisk(
+        &self,
+        vdisk_id: u32,
+        start_after: Option<VDiskPosition>,
+    ) -> Result<BoxStream<'static, Result<StreamedRecord, Error>>, Error>;
+}
+
+/// Client used with `testing` feature. Requests are answered by mock expectations, or by
+/// [`Transport`] if the client is created with [`TestBobClient::with_transport`].
+#[derive(Default)]
+pub struct TestBobClient {
+    mock: MockBobClient,
+    transport: Option<(NodeName, Arc<dyn Transport>)>,
+}
+
+impl TestBobClient {
+    /// Creates client without expectations, they are set through [`MockBobClient`] methods
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_transport(target_node_name: NodeName, transport: Arc<dyn Transport>) -> Self {
+        Self {
+            mock: MockBobClient::new(),
+            transport: Some((target_node_name, transport)),
+        }
+    }
+
+    pub async fn create(
+        node: &Node,
+        operation_timeout: Duration,
+        metrics: BobClientMetrics,
+        local_node_name: NodeName,
+        tls_config: Option<&FactoryTlsConfig>,
+        pool_config: &ConnectionPoolConfig,
+    ) -> Result<Self, String> {
+        MockBobClient::create(
+            node,
+            operation_timeout,
+            metrics,
+            local_node_name,
+            tls_config,
+            pool_config,
+        )
+        .await
+        .map(Self::from)
+    }
+
+    pub fn target_node_name(&self) -> &NodeName {
+        match &self.transport {
+            Some((name, _)) => name,
+            None => self.mock.target_node_name(),
+        }
+    }
+
+    pub async fn put(&self, key: BobKey, d: BobData, options: PutOptions) -> PutResult {
+        match &self.transport {
+            Some((name, transport)) => output(name, transport.put(key, d, options).await),
+            None => self.mock.put(key, d, options).await,
+        }
+    }
+
+    pub async fn get(&self, key: BobKey, options: GetOptions) -> GetResult {
+        match &self.transport {
+            Some((name, transport)) => output(name, transport.get(key, options).await),
+            None => self.mock.get(key, options).await,
+        }
+    }
+
+    pub async fn ping(&self) -> PingResult {
+        match &self.transport {
+            Some((name, transport)) => output(name, transport.ping().await),
+            None => self.mock.ping().await,
+        }
+    }
+
+    pub async fn exist(&self, keys: Vec<BobKey>, options: GetOptions) -> ExistResult {
+        match &self.transport {
+            Some((name, transport)) => output(name, transport.exist(keys, options).await),
+            None => self.mock.exist(keys, options).await,
+        }
+    }
+
+    pub async fn delete(&self, key: BobKey, meta: BobMeta, options: DeleteOptions) -> DeleteResult {
+        match &self.transport {
+            Some((name, transport)) => output(name, transport.delete(key, meta, options).await),
+            None => self.mock.delete(key, meta, options).await,
+        }
+    }
+
+    pub async fn stream_vdisk(
+        &self,
+        vdisk_id: u32,
+        start_after: Option<VDiskPosition>,
+    ) -> StreamVDiskResult {
+        match &self.transport {
+            Some((name, transport)) => {
+                output(name, transport.stream_vdisk(vdisk_id, start_after).await)
+            }
+            None => self.mock.stream_vdisk(vdisk_id, start_after).await,
+        }
+    }
+}
+
+fn output<T>(
+    node_name: &NodeName,
+    result: Result<T, Error>,
+) -> Result<NodeOutput<T>, NodeOutput<Error>> {
+    result
+        .map(|inner| NodeOutput::new(node_name.clone(), inner))
+        .map_err(|e| NodeOutput::new(node_name.clone(), e))
+}
+
+impl From<MockBobClient> for TestBobClient {
+    fn from(mock: MockBobClient) -> Self {
+        Self {
+            mock,
+            transport: None,
+        }
+    }
+}
+
+impl Deref for TestBobClient {
+    type Target = MockBobClient;
+
+    fn deref(&self) -> &Self::Target {
+        &self.mock
+    }
+}
+
+impl DerefMut for TestBobClient {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.mock
+    }
+}
+
+impl Debug for TestBobClient {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("TestBobClient")
+            .field("transport", &self.transport.as_ref().map(|(name, _)| name))
+            .finish()
+    }
+}
@@ -508,7 +508,7 @@ impl Validatable for Cluster {
 }
 
 pub mod tests {
-    use super::{Cluster, DiskPath, DistributionFunc, Node, Replica, VDisk};
+    use super::{Cluster, DiskPath, DistributionFunc, Node, Rack, Replica, VDisk};
 
     #[must_use]
     pub fn cluster_config(count_nodes: u32, count_vdisks: u32, count_replicas: u32) -> Cluster {
@@ -545,4 +545,28 @@ pub mod tests {
             racks: vec![],
         }
     }
+
+    #[must_use]
+    pub fn with_racks(mut cluster: Cluster, racks: &[(&str, &[&str])]) -> Cluster {
+        cluster.racks = racks
+            .iter()
+            .map(|(name, nodes)| Rack {
+                name: name.to_string(),
+                nodes: nodes.iter().map(|node| node.to_string()).collect(),
+            })
+            .collect();
+        cluster
+    }
+
+    /// Moves disks of all nodes into `root` directory.
+    #[must_use]
+    pub fn with_disks_root(mut cluster: Cluster, root: &str) -> Cluster {
+        for node in &mut cluster.nodes {
+            for disk in &mut node.disks {
+                let path = format!("{}/{}", root.trim_end_matches('/'), disk.path());
+                *disk = DiskPath::new(disk.name().clone(), &path);
+            }
+        }
+        cluster
+    }
 }
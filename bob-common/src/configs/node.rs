@@ -5,7 +5,10 @@ use super::{
     validation::Validatable
 };
 use bob_access::AuthenticationType;
-use crate::core_types::{DiskPath, DiskName};
+use crate::{
+    core_types::{DiskPath, DiskName},
+    error::Error,
+};
 use futures::Future;
 use humantime::Duration as HumanDuration;
 use std::{
@@ -553,6 +556,610 @@ impl TLSConfig {
     }
 }
 
+/// Hedged reads params: if replica doesn't answer GET within delay, the same request
+/// is sent to the next replica.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct HedgedReadConfig {
+    delay: String,
+    latency_percentile: Option<f64>,
+}
+
+impl HedgedReadConfig {
+    /// Fixed delay before the next replica is requested, parsed from humantime format.
+    /// Used until enough latency samples are collected if `latency_percentile` is set.
+    pub fn delay(&self) -> Duration {
+        self.delay
+            .parse::<HumanDuration>()
+            .expect("parse humantime duration")
+            .into()
+    }
+
+    /// Percentile of observed remote GET latency used as delay.
+    pub fn latency_percentile(&self) -> Option<f64> {
+        self.latency_percentile
+    }
+}
+
+impl Validatable for HedgedReadConfig {
+    fn validate(&self) -> Result<(), String> {
+        self.delay.parse::<HumanDuration>().map_err(|e| {
+            format!("field 'delay' for 'hedged_read' is not valid: {}", e)
+        })?;
+        match self.latency_percentile {
+            Some(p) if !(p > 0.0 && p <= 100.0) => Err(format!(
+                "field 'latency_percentile' for 'hedged_read' must be in (0, 100], got {}",
+                p
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Phi-accrual failure detector and circuit breaker params for remote nodes.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FailureDetectorConfig {
+    #[serde(default = "FailureDetectorConfig::default_phi_threshold")]
+    phi_threshold: f64,
+    #[serde(default = "FailureDetectorConfig::default_min_std_deviation")]
+    min_std_deviation: String,
+    #[serde(default = "FailureDetectorConfig::default_max_failures")]
+    max_failures: usize,
+    #[serde(default = "FailureDetectorConfig::default_open_duration")]
+    open_duration: String,
+}
+
+impl FailureDetectorConfig {
+    fn default_phi_threshold() -> f64 {
+        8.0
+    }
+
+    fn default_min_std_deviation() -> String {
+        "500ms".to_string()
+    }
+
+    fn default_max_failures() -> usize {
+        5
+    }
+
+    fn default_open_duration() -> String {
+        "5s".to_string()
+    }
+
+    /// Suspicion level above which the node is considered failed.
+    pub fn phi_threshold(&self) -> f64 {
+        self.phi_threshold
+    }
+
+    /// Lower bound of ping intervals deviation, prevents suspecting node on small jitter.
+    pub fn min_std_deviation(&self) -> Duration {
+        self.min_std_deviation
+            .parse::<HumanDuration>()
+            .expect("parse humantime duration")
+            .into()
+    }
+
+    /// Count of consecutive failed requests that opens the circuit.
+    pub fn max_failures(&self) -> usize {
+        self.max_failures
+    }
+
+    /// Time the circuit stays open before a trial request is allowed.
+    pub fn open_duration(&self) -> Duration {
+        self.open_duration
+            .parse::<HumanDuration>()
+            .expect("parse humantime duration")
+            .into()
+    }
+}
+
+impl Default for FailureDetectorConfig {
+    fn default() -> Self {
+        Self {
+            phi_threshold: Self::default_phi_threshold(),
+            min_std_deviation: Self::default_min_std_deviation(),
+            max_failures: Self::default_max_failures(),
+            open_duration: Self::default_open_duration(),
+        }
+    }
+}
+
+impl Validatable for FailureDetectorConfig {
+    fn validate(&self) -> Result<(), String> {
+        if self.phi_threshold.is_nan() || self.phi_threshold <= 0.0 {
+            return Err(format!(
+                "field 'phi_threshold' for 'failure_detector' must be positive, got {}",
+                self.phi_threshold
+            ));
+        }
+        if self.max_failures == 0 {
+            return Err("field 'max_failures' for 'failure_detector' must be positive".to_string());
+        }
+        self.min_std_deviation.parse::<HumanDuration>().map_err(|e| {
+            format!("field 'min_std_deviation' for 'failure_detector' is not valid: {}", e)
+        })?;
+        self.open_duration.parse::<HumanDuration>().map_err(|e| {
+            format!("field 'open_duration' for 'failure_detector' is not valid: {}", e)
+        })?;
+        Ok(())
+    }
+}
+
+/// Durable hinted handoff params: writes that failed for remote node are stored on disk
+/// and replayed when node becomes available.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct HandoffConfig {
+    path: String,
+    #[serde(default = "HandoffConfig::default_max_hints_per_node")]
+    max_hints_per_node: usize,
+    #[serde(default = "HandoffConfig::default_max_background_puts")]
+    max_background_puts: usize,
+}
+
+impl HandoffConfig {
+    fn default_max_hints_per_node() -> usize {
+        100_000
+    }
+
+    fn default_max_background_puts() -> usize {
+        1024
+    }
+
+    /// Directory with queue files, one file per remote node.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// New hints for the node are dropped when its queue reaches this size.
+    pub fn max_hints_per_node(&self) -> usize {
+        self.max_hints_per_node
+    }
+
+    /// Max count of background puts in progress, puts over the limit go to the queue.
+    pub fn max_background_puts(&self) -> usize {
+        self.max_background_puts
+    }
+}
+
+impl Validatable for HandoffConfig {
+    fn validate(&self) -> Result<(), String> {
+        if self.path.is_empty() {
+            Err("field 'path' for 'handoff' is empty".to_string())
+        } else if self.max_hints_per_node == 0 {
+            Err("field 'max_hints_per_node' for 'handoff' must be positive".to_string())
+        } else if self.max_background_puts == 0 {
+            Err("field 'max_background_puts' for 'handoff' must be positive".to_string())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Params of the pool of gRPC channels to every remote node.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ConnectionPoolConfig {
+    #[serde(default = "ConnectionPoolConfig::default_channels_per_node")]
+    channels_per_node: usize,
+    max_concurrent_requests: Option<usize>,
+}
+
+impl ConnectionPoolConfig {
+    fn default_channels_per_node() -> usize {
+        1
+    }
+
+    /// Count of HTTP/2 connections to every remote node, requests go to the least loaded one.
+    pub fn channels_per_node(&self) -> usize {
+        self.channels_per_node
+    }
+
+    /// Max count of requests in progress to one remote node, the rest wait for
+    /// `operation_timeout`. Unlimited if not set.
+    pub fn max_concurrent_requests(&self) -> Option<usize> {
+        self.max_concurrent_requests
+    }
+}
+
+impl Default for ConnectionPoolConfig {
+    fn default() -> Self {
+        Self {
+            channels_per_node: Self::default_channels_per_node(),
+            max_concurrent_requests: None,
+        }
+    }
+}
+
+impl Validatable for ConnectionPoolConfig {
+    fn validate(&self) -> Result<(), String> {
+        if self.channels_per_node == 0 {
+            Err("field 'channels_per_node' for 'connection_pool' must be positive".to_string())
+        } else if self.max_concurrent_requests == Some(0) {
+            Err("field 'max_concurrent_requests' for 'connection_pool' must be positive".to_string())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Where finished spans are sent.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TracingExporter {
+    /// OpenTelemetry collector, gRPC protocol
+    Otlp,
+    /// Local file, one span per line
+    File,
+}
+
+/// Distributed tracing params.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TracingConfig {
+    exporter: TracingExporter,
+    #[serde(default = "TracingConfig::default_otlp_endpoint")]
+    otlp_endpoint: String,
+    path: Option<String>,
+    #[serde(default = "TracingConfig::default_service_name")]
+    service_name: String,
+    #[serde(default = "TracingConfig::default_sample_ratio")]
+    sample_ratio: f64,
+}
+
+impl TracingConfig {
+    fn default_otlp_endpoint() -> String {
+        "http://localhost:4317".to_string()
+    }
+
+    fn default_service_name() -> String {
+        "bob".to_string()
+    }
+
+    fn default_sample_ratio() -> f64 {
+        1.0
+    }
+
+    pub fn exporter(&self) -> TracingExporter {
+        self.exporter
+    }
+
+    pub fn otlp_endpoint(&self) -> &str {
+        &self.otlp_endpoint
+    }
+
+    /// File for spans, used by `file` exporter.
+    pub fn path(&self) -> Option<&str> {
+        self.path.as_deref()
+    }
+
+    pub fn service_name(&self) -> &str {
+        &self.service_name
+    }
+
+    /// Part of traces started on this node that are recorded. Traces started on other nodes
+    /// follow the decision of the caller.
+    pub fn sample_ratio(&self) -> f64 {
+        self.sample_ratio
+    }
+}
+
+impl Validatable for TracingConfig {
+    fn validate(&self) -> Result<(), String> {
+        if self.exporter == TracingExporter::File
+            && !matches!(self.path(), Some(path) if !path.is_empty()) {
+            Err("field 'path' for 'tracing' is required for 'file' exporter".to_string())
+        } else if self.exporter == TracingExporter::Otlp && self.otlp_endpoint.is_empty() {
+            Err("field 'otlp_endpoint' for 'tracing' is empty".to_string())
+        } else if !(0.0..=1.0).contains(&self.sample_ratio) {
+            Err(format!(
+                "field 'sample_ratio' for 'tracing' must be in [0, 1], got {}",
+                self.sample_ratio
+            ))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Rack-aware routing params.
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct LocalityConfig {
+    rack: Option<String>,
+    #[serde(default)]
+    prefer_local_reads: bool,
+    #[serde(default)]
+    spread_writes_across_racks: bool,
+}
+
+impl LocalityConfig {
+    /// Rack of the node, overrides rack from cluster config.
+    pub fn rack(&self) -> Option<&str> {
+        self.rack.as_deref()
+    }
+
+    /// Read from replicas in the same rack before the others.
+    pub fn prefer_local_reads(&self) -> bool {
+        self.prefer_local_reads
+    }
+
+    /// Put waits for replicas in different racks before answering, if they are available.
+    pub fn spread_writes_across_racks(&self) -> bool {
+        self.spread_writes_across_racks
+    }
+}
+
+impl Validatable for LocalityConfig {
+    fn validate(&self) -> Result<(), String> {
+        match self.rack.as_deref() {
+            Some("") => Err("field 'rack' for 'locality' is empty".to_string()),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Params of the rebuild of a replaced disk from other replicas.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RebuildConfig {
+    #[serde(default = "RebuildConfig::default_records_per_sec")]
+    records_per_sec: u64,
+}
+
+impl RebuildConfig {
+    fn default_records_per_sec() -> u64 {
+        1000
+    }
+
+    /// Max count of records written to the disk per second, shared by all its vdisks.
+    pub fn records_per_sec(&self) -> u64 {
+        self.records_per_sec
+    }
+}
+
+impl Default for RebuildConfig {
+    fn default() -> Self {
+        Self {
+            records_per_sec: Self::default_records_per_sec(),
+        }
+    }
+}
+
+impl Validatable for RebuildConfig {
+    fn validate(&self) -> Result<(), String> {
+        if self.records_per_sec == 0 {
+            Err("field 'records_per_sec' for 'rebuild' must be positive".to_string())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Background scrubber params: checksums of closed blobs are validated, corrupted blobs are
+/// quarantined and their records are fetched from other replicas.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ScrubberConfig {
+    #[serde(default = "ScrubberConfig::default_interval")]
+    interval: String,
+    #[serde(default = "ScrubberConfig::default_max_read_per_sec")]
+    max_read_per_sec: ByteUnit,
+}
+
+impl ScrubberConfig {
+    fn default_interval() -> String {
+        "1d".to_string()
+    }
+
+    fn default_max_read_per_sec() -> ByteUnit {
+        50 * ByteUnit::MiB
+    }
+
+    /// Time between starts of scrub passes over all disks, the first pass starts one interval
+    /// after the node start.
+    pub fn interval(&self) -> Duration {
+        self.interval
+            .parse::<HumanDuration>()
+            .expect("parse humantime duration")
+            .into()
+    }
+
+    /// Scrubber sleeps between blobs to keep the read rate of the disk under this limit.
+    pub fn max_read_per_sec(&self) -> u64 {
+        self.max_read_per_sec.as_u64()
+    }
+}
+
+impl Default for ScrubberConfig {
+    fn default() -> Self {
+        Self {
+            interval: Self::default_interval(),
+            max_read_per_sec: Self::default_max_read_per_sec(),
+        }
+    }
+}
+
+impl Validatable for ScrubberConfig {
+    fn validate(&self) -> Result<(), String> {
+        let interval: Duration = self
+            .interval
+            .parse::<HumanDuration>()
+            .map_err(|e| format!("field 'interval' for 'scrubber' is not valid: {}", e))?
+            .into();
+        if interval.is_zero() {
+            Err("field 'interval' for 'scrubber' must be positive".to_string())
+        } else if self.max_read_per_sec.as_u64() == 0 {
+            Err("field 'max_read_per_sec' for 'scrubber' must be positive".to_string())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Params of the in-memory backend.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct InMemoryConfig {
+    #[serde(default)]
+    snapshot_path: Option<String>,
+}
+
+impl InMemoryConfig {
+    /// File the data is saved to on shutdown and loaded from on start, data is lost on
+    /// restart if not set.
+    pub fn snapshot_path(&self) -> Option<&str> {
+        self.snapshot_path.as_deref()
+    }
+}
+
+impl Validatable for InMemoryConfig {
+    fn validate(&self) -> Result<(), String> {
+        if self.snapshot_path.as_deref() == Some("") {
+            Err("field 'snapshot_path' for 'in_memory' must not be empty".to_string())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Operation of the backend affected by injected fault.
+#[derive(Debug, PartialEq, Eq, Hash, Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum BackendOperation {
+    Put,
+    Get,
+    Exist,
+    Delete,
+}
+
+/// Error returned by the backend operation with injected fault.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum InjectedError {
+    PossibleDiskDisconnection,
+    HolderTemporaryUnavailable,
+    Timeout,
+    Internal,
+}
+
+impl InjectedError {
+    pub fn to_error(self) -> Error {
+        match self {
+            Self::PossibleDiskDisconnection => Error::possible_disk_disconnection(),
+            Self::HolderTemporaryUnavailable => Error::holder_temporary_unavailable(),
+            Self::Timeout => Error::timeout(),
+            Self::Internal => Error::internal(),
+        }
+    }
+}
+
+/// Fault injected into backend operations on the disk: operations are delayed by `latency` and
+/// fail with `error` with given `probability`. Rule is active after `delay` for `duration`, so
+/// disk unavailability window is a rule with the error and probability 1.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct FaultRule {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    disk: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    operations: Vec<BackendOperation>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    latency: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    error: Option<InjectedError>,
+    #[serde(default = "FaultRule::default_probability")]
+    probability: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    delay: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    duration: Option<String>,
+}
+
+impl FaultRule {
+    fn default_probability() -> f64 {
+        1.0
+    }
+
+    fn parse_duration(value: &Option<String>) -> Option<Duration> {
+        value.as_ref().map(|value| {
+            value
+                .parse::<HumanDuration>()
+                .expect("parse humantime duration")
+                .into()
+        })
+    }
+
+    /// Rule without disk applies to all disks including the alien one.
+    pub fn matches(&self, disk: &str, operation: BackendOperation) -> bool {
+        self.disk.as_deref().is_none_or(|d| d == disk)
+            && (self.operations.is_empty() || self.operations.contains(&operation))
+    }
+
+    pub fn latency(&self) -> Duration {
+        Self::parse_duration(&self.latency).unwrap_or_default()
+    }
+
+    pub fn error(&self) -> Option<InjectedError> {
+        self.error
+    }
+
+    pub fn probability(&self) -> f64 {
+        self.probability
+    }
+
+    /// Time since the rule is set before it becomes active.
+    pub fn delay(&self) -> Duration {
+        Self::parse_duration(&self.delay).unwrap_or_default()
+    }
+
+    /// Rule is active until removed if duration is not set.
+    pub fn duration(&self) -> Option<Duration> {
+        Self::parse_duration(&self.duration)
+    }
+
+    /// Checks rule received outside of node config.
+    pub fn check(&self) -> Result<(), String> {
+        self.validate()
+    }
+}
+
+impl Validatable for FaultRule {
+    fn validate(&self) -> Result<(), String> {
+        for (field, value) in [
+            ("latency", &self.latency),
+            ("delay", &self.delay),
+            ("duration", &self.duration),
+        ] {
+            if let Some(value) = value {
+                value.parse::<HumanDuration>().map_err(|e| {
+                    format!("field '{}' for fault rule is not valid: {}", field, e)
+                })?;
+            }
+        }
+        if !(0.0..=1.0).contains(&self.probability) {
+            Err("field 'probability' for fault rule must be in range [0, 1]".to_string())
+        } else if self.latency.is_none() && self.error.is_none() {
+            Err("fault rule must set 'latency' or 'error'".to_string())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Backend is wrapped with fault injection, rules can be changed at runtime via REST API.
+/// Intended for chaos testing only.
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct FaultInjectionConfig {
+    #[serde(default)]
+    rules: Vec<FaultRule>,
+}
+
+impl FaultInjectionConfig {
+    /// Rules active since the start of the node.
+    pub fn rules(&self) -> &[FaultRule] {
+        &self.rules
+    }
+}
+
+impl Validatable for FaultInjectionConfig {
+    fn validate(&self) -> Result<(), String> {
+        self.rules.iter().try_for_each(Validatable::validate)
+    }
+}
+
 #[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Copy)]
 pub enum BackendType {
     InMemory = 0,
@@ -571,12 +1178,34 @@ pub struct Node {
     check_interval: String,
     #[serde(default = "NodeConfig::default_count_interval")]
     count_interval: String,
+    #[serde(default = "NodeConfig::default_drain_timeout")]
+    drain_timeout: String,
     cluster_policy: String,
 
     backend_type: String,
     pearl: Option<Pearl>,
+    #[serde(default)]
+    in_memory: InMemoryConfig,
     metrics: Option<MetricsConfig>,
     tls: Option<TLSConfig>,
+    #[serde(default)]
+    hedged_read: Option<HedgedReadConfig>,
+    #[serde(default)]
+    locality: LocalityConfig,
+    #[serde(default)]
+    failure_detector: Option<FailureDetectorConfig>,
+    #[serde(default)]
+    handoff: Option<HandoffConfig>,
+    #[serde(default)]
+    connection_pool: ConnectionPoolConfig,
+    #[serde(default)]
+    tracing: Option<TracingConfig>,
+    #[serde(default)]
+    rebuild: RebuildConfig,
+    #[serde(default)]
+    scrubber: Option<ScrubberConfig>,
+    #[serde(default)]
+    fault_injection: Option<FaultInjectionConfig>,
 
     #[serde(skip)]
     bind_ref: Arc<Mutex<String>>,
@@ -634,6 +1263,10 @@ impl NodeConfig {
         self.pearl.as_ref().expect("get pearl config")
     }
 
+    pub fn in_memory(&self) -> &InMemoryConfig {
+        &self.in_memory
+    }
+
     pub fn metrics(&self) -> &MetricsConfig {
         self.metrics.as_ref().expect("metrics config")
     }
@@ -642,6 +1275,44 @@ impl NodeConfig {
         &self.tls
     }
 
+    pub fn hedged_read(&self) -> Option<&HedgedReadConfig> {
+        self.hedged_read.as_ref()
+    }
+
+    pub fn locality(&self) -> &LocalityConfig {
+        &self.locality
+    }
+
+    pub fn failure_detector(&self) -> Option<&FailureDetectorConfig> {
+        self.failure_detector.as_ref()
+    }
+
+    pub fn handoff(&self) -> Option<&HandoffConfig> {
+        self.handoff.as_ref()
+    }
+
+    pub fn connection_pool(&self) -> &ConnectionPoolConfig {
+        &self.connection_pool
+    }
+
+    pub fn tracing(&self) -> Option<&TracingConfig> {
+        self.tracing.as_ref()
+    }
+
+    pub fn rebuild(&self) -> &RebuildConfig {
+        &self.rebuild
+    }
+
+    /// Scrubber is disabled if the section is not set.
+    pub fn scrubber(&self) -> Option<&ScrubberConfig> {
+        self.scrubber.as_ref()
+    }
+
+    /// Backend faults can't be injected if the section is not set.
+    pub fn fault_injection(&self) -> Option<&FaultInjectionConfig> {
+        self.fault_injection.as_ref()
+    }
+
     /// Get log config file path.
     pub fn log_config(&self) -> &str {
         &self.log_config
@@ -680,6 +1351,18 @@ impl NodeConfig {
         "10000ms".to_string()
     }
 
+    fn default_drain_timeout() -> String {
+        "30sec".to_string()
+    }
+
+    /// Max time to wait for requests in progress on shutdown.
+    pub fn drain_timeout(&self) -> Duration {
+        self.drain_timeout
+            .parse::<HumanDuration>()
+            .expect("parse humantime duration")
+            .into()
+    }
+
     pub fn count_interval(&self) -> Duration {
         self.count_interval
             .parse::<HumanDuration>()
@@ -864,6 +1547,7 @@ impl NodeConfig {
              operation_timeout: String::from("60sec"),
              check_interval: String::from("5000ms"),
              count_interval: NodeConfig::default_count_interval(),
+             drain_timeout: NodeConfig::default_drain_timeout(),
              cluster_policy: String::from("quorum"),
              backend_type: String::from("pearl"),
              pearl: Some(Pearl::get_testmode(disk_name)),
@@ -884,6 +1568,16 @@ impl NodeConfig {
              holder_group_size: NodeConfig::default_holder_group_size(),
              authentication_type: NodeConfig::default_authentication_type(),
              tls: None,
+             hedged_read: None,
+             locality: LocalityConfig::default(),
+             failure_detector: None,
+             handoff: None,
+             connection_pool: ConnectionPoolConfig::default(),
+             tracing: None,
+             rebuild: RebuildConfig::default(),
+             in_memory: InMemoryConfig::default(),
+             scrubber: None,
+             fault_injection: None,
              hostname_resolve_period_ms: NodeConfig::default_hostname_resolve_period_ms()
         }
     }
@@ -905,6 +1599,9 @@ impl Validatable for NodeConfig {
         self.check_interval.parse::<HumanDuration>().map_err(|e| {
             format!("field 'check_interval' for 'config' is not valid: {}", e)
         })?;
+        self.drain_timeout.parse::<HumanDuration>().map_err(|e| {
+            format!("field 'drain_timeout' for 'config' is not valid: {}", e)
+        })?;
         if self.name.is_empty() {
             Err("field 'name' for 'config' is empty".to_string())
         } else if self.cluster_policy.is_empty() {
@@ -916,6 +1613,28 @@ impl Validatable for NodeConfig {
         } else if self.quorum == 0 {
             Err("field 'quorum' for 'config' must be greater than 0".to_string())
         } else {
+            if let Some(hedged_read) = &self.hedged_read {
+                hedged_read.validate()?;
+            }
+            self.locality.validate()?;
+            if let Some(failure_detector) = &self.failure_detector {
+                failure_detector.validate()?;
+            }
+            if let Some(handoff) = &self.handoff {
+                handoff.validate()?;
+            }
+            self.connection_pool.validate()?;
+            if let Some(tracing) = &self.tracing {
+                tracing.validate()?;
+            }
+            self.rebuild.validate()?;
+            self.in_memory.validate()?;
+            if let Some(scrubber) = &self.scrubber {
+                scrubber.validate()?;
+            }
+            if let Some(fault_injection) = &self.fault_injection {
+                fault_injection.validate()?;
+            }
             self.metrics
                 .as_ref()
                 .map_or(Ok(()), |metrics| metrics.validate())
@@ -924,7 +1643,10 @@ impl Validatable for NodeConfig {
 }
 
 pub mod tests {
-    use crate::configs::node::Node as NodeConfig;
+    use crate::configs::node::{
+        ConnectionPoolConfig, FailureDetectorConfig, HandoffConfig, HedgedReadConfig,
+        InMemoryConfig, LocalityConfig, Node as NodeConfig, RebuildConfig,
+    };
     use bob_access::AuthenticationType;
 
     use std::sync::Arc;
@@ -942,6 +1664,16 @@ pub mod tests {
             pearl: None,
             metrics: None,
             tls: None,
+            hedged_read: None,
+            locality: LocalityConfig::default(),
+            failure_detector: None,
+            handoff: None,
+            connection_pool: ConnectionPoolConfig::default(),
+            tracing: None,
+            rebuild: RebuildConfig::default(),
+            in_memory: InMemoryConfig::default(),
+            scrubber: None,
+            fault_injection: None,
             bind_ref: Arc::default(),
             disks_ref: Arc::default(),
             cleanup_interval: "1d".to_string(),
@@ -950,6 +1682,7 @@ pub mod tests {
             init_par_degree: 1,
             disk_access_par_degree: 1,
             count_interval: "10000ms".to_string(),
+            drain_timeout: "30sec".to_string(),
             http_api_port: NodeConfig::default_http_api_port(),
             http_api_address: NodeConfig::default_http_api_address(),
             bind_to_ip_address: None,
@@ -961,4 +1694,55 @@ pub mod tests {
             hostname_resolve_period_ms: NodeConfig::default_hostname_resolve_period_ms(),
         }
     }
+
+    pub fn hedged_read_config(delay: &str, latency_percentile: Option<f64>) -> HedgedReadConfig {
+        HedgedReadConfig {
+            delay: delay.to_string(),
+            latency_percentile,
+        }
+    }
+
+    pub fn locality_config(
+        rack: Option<&str>,
+        prefer_local_reads: bool,
+        spread_writes_across_racks: bool,
+    ) -> LocalityConfig {
+        LocalityConfig {
+            rack: rack.map(str::to_string),
+            prefer_local_reads,
+            spread_writes_across_racks,
+        }
+    }
+
+    pub fn connection_pool_config(
+        channels_per_node: usize,
+        max_concurrent_requests: Option<usize>,
+    ) -> ConnectionPoolConfig {
+        ConnectionPoolConfig {
+            channels_per_node,
+            max_concurrent_requests,
+        }
+    }
+
+    pub fn with_locality(mut config: NodeConfig, locality: LocalityConfig) -> NodeConfig {
+        config.locality = locality;
+        config
+    }
+
+    pub fn with_failure_detector(mut config: NodeConfig, max_failures: usize) -> NodeConfig {
+        config.failure_detector = Some(FailureDetectorConfig {
+            max_failures,
+            ..FailureDetectorConfig::default()
+        });
+        config
+    }
+
+    pub fn with_handoff(mut config: NodeConfig, path: &str, max_hints_per_node: usize) -> NodeConfig {
+        config.handoff = Some(HandoffConfig {
+            path: path.to_string(),
+            max_hints_per_node,
+            max_background_puts: HandoffConfig::default_max_background_puts(),
+        });
+        config
+    }
 }
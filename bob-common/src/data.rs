@@ -158,3 +158,26 @@ impl BobMeta {
         BobMeta { timestamp: 1 }
     }
 }
+
+/// Position in the vdisk stream: partitions are streamed in order of their start timestamps,
+/// records of the partition in order of key bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VDiskPosition {
+    pub partition: u64,
+    pub key: BobKey,
+}
+
+/// Newest version of the key in the partition sent in the vdisk stream.
+#[derive(Debug, Clone)]
+pub struct StreamedRecord {
+    pub position: VDiskPosition,
+    /// Data is empty for delete marker, meta holds its timestamp.
+    pub data: BobData,
+    pub deleted: bool,
+}
+
+impl StreamedRecord {
+    pub fn key(&self) -> BobKey {
+        self.position.key
+    }
+}
@@ -27,6 +27,10 @@ impl Error {
         self.ctx == Kind::DuplicateKey
     }
 
+    pub fn is_precondition_failed(&self) -> bool {
+        self.ctx == Kind::PreconditionFailed
+    }
+
     pub fn is_key_not_found(&self) -> bool {
         matches!(&self.ctx, Kind::KeyNotFound(_))
     }
@@ -63,10 +67,34 @@ impl Error {
         Self::new(Kind::Failed(cause.into()))
     }
 
+    pub fn transport(cause: impl Into<String>) -> Self {
+        Self::new(Kind::Transport(cause.into()))
+    }
+
     pub fn duplicate_key() -> Self {
         Self::new(Kind::DuplicateKey)
     }
 
+    pub fn precondition_failed() -> Self {
+        Self::new(Kind::PreconditionFailed)
+    }
+
+    pub fn condition_partially_applied(msg: impl Into<String>) -> Self {
+        Self::new(Kind::ConditionPartiallyApplied(msg.into()))
+    }
+
+    pub fn invalid_argument(msg: impl Into<String>) -> Self {
+        Self::new(Kind::InvalidArgument(msg.into()))
+    }
+
+    pub fn invalid_consistency_level(msg: impl Into<String>) -> Self {
+        Self::new(Kind::InvalidConsistencyLevel(msg.into()))
+    }
+
+    pub fn consistency_level_not_reached(msg: impl Into<String>) -> Self {
+        Self::new(Kind::ConsistencyLevelNotReached(msg.into()))
+    }
+
     pub fn vdisk_not_found(id: u32) -> Self {
         Self::new(Kind::VDiskNotFound(id))
     }
@@ -91,6 +119,14 @@ impl Error {
         Self::new(Kind::HolderTemporaryUnavailable)
     }
 
+    pub fn node_is_draining() -> Self {
+        Self::new(Kind::NodeIsDraining)
+    }
+
+    pub fn node_is_decommissioning() -> Self {
+        Self::new(Kind::NodeIsDecommissioning)
+    }
+
     pub fn request_failed_completely(local: &Error, alien: &Error) -> Self {
         let msg = format!("local error: {}\nalien error: {}", local, alien);
         let ctx = Kind::RequestFailedCompletely(msg);
@@ -128,6 +164,17 @@ impl From<Error> for Status {
         match &err.ctx {
             Kind::KeyNotFound(key) => Self::not_found(format!("KeyNotFound {}", key)),
             Kind::DuplicateKey => Self::already_exists("DuplicateKey"),
+            Kind::PreconditionFailed => Self::failed_precondition("PreconditionFailed"),
+            Kind::ConditionPartiallyApplied(msg) => {
+                Self::aborted(format!("ConditionPartiallyApplied {}", msg))
+            }
+            Kind::InvalidArgument(msg) => Self::invalid_argument(format!("InvalidArgument {}", msg)),
+            Kind::InvalidConsistencyLevel(msg) => {
+                Self::invalid_argument(format!("InvalidConsistencyLevel {}", msg))
+            }
+            Kind::ConsistencyLevelNotReached(msg) => {
+                Self::unavailable(format!("ConsistencyLevelNotReached {}", msg))
+            }
             Kind::Timeout => Self::deadline_exceeded("Timeout"),
             Kind::VDiskNotFound(id) => Self::not_found(format!("VDiskNotFound {}", id)),
             Kind::Storage(msg) => Self::internal(format!("Storage {}", msg)),
@@ -135,6 +182,7 @@ impl From<Error> for Status {
             Kind::DCIsNotAvailable => Status::internal("Disk Controller is not available"),
             Kind::PossibleDiskDisconnection => Self::internal("Possibly disk was disconnected"),
             Kind::Failed(msg) => Self::internal(format!("Failed {}", msg)),
+            Kind::Transport(msg) => Self::unavailable(format!("Transport {}", msg)),
             Kind::Internal => Self::internal("Internal"),
             Kind::PearlChangeState(msg) => Self::internal(format!("PearlChangeState {}", msg)),
             Kind::RequestFailedCompletely(msg) => Self::internal(format!(
@@ -146,6 +194,8 @@ impl From<Error> for Status {
             },
             Kind::Unauthorized => Self::unauthenticated("Unauthorized"),
             Kind::HolderTemporaryUnavailable => Self::unavailable("HolderTemporaryUnavailable"),
+            Kind::NodeIsDraining => Self::unavailable("NodeIsDraining"),
+            Kind::NodeIsDecommissioning => Self::unavailable("NodeIsDecommissioning"),
         }
     }
 }
@@ -161,16 +211,29 @@ impl From<Status> for Error {
                 "KeyNotFound" => parse_next(words, Self::key_not_found)
                     .unwrap_or_else(|| Self::failed(format!("Failed to parse key from {}", status.message()))),
                 "DuplicateKey" => Self::duplicate_key(),
+                "PreconditionFailed" => Self::precondition_failed(),
+                "ConditionPartiallyApplied" => {
+                    Self::condition_partially_applied(rest_words(words, length))
+                }
+                "InvalidArgument" => Self::invalid_argument(rest_words(words, length)),
+                "InvalidConsistencyLevel" => Self::invalid_consistency_level(rest_words(words, length)),
+                "ConsistencyLevelNotReached" => {
+                    Self::consistency_level_not_reached(rest_words(words, length))
+                }
                 "Timeout" => Self::timeout(),
                 "VDiskNotFound" => parse_next(words, Self::vdisk_not_found)
                     .unwrap_or_else(|| Self::failed(format!("Failed to parse vdisk_id from {}", status.message()))),
                 "Storage" => Self::storage(rest_words(words, length)),
                 "VDiskIsNotReady" => Self::vdisk_is_not_ready(),
                 "Failed" => Self::failed(rest_words(words, length)),
+                // Transport error forwarded by the node means the node itself answered, so it
+                // is not parsed back into `Transport`
                 "Internal" => Self::internal(),
                 "PearlChangeState" => Self::pearl_change_state(rest_words(words, length)),
                 "Unauthorized" => Self::unauthorized(),
                 "HolderTemporaryUnavailable" => Self::holder_temporary_unavailable(),
+                "NodeIsDraining" => Self::node_is_draining(),
+                "NodeIsDecommissioning" => Self::node_is_decommissioning(),
                 _ => Self::failed(format!("Can't parse status {:?} from {:?}, {}", name, status.code(), status.message())),
             },
         }
@@ -194,15 +257,23 @@ pub enum Kind {
     VDiskNotFound(VDiskId),
     Storage(String),
     DuplicateKey,
+    PreconditionFailed,
+    ConditionPartiallyApplied(String),
+    InvalidArgument(String),
+    InvalidConsistencyLevel(String),
+    ConsistencyLevelNotReached(String),
     KeyNotFound(BobKey),
     DCIsNotAvailable,
     PossibleDiskDisconnection,
     VDiskIsNotReady,
     Failed(String),
+    Transport(String),
     Internal,
     PearlChangeState(String),
     RequestFailedCompletely(String),
     DisksEventsLogger(String),
     Unauthorized,
     HolderTemporaryUnavailable,
+    NodeIsDraining,
+    NodeIsDecommissioning,
 }
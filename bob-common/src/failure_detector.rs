@@ -0,0 +1,179 @@
+use crate::{
+    configs::node::FailureDetectorConfig,
+    error::{Error, Kind},
+};
+use std::{
+    collections::VecDeque,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+const PING_INTERVALS_COUNT: usize = 1000;
+
+/// State of the circuit breaker of the remote node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    /// Requests are sent to the node
+    Closed,
+    /// Requests fail immediately without reaching the node
+    Open,
+    /// Single trial request is allowed to check if node recovered
+    HalfOpen,
+}
+
+/// Phi-accrual failure detector: suspicion level grows with the time passed since the last
+/// successful answer from the node, relative to the distribution of intervals between pings.
+/// Circuit is opened when suspicion exceeds threshold or when too many requests to the node
+/// failed in a row.
+#[derive(Debug)]
+pub struct FailureDetector {
+    phi_threshold: f64,
+    min_std_deviation: f64,
+    max_failures: usize,
+    open_duration: Duration,
+    state: Mutex<State>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    // Intervals between successful pings in seconds
+    ping_intervals: VecDeque<f64>,
+    last_ping: Option<Instant>,
+    // Last successful ping or request
+    last_success: Option<Instant>,
+    consecutive_failures: usize,
+    opened_at: Option<Instant>,
+    trial_started_at: Option<Instant>,
+}
+
+impl FailureDetector {
+    pub fn new(config: &FailureDetectorConfig) -> Self {
+        Self {
+            phi_threshold: config.phi_threshold(),
+            min_std_deviation: config.min_std_deviation().as_secs_f64(),
+            max_failures: config.max_failures(),
+            open_duration: config.open_duration(),
+            state: Mutex::new(State::default()),
+        }
+    }
+
+    pub fn record_ping(&self, at: Instant) {
+        let mut state = self.state.lock().expect("failure detector mutex");
+        if let Some(last_ping) = state.last_ping {
+            if state.ping_intervals.len() == PING_INTERVALS_COUNT {
+                state.ping_intervals.pop_front();
+            }
+            let interval = at.saturating_duration_since(last_ping).as_secs_f64();
+            state.ping_intervals.push_back(interval);
+        }
+        state.last_ping = Some(at);
+        state.last_success = state.last_success.max(Some(at));
+    }
+
+    /// Only transport errors and timeouts are counted as failures: any answer from the node,
+    /// even an error, means that node is alive.
+    pub fn record_request(&self, result: Result<(), &Error>, at: Instant) {
+        let mut state = self.state.lock().expect("failure detector mutex");
+        match result {
+            Err(e) if Self::is_node_failure(e) => {
+                state.consecutive_failures += 1;
+                if state.trial_started_at.is_some() || state.consecutive_failures >= self.max_failures {
+                    state.opened_at = Some(at);
+                    state.trial_started_at = None;
+                }
+            }
+            _ => {
+                state.last_success = state.last_success.max(Some(at));
+                state.consecutive_failures = 0;
+                state.opened_at = None;
+                state.trial_started_at = None;
+            }
+        }
+    }
+
+    fn is_node_failure(error: &Error) -> bool {
+        matches!(error.kind(), Kind::Timeout | Kind::Transport(_))
+    }
+
+    /// Suspicion level, 0 if there are not enough pings to estimate it.
+    pub fn phi(&self, now: Instant) -> f64 {
+        let state = self.state.lock().expect("failure detector mutex");
+        self.phi_locked(&state, now)
+    }
+
+    fn phi_locked(&self, state: &State, now: Instant) -> f64 {
+        let last_success = if let Some(last_success) = state.last_success {
+            last_success
+        } else {
+            return 0.0;
+        };
+        let count = state.ping_intervals.len();
+        if count == 0 {
+            return 0.0;
+        }
+        let mean = state.ping_intervals.iter().sum::<f64>() / count as f64;
+        let variance = state
+            .ping_intervals
+            .iter()
+            .map(|i| (i - mean) * (i - mean))
+            .sum::<f64>()
+            / count as f64;
+        let std_deviation = variance.sqrt().max(self.min_std_deviation);
+        let elapsed = now.saturating_duration_since(last_success).as_secs_f64();
+        // Logistic approximation of the normal distribution CDF
+        let y = (elapsed - mean) / std_deviation;
+        let e = (-y * (1.5976 + 0.070566 * y * y)).exp();
+        let phi = if elapsed > mean {
+            -(e / (1.0 + e)).log10()
+        } else {
+            -(1.0 - 1.0 / (1.0 + e)).log10()
+        };
+        // Infinity can't be serialized to json
+        phi.min(f64::MAX)
+    }
+
+    pub fn circuit_state(&self, now: Instant) -> CircuitState {
+        let state = self.state.lock().expect("failure detector mutex");
+        self.circuit_state_locked(&state, now)
+    }
+
+    fn circuit_state_locked(&self, state: &State, now: Instant) -> CircuitState {
+        if self.phi_locked(state, now) > self.phi_threshold {
+            CircuitState::Open
+        } else {
+            match state.opened_at {
+                Some(opened_at) if now < opened_at + self.open_duration => CircuitState::Open,
+                Some(_) => CircuitState::HalfOpen,
+                None => CircuitState::Closed,
+            }
+        }
+    }
+
+    /// Frees the trial slot of the request that ended without result, e.g. was cancelled, so
+    /// the next request can check the node without waiting for another `open_duration`.
+    pub fn release_trial(&self, started_at: Instant) {
+        let mut state = self.state.lock().expect("failure detector mutex");
+        if state.trial_started_at == Some(started_at) {
+            state.trial_started_at = None;
+        }
+    }
+
+    /// Returns false if the request must not be sent to the node. In half-open state only one
+    /// trial request is allowed per `open_duration`.
+    pub fn try_acquire(&self, now: Instant) -> bool {
+        let mut state = self.state.lock().expect("failure detector mutex");
+        match self.circuit_state_locked(&state, now) {
+            CircuitState::Closed => true,
+            CircuitState::Open => false,
+            CircuitState::HalfOpen => match state.trial_started_at {
+                Some(started_at) if now < started_at + self.open_duration => false,
+                _ => {
+                    state.trial_started_at = Some(now);
+                    true
+                }
+            },
+        }
+    }
+}
+
@@ -0,0 +1,112 @@
+#[cfg(test)]
+mod tests {
+    use crate::{
+        configs::node::FailureDetectorConfig,
+        error::Error,
+        failure_detector::{CircuitState, FailureDetector},
+    };
+    use std::time::{Duration, Instant};
+
+    fn detector() -> FailureDetector {
+        FailureDetector::new(&FailureDetectorConfig::default())
+    }
+
+    fn ping_every_second(detector: &FailureDetector, start: Instant, count: u64) -> Instant {
+        let mut last = start;
+        for i in 0..count {
+            last = start + Duration::from_secs(i);
+            detector.record_ping(last);
+        }
+        last
+    }
+
+    #[test]
+    fn phi_grows_without_pings() {
+        let detector = detector();
+        let start = Instant::now();
+        assert_eq!(0.0, detector.phi(start));
+        let last = ping_every_second(&detector, start, 10);
+
+        assert!(detector.phi(last + Duration::from_millis(500)) < 1.0);
+        assert_eq!(CircuitState::Closed, detector.circuit_state(last + Duration::from_secs(1)));
+        assert!(detector.phi(last + Duration::from_secs(5)) > 8.0);
+        assert_eq!(CircuitState::Open, detector.circuit_state(last + Duration::from_secs(5)));
+    }
+
+    #[test]
+    fn successful_request_resets_suspicion() {
+        let detector = detector();
+        let start = Instant::now();
+        let last = ping_every_second(&detector, start, 10);
+        let now = last + Duration::from_secs(5);
+
+        detector.record_request(Err(&Error::key_not_found(1.into())), now);
+
+        assert_eq!(CircuitState::Closed, detector.circuit_state(now));
+    }
+
+    #[test]
+    fn circuit_opens_after_consecutive_failures() {
+        let detector = detector();
+        let now = Instant::now();
+        for _ in 0..4 {
+            detector.record_request(Err(&Error::timeout()), now);
+        }
+        assert!(detector.try_acquire(now));
+        detector.record_request(Err(&Error::timeout()), now);
+        assert!(!detector.try_acquire(now));
+
+        let after_open = now + Duration::from_secs(5);
+        assert_eq!(CircuitState::HalfOpen, detector.circuit_state(after_open));
+        assert!(detector.try_acquire(after_open));
+        assert!(!detector.try_acquire(after_open));
+        detector.record_request(Ok(()), after_open);
+        assert_eq!(CircuitState::Closed, detector.circuit_state(after_open));
+    }
+
+    #[test]
+    fn failed_trial_request_reopens_circuit() {
+        let detector = detector();
+        let now = Instant::now();
+        for _ in 0..5 {
+            detector.record_request(Err(&Error::timeout()), now);
+        }
+        let after_open = now + Duration::from_secs(5);
+        assert!(detector.try_acquire(after_open));
+        detector.record_request(Err(&Error::timeout()), after_open);
+
+        assert_eq!(CircuitState::Open, detector.circuit_state(after_open + Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn only_transport_errors_and_timeouts_are_failures() {
+        let detector = detector();
+        let now = Instant::now();
+        for _ in 0..5 {
+            detector.record_request(Err(&Error::failed("answered by node")), now);
+        }
+        assert_eq!(CircuitState::Closed, detector.circuit_state(now));
+
+        for _ in 0..5 {
+            detector.record_request(Err(&Error::transport("connection refused")), now);
+        }
+        assert_eq!(CircuitState::Open, detector.circuit_state(now));
+    }
+
+    #[test]
+    fn released_trial_allows_next_trial() {
+        let detector = detector();
+        let now = Instant::now();
+        for _ in 0..5 {
+            detector.record_request(Err(&Error::timeout()), now);
+        }
+        let after_open = now + Duration::from_secs(5);
+        assert!(detector.try_acquire(after_open));
+        detector.release_trial(after_open);
+
+        let next = after_open + Duration::from_millis(1);
+        assert_eq!(CircuitState::HalfOpen, detector.circuit_state(next));
+        assert!(detector.try_acquire(next));
+        assert!(!detector.try_acquire(next));
+    }
+}
@@ -5,11 +5,15 @@ pub mod core_types;
 pub mod data;
 pub mod operation_options;
 pub mod error;
+pub mod failure_detector;
+mod failure_detector_tests;
 pub mod mapper;
 pub mod metrics;
 pub mod node;
 pub mod interval_logger;
 pub mod stopwatch;
+pub mod telemetry;
+mod telemetry_tests;
 
 #[macro_use]
 extern crate serde_derive;
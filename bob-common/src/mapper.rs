@@ -32,6 +32,8 @@ pub struct Virtual {
     disks: Vec<DiskPath>,
     vdisks: VDisksMap,
     nodes: Vec<Node>,
+    node_racks: HashMap<NodeName, String>,
+    local_rack: Option<String>,
     distribution_func: DistributionFunc,
     support_nodes_offset: AtomicUsize,
 }
@@ -39,7 +41,7 @@ pub struct Virtual {
 impl Virtual {
     /// Creates new instance of the Virtual disk mapper
     pub fn new(config: &NodeConfig, cluster: &ClusterConfig) -> Self {
-        let nodes = Self::prepare_nodes(cluster);
+        let nodes = Self::prepare_nodes(config, cluster);
         let vdisks = Self::prepare_vdisks_map(cluster, nodes.as_slice());
         let local_node_name = config.name().into();
         let local_node_address = nodes
@@ -49,28 +51,54 @@ impl Virtual {
             .address()
             .to_string();
         let disks = config.disks().lock().expect("mutex").clone();
+        let node_racks = Self::prepare_node_racks(cluster);
+        let local_rack = config
+            .locality()
+            .rack()
+            .map(str::to_string)
+            .or_else(|| node_racks.get(&local_node_name).cloned());
         Self {
             local_node_name,
             local_node_address,
             disks,
             vdisks,
             nodes,
+            node_racks,
+            local_rack,
             distribution_func: cluster.distribution_func(),
             support_nodes_offset: AtomicUsize::new(0),
         }
     }
 
-    fn prepare_nodes(cluster: &ClusterConfig) -> Vec<Node> {
+    fn prepare_nodes(config: &NodeConfig, cluster: &ClusterConfig) -> Vec<Node> {
         return cluster
             .nodes()
             .iter()
             .enumerate()
             .map(|(i, conf)| {
                 let index = i.try_into().expect("usize to u16");
-                Node::new(conf.name().into(), conf.address().to_owned(), index)
+                Node::new(
+                    conf.name().into(),
+                    conf.address().to_owned(),
+                    index,
+                    config.failure_detector(),
+                )
             })
             .collect();
     }
+
+    fn prepare_node_racks(cluster: &ClusterConfig) -> HashMap<NodeName, String> {
+        cluster
+            .racks()
+            .iter()
+            .flat_map(|rack| {
+                rack.nodes()
+                    .iter()
+                    .map(move |node| (node.as_str().into(), rack.name().to_string()))
+            })
+            .collect()
+    }
+
     fn prepare_vdisks_map(cluster: &ClusterConfig, nodes: &[Node]) -> VDisksMap {
         let mut vdisks = VDisksMap::new();
         let vdisks_replicas = cluster.collect_vdisk_replicas().unwrap();
@@ -121,6 +149,36 @@ impl Virtual {
         &self.nodes
     }
 
+    /// Rack of the local node, if it is known from node or cluster config.
+    pub fn local_rack(&self) -> Option<&str> {
+        self.local_rack.as_deref()
+    }
+
+    pub fn node_rack(&self, node_name: &NodeName) -> Option<&str> {
+        if *node_name == self.local_node_name {
+            self.local_rack()
+        } else {
+            self.node_racks.get(node_name).map(String::as_str)
+        }
+    }
+
+    /// Node without rack is considered to be the only member of its own rack.
+    pub fn is_same_rack(&self, node_name: &NodeName) -> bool {
+        match (self.local_rack(), self.node_rack(node_name)) {
+            (Some(local), Some(rack)) => local == rack,
+            _ => *node_name == self.local_node_name,
+        }
+    }
+
+    /// Number of distinct racks among `nodes`.
+    pub fn racks_count(&self, nodes: &[Node]) -> usize {
+        nodes
+            .iter()
+            .map(|node| self.node_rack(node.name()).unwrap_or_else(|| node.name().as_str()))
+            .collect::<HashSet<_>>()
+            .len()
+    }
+
     pub fn distribution_func(&self) -> DistributionFunc {
         self.distribution_func
     }
@@ -131,7 +189,7 @@ impl Virtual {
     }
 
     /// Skips nodes according to `offset` value and counts available nodes at the same time. 
-    /// Available nodes: connected nodes and not in `target_nodes` list.
+    /// Available nodes: connected nodes, not suspected to be failed and not in `target_nodes` list.
     /// If stops before the collection ends, then return value contains the node index for the specified offset.
     /// If it skips the whole collection of nodes, then return value contains the number of available nodes.
     fn try_find_support_node_offset_at_one_pass(
@@ -144,7 +202,9 @@ impl Virtual {
         let mut avail = 0;
         for i in 0..nodes.len() {
             let node = &nodes[i];
-            if node.connection_available() && target_nodes.iter().all(|n| n.index() != node.index())
+            if node.connection_available()
+                && !node.is_suspected()
+                && target_nodes.iter().all(|n| n.index() != node.index())
             {
                 if avail == offset {
                     return SupportIndexResult {
@@ -159,7 +219,7 @@ impl Virtual {
     }
 
     /// Look for an offset respecting the uniform distribution for available nodes.
-    /// Available nodes: connected nodes and not in `target_nodes` list.
+    /// Available nodes: connected nodes, not suspected to be failed and not in `target_nodes` list.
     fn find_support_node_offset(nodes: &[Node], target_nodes: &[Node], offset: usize) -> usize {
         if target_nodes.len() >= nodes.len() {
             // target_nodes is equal to nodes => cannot find the proper offset
@@ -219,7 +279,9 @@ impl Virtual {
         let len = self.nodes.len();
         for i in 0..len {
             let node = &self.nodes[(i + starting_index) % len];
-            if node.connection_available() && target_nodes.iter().all(|n| n.index() != node.index())
+            if node.connection_available()
+                && !node.is_suspected()
+                && target_nodes.iter().all(|n| n.index() != node.index())
             {
                 support_nodes.push(node);
                 if support_nodes.len() >= count {
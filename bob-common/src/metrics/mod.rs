@@ -33,6 +33,16 @@ pub const GRINDER_GET_COUNTER: &str = "cluster_grinder.get_count";
 pub const GRINDER_GET_ERROR_COUNT_COUNTER: &str = "cluster_grinder.get_error_count";
 /// Measures processing time of the GET request
 pub const GRINDER_GET_TIMER: &str = "cluster_grinder.get_timer";
+/// Counts number of hedged GET requests sent to additional replicas
+pub const GRINDER_GET_HEDGED_COUNTER: &str = "cluster_grinder.get_hedged_count";
+/// Counts number of GET requests answered by hedged request
+pub const GRINDER_GET_HEDGED_WON_COUNTER: &str = "cluster_grinder.get_hedged_won_count";
+/// Counts number of GET requests answered by remote replica in the same rack
+pub const GRINDER_GET_SAME_RACK_COUNTER: &str = "cluster_grinder.get_same_rack_count";
+/// Counts number of GET requests answered by remote replica in another rack
+pub const GRINDER_GET_OTHER_RACK_COUNTER: &str = "cluster_grinder.get_other_rack_count";
+/// Counts number of PUT requests that waited for additional replicas to spread data across racks
+pub const GRINDER_PUT_RACK_SPREAD_WAIT_COUNTER: &str = "cluster_grinder.put_rack_spread_wait_count";
 
 /// Counts number of EXIST requests, processed by Grinder
 pub const GRINDER_EXIST_COUNTER: &str = "cluster_grinder.exist_count";
@@ -86,6 +96,17 @@ pub const CLIENT_DELETE_TIMER: &str = "client.delete_timer";
 
 /// Observes number of connected nodes
 pub const AVAILABLE_NODES_COUNT: &str = "link_manager.nodes_number";
+/// Observes number of nodes with open circuit breaker
+pub const SUSPECTED_NODES_COUNT: &str = "link_manager.suspected_nodes_number";
+/// Counts number of requests rejected by open circuit breaker without reaching the node
+pub const CIRCUIT_OPEN_REJECTED_COUNTER: &str = "link_manager.circuit_open_rejected_count";
+
+/// Prefix of per-node gauges observing count of pending hints in handoff queue
+pub const HANDOFF_QUEUE_DEPTH_PREFIX: &str = "handoff.queue_depth";
+/// Counts number of hints replayed to the target nodes
+pub const HANDOFF_REPLAYED_COUNTER: &str = "handoff.replayed_count";
+/// Counts number of hints dropped because queue is full or can't be written
+pub const HANDOFF_DROPPED_COUNTER: &str = "handoff.dropped_count";
 
 /// Observes if bob has started already
 pub const BACKEND_STATE: &str = "backend.backend_state";
@@ -149,6 +170,7 @@ struct PrefixedNames {
     delete_count: String,
     delete_timer: String,
     delete_error_count: String,
+    pool: String,
 }
 
 impl PrefixedNames {
@@ -166,6 +188,7 @@ impl PrefixedNames {
             delete_count: format!("{}.delete_count", prefix),
             delete_timer: format!("{}.delete_timer", prefix),
             delete_error_count: format!("{}.delete_error_count", prefix),
+            pool: format!("{}.pool", prefix),
         }
     }
 }
@@ -239,6 +262,63 @@ impl BobClient {
     pub(crate) fn delete_error_count(&self) {
         counter!(self.prefixed_names.delete_error_count.clone(), 1);
     }
+
+    /// Metrics of the channel pool to the given node
+    pub(crate) fn channel_pool(&self, node_name: &str) -> ChannelPool {
+        ChannelPool::new(&format!("{}.{}", self.prefixed_names.pool, node_name))
+    }
+}
+
+/// Structure contains statistics of the pool of channels to one remote node
+#[derive(Debug, Clone)]
+pub struct ChannelPool {
+    prefixed_names: Arc<ChannelPoolNames>,
+}
+
+#[derive(Debug)]
+struct ChannelPoolNames {
+    channels: String,
+    in_flight: String,
+    reconnect_count: String,
+    limit_wait_count: String,
+    limit_timeout_count: String,
+}
+
+impl ChannelPool {
+    pub(crate) fn new(prefix: &str) -> Self {
+        let prefixed_names = ChannelPoolNames {
+            channels: format!("{}.channels", prefix),
+            in_flight: format!("{}.in_flight", prefix),
+            reconnect_count: format!("{}.reconnect_count", prefix),
+            limit_wait_count: format!("{}.limit_wait_count", prefix),
+            limit_timeout_count: format!("{}.limit_timeout_count", prefix),
+        };
+        Self {
+            prefixed_names: Arc::new(prefixed_names),
+        }
+    }
+
+    pub(crate) fn channels(&self, count: usize) {
+        gauge!(self.prefixed_names.channels.clone(), count as f64);
+    }
+
+    pub(crate) fn in_flight(&self, count: usize) {
+        gauge!(self.prefixed_names.in_flight.clone(), count as f64);
+    }
+
+    pub(crate) fn reconnect_count(&self) {
+        counter!(self.prefixed_names.reconnect_count.clone(), 1);
+    }
+
+    /// Request waits because the node has too many requests in progress
+    pub(crate) fn limit_wait_count(&self) {
+        counter!(self.prefixed_names.limit_wait_count.clone(), 1);
+    }
+
+    /// Request failed waiting because the node has too many requests in progress
+    pub(crate) fn limit_timeout_count(&self) {
+        counter!(self.prefixed_names.limit_timeout_count.clone(), 1);
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -304,6 +384,11 @@ pub async fn init_counters(
 
 fn init_grinder() {
     register_counter!(GRINDER_GET_COUNTER);
+    register_counter!(GRINDER_GET_HEDGED_COUNTER);
+    register_counter!(GRINDER_GET_HEDGED_WON_COUNTER);
+    register_counter!(GRINDER_GET_SAME_RACK_COUNTER);
+    register_counter!(GRINDER_GET_OTHER_RACK_COUNTER);
+    register_counter!(GRINDER_PUT_RACK_SPREAD_WAIT_COUNTER);
     register_counter!(GRINDER_PUT_COUNTER);
     register_counter!(GRINDER_EXIST_COUNTER);
     register_counter!(GRINDER_DELETE_COUNTER);
@@ -339,6 +424,10 @@ fn init_backend() {
 
 fn init_link_manager() {
     register_gauge!(AVAILABLE_NODES_COUNT);
+    register_gauge!(SUSPECTED_NODES_COUNT);
+    register_counter!(CIRCUIT_OPEN_REJECTED_COUNTER);
+    register_counter!(HANDOFF_REPLAYED_COUNTER);
+    register_counter!(HANDOFF_DROPPED_COUNTER);
 }
 
 async fn install_global(node_config: &NodeConfig, local_address: &str) -> SharedMetricsSnapshot {
@@ -1,7 +1,10 @@
 use super::name_types::{Name, NameMarker};
 use crate::{
     bob_client::{BobClient, Factory},
+    configs::node::FailureDetectorConfig,
     data::BobData,
+    error::Error,
+    failure_detector::{CircuitState, FailureDetector},
 };
 use http::Uri;
 use std::{
@@ -11,6 +14,7 @@ use std::{
         atomic::{AtomicBool, Ordering},
         Arc, RwLock,
     },
+    time::Instant,
 };
 
 /// Marker type for [`NodeName`]
@@ -40,6 +44,30 @@ struct NodeInner {
 
     conn: RwLock<Option<Arc<BobClient>>>,
     conn_available: AtomicBool,
+    failure_detector: Option<FailureDetector>,
+}
+
+/// Request allowed by the circuit of the node.
+#[derive(Debug)]
+pub struct RequestPermit<'a> {
+    failure_detector: Option<&'a FailureDetector>,
+    acquired_at: Instant,
+}
+
+impl RequestPermit<'_> {
+    pub fn record(mut self, result: Result<(), &Error>) {
+        if let Some(fd) = self.failure_detector.take() {
+            fd.record_request(result, Instant::now());
+        }
+    }
+}
+
+impl Drop for RequestPermit<'_> {
+    fn drop(&mut self) {
+        if let Some(fd) = self.failure_detector {
+            fd.release_trial(self.acquired_at);
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -50,7 +78,12 @@ pub struct Output<T> {
 
 
 impl Node {
-    pub fn new(name: NodeName, address: String, index: u16) -> Self {
+    pub fn new(
+        name: NodeName,
+        address: String,
+        index: u16,
+        failure_detector: Option<&FailureDetectorConfig>,
+    ) -> Self {
         Self {
             inner: Arc::new(NodeInner {
                 index,
@@ -58,6 +91,7 @@ impl Node {
                 address,
                 conn: RwLock::new(None),
                 conn_available: AtomicBool::new(false),
+                failure_detector: failure_detector.map(FailureDetector::new),
             })
         }
     }
@@ -107,6 +141,46 @@ impl Node {
         self.inner.conn_available.load(Ordering::Acquire)
     }
 
+    /// Suspicion level of the node, `None` if failure detector is disabled.
+    pub fn suspicion(&self) -> Option<f64> {
+        self.inner
+            .failure_detector
+            .as_ref()
+            .map(|fd| fd.phi(Instant::now()))
+    }
+
+    pub fn circuit_state(&self) -> Option<CircuitState> {
+        self.inner
+            .failure_detector
+            .as_ref()
+            .map(|fd| fd.circuit_state(Instant::now()))
+    }
+
+    /// Node is suspected to be failed if its circuit is open, requests to it must be skipped.
+    pub fn is_suspected(&self) -> bool {
+        self.circuit_state() == Some(CircuitState::Open)
+    }
+
+    /// Returns `None` if circuit of the node doesn't allow request now. Result of the request
+    /// must be recorded with the returned permit, dropped permit frees the trial slot of the
+    /// half-open circuit.
+    pub fn try_acquire_request(&self) -> Option<RequestPermit<'_>> {
+        let acquired_at = Instant::now();
+        match &self.inner.failure_detector {
+            Some(fd) if !fd.try_acquire(acquired_at) => None,
+            failure_detector => Some(RequestPermit {
+                failure_detector: failure_detector.as_ref(),
+                acquired_at,
+            }),
+        }
+    }
+
+    pub fn record_request_result(&self, result: Result<(), &Error>) {
+        if let Some(fd) = &self.inner.failure_detector {
+            fd.record_request(result, Instant::now());
+        }
+    }
+
     pub async fn check(&self, client_factory: &Factory) -> Result<(), String> {
         if let Some(conn) = self.get_connection() {
             self.ping(conn.as_ref()).await
@@ -126,6 +200,9 @@ impl Node {
             Err(format!("{:?}", e))
         } else {
             debug!("All good with pinging node {:?}", self);
+            if let Some(fd) = &self.inner.failure_detector {
+                fd.record_ping(Instant::now());
+            }
             Ok(())
         }
     }
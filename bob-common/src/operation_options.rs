@@ -1,26 +1,178 @@
 use crate::{
+    error::Error,
     node::NodeName,
 };
-use bob_grpc::{DeleteOptions, GetOptions, GetSource, PutOptions};
+use bob_grpc::{ConsistencyLevel, DeleteOptions, GetOptions, GetSource, PutOptions, WriteCondition};
+use std::str::FromStr;
 
-#[derive(Debug, Clone)]
+/// Condition checked against the actual record on a replica before put or delete is applied.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BobWriteCondition {
+    /// Record must be absent or deleted.
+    IfAbsent,
+    /// Record must exist.
+    IfExists,
+    /// Record must exist and have one of these timestamps.
+    IfTimestampMatches(Vec<u64>),
+    /// Record must be absent or have timestamp less than this one.
+    IfNewerThan(u64),
+}
+
+/// Number of vdisk replicas that must acknowledge operation, quorum is the majority of replicas
+/// of the key. Unlike node quorum used when level is not set, records written to aliens are not
+/// counted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BobConsistencyLevel {
+    One,
+    Quorum,
+    All,
+}
+
+#[derive(Debug, Clone, Default)]
 pub struct BobPutOptions {
     force_node: bool,
     overwrite: bool,
-    remote_nodes: Vec<NodeName>
+    remote_nodes: Vec<NodeName>,
+    condition: Option<BobWriteCondition>,
+    consistency: Option<BobConsistencyLevel>,
 }
 
 #[derive(Debug, Clone)]
 pub struct BobGetOptions {
     force_node: bool,
     get_source: GetSource,
+    consistency: Option<BobConsistencyLevel>,
 }
 
-#[derive(Debug, Clone)]
+impl Default for BobGetOptions {
+    fn default() -> Self {
+        BobGetOptions {
+            force_node: false,
+            get_source: GetSource::All,
+            consistency: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
 pub struct BobDeleteOptions {
     force_node: bool,
     is_alien: bool,
-    force_alien_nodes: Vec<NodeName>
+    force_alien_nodes: Vec<NodeName>,
+    condition: Option<BobWriteCondition>,
+    consistency: Option<BobConsistencyLevel>,
+}
+
+impl BobWriteCondition {
+    /// Checks condition against timestamp of the actual record, `None` means that record is
+    /// absent or deleted.
+    /// # Errors
+    /// Returns precondition failed error if condition doesn't hold.
+    pub fn check(&self, actual_timestamp: Option<u64>) -> Result<(), Error> {
+        let holds = match self {
+            Self::IfAbsent => actual_timestamp.is_none(),
+            Self::IfExists => actual_timestamp.is_some(),
+            Self::IfTimestampMatches(timestamps) => {
+                matches!(actual_timestamp, Some(ts) if timestamps.contains(&ts))
+            }
+            Self::IfNewerThan(timestamp) => !matches!(actual_timestamp, Some(ts) if ts >= *timestamp),
+        };
+        if holds {
+            Ok(())
+        } else {
+            Err(Error::precondition_failed())
+        }
+    }
+
+    fn from_grpc(condition: i32, timestamps: Vec<u64>) -> Result<Option<Self>, Error> {
+        match WriteCondition::from_i32(condition) {
+            Some(WriteCondition::None) => Ok(None),
+            Some(WriteCondition::IfAbsent) => Ok(Some(Self::IfAbsent)),
+            Some(WriteCondition::IfExists) => Ok(Some(Self::IfExists)),
+            Some(WriteCondition::IfTimestampMatches) => {
+                Ok(Some(Self::IfTimestampMatches(timestamps)))
+            }
+            Some(WriteCondition::IfNewerThan) => match timestamps[..] {
+                [timestamp] => Ok(Some(Self::IfNewerThan(timestamp))),
+                _ => Err(Error::invalid_argument(format!(
+                    "IF_NEWER_THAN requires one timestamp, got {}",
+                    timestamps.len()
+                ))),
+            },
+            None => Err(Error::invalid_argument(format!(
+                "unknown write condition {}",
+                condition
+            ))),
+        }
+    }
+
+    fn to_grpc(condition: Option<&Self>) -> (i32, Vec<u64>) {
+        let (condition, timestamps) = match condition {
+            None => (WriteCondition::None, vec![]),
+            Some(Self::IfAbsent) => (WriteCondition::IfAbsent, vec![]),
+            Some(Self::IfExists) => (WriteCondition::IfExists, vec![]),
+            Some(Self::IfTimestampMatches(ts)) => (WriteCondition::IfTimestampMatches, ts.clone()),
+            Some(Self::IfNewerThan(ts)) => (WriteCondition::IfNewerThan, vec![*ts]),
+        };
+        (condition as i32, timestamps)
+    }
+}
+
+impl BobConsistencyLevel {
+    /// Returns number of replicas required by level for vdisk with `replicas` replicas.
+    /// # Errors
+    /// Returns error if vdisk has less replicas than level requires.
+    pub fn required_replicas(&self, replicas: usize) -> Result<usize, Error> {
+        let required = match self {
+            Self::One => 1,
+            Self::Quorum => replicas / 2 + 1,
+            Self::All => replicas,
+        };
+        if required == 0 || required > replicas {
+            Err(Error::invalid_consistency_level(format!(
+                "{:?} requires {} replicas, but vdisk has {}",
+                self, required, replicas
+            )))
+        } else {
+            Ok(required)
+        }
+    }
+
+    fn from_grpc(level: i32) -> Result<Option<Self>, Error> {
+        match ConsistencyLevel::from_i32(level) {
+            Some(ConsistencyLevel::Default) => Ok(None),
+            Some(ConsistencyLevel::One) => Ok(Some(Self::One)),
+            Some(ConsistencyLevel::Quorum) => Ok(Some(Self::Quorum)),
+            Some(ConsistencyLevel::All) => Ok(Some(Self::All)),
+            None => Err(Error::invalid_argument(format!(
+                "unknown consistency level {}",
+                level
+            ))),
+        }
+    }
+
+    fn to_grpc(level: Option<Self>) -> i32 {
+        let level = match level {
+            None => ConsistencyLevel::Default,
+            Some(Self::One) => ConsistencyLevel::One,
+            Some(Self::Quorum) => ConsistencyLevel::Quorum,
+            Some(Self::All) => ConsistencyLevel::All,
+        };
+        level as i32
+    }
+}
+
+impl FromStr for BobConsistencyLevel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "one" => Ok(Self::One),
+            "quorum" => Ok(Self::Quorum),
+            "all" => Ok(Self::All),
+            _ => Err(format!("unknown consistency level: {}, expected one, quorum or all", s)),
+        }
+    }
 }
 
 impl BobPutOptions {
@@ -29,6 +181,8 @@ impl BobPutOptions {
             remote_nodes: vec![],
             force_node: true,
             overwrite: false,
+            condition: None,
+            consistency: None,
         }
     }
 
@@ -37,33 +191,57 @@ impl BobPutOptions {
             remote_nodes,
             force_node: true,
             overwrite: false,
+            condition: None,
+            consistency: None,
         }
     }
 
-    pub fn from_grpc(options: Option<PutOptions>) -> Self {
+    /// # Errors
+    /// Returns invalid argument error if write condition or consistency level is unknown.
+    pub fn from_grpc(options: Option<PutOptions>) -> Result<Self, Error> {
         if let Some(vopts) = options {
-            BobPutOptions {
+            Ok(BobPutOptions {
                 force_node: vopts.force_node,
                 overwrite: vopts.overwrite,
-                remote_nodes: vopts.remote_nodes.iter().map(|nn| NodeName::from(nn)).collect()
-            }
+                remote_nodes: vopts.remote_nodes.iter().map(NodeName::from).collect(),
+                condition: BobWriteCondition::from_grpc(vopts.condition, vopts.condition_timestamps)?,
+                consistency: BobConsistencyLevel::from_grpc(vopts.consistency)?,
+            })
         } else {
-            BobPutOptions {
-                force_node: false,
-                overwrite: false,
-                remote_nodes: vec![]
-            }
+            Ok(BobPutOptions::default())
         }
     }
 
     pub fn to_grpc(&self) -> PutOptions {
+        let (condition, condition_timestamps) = BobWriteCondition::to_grpc(self.condition.as_ref());
         PutOptions { 
             remote_nodes: self.remote_nodes.iter().map(|nn| nn.to_string()).collect(), 
             force_node: self.force_node, 
-            overwrite: self.overwrite 
+            overwrite: self.overwrite,
+            condition,
+            condition_timestamps,
+            consistency: BobConsistencyLevel::to_grpc(self.consistency),
         }
     }
 
+    pub fn with_condition(mut self, condition: Option<BobWriteCondition>) -> Self {
+        self.condition = condition;
+        self
+    }
+
+    pub fn condition(&self) -> Option<BobWriteCondition> {
+        self.condition.clone()
+    }
+
+    pub fn with_consistency(mut self, consistency: Option<BobConsistencyLevel>) -> Self {
+        self.consistency = consistency;
+        self
+    }
+
+    pub fn consistency(&self) -> Option<BobConsistencyLevel> {
+        self.consistency
+    }
+
     pub fn force_node(&self) -> bool {
         self.force_node
     }
@@ -86,6 +264,7 @@ impl BobGetOptions {
         BobGetOptions {
             force_node: true,
             get_source: GetSource::Normal,
+            consistency: None,
         }
     }
 
@@ -93,6 +272,7 @@ impl BobGetOptions {
         BobGetOptions {
             force_node: true,
             get_source: GetSource::Alien,
+            consistency: None,
         }
     }
 
@@ -100,31 +280,42 @@ impl BobGetOptions {
         BobGetOptions {
             force_node: true,
             get_source: GetSource::All,
+            consistency: None,
         }
     }
 
 
-    pub fn from_grpc(options: Option<GetOptions>) -> Self {
+    /// # Errors
+    /// Returns invalid argument error if consistency level is unknown.
+    pub fn from_grpc(options: Option<GetOptions>) -> Result<Self, Error> {
         if let Some(vopts) = options {
-            BobGetOptions {
+            Ok(BobGetOptions {
                 force_node: vopts.force_node,
-                get_source: GetSource::from(vopts.source)
-            }
+                get_source: GetSource::from(vopts.source),
+                consistency: BobConsistencyLevel::from_grpc(vopts.consistency)?,
+            })
         } else {
-            BobGetOptions {
-                force_node: false,
-                get_source: GetSource::All
-            }
+            Ok(BobGetOptions::default())
         }
     }
 
     pub fn to_grpc(&self) -> GetOptions {
         GetOptions { 
             force_node: self.force_node, 
-            source: self.get_source.into()
+            source: self.get_source.into(),
+            consistency: BobConsistencyLevel::to_grpc(self.consistency),
         }
     }
 
+    pub fn with_consistency(mut self, consistency: Option<BobConsistencyLevel>) -> Self {
+        self.consistency = consistency;
+        self
+    }
+
+    pub fn consistency(&self) -> Option<BobConsistencyLevel> {
+        self.consistency
+    }
+
     pub fn force_node(&self) -> bool {
         self.force_node
     }
@@ -148,7 +339,9 @@ impl BobDeleteOptions {
         BobDeleteOptions {
             force_node: true,
             is_alien: false,
-            force_alien_nodes: vec![]
+            force_alien_nodes: vec![],
+            condition: None,
+            consistency: None,
         }
     }
 
@@ -156,34 +349,58 @@ impl BobDeleteOptions {
         BobDeleteOptions { 
             force_node: true, 
             is_alien: true, 
-            force_alien_nodes: force_alien_nodes
+            force_alien_nodes,
+            condition: None,
+            consistency: None,
         }
     }
 
-    pub fn from_grpc(options: Option<DeleteOptions>) -> Self {
+    /// # Errors
+    /// Returns invalid argument error if write condition or consistency level is unknown.
+    pub fn from_grpc(options: Option<DeleteOptions>) -> Result<Self, Error> {
         if let Some(vopts) = options {
-            BobDeleteOptions {
+            Ok(BobDeleteOptions {
                 force_node: vopts.force_node,
                 is_alien: vopts.is_alien,
-                force_alien_nodes: vopts.force_alien_nodes.iter().map(|nn| NodeName::from(nn)).collect()
-            }
+                force_alien_nodes: vopts.force_alien_nodes.iter().map(NodeName::from).collect(),
+                condition: BobWriteCondition::from_grpc(vopts.condition, vopts.condition_timestamps)?,
+                consistency: BobConsistencyLevel::from_grpc(vopts.consistency)?,
+            })
         } else {
-            BobDeleteOptions {
-                force_node: false,
-                is_alien: false,
-                force_alien_nodes: vec![]
-            }
+            Ok(BobDeleteOptions::default())
         }
     }
 
     pub fn to_grpc(&self) -> DeleteOptions {
+        let (condition, condition_timestamps) = BobWriteCondition::to_grpc(self.condition.as_ref());
         DeleteOptions { 
             force_alien_nodes: self.force_alien_nodes.iter().map(|nn| nn.to_string()).collect(), 
             force_node: self.force_node, 
-            is_alien: self.is_alien 
+            is_alien: self.is_alien,
+            condition,
+            condition_timestamps,
+            consistency: BobConsistencyLevel::to_grpc(self.consistency),
         }
     }
 
+    pub fn with_condition(mut self, condition: Option<BobWriteCondition>) -> Self {
+        self.condition = condition;
+        self
+    }
+
+    pub fn condition(&self) -> Option<BobWriteCondition> {
+        self.condition.clone()
+    }
+
+    pub fn with_consistency(mut self, consistency: Option<BobConsistencyLevel>) -> Self {
+        self.consistency = consistency;
+        self
+    }
+
+    pub fn consistency(&self) -> Option<BobConsistencyLevel> {
+        self.consistency
+    }
+
     pub fn force_node(&self) -> bool {
         self.force_node
     }
@@ -199,4 +416,4 @@ impl BobDeleteOptions {
     pub fn is_force_delete(&self, node_name: &NodeName) -> bool {
         self.force_alien_nodes.iter().any(|x| x == node_name)
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,149 @@
+use crate::configs::node::{TracingConfig, TracingExporter};
+use http::HeaderMap;
+use opentelemetry::{
+    global,
+    propagation::{Extractor, Injector, TextMapPropagator},
+    runtime,
+    sdk::{
+        export::trace::stdout,
+        propagation::TraceContextPropagator,
+        trace::{self, Sampler, TracerProvider},
+        Resource,
+    },
+    trace::TracerProvider as _,
+    Context, KeyValue,
+};
+use opentelemetry_otlp::WithExportConfig;
+use std::fs::OpenOptions;
+use tonic::metadata::{KeyRef, MetadataKey, MetadataMap, MetadataValue};
+use tracing::Span;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::{layer::SubscriberExt, Registry};
+
+/// Installs global tracing subscriber that sends spans to the configured exporter. Without
+/// config spans are not recorded and trace context is not propagated.
+pub fn init_tracing(config: Option<&TracingConfig>, node_name: &str) -> Result<(), String> {
+    let config = match config {
+        Some(config) => config,
+        None => return Ok(()),
+    };
+    let trace_config = trace::config()
+        .with_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(
+            config.sample_ratio(),
+        ))))
+        .with_resource(Resource::new(vec![
+            KeyValue::new("service.name", config.service_name().to_string()),
+            KeyValue::new("service.instance.id", node_name.to_string()),
+        ]));
+    let tracer = match config.exporter() {
+        TracingExporter::Otlp => opentelemetry_otlp::new_pipeline()
+            .tracing()
+            .with_exporter(
+                opentelemetry_otlp::new_exporter()
+                    .tonic()
+                    .with_endpoint(config.otlp_endpoint()),
+            )
+            .with_trace_config(trace_config)
+            .install_batch(runtime::Tokio)
+            .map_err(|e| format!("can't create otlp exporter: {}", e))?,
+        TracingExporter::File => {
+            let path = config.path().expect("validated tracing config");
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .map_err(|e| format!("can't open tracing file {}: {}", path, e))?;
+            let provider = TracerProvider::builder()
+                .with_batch_exporter(stdout::Exporter::new(file, false), runtime::Tokio)
+                .with_config(trace_config)
+                .build();
+            let tracer = provider.tracer("bob");
+            global::set_tracer_provider(provider);
+            tracer
+        }
+    };
+    let subscriber = Registry::default().with(tracing_opentelemetry::layer().with_tracer(tracer));
+    tracing::subscriber::set_global_default(subscriber).map_err(|e| e.to_string())?;
+    info!(
+        "tracing enabled, spans are exported to {:?}",
+        config.exporter()
+    );
+    Ok(())
+}
+
+/// Sends spans that are not exported yet.
+pub fn shutdown_tracing() {
+    global::shutdown_tracer_provider();
+}
+
+/// Adds context of the current span to the request to the other node.
+pub fn inject_context(metadata: &mut MetadataMap) {
+    inject_otel_context(&Span::current().context(), metadata);
+}
+
+pub(crate) fn inject_otel_context(context: &Context, metadata: &mut MetadataMap) {
+    TraceContextPropagator::new().inject_context(context, &mut MetadataInjector(metadata));
+}
+
+/// Makes the span a child of the span of the node that sent the gRPC request.
+pub fn set_grpc_parent(span: &Span, metadata: &MetadataMap) {
+    span.set_parent(extract_context(metadata));
+}
+
+/// Makes the span a child of the span of the REST client.
+pub fn set_http_parent(span: &Span, headers: &HeaderMap) {
+    span.set_parent(extract_http_context(headers));
+}
+
+/// Context of the caller from gRPC request, used as parent of the request span.
+pub fn extract_context(metadata: &MetadataMap) -> Context {
+    TraceContextPropagator::new().extract(&MetadataExtractor(metadata))
+}
+
+/// Context of the caller from `traceparent` header of REST request.
+pub fn extract_http_context(headers: &HeaderMap) -> Context {
+    TraceContextPropagator::new().extract(&HeaderExtractor(headers))
+}
+
+struct MetadataInjector<'a>(&'a mut MetadataMap);
+
+impl Injector for MetadataInjector<'_> {
+    fn set(&mut self, key: &str, value: String) {
+        if let (Ok(key), Ok(value)) = (
+            MetadataKey::from_bytes(key.as_bytes()),
+            MetadataValue::from_str(&value),
+        ) {
+            self.0.insert(key, value);
+        }
+    }
+}
+
+struct MetadataExtractor<'a>(&'a MetadataMap);
+
+impl Extractor for MetadataExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0
+            .keys()
+            .map(|key| match key {
+                KeyRef::Ascii(key) => key.as_str(),
+                KeyRef::Binary(key) => key.as_str(),
+            })
+            .collect()
+    }
+}
+
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|key| key.as_str()).collect()
+    }
+}
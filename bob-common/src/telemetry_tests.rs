@@ -0,0 +1,68 @@
+#[cfg(test)]
+mod tests {
+    use crate::telemetry::{extract_context, extract_http_context, inject_otel_context};
+    use http::HeaderMap;
+    use opentelemetry::{
+        trace::{SpanContext, SpanId, TraceContextExt, TraceFlags, TraceId, TraceState},
+        Context,
+    };
+    use tonic::metadata::MetadataMap;
+
+    fn remote_context() -> Context {
+        let span_context = SpanContext::new(
+            TraceId::from_hex("4bf92f3577b34da6a3ce929d0e0e4736").unwrap(),
+            SpanId::from_hex("00f067aa0ba902b7").unwrap(),
+            TraceFlags::SAMPLED,
+            true,
+            TraceState::default(),
+        );
+        Context::new().with_remote_span_context(span_context)
+    }
+
+    #[test]
+    fn context_is_propagated_in_metadata() {
+        let context = remote_context();
+        let mut metadata = MetadataMap::new();
+
+        inject_otel_context(&context, &mut metadata);
+        let extracted = extract_context(&metadata);
+
+        assert_eq!(
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+            metadata.get("traceparent").unwrap().to_str().unwrap()
+        );
+        let span = extracted.span();
+        let extracted = span.span_context();
+        assert!(extracted.is_remote());
+        assert_eq!(context.span().span_context().trace_id(), extracted.trace_id());
+        assert_eq!(context.span().span_context().span_id(), extracted.span_id());
+        assert!(extracted.is_sampled());
+    }
+
+    #[test]
+    fn context_is_extracted_from_http_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "traceparent",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-00"
+                .parse()
+                .unwrap(),
+        );
+
+        let context = extract_http_context(&headers);
+
+        let span = context.span();
+        assert_eq!(
+            TraceId::from_hex("4bf92f3577b34da6a3ce929d0e0e4736").unwrap(),
+            span.span_context().trace_id()
+        );
+        assert!(!span.span_context().is_sampled());
+    }
+
+    #[test]
+    fn request_without_context_has_no_parent() {
+        let context = extract_context(&MetadataMap::new());
+
+        assert!(!context.span().span_context().is_valid());
+    }
+}
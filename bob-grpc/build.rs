@@ -4,17 +4,29 @@ use prost_build::Config;
 extern crate tonic_build;
 extern crate prost_build;
 
+const GENERATED: [&str; 3] = [
+    "bob_storage.rs",
+    "grpc.health.v1.rs",
+    "bob_descriptor.bin",
+];
+
 fn main() {
     let path: PathBuf = format!("{}/src", env!("CARGO_MANIFEST_DIR")).into();
     let mut prost_config = Config::new();
     prost_config.bytes(&["Blob.data"]);
-    if !path.join("bob_storage.rs").exists() {
+    if !GENERATED.iter().all(|file| path.join(file).exists()) {
         tonic_build::configure()
             .build_server(true)
             .build_client(true)
             .format(false)
-            .out_dir(format!("{}/src", env!("CARGO_MANIFEST_DIR")))
-            .compile_with_config(prost_config, &["proto/bob.proto"], &["proto"])
+            .out_dir(&path)
+            // Served by reflection service
+            .file_descriptor_set_path(path.join("bob_descriptor.bin"))
+            .compile_with_config(
+                prost_config,
+                &["proto/bob.proto", "proto/health.proto"],
+                &["proto"],
+            )
             .expect("protobuf compilation");
     }
 }
@@ -8,12 +8,23 @@ pub mod grpc {
     include!("bob_storage.rs");
 }
 
+/// Standard gRPC health checking protocol
+pub mod health {
+    include!("grpc.health.v1.rs");
+}
+
+/// Encoded `FileDescriptorSet` of all services served by bob
+pub const FILE_DESCRIPTOR_SET: &[u8] = include_bytes!("bob_descriptor.bin");
+
 impl PutOptions {
     pub fn new_local() -> Self {
         PutOptions {
             remote_nodes: vec![],
             force_node: true,
             overwrite: false,
+            condition: WriteCondition::None as i32,
+            condition_timestamps: vec![],
+            consistency: ConsistencyLevel::Default as i32,
         }
     }
 
@@ -22,6 +33,9 @@ impl PutOptions {
             remote_nodes,
             force_node: true,
             overwrite: false,
+            condition: WriteCondition::None as i32,
+            condition_timestamps: vec![],
+            consistency: ConsistencyLevel::Default as i32,
         }
     }
 }
@@ -31,6 +45,7 @@ impl GetOptions {
         GetOptions {
             force_node: true,
             source: GetSource::Normal as i32,
+            consistency: ConsistencyLevel::Default as i32,
         }
     }
 
@@ -38,6 +53,7 @@ impl GetOptions {
         GetOptions {
             force_node: true,
             source: GetSource::Alien as i32,
+            consistency: ConsistencyLevel::Default as i32,
         }
     }
 
@@ -45,6 +61,7 @@ impl GetOptions {
         GetOptions {
             force_node: true,
             source: GetSource::All as i32,
+            consistency: ConsistencyLevel::Default as i32,
         }
     }
 }
@@ -54,7 +71,10 @@ impl DeleteOptions {
         Self {
             force_alien_nodes: vec![],
             force_node: true,
-            is_alien: false
+            is_alien: false,
+            condition: WriteCondition::None as i32,
+            condition_timestamps: vec![],
+            consistency: ConsistencyLevel::Default as i32,
         }
     }
 
@@ -63,6 +83,9 @@ impl DeleteOptions {
             force_alien_nodes,
             force_node: true,
             is_alien: true,
+            condition: WriteCondition::None as i32,
+            condition_timestamps: vec![],
+            consistency: ConsistencyLevel::Default as i32,
         }
     }
 }
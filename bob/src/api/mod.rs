@@ -1,9 +1,12 @@
 use crate::{
-    build_info::BuildInfo, hw_metrics_collector::DiskSpaceMetrics, server::Server as BobServer,
+    build_info::BuildInfo, decommission::DecommissionStatus, health::Readiness,
+    hw_metrics_collector::DiskSpaceMetrics, rebuild::RebuildStatus,
+    scrubber::DiskScrubStatus, server::Server as BobServer,
+    snapshot::{self, SnapshotManifest},
 };
 use axum::{
     body::{self, BoxBody},
-    extract::{Extension, Path as AxumPath},
+    extract::{Extension, FromRequest, Path as AxumPath, RequestParts},
     response::IntoResponse,
     routing::{delete, get, head, post, MethodRouter},
     Json, Router, Server,
@@ -16,17 +19,30 @@ use axum_server::{
 
 pub(crate) use bob_access::Error as AuthError;
 use bob_access::{Authenticator, CredentialsHolder};
-use bob_backend::pearl::{Group as PearlGroup, Holder, NoopHooks};
+use bob_backend::{
+    faulty_backend::{Fault as InjectedFault, Faults},
+    pearl::{Group as PearlGroup, Holder, NoopHooks},
+};
 use bob_common::{
-    configs::node::TLSConfig,
+    configs::{
+        cluster::Cluster as ClusterConfig,
+        node::{FaultRule, TLSConfig},
+    },
     data::{BobData, BobKey, BobMeta, BOB_KEY_SIZE},
-    core_types::{VDisk as DataVDisk, NodeDisk},
-    operation_options::{BobPutOptions, BobGetOptions, BobDeleteOptions},
+    core_types::{DiskPath, VDisk as DataVDisk, NodeDisk},
+    operation_options::{
+        BobConsistencyLevel, BobDeleteOptions, BobGetOptions, BobPutOptions, BobWriteCondition,
+    },
     error::Error as BobError,
+    failure_detector::CircuitState,
+    telemetry,
 };
 use bytes::Bytes;
 use futures::{future::BoxFuture, stream::FuturesUnordered, FutureExt, StreamExt};
-use http::{header::CONTENT_TYPE, HeaderMap, Response, StatusCode};
+use http::{
+    header::{CONTENT_TYPE, ETAG, IF_MATCH, IF_NONE_MATCH},
+    HeaderMap, Response, StatusCode,
+};
 use std::{
     collections::HashMap,
     future::ready,
@@ -34,14 +50,21 @@ use std::{
     net::{IpAddr, SocketAddr},
     path::{Path, PathBuf},
     str::FromStr,
+    time::Instant,
 };
 use tokio::fs::{read_dir, ReadDir};
+use tower_http::trace::TraceLayer;
+use tracing::{info_span, Span};
 use uuid::Uuid;
 
 use self::metric_models::MetricsSnapshotModel;
 
 mod metric_models;
 mod s3;
+#[cfg(test)]
+mod tests;
+
+const CONSISTENCY_LEVEL_HEADER: &str = "x-bob-consistency-level";
 
 #[derive(Debug, Clone, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -55,6 +78,12 @@ pub(crate) struct Node {
     name: String,
     address: String,
     vdisks: Vec<VDisk>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    suspicion: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    circuit: Option<CircuitState>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    handoff_queue: Option<usize>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -111,6 +140,14 @@ pub(crate) struct Dir {
     children: Vec<Dir>,
 }
 
+#[derive(Debug, Serialize)]
+pub(crate) struct Fault {
+    id: u64,
+    #[serde(flatten)]
+    rule: FaultRule,
+    active: bool,
+}
+
 #[derive(Debug, Serialize)]
 pub(crate) struct DistrFunc {
     func: String,
@@ -126,6 +163,16 @@ pub(crate) struct DiskState {
 #[derive(Debug, Clone)]
 pub(crate) struct DataKey(BobKey);
 
+/// Write condition from `If-Match`/`If-None-Match` headers
+#[derive(Debug, Default)]
+pub(crate) struct WriteConditionHeaders {
+    condition: Option<BobWriteCondition>,
+}
+
+/// Consistency level from `X-Bob-Consistency-Level` header
+#[derive(Debug, Default)]
+pub(crate) struct ConsistencyHeader(Option<BobConsistencyLevel>);
+
 #[derive(Debug, Serialize)]
 pub(crate) struct Version {
     version: String,
@@ -175,7 +222,9 @@ pub(crate) async fn spawn<A>(
 {
     let socket_addr = SocketAddr::new(address, port);
 
-    let router = router::<A>().layer(Extension(bob));
+    let router = router::<A>()
+        .layer(Extension(bob))
+        .layer(TraceLayer::new_for_http().make_span_with(request_span));
 
     if let Some(tls_config) = tls_config
         .as_ref()
@@ -192,6 +241,17 @@ pub(crate) async fn spawn<A>(
     info!("API server started, listening: {}", socket_addr);
 }
 
+/// Span of the REST request, continues the trace of the client if it sent `traceparent`.
+fn request_span(request: &http::Request<body::Body>) -> Span {
+    let span = info_span!(
+        "http.request",
+        method = %request.method(),
+        path = %request.uri().path()
+    );
+    telemetry::set_http_parent(&span, request.headers());
+    span
+}
+
 fn router<A>() -> Router
 where
     A: Authenticator,
@@ -215,6 +275,12 @@ where
         ("/status/space", get(get_space_info::<A>)),
         ("/metrics", get(metrics::<A>)),
         ("/version", get(version)),
+        ("/health/live", get(health_live)),
+        ("/health/ready", get(health_ready::<A>)),
+        ("/drain", post(drain::<A>)),
+        ("/undrain", post(undrain::<A>)),
+        ("/decommission", get(decommission_status::<A>)),
+        ("/decommission", post(start_decommission::<A>)),
         ("/nodes", get(nodes::<A>)),
         ("/disks/list", get(disks_list::<A>)),
         ("/metadata/distrfunc", get(distribution_function::<A>)),
@@ -227,6 +293,9 @@ where
             "/disks/:disk_name/start",
             post(start_all_disk_controllers::<A>),
         ),
+        ("/disks/:disk_name/rebuild", get(rebuild_status::<A>)),
+        ("/disks/:disk_name/rebuild", post(start_rebuild::<A>)),
+        ("/disks/:disk_name/scrub", get(scrub_status::<A>)),
         ("/vdisks", get(vdisks::<A>)),
         ("/blobs/outdated", delete(finalize_outdated_blobs::<A>)),
         ("/vdisks/:vdisk_id", get(vdisk_by_id::<A>)),
@@ -248,6 +317,20 @@ where
             "/vdisks/:vdisk_id/partitions/by_timestamp/:timestamp",
             delete(delete_partition::<A>),
         ),
+        ("/vdisks/:vdisk_id/snapshots", get(snapshots::<A>)),
+        ("/vdisks/:vdisk_id/snapshots", post(create_snapshot::<A>)),
+        (
+            "/vdisks/:vdisk_id/snapshots/:name/restore",
+            post(restore_snapshot::<A>),
+        ),
+        (
+            "/vdisks/:vdisk_id/snapshots/:name",
+            delete(delete_snapshot::<A>),
+        ),
+        ("/faults", get(faults::<A>)),
+        ("/faults", post(add_fault::<A>)),
+        ("/faults", delete(clear_faults::<A>)),
+        ("/faults/:id", delete(remove_fault::<A>)),
         ("/alien", get(alien)),
         ("/alien/detach", post(detach_alien_partitions::<A>)),
         ("/alien/dir", get(get_alien_directory::<A>)),
@@ -305,6 +388,9 @@ async fn status<A: Authenticator>(bob: Extension<BobServer<A>>) -> Json<Node> {
         name,
         address,
         vdisks,
+        suspicion: None,
+        circuit: None,
+        handoff_queue: None,
     };
     Json(node)
 }
@@ -386,12 +472,149 @@ async fn find_group<A: Authenticator>(
     })
 }
 
+async fn find_group_disk<A: Authenticator>(
+    bob: &BobServer<A>,
+    vdisk_id: u32,
+) -> Result<(PearlGroup, DiskPath), StatusExt> {
+    let group = find_group(bob, vdisk_id).await?;
+    let mapper = bob.grinder().backend().mapper();
+    let disk = mapper.get_disk(group.disk_name().as_str()).cloned().ok_or_else(|| {
+        let err = format!("Disk with name '{}' not found", group.disk_name());
+        StatusExt::new(StatusCode::NOT_FOUND, false, err)
+    })?;
+    Ok((group, disk))
+}
+
+async fn find_snapshot(
+    disk: &DiskPath,
+    vdisk_id: u32,
+    name: &str,
+) -> Result<SnapshotManifest, StatusExt> {
+    let snapshots = snapshot::list(disk, vdisk_id).await?;
+    snapshots
+        .into_iter()
+        .find(|snapshot| snapshot.name == name)
+        .ok_or_else(|| {
+            let err = format!("snapshot {} of vdisk {} not found", name, vdisk_id);
+            StatusExt::new(StatusCode::NOT_FOUND, false, err)
+        })
+}
+
 // GET /metrics
 async fn metrics<A: Authenticator>(bob: Extension<BobServer<A>>) -> Json<MetricsSnapshotModel> {
     let snapshot = bob.metrics().read().expect("rwlock").clone();
     Json(snapshot.into())
 }
 
+// GET /health/live
+async fn health_live() -> StatusCode {
+    StatusCode::OK
+}
+
+// GET /health/ready
+async fn health_ready<A>(bob: Extension<BobServer<A>>) -> (StatusCode, Json<Readiness>)
+where
+    A: Authenticator,
+{
+    let readiness = bob.readiness().await;
+    let status = if readiness.ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(readiness))
+}
+
+// POST /drain
+async fn drain<A>(
+    bob: Extension<BobServer<A>>,
+    creds: CredentialsHolder<A>,
+) -> Result<StatusExt, StatusExt>
+where
+    A: Authenticator,
+{
+    if !bob
+        .auth()
+        .check_credentials_rest(creds.into())?
+        .has_rest_write()
+    {
+        return Err(AuthError::PermissionDenied.into());
+    }
+    bob.drain();
+    let msg = "Node is draining, new data requests from clients are rejected".to_owned();
+    Ok(StatusExt::new(StatusCode::OK, true, msg))
+}
+
+// POST /undrain
+async fn undrain<A>(
+    bob: Extension<BobServer<A>>,
+    creds: CredentialsHolder<A>,
+) -> Result<StatusExt, StatusExt>
+where
+    A: Authenticator,
+{
+    if !bob
+        .auth()
+        .check_credentials_rest(creds.into())?
+        .has_rest_write()
+    {
+        return Err(AuthError::PermissionDenied.into());
+    }
+    if !bob.undrain() {
+        let msg = "Node is shutting down, drain can't be stopped".to_owned();
+        return Err(StatusExt::new(StatusCode::SERVICE_UNAVAILABLE, false, msg));
+    }
+    let msg = "Node accepts data requests".to_owned();
+    Ok(StatusExt::new(StatusCode::OK, true, msg))
+}
+
+// GET /decommission
+async fn decommission_status<A>(
+    bob: Extension<BobServer<A>>,
+    creds: CredentialsHolder<A>,
+) -> Result<Json<DecommissionStatus>, StatusExt>
+where
+    A: Authenticator,
+{
+    if !bob
+        .auth()
+        .check_credentials_rest(creds.into())?
+        .has_rest_read()
+    {
+        return Err(AuthError::PermissionDenied.into());
+    }
+    Ok(Json(bob.decommission_status()))
+}
+
+// POST /decommission
+async fn start_decommission<A>(
+    bob: Extension<BobServer<A>>,
+    creds: CredentialsHolder<A>,
+    cluster_config: String,
+) -> Result<StatusExt, StatusExt>
+where
+    A: Authenticator,
+{
+    if !bob
+        .auth()
+        .check_credentials_rest(creds.into())?
+        .has_rest_write()
+    {
+        return Err(AuthError::PermissionDenied.into());
+    }
+    if bob.grinder().decommission().is_running() {
+        let msg = "decommission is already running".to_owned();
+        return Err(StatusExt::new(StatusCode::CONFLICT, false, msg));
+    }
+    let new_cluster = ClusterConfig::get_from_string(&cluster_config)
+        .map_err(|e| StatusExt::new(StatusCode::BAD_REQUEST, false, e))?;
+    bob.start_decommission(&new_cluster)
+        .await
+        .map_err(|e| StatusExt::new(StatusCode::BAD_REQUEST, false, e))?;
+    let msg = "Decommission started, normal writes are rejected".to_owned();
+    Ok(StatusExt::new(StatusCode::OK, true, msg))
+}
+
 // GET /version
 async fn version() -> Json<VersionInfo> {
     let build_info = BuildInfo::default();
@@ -455,6 +678,9 @@ where
             name,
             address,
             vdisks,
+            suspicion: node.suspicion(),
+            circuit: node.circuit_state(),
+            handoff_queue: bob.grinder().handoff().and_then(|h| h.depth(node.name())),
         };
 
         nodes.push(node);
@@ -630,6 +856,103 @@ where
     }
 }
 
+// GET /disks/:disk_name/rebuild
+async fn rebuild_status<A>(
+    bob: Extension<BobServer<A>>,
+    AxumPath(disk_name): AxumPath<String>,
+    creds: CredentialsHolder<A>,
+) -> Result<Json<RebuildStatus>, StatusExt>
+where
+    A: Authenticator,
+{
+    if !bob
+        .auth()
+        .check_credentials_rest(creds.into())?
+        .has_rest_read()
+    {
+        return Err(AuthError::PermissionDenied.into());
+    }
+    if bob.grinder().backend().mapper().get_disk(&disk_name).is_none() {
+        let err = format!("Disk with name '{}' not found", disk_name);
+        return Err(StatusExt::new(StatusCode::NOT_FOUND, false, err));
+    }
+    let status = bob.rebuild_status(&disk_name).unwrap_or_else(|| RebuildStatus {
+        disk: disk_name,
+        ..Default::default()
+    });
+    Ok(Json(status))
+}
+
+// POST /disks/:disk_name/rebuild
+async fn start_rebuild<A>(
+    bob: Extension<BobServer<A>>,
+    AxumPath(disk_name): AxumPath<String>,
+    creds: CredentialsHolder<A>,
+) -> Result<StatusExt, StatusExt>
+where
+    A: Authenticator,
+{
+    if !bob
+        .auth()
+        .check_credentials_rest(creds.into())?
+        .has_rest_write()
+    {
+        return Err(AuthError::PermissionDenied.into());
+    }
+    let backend = bob.grinder().backend();
+    let disk = backend.mapper().get_disk(&disk_name).cloned().ok_or_else(|| {
+        let err = format!("Disk with name '{}' not found", disk_name);
+        StatusExt::new(StatusCode::NOT_FOUND, false, err)
+    })?;
+    let (dcs, _) = backend
+        .inner()
+        .disk_controllers()
+        .ok_or_else(not_acceptable_backend)?;
+    let dc = dcs
+        .iter()
+        .find(|dc| *dc.disk().name() == disk_name)
+        .ok_or_else(|| {
+            let err = format!("Disk Controller with name '{}' not found", disk_name);
+            StatusExt::new(StatusCode::NOT_FOUND, false, err)
+        })?;
+    if !dc.is_ready().await {
+        let err = format!("Disk '{}' is not started, start it before rebuild", disk_name);
+        return Err(StatusExt::new(StatusCode::CONFLICT, false, err));
+    }
+    if bob.grinder().rebuilds().is_running(&disk_name) {
+        let err = format!("Rebuild of disk '{}' is already running", disk_name);
+        return Err(StatusExt::new(StatusCode::CONFLICT, false, err));
+    }
+    bob.start_rebuild(disk)
+        .map_err(|e| StatusExt::new(StatusCode::BAD_REQUEST, false, e))?;
+    let msg = format!("Rebuild of disk '{}' started", disk_name);
+    info!("{}", msg);
+    Ok(StatusExt::new(StatusCode::OK, true, msg))
+}
+
+// GET /disks/:disk_name/scrub
+async fn scrub_status<A>(
+    bob: Extension<BobServer<A>>,
+    AxumPath(disk_name): AxumPath<String>,
+    creds: CredentialsHolder<A>,
+) -> Result<Json<DiskScrubStatus>, StatusExt>
+where
+    A: Authenticator,
+{
+    if !bob
+        .auth()
+        .check_credentials_rest(creds.into())?
+        .has_rest_read()
+    {
+        return Err(AuthError::PermissionDenied.into());
+    }
+    if bob.grinder().backend().mapper().get_disk(&disk_name).is_none() {
+        let err = format!("Disk with name '{}' not found", disk_name);
+        return Err(StatusExt::new(StatusCode::NOT_FOUND, false, err));
+    }
+    Ok(Json(bob.scrub_status(&disk_name)))
+}
+
 // GET /vdisks
 async fn vdisks<A>(
     bob: Extension<BobServer<A>>,
@@ -708,13 +1031,7 @@ where
         return Err(AuthError::PermissionDenied.into());
     }
     let group = find_group(&bob, vdisk_id).await?;
-    let holders = group.holders();
-    let pearls = holders.read().await;
-    let mut sum = 0;
-    for pearl in pearls.iter() {
-        sum += pearl.records_count().await;
-    }
-    Ok(Json(sum as u64))
+    Ok(Json(group.records_count().await as u64))
 }
 
 // GET /vdisks/:vdisk_id/partitions
@@ -830,6 +1147,203 @@ where
     }
 }
 
+// GET /vdisks/:vdisk_id/snapshots
+async fn snapshots<A>(
+    bob: Extension<BobServer<A>>,
+    AxumPath(vdisk_id): AxumPath<u32>,
+    creds: CredentialsHolder<A>,
+) -> Result<Json<Vec<SnapshotManifest>>, StatusExt>
+where
+    A: Authenticator,
+{
+    if !bob
+        .auth()
+        .check_credentials_rest(creds.into())?
+        .has_rest_read()
+    {
+        return Err(AuthError::PermissionDenied.into());
+    }
+    let (_, disk) = find_group_disk(&bob, vdisk_id).await?;
+    Ok(Json(snapshot::list(&disk, vdisk_id).await?))
+}
+
+// POST /vdisks/:vdisk_id/snapshots
+async fn create_snapshot<A>(
+    bob: Extension<BobServer<A>>,
+    AxumPath(vdisk_id): AxumPath<u32>,
+    creds: CredentialsHolder<A>,
+) -> Result<Json<SnapshotManifest>, StatusExt>
+where
+    A: Authenticator,
+{
+    if !bob
+        .auth()
+        .check_credentials_rest(creds.into())?
+        .has_rest_write()
+    {
+        return Err(AuthError::PermissionDenied.into());
+    }
+    let (group, disk) = find_group_disk(&bob, vdisk_id).await?;
+    let manifest = snapshot::create(&group, &disk).await?;
+    info!("snapshot {} of vdisk {} created", manifest.name, vdisk_id);
+    Ok(Json(manifest))
+}
+
+// POST /vdisks/:vdisk_id/snapshots/:name/restore
+async fn restore_snapshot<A>(
+    bob: Extension<BobServer<A>>,
+    AxumPath((vdisk_id, name)): AxumPath<(u32, String)>,
+    creds: CredentialsHolder<A>,
+) -> Result<StatusExt, StatusExt>
+where
+    A: Authenticator,
+{
+    if !bob
+        .auth()
+        .check_credentials_rest(creds.into())?
+        .has_rest_write()
+    {
+        return Err(AuthError::PermissionDenied.into());
+    }
+    let (group, disk) = find_group_disk(&bob, vdisk_id).await?;
+    let manifest = find_snapshot(&disk, vdisk_id, &name).await?;
+    snapshot::restore(&group, &disk, &manifest).await?;
+    let msg = format!("snapshot {} of vdisk {} is restored", name, vdisk_id);
+    info!("{}", msg);
+    Ok(StatusExt::new(StatusCode::OK, true, msg))
+}
+
+// DELETE /vdisks/:vdisk_id/snapshots/:name
+async fn delete_snapshot<A>(
+    bob: Extension<BobServer<A>>,
+    AxumPath((vdisk_id, name)): AxumPath<(u32, String)>,
+    creds: CredentialsHolder<A>,
+) -> Result<StatusExt, StatusExt>
+where
+    A: Authenticator,
+{
+    if !bob
+        .auth()
+        .check_credentials_rest(creds.into())?
+        .has_rest_write()
+    {
+        return Err(AuthError::PermissionDenied.into());
+    }
+    let (_, disk) = find_group_disk(&bob, vdisk_id).await?;
+    let manifest = find_snapshot(&disk, vdisk_id, &name).await?;
+    snapshot::delete(&disk, &manifest).await?;
+    let msg = format!("snapshot {} of vdisk {} is deleted", name, vdisk_id);
+    info!("{}", msg);
+    Ok(StatusExt::new(StatusCode::OK, true, msg))
+}
+
+fn fault_injection_disabled() -> StatusExt {
+    StatusExt::new(
+        StatusCode::NOT_ACCEPTABLE,
+        false,
+        "fault injection is disabled in node config".into(),
+    )
+}
+
+fn faults_of<A: Authenticator>(bob: &BobServer<A>) -> Result<&Faults, StatusExt> {
+    bob.grinder()
+        .backend()
+        .faults()
+        .ok_or_else(fault_injection_disabled)
+}
+
+// GET /faults
+async fn faults<A>(
+    bob: Extension<BobServer<A>>,
+    creds: CredentialsHolder<A>,
+) -> Result<Json<Vec<Fault>>, StatusExt>
+where
+    A: Authenticator,
+{
+    if !bob
+        .auth()
+        .check_credentials_rest(creds.into())?
+        .has_rest_read()
+    {
+        return Err(AuthError::PermissionDenied.into());
+    }
+    let now = Instant::now();
+    let faults = faults_of(&bob)?
+        .list()
+        .iter()
+        .map(|fault| Fault::new(fault, now))
+        .collect();
+    Ok(Json(faults))
+}
+
+// POST /faults
+async fn add_fault<A>(
+    bob: Extension<BobServer<A>>,
+    creds: CredentialsHolder<A>,
+    Json(rule): Json<FaultRule>,
+) -> Result<Json<Fault>, StatusExt>
+where
+    A: Authenticator,
+{
+    if !bob
+        .auth()
+        .check_credentials_rest(creds.into())?
+        .has_rest_write()
+    {
+        return Err(AuthError::PermissionDenied.into());
+    }
+    let faults = faults_of(&bob)?;
+    rule.check()
+        .map_err(|msg| StatusExt::new(StatusCode::BAD_REQUEST, false, msg))?;
+    let fault = faults.add(rule);
+    Ok(Json(Fault::new(&fault, Instant::now())))
+}
+
+// DELETE /faults
+async fn clear_faults<A>(
+    bob: Extension<BobServer<A>>,
+    creds: CredentialsHolder<A>,
+) -> Result<StatusExt, StatusExt>
+where
+    A: Authenticator,
+{
+    if !bob
+        .auth()
+        .check_credentials_rest(creds.into())?
+        .has_rest_write()
+    {
+        return Err(AuthError::PermissionDenied.into());
+    }
+    faults_of(&bob)?.clear();
+    info!("all injected faults are removed");
+    Ok(StatusExt::new(StatusCode::OK, true, "faults are removed".into()))
+}
+
+// DELETE /faults/:id
+async fn remove_fault<A>(
+    bob: Extension<BobServer<A>>,
+    AxumPath(id): AxumPath<u64>,
+    creds: CredentialsHolder<A>,
+) -> Result<StatusExt, StatusExt>
+where
+    A: Authenticator,
+{
+    if !bob
+        .auth()
+        .check_credentials_rest(creds.into())?
+        .has_rest_write()
+    {
+        return Err(AuthError::PermissionDenied.into());
+    }
+    if !faults_of(&bob)?.remove(id) {
+        let msg = format!("fault {} not found", id);
+        return Err(StatusExt::new(StatusCode::NOT_FOUND, false, msg));
+    }
+    let msg = format!("fault {} is removed", id);
+    info!("{}", msg);
+    Ok(StatusExt::new(StatusCode::OK, true, msg))
+}
+
 // POST /vdisks/:vdisk_id/remount
 async fn remount_vdisks_group<A>(
     bob: Extension<BobServer<A>>,
@@ -1041,6 +1555,7 @@ async fn read_directory_children(mut read_dir: ReadDir, name: &str, path: &str)
 async fn get_data<A>(
     bob: Extension<BobServer<A>>,
     AxumPath(key): AxumPath<String>,
+    consistency: ConsistencyHeader,
     creds: CredentialsHolder<A>,
 ) -> Result<impl IntoResponse, StatusExt>
 where
@@ -1050,8 +1565,8 @@ where
         return Err(AuthError::PermissionDenied.into());
     }
     let key = DataKey::from_str(&key)?.0;
-    let opts = BobGetOptions::from_grpc(None);
-    let result = bob.grinder().get(key, &opts).await?;
+    let opts = BobGetOptions::default().with_consistency(consistency.0);
+    let result = bob.grinder().get(key, &opts, false).await?;
 
     let content_type = infer_data_type(&result);
     let mut headers = HeaderMap::new();
@@ -1067,6 +1582,7 @@ where
         })
         .expect("failed to parse content type value");
     headers.insert(CONTENT_TYPE, val);
+    headers.insert(ETAG, etag(&result));
     Ok((headers, result.inner().to_owned()))
 }
 
@@ -1083,8 +1599,8 @@ where
         return Err(AuthError::PermissionDenied.into());
     }
     let keys = [DataKey::from_str(&key)?.0];
-    let opts = BobGetOptions::from_grpc(None);
-    let result = bob.grinder().exist(&keys, &opts).await?;
+    let opts = BobGetOptions::default();
+    let result = bob.grinder().exist(&keys, &opts, false).await?;
 
     match result.get(0) {
         Some(true) => Ok(StatusCode::OK),
@@ -1102,6 +1618,8 @@ async fn put_data<A>(
     bob: Extension<BobServer<A>>,
     AxumPath(key): AxumPath<String>,
     body: Bytes,
+    condition: WriteConditionHeaders,
+    consistency: ConsistencyHeader,
     creds: CredentialsHolder<A>,
 ) -> Result<StatusExt, StatusExt>
 where
@@ -1114,8 +1632,10 @@ where
     let meta = BobMeta::new(chrono::Utc::now().timestamp() as u64);
     let data = BobData::new(body, meta);
 
-    let opts = BobPutOptions::from_grpc(None);
-    bob.grinder().put(key, &data, opts).await?;
+    let opts = BobPutOptions::default()
+        .with_condition(condition.condition())
+        .with_consistency(consistency.0);
+    bob.grinder().put(key, &data, opts, false).await?;
     Ok(StatusCode::CREATED.into())
 }
 
@@ -1123,6 +1643,8 @@ where
 async fn delete_data<A>(
     bob: Extension<BobServer<A>>,
     AxumPath(key): AxumPath<String>,
+    condition: WriteConditionHeaders,
+    consistency: ConsistencyHeader,
     creds: CredentialsHolder<A>,
 ) -> Result<StatusExt, StatusExt>
 where
@@ -1136,10 +1658,25 @@ where
         .delete(
             key,
             &BobMeta::new(chrono::Utc::now().timestamp() as u64),
-            BobDeleteOptions::from_grpc(None),
+            BobDeleteOptions::default()
+                .with_condition(condition.condition())
+                .with_consistency(consistency.0),
+            false,
         )
         .await
-        .map_err(|e| internal(e.to_string()))?;
+        .map_err(|e| {
+            if e.is_precondition_failed()
+                || matches!(
+                    e.kind(),
+                    bob_common::error::Kind::InvalidConsistencyLevel(_)
+                        | bob_common::error::Kind::ConsistencyLevelNotReached(_)
+                )
+            {
+                e.into()
+            } else {
+                internal(e.to_string())
+            }
+        })?;
     Ok(StatusExt::new(StatusCode::OK, true, format!("Done")))
 }
 
@@ -1195,6 +1732,115 @@ impl DataKey {
     }
 }
 
+// Record timestamp is used as ETag
+pub(crate) fn etag(data: &BobData) -> http::HeaderValue {
+    format!("\"{}\"", data.meta().timestamp())
+        .parse()
+        .expect("timestamp is valid header value")
+}
+
+// Returns timestamps of the ETag list, `None` if list is empty or malformed. Weak ETags and
+// ETags of another format can't match any record, so they are skipped
+fn parse_etags(value: &str) -> Option<Vec<u64>> {
+    let mut timestamps = Vec::new();
+    let mut tags = 0;
+    for tag in value.split(',').map(str::trim).filter(|tag| !tag.is_empty()) {
+        tags += 1;
+        let (weak, tag) = match tag.strip_prefix("W/") {
+            Some(tag) => (true, tag),
+            None => (false, tag),
+        };
+        let opaque = tag.strip_prefix('"')?.strip_suffix('"')?;
+        if let (false, Ok(timestamp)) = (weak, opaque.parse()) {
+            timestamps.push(timestamp);
+        }
+    }
+    (tags > 0).then_some(timestamps)
+}
+
+/// `If-Match: *` allows write only over existing record, `If-Match: "<etag>", ...` only over
+/// one of the given versions of record, `If-None-Match: *` only if there is no record.
+pub(crate) fn write_condition_from_headers(
+    headers: &HeaderMap,
+) -> Result<WriteConditionHeaders, StatusExt> {
+    let condition = match (headers.get(IF_MATCH), headers.get(IF_NONE_MATCH)) {
+        (Some(_), Some(_)) => {
+            return Err(bad_request(
+                "If-Match and If-None-Match headers can't be used together",
+            ))
+        }
+        (Some(value), None) => match value.to_str().map(str::trim) {
+            Ok("*") => Some(BobWriteCondition::IfExists),
+            Ok(value) => Some(BobWriteCondition::IfTimestampMatches(
+                parse_etags(value).ok_or_else(|| {
+                    bad_request("If-Match header must contain '*' or list of record ETags")
+                })?,
+            )),
+            Err(e) => return Err(bad_request(e.to_string())),
+        },
+        (None, Some(value)) => {
+            if value == "*" {
+                Some(BobWriteCondition::IfAbsent)
+            } else {
+                return Err(bad_request("only '*' is supported in If-None-Match header"));
+            }
+        }
+        (None, None) => None,
+    };
+    Ok(WriteConditionHeaders { condition })
+}
+
+impl WriteConditionHeaders {
+    pub(crate) fn condition(&self) -> Option<BobWriteCondition> {
+        self.condition.clone()
+    }
+}
+
+pub(crate) fn consistency_from_headers(
+    headers: &HeaderMap,
+) -> Result<Option<BobConsistencyLevel>, StatusExt> {
+    headers
+        .get(CONSISTENCY_LEVEL_HEADER)
+        .map(|value| {
+            value
+                .to_str()
+                .map_err(|e| bad_request(e.to_string()))?
+                .parse()
+                .map_err(bad_request)
+        })
+        .transpose()
+}
+
+#[async_trait]
+impl<B> FromRequest<B> for ConsistencyHeader
+where
+    B: Send,
+{
+    type Rejection = StatusExt;
+
+    async fn from_request(request: &mut RequestParts<B>) -> Result<Self, Self::Rejection> {
+        let headers = request
+            .headers()
+            .expect("headers removed by another extractor");
+        consistency_from_headers(headers).map(Self)
+    }
+}
+
+#[async_trait]
+impl<B> FromRequest<B> for WriteConditionHeaders
+where
+    B: Send,
+{
+    type Rejection = StatusExt;
+
+    async fn from_request(request: &mut RequestParts<B>) -> Result<Self, Self::Rejection> {
+        let headers = request
+            .headers()
+            .expect("headers removed by another extractor");
+        write_condition_from_headers(headers)
+    }
+}
+
 impl FromStr for DataKey {
     type Err = StatusExt;
 
@@ -1224,6 +1870,16 @@ impl IntoResponse for StatusExt {
     }
 }
 
+impl Fault {
+    fn new(fault: &InjectedFault, now: Instant) -> Self {
+        Self {
+            id: fault.id(),
+            rule: fault.rule().clone(),
+            active: fault.is_active(now),
+        }
+    }
+}
+
 impl StatusExt {
     fn new(status: StatusCode, ok: bool, msg: String) -> Self {
         Self { status, ok, msg }
@@ -1257,11 +1913,17 @@ impl From<BobError> for StatusExt {
     fn from(err: BobError) -> Self {
         use bob_common::error::Kind;
         let status = match err.kind() {
-            Kind::DuplicateKey => StatusCode::CONFLICT,
+            Kind::DuplicateKey | Kind::ConditionPartiallyApplied(_) => StatusCode::CONFLICT,
+            Kind::PreconditionFailed => StatusCode::PRECONDITION_FAILED,
             Kind::Internal => StatusCode::INTERNAL_SERVER_ERROR,
             Kind::VDiskIsNotReady => StatusCode::INTERNAL_SERVER_ERROR,
             Kind::KeyNotFound(_) => StatusCode::NOT_FOUND,
-            Kind::HolderTemporaryUnavailable => StatusCode::SERVICE_UNAVAILABLE,
+            Kind::HolderTemporaryUnavailable
+            | Kind::NodeIsDraining
+            | Kind::NodeIsDecommissioning
+            | Kind::ConsistencyLevelNotReached(_) => {
+                StatusCode::SERVICE_UNAVAILABLE
+            }
             _ => StatusCode::BAD_REQUEST,
         };
         Self {
@@ -1,6 +1,9 @@
 use std::{convert::TryInto, str::FromStr};
 
-use super::{infer_data_type, AuthError, DataKey, StatusExt};
+use super::{
+    consistency_from_headers, etag, infer_data_type, write_condition_from_headers, AuthError,
+    DataKey, StatusExt, WriteConditionHeaders,
+};
 use crate::server::Server as BobServer;
 use axum::{
     body::{boxed, BoxBody, Empty, Full},
@@ -12,7 +15,7 @@ use axum::{
 use bob_access::{Authenticator, CredentialsHolder};
 use bob_common::{
     data::{BobData, BobKey, BobMeta},
-    operation_options::{BobPutOptions, BobGetOptions},
+    operation_options::{BobConsistencyLevel, BobGetOptions, BobPutOptions},
     error::Error,
 };
 use bytes::Bytes;
@@ -71,6 +74,7 @@ struct GetObjectHeaders {
     content_type: Option<String>,
     if_modified_since: Option<u64>,
     if_unmodified_since: Option<u64>,
+    consistency: Option<BobConsistencyLevel>,
 }
 
 #[async_trait]
@@ -101,10 +105,12 @@ where
                 DateTime::parse_from_rfc2822(s).ok()
             })
             .and_then(|x| x.timestamp().try_into().ok());
+        let consistency = consistency_from_headers(headers)?;
         let headers = GetObjectHeaders {
             content_type,
             if_modified_since,
             if_unmodified_since,
+            consistency,
         };
         Ok(headers)
     }
@@ -122,6 +128,7 @@ impl IntoResponse for GetObjectOutput {
             .status(StatusCode::OK)
             .header("Content-Type", self.content_type)
             .header("Last-Modified", self.data.meta().timestamp().to_string())
+            .header("ETag", etag(&self.data))
             .body(boxed(Full::new(self.data.into_inner().into())))
             .expect("failed to set body")
     }
@@ -129,7 +136,7 @@ impl IntoResponse for GetObjectOutput {
 
 // GET /s3/default/:key
 async fn get_object<A>(
-    Extension(bob): Extension<&BobServer<A>>,
+    Extension(bob): Extension<BobServer<A>>,
     Path(key): Path<String>,
     headers: GetObjectHeaders,
     creds: CredentialsHolder<A>,
@@ -141,8 +148,8 @@ where
         return Err(AuthError::PermissionDenied.into());
     }
     let key = DataKey::from_str(&key)?.0;
-    let opts = BobGetOptions::from_grpc(None);
-    let data = bob.grinder().get(key, &opts).await?;
+    let opts = BobGetOptions::default().with_consistency(headers.consistency);
+    let data = bob.grinder().get(key, &opts, false).await?;
     let content_type = headers
         .content_type
         .unwrap_or_else(|| infer_data_type(&data).to_string());
@@ -162,7 +169,7 @@ where
 
 // PUT /s3/default/:key
 async fn put_object<A>(
-    Extension(bob): Extension<&BobServer<A>>,
+    Extension(bob): Extension<BobServer<A>>,
     Path(key): Path<String>,
     body: Bytes,
     headers: CopyObjectHeaders,
@@ -176,15 +183,17 @@ where
     }
     let key = DataKey::from_str(&key)?.0;
     if headers.is_source_key_set() {
-        return copy_object(bob, key, headers).await;
+        return copy_object(&bob, key, headers).await;
     }
     let data = BobData::new(
         body,
         BobMeta::new(chrono::Utc::now().timestamp() as u64),
     );
 
-    let opts = BobPutOptions::from_grpc(None);
-    bob.grinder().put(key, &data, opts).await?;
+    let opts = BobPutOptions::default()
+        .with_condition(headers.condition.condition())
+        .with_consistency(headers.consistency);
+    bob.grinder().put(key, &data, opts, false).await?;
 
     Ok(StatusS3::from(StatusExt::from(StatusCode::CREATED)))
 }
@@ -194,6 +203,8 @@ pub(crate) struct CopyObjectHeaders {
     if_modified_since: Option<u64>,
     if_unmodified_since: Option<u64>,
     source_key: Option<DataKey>,
+    condition: WriteConditionHeaders,
+    consistency: Option<BobConsistencyLevel>,
 }
 
 impl CopyObjectHeaders {
@@ -216,6 +227,8 @@ where
             let key = x.to_str().map(|s| s.to_string()).ok()?;
             DataKey::from_str(&key).ok()
         });
+        let condition = write_condition_from_headers(headers)?;
+        let consistency = consistency_from_headers(headers)?;
         Ok(CopyObjectHeaders {
             if_modified_since: headers
                 .get("If-Modified-Since")
@@ -232,6 +245,8 @@ where
                 })
                 .and_then(|x| x.timestamp().try_into().ok()),
             source_key,
+            condition,
+            consistency,
         })
     }
 }
@@ -241,8 +256,8 @@ async fn copy_object<A: Authenticator>(
     key: BobKey,
     headers: CopyObjectHeaders,
 ) -> Result<StatusS3, StatusS3> {
-    let opts = BobGetOptions::from_grpc(None);
-    let data = bob.grinder().get(key, &opts).await?;
+    let opts = BobGetOptions::default().with_consistency(headers.consistency);
+    let data = bob.grinder().get(key, &opts, false).await?;
     let last_modified = data.meta().timestamp();
     if let Some(time) = headers.if_modified_since {
         if time > last_modified {
@@ -259,8 +274,10 @@ async fn copy_object<A: Authenticator>(
         BobMeta::new(chrono::Utc::now().timestamp() as u64),
     );
 
-    let opts = BobPutOptions::from_grpc(None);
-    bob.grinder().put(key, &data, opts).await?;
+    let opts = BobPutOptions::default()
+        .with_condition(headers.condition.condition())
+        .with_consistency(headers.consistency);
+    bob.grinder().put(key, &data, opts, false).await?;
 
     Ok(StatusS3::from(StatusExt::from(StatusCode::OK)))
 }
@@ -0,0 +1,95 @@
+use crate::{grinder::Grinder, server::Server as BobServer};
+use axum::{
+    body::{Body, HttpBody},
+    extract::Extension,
+};
+use bob_access::StubAuthenticator;
+use bob_common::{
+    configs::{
+        cluster::tests::{cluster_config, with_disks_root},
+        node::tests::node_config,
+    },
+    mapper::Virtual,
+};
+use http::{
+    header::{ETAG, IF_MATCH},
+    HeaderMap, Method, Request, StatusCode,
+};
+use tokio::runtime::Handle;
+use tower::ServiceExt;
+
+use super::router;
+
+async fn bob() -> BobServer<StubAuthenticator> {
+    let config = node_config("0", 1);
+    let root = std::env::temp_dir().join(format!("bob_api_{}", std::process::id()));
+    std::fs::create_dir_all(root.join("0")).expect("create disk directory");
+    let cluster = with_disks_root(cluster_config(1, 1, 1), root.to_str().unwrap());
+    cluster.check(&config).expect("check node config");
+    let mapper = Virtual::new(&config, &cluster);
+    let grinder = Grinder::new(mapper, &config).await;
+    grinder.run_backend().await.expect("run backend");
+    BobServer::new(grinder, Handle::current(), Default::default(), StubAuthenticator::new())
+}
+
+async fn call(
+    bob: &BobServer<StubAuthenticator>,
+    request: Request<Body>,
+) -> (StatusCode, HeaderMap, Vec<u8>) {
+    let response = router::<StubAuthenticator>()
+        .layer(Extension(bob.clone()))
+        .oneshot(request)
+        .await
+        .unwrap();
+    let status = response.status();
+    let headers = response.headers().clone();
+    let mut body = response.into_body();
+    let mut data = vec![];
+    while let Some(chunk) = body.data().await {
+        data.extend_from_slice(&chunk.unwrap());
+    }
+    (status, headers, data)
+}
+
+fn request(method: Method, uri: &str, body: Body) -> Request<Body> {
+    Request::builder().method(method).uri(uri).body(body).unwrap()
+}
+
+async fn put_if_match(bob: &BobServer<StubAuthenticator>, if_match: Option<&str>) -> StatusCode {
+    let mut request = request(Method::POST, "/data/1", Body::from("data"));
+    if let Some(value) = if_match {
+        request.headers_mut().insert(IF_MATCH, value.parse().unwrap());
+    }
+    call(bob, request).await.0
+}
+
+#[tokio::test]
+async fn s3_routes_get_server_extension() {
+    let bob = bob().await;
+
+    let (status, _, _) = call(&bob, request(Method::PUT, "/s3/default/1", Body::from("s3 data"))).await;
+    assert_eq!(StatusCode::CREATED, status);
+
+    let (status, _, body) = call(&bob, request(Method::GET, "/s3/default/1", Body::empty())).await;
+    assert_eq!(StatusCode::OK, status);
+    assert_eq!(b"s3 data".to_vec(), body);
+}
+
+#[tokio::test]
+async fn if_match_accepts_any_and_etag_list() {
+    let bob = bob().await;
+
+    assert_eq!(StatusCode::PRECONDITION_FAILED, put_if_match(&bob, Some("*")).await);
+    assert_eq!(StatusCode::CREATED, put_if_match(&bob, None).await);
+
+    let (status, headers, _) = call(&bob, request(Method::GET, "/data/1", Body::empty())).await;
+    assert_eq!(StatusCode::OK, status);
+    let etag = headers[ETAG].to_str().unwrap().to_owned();
+
+    let weak = format!("W/{}", etag);
+    assert_eq!(StatusCode::PRECONDITION_FAILED, put_if_match(&bob, Some(&weak)).await);
+    let list = format!("\"other\", {}", etag);
+    assert_eq!(StatusCode::CREATED, put_if_match(&bob, Some(&list)).await);
+    assert_eq!(StatusCode::CREATED, put_if_match(&bob, Some("*")).await);
+    assert_eq!(StatusCode::BAD_REQUEST, put_if_match(&bob, Some("unquoted")).await);
+}
@@ -0,0 +1,127 @@
+use crate::link_manager::LinkManager;
+use crate::prelude::*;
+use bob_common::configs::node::HedgedReadConfig;
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+};
+use tokio::time::{self, sleep};
+
+const LATENCY_SAMPLES_COUNT: usize = 1024;
+const DELAY_UPDATE_PERIOD: usize = 64;
+
+/// Sends GET to the next replica if previous ones didn't answer within delay.
+/// Delay is either fixed or taken as percentile of the observed remote GET latency.
+pub(crate) struct HedgedRead {
+    delay: Duration,
+    latency_percentile: Option<f64>,
+    // Latency samples and count of samples recorded since the last delay update
+    latencies: Mutex<(VecDeque<Duration>, usize)>,
+    // Zero until enough latency samples are collected
+    percentile_delay_us: AtomicU64,
+}
+
+impl HedgedRead {
+    pub(crate) fn new(config: &HedgedReadConfig) -> Self {
+        Self {
+            delay: config.delay(),
+            latency_percentile: config.latency_percentile(),
+            latencies: Mutex::new((VecDeque::with_capacity(LATENCY_SAMPLES_COUNT), 0)),
+            percentile_delay_us: AtomicU64::new(0),
+        }
+    }
+
+    pub(crate) fn delay(&self) -> Duration {
+        match self.percentile_delay_us.load(Ordering::Relaxed) {
+            0 => self.delay,
+            us => Duration::from_micros(us),
+        }
+    }
+
+    pub(crate) fn record_latency(&self, latency: Duration) {
+        let percentile = if let Some(percentile) = self.latency_percentile {
+            percentile
+        } else {
+            return;
+        };
+        let mut guard = self.latencies.lock().expect("latencies mutex");
+        let (latencies, recorded) = &mut *guard;
+        if latencies.len() == LATENCY_SAMPLES_COUNT {
+            latencies.pop_front();
+        }
+        latencies.push_back(latency);
+        *recorded += 1;
+        if *recorded == DELAY_UPDATE_PERIOD {
+            *recorded = 0;
+            let mut sorted: Vec<_> = latencies.iter().copied().collect();
+            sorted.sort_unstable();
+            let index = ((sorted.len() as f64 * percentile / 100.0).ceil() as usize)
+                .clamp(1, sorted.len())
+                - 1;
+            let delay_us = (sorted[index].as_micros() as u64).max(1);
+            self.percentile_delay_us.store(delay_us, Ordering::Relaxed);
+        }
+    }
+
+    /// Returns first successful answer. Replicas are requested one by one: the next one is
+    /// requested when delay passes or the previous one fails, pending requests are cancelled
+    /// once the answer is received.
+    pub(crate) async fn get_any(
+        &self,
+        key: BobKey,
+        target_nodes: Vec<Node>,
+        options: GetOptions,
+    ) -> Option<NodeOutput<BobData>> {
+        let request = |node: Node, hedged: bool| {
+            let options = options.clone();
+            async move {
+                let start = Instant::now();
+                let result =
+                    LinkManager::call_node(&node, |conn| conn.get(key, options).boxed()).await;
+                (result, start.elapsed(), hedged)
+            }
+        };
+        let mut nodes = target_nodes.into_iter().peekable();
+        let mut requests = FuturesUnordered::new();
+        requests.push(request(nodes.next()?, false));
+        // Delay is counted from the last sent request, answers of other requests don't reset it
+        let delay = sleep(self.delay());
+        tokio::pin!(delay);
+        loop {
+            let has_next = nodes.peek().is_some();
+            tokio::select! {
+                Some((result, latency, hedged)) = requests.next() => {
+                    match result {
+                        Ok(answer) => {
+                            // Errors may return faster than data, they would lower the delay
+                            self.record_latency(latency);
+                            if hedged {
+                                counter!(GRINDER_GET_HEDGED_WON_COUNTER, 1);
+                            }
+                            return Some(answer);
+                        }
+                        Err(e) => {
+                            debug!("GET[{}] failed to get from node {}: {:?}", key, e.node_name(), e);
+                            if requests.is_empty() {
+                                requests.push(request(nodes.next()?, false));
+                                delay.as_mut().reset(time::Instant::now() + self.delay());
+                            }
+                        }
+                    }
+                }
+                _ = &mut delay, if has_next => {
+                    if let Some(node) = nodes.next() {
+                        debug!("GET[{}] hedged request to node {}", key, node.name());
+                        counter!(GRINDER_GET_HEDGED_COUNTER, 1);
+                        requests.push(request(node, true));
+                    }
+                    delay.as_mut().reset(time::Instant::now() + self.delay());
+                }
+                else => return None,
+            }
+        }
+    }
+}
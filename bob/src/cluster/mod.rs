@@ -1,3 +1,4 @@
+mod hedged_read;
 mod operations;
 mod quorum;
 mod simple;
@@ -6,26 +7,32 @@ mod support_types;
 #[cfg(test)]
 mod tests;
 
-use crate::prelude::*;
+use crate::{handoff::HandoffQueue, prelude::*};
 use quorum::Quorum;
 use simple::Quorum as SimpleQuorum;
 
 #[async_trait]
 pub(crate) trait Cluster {
-    async fn put(&self, key: BobKey, data: &BobData) -> Result<(), Error>;
-    async fn get(&self, key: BobKey) -> Result<BobData, Error>;
+    async fn put(&self, key: BobKey, data: &BobData, options: BobPutOptions) -> Result<(), Error>;
+    async fn get(&self, key: BobKey, options: &BobGetOptions) -> Result<BobData, Error>;
     async fn exist(&self, keys: &[BobKey]) -> Result<Vec<bool>, Error>;
-    async fn delete(&self, key: BobKey, meat: &BobMeta) -> Result<(), Error>;
+    async fn delete(&self, key: BobKey, meta: &BobMeta, options: BobDeleteOptions) -> Result<(), Error>;
 }
 
 pub(crate) fn get_cluster(
     mapper: Arc<Virtual>,
     config: &NodeConfig,
     backend: Arc<Backend>,
+    handoff: Option<Arc<HandoffQueue>>,
 ) -> Arc<dyn Cluster + Send + Sync> {
     match config.cluster_policy() {
         "simple" => Arc::new(SimpleQuorum::new(mapper, config.quorum())),
-        "quorum" => Arc::new(Quorum::new(backend, mapper, config.quorum())),
+        "quorum" => Arc::new(
+            Quorum::new(backend, mapper, config.quorum())
+                .with_hedged_read(config.hedged_read())
+                .with_locality(config.locality())
+                .with_handoff(handoff),
+        ),
         p => panic!("unknown cluster policy: {}", p),
     }
 }
@@ -1,6 +1,8 @@
 use crate::link_manager::LinkManager;
 use crate::prelude::*;
+use super::hedged_read::HedgedRead;
 use super::support_types::{RemoteDeleteError, RemotePutResponse, RemotePutError};
+use tracing::Instrument;
 
 pub(crate) type Tasks<Res, Err> = FuturesUnordered<JoinHandle<Result<NodeOutput<Res>, NodeOutput<Err>>>>;
 
@@ -66,6 +68,35 @@ pub(crate) async fn finish_at_least_handles<TRes: AffectedReplicasProvider, TErr
     (oks, errors)
 }
 
+// Waits for the remaining handles until successful puts cover `required_racks` racks.
+// `acked_racks` must contain racks of the replicas already written
+pub(crate) async fn finish_rack_spread(
+    mapper: &Virtual,
+    handles: &mut Tasks<RemotePutResponse, RemotePutError>,
+    oks: &mut Vec<NodeOutput<RemotePutResponse>>,
+    errors: &mut Vec<NodeOutput<RemotePutError>>,
+    mut acked_racks: HashSet<String>,
+    required_racks: usize,
+) -> usize {
+    let rack_of = |name: &NodeName| mapper.node_rack(name).unwrap_or_else(|| name.as_str()).to_string();
+    acked_racks.extend(oks.iter().map(|o| rack_of(o.node_name())));
+    if acked_racks.len() < required_racks && !handles.is_empty() {
+        counter!(GRINDER_PUT_RACK_SPREAD_WAIT_COUNTER, 1);
+    }
+    while acked_racks.len() < required_racks {
+        if let Some(join_res) = handles.next().await {
+            let oks_before = oks.len();
+            process_result(join_res, oks, errors);
+            if let Some(ok) = oks.get(oks_before) {
+                acked_racks.insert(rack_of(ok.node_name()));
+            }
+        } else {
+            break;
+        }
+    }
+    acked_racks.len()
+}
+
 async fn call_at_least<TOp, TRes: AffectedReplicasProvider, TErr: Debug>(
     target_nodes: impl Iterator<Item = TOp>,
     at_least: usize,
@@ -182,13 +213,40 @@ pub(crate) async fn lookup_remote_aliens(mapper: &Virtual, key: BobKey) -> Optio
     }
 }
 
-pub(crate) async fn lookup_remote_nodes(mapper: &Virtual, key: BobKey) -> Option<BobData> {
+// With `prefer_same_rack` replicas in the local rack are requested first, the others are
+// requested only if none of them answered (or hedged after them)
+pub(crate) async fn lookup_remote_nodes(
+    mapper: &Virtual,
+    key: BobKey,
+    hedged_read: Option<&HedgedRead>,
+    prefer_same_rack: bool,
+) -> Option<BobData> {
     let local_node = mapper.local_node_name();
     let target_nodes = mapper
         .get_target_nodes_for_key(key)
         .iter()
-        .filter(|node| node.name() != local_node);
-    let result = get_any(key, target_nodes, GetOptions::new_local()).await;
+        .filter(|node| node.name() != local_node)
+        .cloned();
+    let (same_rack, other_racks): (Vec<_>, Vec<_>) = if prefer_same_rack {
+        target_nodes.partition(|node| mapper.is_same_rack(node.name()))
+    } else {
+        (Vec::new(), target_nodes.collect())
+    };
+    if prefer_same_rack {
+        debug!(
+            "GET[{}] same rack nodes: {:?}, other racks nodes: {:?}",
+            key, same_rack, other_racks
+        );
+    }
+    let result = if let Some(hedged_read) = hedged_read {
+        let ordered = same_rack.into_iter().chain(other_racks).collect();
+        hedged_read.get_any(key, ordered, GetOptions::new_local()).await
+    } else {
+        match get_any(key, same_rack.iter(), GetOptions::new_local()).await {
+            Some(answer) => Some(answer),
+            None => get_any(key, other_racks.iter(), GetOptions::new_local()).await,
+        }
+    };
     if let Some(answer) = result {
         debug!(
             "GET[{}] take data from node: {}, timestamp: {}",
@@ -196,6 +254,13 @@ pub(crate) async fn lookup_remote_nodes(mapper: &Virtual, key: BobKey) -> Option
             answer.node_name(),
             answer.timestamp()
         );
+        if prefer_same_rack {
+            if mapper.is_same_rack(answer.node_name()) {
+                counter!(GRINDER_GET_SAME_RACK_COUNTER, 1);
+            } else {
+                counter!(GRINDER_GET_OTHER_RACK_COUNTER, 1);
+            }
+        }
         Some(answer.into_inner())
     } else {
         debug!("GET[{}] data not found on any node in regular dir", key);
@@ -203,6 +268,21 @@ pub(crate) async fn lookup_remote_nodes(mapper: &Virtual, key: BobKey) -> Option
     }
 }
 
+pub(crate) async fn lookup_all_remote_nodes(
+    mapper: &Virtual,
+    key: BobKey,
+) -> Vec<Result<NodeOutput<BobData>, NodeOutput<Error>>> {
+    let local_node = mapper.local_node_name();
+    let target_nodes = mapper
+        .get_target_nodes_for_key(key)
+        .iter()
+        .filter(|node| node.name() != local_node);
+    LinkManager::call_nodes(target_nodes, |conn| {
+        conn.get(key, GetOptions::new_local()).boxed()
+    })
+    .await
+}
+
 // ==================== PUT ======================
 
 fn call_node_put(
@@ -220,7 +300,7 @@ fn call_node_put(
             .map(|o| o.map(|_| RemotePutResponse::new(affected_replicas)))
             .map_err(|o| o.map(|e| RemotePutError::new(affected_replicas, e)))
     };
-    tokio::spawn(task)
+    tokio::spawn(task.in_current_span())
 }
 
 
@@ -253,7 +333,7 @@ pub(crate) async fn put_local_all(
         op.set_remote_node_name(node_name.clone());
         debug!("PUT[{}] put to local alien: {:?}", key, node_name);
 
-        if let Err(e) = backend.put_local(key, data, op).await {
+        if let Err(e) = backend.put_local(key, data, op, None).await {
             debug!("PUT[{}] local support put result: {:?}", key, e);
             add_nodes.push(node_name);
         }
@@ -292,24 +372,32 @@ pub(crate) async fn put_sup_nodes(
     }
 }
 
+// Returns count of successful puts and flag if any replica rejected put by condition
 pub(crate) async fn put_local_node_all(
     backend: &Backend,
     key: BobKey,
     data: &BobData,
     vdisk_id: VDiskId,
-    disk_paths: smallvec::SmallVec<[DiskPath; 1]>
-) -> usize {
+    disk_paths: smallvec::SmallVec<[DiskPath; 1]>,
+    condition: Option<BobWriteCondition>,
+) -> (usize, bool) {
     let mut successes = 0;
+    let mut rejected = false;
     for path in disk_paths {
-        let res = put_local_node(&backend, key, data, vdisk_id, path).await;
-        if let Err(e) = res {
-            error!("{}", e);
-        } else {
-            successes += 1;
-            debug!("PUT[{}] local node put successful", key);
+        let res = put_local_node(backend, key, data, vdisk_id, path, condition.clone()).await;
+        match res {
+            Err(e) if e.is_precondition_failed() => {
+                debug!("PUT[{}] local node rejected put by condition {:?}", key, condition);
+                rejected = true;
+            }
+            Err(e) => error!("{}", e),
+            Ok(()) => {
+                successes += 1;
+                debug!("PUT[{}] local node put successful", key);
+            }
         }
     }
-    successes
+    (successes, rejected)
 }
 
 pub(crate) async fn put_local_node(
@@ -318,10 +406,11 @@ pub(crate) async fn put_local_node(
     data: &BobData,
     vdisk_id: VDiskId,
     disk_path: DiskPath,
+    condition: Option<BobWriteCondition>,
 ) -> Result<(), Error> {
     debug!("local node has vdisk replica, put local");
     let op = Operation::new_local(vdisk_id, disk_path);
-    backend.put_local(key, data, op).await
+    backend.put_local(key, data, op, condition).await
 }
 
 // =================== EXIST ==================
@@ -374,10 +463,11 @@ pub(crate) async fn delete_on_local_node(
     meta: &BobMeta,
     vdisk_id: VDiskId,
     disk_path: DiskPath,
+    condition: Option<BobWriteCondition>,
 ) -> Result<(), Error> {
     trace!("local node has vdisk replica, delete local");
     let op = Operation::new_local(vdisk_id, disk_path);
-    backend.delete_local(key, meta, op, true).await?;
+    backend.delete_local(key, meta, op, true, condition).await?;
     Ok(())
 }
 
@@ -397,7 +487,7 @@ pub(crate) async fn delete_on_local_aliens(
         trace!("DELETE[{}] delete to local alien: {:?}", key, node_name);
         let force_delete = force_nodes.contains(&node_name);
         op.set_remote_node_name(node_name);
-        if let Err(e) = backend.delete_local(key, meta, op, force_delete).await {
+        if let Err(e) = backend.delete_local(key, meta, op, force_delete, None).await {
             trace!("DELETE[{}] local alien delete result: {:?}", key, e);
             if force_delete {
                 fully_failed_nodes.push(node.name().clone());
@@ -426,7 +516,7 @@ fn call_node_delete(
         let call_result = LinkManager::call_node(&node, |conn| conn.delete(key, meta, grpc_options).boxed()).await;
         call_result.map_err(|err| err.map(|inner| RemoteDeleteError::new(force_alien_nodes_copy, inner)))
     };
-    tokio::spawn(task)
+    tokio::spawn(task.in_current_span())
 }
 
 
@@ -1,10 +1,16 @@
-use crate::prelude::*;
+use crate::{
+    handoff::{HandoffQueue, Hint},
+    prelude::*,
+};
+use bob_common::configs::node::{HedgedReadConfig, LocalityConfig};
+use tracing::Instrument;
 
 use super::{
+    hedged_read::HedgedRead,
     operations::{
         delete_on_local_aliens, delete_on_local_node, delete_on_remote_nodes,
         delete_on_remote_nodes_with_options, exist_on_local_alien, exist_on_local_node,
-        exist_on_remote_aliens, exist_on_remote_nodes, lookup_local_alien,
+        exist_on_remote_aliens, exist_on_remote_nodes, finish_rack_spread, lookup_all_remote_nodes, lookup_local_alien,
         lookup_local_node, lookup_remote_aliens, lookup_remote_nodes, put_at_least, put_local_all,
         put_local_node_all, put_sup_nodes, Tasks,
     },
@@ -17,6 +23,10 @@ pub(crate) struct Quorum {
     backend: Arc<Backend>,
     mapper: Arc<Virtual>,
     quorum: usize,
+    hedged_read: Option<Arc<HedgedRead>>,
+    prefer_local_reads: bool,
+    spread_writes_across_racks: bool,
+    handoff: Option<Arc<HandoffQueue>>,
 }
 
 impl Quorum {
@@ -25,61 +35,197 @@ impl Quorum {
             backend,
             mapper,
             quorum,
+            hedged_read: None,
+            prefer_local_reads: false,
+            spread_writes_across_racks: false,
+            handoff: None,
+        }
+    }
+
+    pub(crate) fn with_hedged_read(mut self, config: Option<&HedgedReadConfig>) -> Self {
+        self.hedged_read = config.map(|config| Arc::new(HedgedRead::new(config)));
+        self
+    }
+
+    pub(crate) fn with_locality(mut self, config: &LocalityConfig) -> Self {
+        self.prefer_local_reads = config.prefer_local_reads();
+        self.spread_writes_across_racks = config.spread_writes_across_racks();
+        self
+    }
+
+    pub(crate) fn with_handoff(mut self, handoff: Option<Arc<HandoffQueue>>) -> Self {
+        self.handoff = handoff;
+        self
+    }
+
+    // Saves hint for remote nodes if handoff is enabled
+    async fn handoff(&self, node_names: Vec<NodeName>, hint: Hint) {
+        if let Some(handoff) = &self.handoff {
+            let local_node = self.mapper.local_node_name();
+            let node_names: Vec<_> = node_names.into_iter().filter(|n| n != local_node).collect();
+            handoff.push_for_nodes(&node_names, hint).await;
+        }
+    }
+
+    fn required_replicas(
+        &self,
+        replicas_by_node: &HashMap<NodeName, usize>,
+        consistency: Option<BobConsistencyLevel>,
+    ) -> Result<usize, Error> {
+        match consistency {
+            Some(level) => level.required_replicas(replicas_by_node.values().sum()),
+            None => Ok(self.quorum),
         }
     }
 
     // ================== PUT ==================
 
-    async fn put_at_least(&self, key: BobKey, data: &BobData) -> Result<(), Error> {
+    async fn put_at_least(
+        &self,
+        key: BobKey,
+        data: &BobData,
+        options: &BobPutOptions,
+    ) -> Result<(), Error> {
+        let condition = options.condition();
+        let consistency = options.consistency();
         let mut local_put_ok = 0_usize;
-        let at_least = self.quorum;
+        let affected_replicas_by_node = self.mapper.get_replicas_count_by_node(key);
+        let at_least = self.required_replicas(&affected_replicas_by_node, consistency)?;
         let mut failed_nodes = Vec::new();
+        let mut rejected_nodes = Vec::new();
         let (vdisk_id, disk_paths) = self.mapper.get_operation(key);
-        let affected_replicas_by_node = self.mapper.get_replicas_count_by_node(key);
-        let (tasks, oks, errors) = 
+        let (mut tasks, mut oks, mut errors) = 
             if let Some(paths) = disk_paths {
                 let paths_len = paths.len();
-                debug!("PUT[{}] ~~~PUT {} REPLICAS TO REMOTE NODES AND {} REPLICAS TO LOCAL NODE~~~", key, at_least - paths_len, paths_len);
-                let ((tasks, oks, errors), local_puts) = tokio::join!(
-                    self.put_remote_nodes(key, data, at_least, &affected_replicas_by_node),
-                    put_local_node_all(&self.backend, key, data, vdisk_id, paths));
-                if local_puts != paths_len {
+                debug!("PUT[{}] ~~~PUT {} REPLICAS TO REMOTE NODES AND {} REPLICAS TO LOCAL NODE~~~", key, at_least.saturating_sub(paths_len), paths_len);
+                let ((tasks, oks, errors), (local_puts, local_rejected)) = tokio::join!(
+                    self.put_remote_nodes(key, data, at_least, &affected_replicas_by_node, condition.clone()),
+                    put_local_node_all(&self.backend, key, data, vdisk_id, paths, condition.clone()));
+                if local_rejected {
+                    rejected_nodes.push(self.mapper.local_node_name().to_owned());
+                }
+                if local_puts != paths_len && !local_rejected {
                     failed_nodes.push(self.mapper.local_node_name().to_owned());
                 }
                 local_put_ok += local_puts;
                 (tasks, oks, errors)
             } else {
                 debug!("PUT[{}] ~~~PUT {} REPLICAS TO REMOTE NODES~~~", key, at_least);
-                self.put_remote_nodes(key, data, at_least, &affected_replicas_by_node).await
+                self.put_remote_nodes(key, data, at_least, &affected_replicas_by_node, condition.clone()).await
             };
+        if self.spread_writes_across_racks {
+            let target_nodes = self.mapper.get_target_nodes_for_key(key);
+            let required_racks = at_least.min(self.mapper.racks_count(target_nodes));
+            let mut acked_racks = HashSet::new();
+            if local_put_ok > 0 {
+                let local_node = self.mapper.local_node_name();
+                let local_rack = self.mapper.local_rack().unwrap_or_else(|| local_node.as_str());
+                acked_racks.insert(local_rack.to_string());
+            }
+            let spread = finish_rack_spread(&self.mapper, &mut tasks, &mut oks, &mut errors, acked_racks, required_racks).await;
+            debug!("PUT[{}] data written to {} racks, required: {}", key, spread, required_racks);
+        }
         let remote_ok_count = oks.iter().map(|f| f.inner().affected_replicas()).sum::<usize>();
-        failed_nodes.extend(errors.iter().map(|e| e.node_name().clone()));
+        // Nodes that rejected put by condition hold another version of data, so they
+        // must not be replaced by aliens
+        rejected_nodes.extend(
+            errors
+                .iter()
+                .filter(|e| e.inner().error().is_precondition_failed())
+                .map(|e| e.node_name().clone()),
+        );
+        failed_nodes.extend(
+            errors
+                .iter()
+                .filter(|e| !e.inner().error().is_precondition_failed())
+                .map(|e| e.node_name().clone()),
+        );
         debug!("PUT[{}] LOCAL PUT OK: {}, REMOTE PUT OK: {}, REMOTE PUT ERRORS: {}", 
                key, local_put_ok, remote_ok_count, 
                errors.iter().map(|e| e.inner().affected_replicas()).sum::<usize>());
-        if remote_ok_count + local_put_ok >= self.quorum {
+        if remote_ok_count + local_put_ok >= at_least {
+            if !rejected_nodes.is_empty() {
+                self.spawn_repair_put(key, data, rejected_nodes);
+            }
             if tasks.is_empty() && failed_nodes.is_empty() {
                 return Ok(());
             }
 
+            let permit = self.handoff.as_ref().map(|handoff| handoff.try_acquire_background_put());
+            if let Some(None) = permit {
+                let answered: HashSet<_> = oks
+                    .iter()
+                    .map(|o| o.node_name())
+                    .chain(errors.iter().map(|e| e.node_name()))
+                    .collect();
+                let local_node = self.mapper.local_node_name();
+                let not_answered: Vec<_> = self
+                    .mapper
+                    .get_target_nodes_for_key(key)
+                    .iter()
+                    .map(|node| node.name())
+                    .filter(|name| *name != local_node && !answered.contains(name))
+                    .cloned()
+                    .collect();
+                warn!(
+                    "PUT[{}] too many background puts, {:?} go to handoff queue",
+                    key, not_answered
+                );
+                tasks.iter().for_each(|task| task.abort());
+                self.handoff(not_answered, Hint::Put(key, data.clone())).await;
+                if !failed_nodes.is_empty() {
+                    if let Err(e) = self.put_aliens(failed_nodes, key, data).await {
+                        error!("{}", e);
+                    }
+                }
+                return Ok(());
+            }
             debug!("PUT[{}] spawn {} background put tasks", key, tasks.len());
             let q = self.clone();
             let data = data.clone();
-            tokio::spawn(async move { q.background_put(tasks, key, &data, failed_nodes).await });
+            tokio::spawn(
+                async move {
+                    q.background_put(tasks, key, &data, failed_nodes).await;
+                    drop(permit);
+                }
+                .in_current_span(),
+            );
             Ok(())
+        } else if !rejected_nodes.is_empty() {
+            warn!(
+                "PUT[{}] rejected by condition {:?}. ok {}, quorum {}, errors: {:?}",
+                key,
+                condition,
+                remote_ok_count + local_put_ok,
+                at_least,
+                errors
+            );
+            let mut applied: Vec<_> = oks.iter().map(|o| o.node_name().clone()).collect();
+            if local_put_ok > 0 {
+                applied.push(self.mapper.local_node_name().clone());
+            }
+            Err(condition_rejected(applied, rejected_nodes))
         } else {
             assert!(tasks.is_empty(), "All target nodes put are expected to be completed before alien put begins");
             warn!(
                 "PUT[{}] quorum was not reached. ok {}, quorum {}, errors: {:?}",
                 key,
                 remote_ok_count + local_put_ok,
-                self.quorum,
+                at_least,
                 errors
             );
             if let Err(err) = self.put_aliens(failed_nodes, key, data).await {
                 error!("PUT[{}] smth wrong with cluster/node configuration", key);
                 error!("PUT[{}] node errors: {:?}", key, errors);
                 Err(err)
+            } else if let Some(level) = consistency {
+                warn!("PUT[{}] data get into alien, but consistency level {:?} was not reached", key, level);
+                Err(Error::consistency_level_not_reached(format!(
+                    "consistency level {:?} was not reached: ok {}, required {}",
+                    level,
+                    remote_ok_count + local_put_ok,
+                    at_least
+                )))
             } else {
                 warn!("PUT[{}] succeed, but some data get into alien", key);
                 Ok(())
@@ -95,6 +241,7 @@ impl Quorum {
         mut failed_nodes: Vec<NodeName>
     ) {
         debug!("PUT[{}] ~~~BACKGROUND PUT TO REMOTE NODES~~~", key);
+        let mut rejected_nodes = vec![];
         while let Some(join_res) = rest_tasks.next().await {
             match join_res {
                 Ok(Ok(output)) => debug!(
@@ -102,6 +249,10 @@ impl Quorum {
                     key,
                     output.node_name()
                 ),
+                Ok(Err(e)) if e.inner().error().is_precondition_failed() => {
+                    warn!("PUT[{}] background put rejected by condition on: {}", key, e.node_name());
+                    rejected_nodes.push(e.node_name().clone());
+                }
                 Ok(Err(e)) => {
                     error!("{:?}", e);
                     failed_nodes.push(e.node_name().clone());
@@ -109,6 +260,9 @@ impl Quorum {
                 Err(e) => error!("{:?}", e),
             }
         }
+        if !rejected_nodes.is_empty() {
+            self.repair_put(key, data, rejected_nodes).await;
+        }
         debug!("PUT[{}] ~~~PUT TO REMOTE NODES ALIEN~~~", key);
         if !failed_nodes.is_empty() {
             if let Err(e) = self.put_aliens(failed_nodes, key, &data).await {
@@ -117,12 +271,48 @@ impl Quorum {
         }
     }
 
+    fn spawn_repair_put(&self, key: BobKey, data: &BobData, nodes: Vec<NodeName>) {
+        let q = self.clone();
+        let data = data.clone();
+        tokio::spawn(async move { q.repair_put(key, &data, nodes).await }.in_current_span());
+    }
+
+    /// Conditional put reached quorum, replicas that rejected it hold another version. They
+    /// get the accepted version without condition, the newest version still wins on read.
+    async fn repair_put(&self, key: BobKey, data: &BobData, nodes: Vec<NodeName>) {
+        debug!("PUT[{}] repair replicas that rejected put by condition: {:?}", key, nodes);
+        let local_node = self.mapper.local_node_name();
+        let (vdisk_id, disk_paths) = self.mapper.get_operation(key);
+        if let Some(paths) = disk_paths.filter(|_| nodes.contains(local_node)) {
+            let paths_len = paths.len();
+            let (local_puts, _) = put_local_node_all(&self.backend, key, data, vdisk_id, paths, None).await;
+            if local_puts != paths_len {
+                error!("PUT[{}] failed to repair local replica", key);
+            }
+        }
+        let targets: Vec<_> = self
+            .mapper
+            .get_target_nodes_for_key(key)
+            .iter()
+            .filter(|n| n.name() != local_node && nodes.contains(n.name()))
+            .collect();
+        let replicas = self.mapper.get_replicas_count_by_node(key);
+        let (_, _, errors) =
+            put_at_least(key, data, targets.iter().copied(), targets.len(), BobPutOptions::new_local(), &replicas).await;
+        let failed: Vec<_> = errors.iter().map(|e| e.node_name().clone()).collect();
+        if !failed.is_empty() {
+            error!("PUT[{}] failed to repair replicas: {:?}", key, failed);
+            self.handoff(failed, Hint::Put(key, data.clone())).await;
+        }
+    }
+
     async fn put_remote_nodes(
         &self,
         key: BobKey,
         data: &BobData,
         at_least: usize,
-        affected_replicas_by_node: &HashMap<NodeName, usize>
+        affected_replicas_by_node: &HashMap<NodeName, usize>,
+        condition: Option<BobWriteCondition>,
     ) -> (Tasks<RemotePutResponse, RemotePutError>, Vec<NodeOutput<RemotePutResponse>>, Vec<NodeOutput<RemotePutError>>) {
         let local_node = self.mapper.local_node_name();
         let target_nodes = self.mapper.get_target_nodes_for_key(key);
@@ -132,7 +322,8 @@ impl Quorum {
             target_nodes.len(),
         );
         let target_nodes = target_nodes.iter().filter(|node| node.name() != local_node);
-        put_at_least(key, data, target_nodes, at_least, BobPutOptions::new_local(), affected_replicas_by_node).await
+        let options = BobPutOptions::new_local().with_condition(condition);
+        put_at_least(key, data, target_nodes, at_least, options, affected_replicas_by_node).await
     }
 
 
@@ -178,6 +369,7 @@ impl Quorum {
                 "PUT[{}] local put failed, smth wrong with backend: {:?}",
                 key, e
             );
+            self.handoff(e.remote_nodes().to_vec(), Hint::Put(key, data.clone())).await;
             Err(Error::internal())
         } else {
             Ok(())
@@ -187,26 +379,78 @@ impl Quorum {
 
     // =============================== DELETE ======================
 
-    async fn delete_on_nodes(&self, key: BobKey, meta: &BobMeta) -> Result<(), Error> {
+    async fn delete_on_nodes(
+        &self,
+        key: BobKey,
+        meta: &BobMeta,
+        options: &BobDeleteOptions,
+    ) -> Result<(), Error> {
+        let condition = options.condition();
+        let consistency = options.consistency();
+        let replicas_by_node = self.mapper.get_replicas_count_by_node(key);
+        let required = self.required_replicas(&replicas_by_node, consistency)?;
         debug!("DELETE[{}] ~~~DELETE LOCAL NODE FIRST~~~", key);
         let (vdisk_id, disk_paths) = self.mapper.get_operation(key);
+        let local_node = self.mapper.local_node_name();
         let mut failed_nodes = HashSet::new();
+        let mut rejected_nodes = HashSet::new();
+        let mut failed_replicas = 0;
         let mut total = 0;
         if let Some(disk_paths) = disk_paths {
             total += 1;
             for disk_path in disk_paths {
-                let res = delete_on_local_node(&self.backend, key, meta, vdisk_id, disk_path).await;
-                if let Err(e) = res {
-                    error!("{}", e);
-                    failed_nodes.insert(self.mapper.local_node_name().clone());
+                let res = delete_on_local_node(&self.backend, key, meta, vdisk_id, disk_path, condition.clone()).await;
+                match res {
+                    Err(e) if e.is_precondition_failed() => {
+                        failed_replicas += 1;
+                        rejected_nodes.insert(local_node.clone());
+                    }
+                    Err(e) => {
+                        error!("{}", e);
+                        failed_replicas += 1;
+                        failed_nodes.insert(self.mapper.local_node_name().clone());
+                    }
+                    Ok(()) => {}
                 }
             }
         };
 
         debug!("DELETE[{}] ~~~DELETE TO REMOTE NODES~~~", key);
-        let (errors, remote_count) = self.delete_at_remote_nodes(key, meta).await;
+        let (errors, remote_count) = self.delete_at_remote_nodes(key, meta, condition.clone()).await;
         total += remote_count;
-        failed_nodes.extend(errors.iter().map(|o| o.node_name().clone()));
+        rejected_nodes.extend(
+            errors
+                .iter()
+                .filter(|o| o.inner().error().is_precondition_failed())
+                .map(|o| o.node_name().clone()),
+        );
+        failed_replicas += errors
+            .iter()
+            .map(|o| replicas_by_node.get(o.node_name()).copied().unwrap_or(1))
+            .sum::<usize>();
+        failed_nodes.extend(
+            errors
+                .iter()
+                .filter(|o| !o.inner().error().is_precondition_failed())
+                .map(|o| o.node_name().clone()),
+        );
+
+        let deleted_replicas = replicas_by_node.values().sum::<usize>().saturating_sub(failed_replicas);
+        if !rejected_nodes.is_empty() {
+            warn!(
+                "DELETE[{}] rejected by condition {:?}. total {}, failed {:?}, errors: {:?}",
+                key, condition, total, failed_nodes, errors
+            );
+            if deleted_replicas < required {
+                let applied: Vec<_> = replicas_by_node
+                    .keys()
+                    .filter(|n| !rejected_nodes.contains(*n) && !failed_nodes.contains(*n))
+                    .cloned()
+                    .collect();
+                return Err(condition_rejected(applied, rejected_nodes.into_iter().collect()));
+            }
+            self.spawn_repair_delete(key, meta, rejected_nodes);
+        }
 
         if !failed_nodes.is_empty() {
             warn!(
@@ -218,17 +462,59 @@ impl Quorum {
         if let Err(err) = self.delete_aliens(failed_nodes, key, meta).await {
             error!("DELETE[{}] delete failed. Smth wrong with cluster/node configuration", key);
             Err(err)
+        } else if consistency.is_some() && deleted_replicas < required {
+            warn!(
+                "DELETE[{}] consistency level {:?} was not reached: deleted {}, required {}",
+                key, consistency, deleted_replicas, required
+            );
+            Err(Error::consistency_level_not_reached(format!(
+                "consistency level {:?} was not reached: deleted {}, required {}",
+                consistency, deleted_replicas, required
+            )))
         } else {
             debug!("DELETE[{}] succeed", key);
             Ok(())
         }
     }
 
+    fn spawn_repair_delete(&self, key: BobKey, meta: &BobMeta, nodes: HashSet<NodeName>) {
+        let q = self.clone();
+        let meta = meta.clone();
+        tokio::spawn(async move { q.repair_delete(key, &meta, nodes).await }.in_current_span());
+    }
+
+    /// Conditional delete reached quorum, replicas that rejected it get the delete without
+    /// condition.
+    async fn repair_delete(&self, key: BobKey, meta: &BobMeta, nodes: HashSet<NodeName>) {
+        debug!("DELETE[{}] repair replicas that rejected delete by condition: {:?}", key, nodes);
+        let local_node = self.mapper.local_node_name();
+        let (vdisk_id, disk_paths) = self.mapper.get_operation(key);
+        if let Some(paths) = disk_paths.filter(|_| nodes.contains(local_node)) {
+            for path in paths {
+                if let Err(e) = delete_on_local_node(&self.backend, key, meta, vdisk_id, path, None).await {
+                    error!("DELETE[{}] failed to repair local replica: {:?}", key, e);
+                }
+            }
+        }
+        let targets: Vec<_> = self
+            .mapper
+            .get_target_nodes_for_key(key)
+            .iter()
+            .filter(|n| n.name() != local_node && nodes.contains(n.name()))
+            .collect();
+        let errors = delete_on_remote_nodes_with_options(key, meta, targets, BobDeleteOptions::new_local()).await;
+        let failed: Vec<_> = errors.iter().map(|e| e.node_name().clone()).collect();
+        if !failed.is_empty() {
+            error!("DELETE[{}] failed to repair replicas: {:?}", key, failed);
+            self.handoff(failed, Hint::Delete(key, meta.clone())).await;
+        }
+    }
 
     async fn delete_at_remote_nodes(
         &self,
         key: BobKey,
         meta: &BobMeta,
+        condition: Option<BobWriteCondition>,
     ) -> (Vec<NodeOutput<RemoteDeleteError>>, usize) {
         let local_node = self.mapper.local_node_name();
         let target_nodes: Vec<_> = self
@@ -244,8 +530,9 @@ impl Quorum {
         );
 
         let count = target_nodes.len();
+        let options = BobDeleteOptions::new_local().with_condition(condition);
         (
-            delete_on_remote_nodes_with_options(key, meta, target_nodes, BobDeleteOptions::new_local()).await,
+            delete_on_remote_nodes_with_options(key, meta, target_nodes, options).await,
             count
         )
     }
@@ -315,11 +602,82 @@ impl Quorum {
                 "DELETE[{}] local delete failed, smth wrong with backend: {:?}",
                 key, e
             );
+            self.handoff(e, Hint::Delete(key, meta.clone())).await;
             return Err(Error::internal());
         }
         Ok(())
     }
 
+    // =============================== GET ======================
+
+    // Reads all target replicas and returns the newest record, if at least `required`
+    // of them answered
+    async fn get_from_replicas(
+        &self,
+        key: BobKey,
+        required: usize,
+        replicas_by_node: &HashMap<NodeName, usize>,
+    ) -> Result<BobData, Error> {
+        debug!("GET[{}] ~~~LOOKUP {} REPLICAS~~~", key, required);
+        let (vdisk_id, disk_paths) = self.mapper.get_operation(key);
+        let mut answered = 0;
+        let mut newest: Option<BobData> = None;
+        let mut update_newest = |data: BobData| {
+            if !matches!(&newest, Some(n) if n.meta().timestamp() >= data.meta().timestamp()) {
+                newest = Some(data);
+            }
+        };
+        if let Some(paths) = disk_paths {
+            for path in paths {
+                match self.backend.get_local(key, Operation::new_local(vdisk_id, path)).await {
+                    Ok(data) => {
+                        answered += 1;
+                        update_newest(data);
+                    }
+                    Err(e) if e.is_key_not_found() => answered += 1,
+                    Err(e) => error!("GET[{}] local node backend returned error: {}", key, e),
+                }
+            }
+        }
+        for result in lookup_all_remote_nodes(&self.mapper, key).await {
+            match result {
+                Ok(output) => {
+                    answered += replicas_by_node.get(output.node_name()).copied().unwrap_or(1);
+                    update_newest(output.into_inner());
+                }
+                Err(e) if e.inner().is_key_not_found() => {
+                    answered += replicas_by_node.get(e.node_name()).copied().unwrap_or(1);
+                }
+                Err(e) => debug!("GET[{}] failed to get from node {}: {:?}", key, e.node_name(), e),
+            }
+        }
+        if answered < required {
+            warn!("GET[{}] consistency level was not reached: answered {}, required {}", key, answered, required);
+            return Err(Error::consistency_level_not_reached(format!(
+                "consistency level was not reached: answered {}, required {}",
+                answered, required
+            )));
+        }
+        match newest {
+            Some(data) => Ok(data),
+            None => self.get_from_aliens(key, vdisk_id).await,
+        }
+    }
+
+    async fn get_from_aliens(&self, key: BobKey, vdisk_id: VDiskId) -> Result<BobData, Error> {
+        debug!("GET[{}] ~~~LOOKUP LOCAL NODE ALIEN~~~", key);
+        if let Some(data) = lookup_local_alien(&self.backend, key, vdisk_id).await {
+            return Ok(data);
+        }
+
+        debug!("GET[{}] ~~~LOOKUP REMOTE NODES ALIEN~~~", key);
+        if let Some(data) = lookup_remote_aliens(&self.mapper, key).await {
+            return Ok(data);
+        }
+        debug!("GET[{}] Key not found", key);
+        Err(Error::key_not_found(key))
+    }
+
     // =============================== EXIST ======================
 
     async fn collect_remote_exists(
@@ -412,12 +770,19 @@ impl Quorum {
 
 #[async_trait]
 impl Cluster for Quorum {
-    async fn put(&self, key: BobKey, data: &BobData) -> Result<(), Error> {
-        self.put_at_least(key, data).await
+    async fn put(&self, key: BobKey, data: &BobData, options: BobPutOptions) -> Result<(), Error> {
+        self.put_at_least(key, data, &options).await
     }
 
     //todo check no data (no error)
-    async fn get(&self, key: BobKey) -> Result<BobData, Error> {
+    async fn get(&self, key: BobKey, options: &BobGetOptions) -> Result<BobData, Error> {
+        let replicas_by_node = self.mapper.get_replicas_count_by_node(key);
+        let required = self.required_replicas(&replicas_by_node, options.consistency())?;
+        if let Some(level) = options.consistency() {
+            if level != BobConsistencyLevel::One {
+                return self.get_from_replicas(key, required, &replicas_by_node).await;
+            }
+        }
         debug!("GET[{}] ~~~LOOKUP LOCAL NODE~~~", key);
         let (vdisk_id, disk_paths) = self.mapper.get_operation(key);
         if let Some(paths) = disk_paths {
@@ -428,7 +793,7 @@ impl Cluster for Quorum {
             }
         }
         debug!("GET[{}] ~~~LOOKUP REMOTE NODES~~~", key);
-        if let Some(data) = lookup_remote_nodes(&self.mapper, key).await {
+        if let Some(data) = lookup_remote_nodes(&self.mapper, key, self.hedged_read.as_deref(), self.prefer_local_reads).await {
             return Ok(data);
         }
         debug!("GET[{}] ~~~LOOKUP LOCAL NODE ALIEN~~~", key);
@@ -436,12 +801,7 @@ impl Cluster for Quorum {
             return Ok(data);
         }
 
-        debug!("GET[{}] ~~~LOOKUP REMOTE NODES ALIEN~~~", key);
-        if let Some(data) = lookup_remote_aliens(&self.mapper, key).await {
-            return Ok(data);
-        }
-        debug!("GET[{}] Key not found", key);
-        Err(Error::key_not_found(key))
+        self.get_from_aliens(key, vdisk_id).await
     }
 
     async fn exist(&self, keys: &[BobKey]) -> Result<Vec<bool>, Error> {
@@ -518,7 +878,20 @@ impl Cluster for Quorum {
         Ok(result)
     }
 
-    async fn delete(&self, key: BobKey, meta: &BobMeta) -> Result<(), Error> {
-        self.delete_on_nodes(key, meta).await
+    async fn delete(&self, key: BobKey, meta: &BobMeta, options: BobDeleteOptions) -> Result<(), Error> {
+        self.delete_on_nodes(key, meta, &options).await
+    }
+}
+
+/// Plain `PreconditionFailed` if no replica applied the conditional write, otherwise client
+/// must know that some replicas hold the new version.
+fn condition_rejected(applied: Vec<NodeName>, rejected: Vec<NodeName>) -> Error {
+    if applied.is_empty() {
+        Error::precondition_failed()
+    } else {
+        Error::condition_partially_applied(format!(
+            "applied on {:?}, rejected on {:?}",
+            applied, rejected
+        ))
     }
 }
@@ -39,10 +39,22 @@ impl Quorum {
         keys_by_nodes
     }
 
+    fn required_replicas(
+        &self,
+        key: BobKey,
+        consistency: Option<BobConsistencyLevel>,
+    ) -> Result<usize, Error> {
+        match consistency {
+            Some(level) => level.required_replicas(self.get_target_nodes(key).len()),
+            None => Ok(self.quorum),
+        }
+    }
+
     async fn perform_on_nodes<F, T>(
         &self,
         key: BobKey,
         metrics_prefix: &str,
+        l_quorum: usize,
         client_fun: F,
     ) -> Result<(), Error>
     where
@@ -56,7 +68,6 @@ impl Quorum {
             metrics_prefix, key, &target_nodes
         );
 
-        let l_quorum = self.quorum as usize;
         let reqs = LinkManager::call_nodes(target_nodes.iter(), client_fun);
         let results = reqs.await;
         let total_count = results.len();
@@ -69,6 +80,8 @@ impl Quorum {
         // TODO: send actuall list of vdisk it has been written on
         if ok_count >= l_quorum {
             Ok(())
+        } else if errors.iter().any(|r| matches!(r, Err(e) if e.inner().is_precondition_failed())) {
+            Err(Error::precondition_failed())
         } else {
             Err(Error::failed(format!(
                 "failed: total requests: {}, ok: {}, quorum: {}, errors: {:?}",
@@ -80,28 +93,48 @@ impl Quorum {
 
 #[async_trait]
 impl Cluster for Quorum {
-    async fn put(&self, key: BobKey, data: &BobData) -> Result<(), Error> {
-        self.perform_on_nodes(key, "PUT", |c| {
+    async fn put(&self, key: BobKey, data: &BobData, options: BobPutOptions) -> Result<(), Error> {
+        let grpc_options = BobPutOptions::new_local()
+            .with_condition(options.condition())
+            .to_grpc();
+        let quorum = self.required_replicas(key, options.consistency())?;
+        self.perform_on_nodes(key, "PUT", quorum, |c| {
             Box::pin(c.put(
                 key,
                 data.clone(),
-                PutOptions {
-                    remote_nodes: vec![], //TODO check
-                    force_node: true,
-                    overwrite: false,
-                }
+                grpc_options.clone(),
             ))
         })
         .await
     }
 
-    async fn get(&self, key: BobKey) -> Result<BobData, Error> {
+    async fn get(&self, key: BobKey, options: &BobGetOptions) -> Result<BobData, Error> {
+        let required = self.required_replicas(key, options.consistency())?;
         let target_nodes = self.get_target_nodes(key);
         debug!("GET[{}]: Nodes for fan out: {:?}", key, &target_nodes);
         let reqs = LinkManager::call_nodes(target_nodes.iter(), |conn| {
             conn.get(key, GetOptions::new_local()).boxed()
         });
         let results = reqs.await;
+        if options.consistency().is_some() {
+            // Replica that doesn't have the key still answers the request
+            let answered = results
+                .iter()
+                .filter(|r| r.as_ref().map_or_else(|e| e.inner().is_key_not_found(), |_| true))
+                .count();
+            if answered < required {
+                return Err(Error::consistency_level_not_reached(format!(
+                    "GET[{}] consistency level not reached: answered: {}, required: {}",
+                    key, answered, required
+                )));
+            }
+            return results
+                .into_iter()
+                .filter_map(Result::ok)
+                .map(|res| res.into_inner())
+                .max_by_key(|data| data.meta().timestamp())
+                .ok_or_else(|| Error::key_not_found(key));
+        }
         let ok_results = results
             .iter()
             .filter_map(|r| r.as_ref().ok())
@@ -133,9 +166,13 @@ impl Cluster for Quorum {
         Ok(exist)
     }
 
-    async fn delete(&self, key: BobKey, meta: &BobMeta) -> Result<(), Error> {
-        self.perform_on_nodes(key, "DELETE", move |c| {
-            Box::pin(c.delete(key, meta.clone(), DeleteOptions::new_local()))
+    async fn delete(&self, key: BobKey, meta: &BobMeta, options: BobDeleteOptions) -> Result<(), Error> {
+        let grpc_options = BobDeleteOptions::new_local()
+            .with_condition(options.condition())
+            .to_grpc();
+        let quorum = self.required_replicas(key, options.consistency())?;
+        self.perform_on_nodes(key, "DELETE", quorum, move |c| {
+            Box::pin(c.delete(key, meta.clone(), grpc_options.clone()))
         })
         .await
     }
@@ -43,7 +43,6 @@ impl RemotePutResponse {
 #[derive(Debug)]
 pub(crate) struct RemotePutError {
     affected_replicas: usize,
-    #[allow(dead_code)] // We need error for debug print
     error: Error
 }
 
@@ -55,6 +54,10 @@ impl RemotePutError {
     pub(crate) fn affected_replicas(&self) -> usize {
         self.affected_replicas
     }
+
+    pub(crate) fn error(&self) -> &Error {
+        &self.error
+    }
 }
 
 #[derive(Debug)]
@@ -71,7 +74,6 @@ impl RemoteDeleteError {
     pub(super) fn force_alien_nodes(&self) -> &[NodeName] {
         return &self.force_alien_nodes;
     }
-    #[allow(dead_code)]
     pub(super) fn error(&self) -> &Error {
         return &self.error;
     }
@@ -3,19 +3,36 @@ use std::sync::atomic::{AtomicU64, Ordering};
 use crate::{
     prelude::*,
     {cluster::Cluster, test_utils},
+    WriteCondition,
 };
 
 use bob_common::{
     bob_client::b_client::MockBobClient as BobClient,
     configs::{
-        cluster::{tests::cluster_config, Cluster as ClusterConfig},
-        node::tests::node_config,
+        cluster::{
+            tests::{cluster_config, with_racks},
+            Cluster as ClusterConfig,
+        },
+        node::tests::{
+            hedged_read_config, locality_config, node_config, with_failure_detector, with_locality,
+        },
     },
     data::BobMeta,
+    error::Kind,
+    failure_detector::CircuitState,
 };
+use opentelemetry::{
+    sdk::trace::TracerProvider,
+    trace::{TraceContextExt, TracerProvider as _},
+};
+use std::sync::Mutex;
 use tokio::time::sleep;
+use tonic::metadata::MetadataMap;
+use tracing::Instrument;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::{layer::SubscriberExt, Registry};
 
-use super::quorum::Quorum;
+use super::{hedged_read::HedgedRead, quorum::Quorum};
 
 fn ping_ok(client: &mut BobClient, node: Node) {
     let cl = node;
@@ -41,6 +58,37 @@ fn put_err(client: &mut BobClient, node: Node, call: Arc<CountCall>) {
     });
 }
 
+/// Rejects conditional puts, accepts unconditional ones
+fn put_precondition_failed(client: &mut BobClient, node: Node, call: Arc<CountCall>) {
+    client.expect_put().returning(move |_key, _data, options| {
+        call.put_inc();
+        if options.condition == WriteCondition::None as i32 {
+            test_utils::put_ok(node.name().to_owned())
+        } else {
+            test_utils::put_precondition_failed(node.name().to_owned())
+        }
+    });
+}
+
+fn delete_ok(client: &mut BobClient, node: Node, call: Arc<CountCall>) {
+    client.expect_delete().returning(move |_key, _meta, _options| {
+        call.delete_inc();
+        test_utils::delete_ok(node.name().to_owned())
+    });
+}
+
+/// Rejects conditional deletes, accepts unconditional ones
+fn delete_precondition_failed(client: &mut BobClient, node: Node, call: Arc<CountCall>) {
+    client.expect_delete().returning(move |_key, _meta, options| {
+        call.delete_inc();
+        if options.condition == WriteCondition::None as i32 {
+            test_utils::delete_ok(node.name().to_owned())
+        } else {
+            test_utils::delete_precondition_failed(node.name().to_owned())
+        }
+    });
+}
+
 fn get_ok_timestamp(client: &mut BobClient, node: Node, call: Arc<CountCall>, timestamp: u64) {
     trace!("get ok timestamp");
     client.expect_get().returning(move |_key, _options| {
@@ -62,6 +110,7 @@ fn get_err(client: &mut BobClient, node: Node, call: Arc<CountCall>) {
 struct CountCall {
     put_count: AtomicU64,
     get_count: AtomicU64,
+    delete_count: AtomicU64,
 }
 
 impl CountCall {
@@ -69,6 +118,7 @@ impl CountCall {
         Self {
             put_count: AtomicU64::new(0),
             get_count: AtomicU64::new(0),
+            delete_count: AtomicU64::new(0),
         }
     }
 
@@ -90,6 +140,14 @@ impl CountCall {
         debug!("increment get count");
         self.get_count.fetch_add(1, Ordering::SeqCst);
     }
+
+    fn delete_inc(&self) {
+        self.delete_count.fetch_add(1, Ordering::SeqCst);
+    }
+
+    fn delete_count(&self) -> u64 {
+        self.delete_count.load(Ordering::Relaxed)
+    }
 }
 
 fn prepare_configs(
@@ -118,7 +176,7 @@ async fn create_cluster(
             .find(|(name, _, _)| *name == node.name())
             .expect("find node with name");
         func(&mut mock_client, node.clone(), call.clone());
-        node.set_connection(mock_client);
+        node.set_connection(mock_client.into());
     }
 
     let backend = Arc::new(Backend::new(mapper.clone(), node).await);
@@ -147,6 +205,7 @@ fn create_node(
                  set_get_ok: bool,
                  timestamp: u64| {
             ping_ok(client, n.clone());
+            delete_ok(client, n.clone(), c.clone());
             if set_put_ok {
                 put_ok(client, n.clone(), c.clone());
             } else {
@@ -184,6 +243,25 @@ fn create_node(
     (name, call, Arc::new(CountCall::new()))
 }
 
+fn create_rejecting_node(name: &str) -> (&str, Call, Arc<CountCall>) {
+    let call = move |client: &mut BobClient, n: Node, call: Arc<CountCall>| {
+        let f = |client: &mut BobClient, n: Node, c: Arc<CountCall>| {
+            ping_ok(client, n.clone());
+            put_precondition_failed(client, n.clone(), c.clone());
+            delete_precondition_failed(client, n.clone(), c.clone());
+            get_err(client, n, c);
+        };
+        f(client, n.clone(), call.clone());
+        client.expect_clone().returning(move || {
+            let mut cl = BobClient::default();
+            f(&mut cl, n.clone(), call.clone());
+            cl
+        });
+    };
+    let call = Box::new(call);
+    (name, call, Arc::new(CountCall::new()))
+}
+
 type Call = Box<dyn Fn(&mut BobClient, Node, Arc<CountCall>)>;
 
 //////////////////////////////////////////////////
@@ -204,7 +282,7 @@ async fn simple_one_node_put_ok() {
 
     let key = 1;
     let result = quorum
-        .put(BobKey::from(key), &BobData::new(vec![].into(), BobMeta::new(11)))
+        .put(BobKey::from(key), &BobData::new(vec![].into(), BobMeta::new(11)), BobPutOptions::default())
         .await;
 
     assert!(result.is_ok());
@@ -236,7 +314,7 @@ async fn simple_two_node_one_vdisk_cluster_put_ok() {
     let (quorum, backend) = create_cluster(&node, &cluster, &actions).await;
     let key = 2;
     let result = quorum
-        .put(BobKey::from(key), &BobData::new(vec![].into(), BobMeta::new(11)))
+        .put(BobKey::from(key), &BobData::new(vec![].into(), BobMeta::new(11)), BobPutOptions::default())
         .await;
     sleep(Duration::from_millis(1)).await;
 
@@ -272,7 +350,7 @@ async fn simple_two_node_two_vdisk_one_replica_cluster_put_ok() {
     let (quorum, backend) = create_cluster(&node, &cluster, &actions).await;
 
     let mut result = quorum
-        .put(BobKey::from(3), &BobData::new(vec![].into(), BobMeta::new(11)))
+        .put(BobKey::from(3), &BobData::new(vec![].into(), BobMeta::new(11)), BobPutOptions::default())
         .await;
 
     assert!(result.is_ok());
@@ -281,7 +359,7 @@ async fn simple_two_node_two_vdisk_one_replica_cluster_put_ok() {
     assert_eq!(1, calls[1].1.put_count());
 
     result = quorum
-        .put(BobKey::from(4), &BobData::new(vec![].into(), BobMeta::new(11)))
+        .put(BobKey::from(4), &BobData::new(vec![].into(), BobMeta::new(11)), BobPutOptions::default())
         .await;
 
     assert!(result.is_ok());
@@ -319,7 +397,7 @@ async fn two_node_one_vdisk_cluster_one_node_failed_put_err() {
     let (quorum, backend) = create_cluster(&node, &cluster, &actions).await;
 
     let result = quorum
-        .put(BobKey::from(5), &BobData::new(vec![].into(), BobMeta::new(11)))
+        .put(BobKey::from(5), &BobData::new(vec![].into(), BobMeta::new(11)), BobPutOptions::default())
         .await;
     sleep(Duration::from_millis(1)).await;
 
@@ -334,6 +412,159 @@ async fn two_node_one_vdisk_cluster_one_node_failed_put_err() {
     assert!(get.is_ok());
 }
 
+/// 2 node, 1 vdisk, 2 replics in vdisk, quorum = 2
+/// one node rejects conditional put, local node applies it => no quorum => conflict with list
+/// of nodes that applied put, no alien data
+#[tokio::test]
+async fn two_node_one_vdisk_cluster_one_node_rejected_put_err() {
+    test_utils::init_logger();
+    let (node, cluster) = prepare_configs(2, 1, 2, 2);
+    let actions: Vec<(&str, Call, Arc<CountCall>)> = vec![
+        create_ok_node("0", true, true),
+        create_rejecting_node("1"),
+    ];
+
+    let calls: Vec<_> = actions
+        .iter()
+        .map(|(name, _, call)| ((*name).to_string(), call.clone()))
+        .collect();
+    let (quorum, backend) = create_cluster(&node, &cluster, &actions).await;
+
+    let options = BobPutOptions::default().with_condition(Some(BobWriteCondition::IfAbsent));
+    let result = quorum
+        .put(BobKey::from(5), &BobData::new(vec![].into(), BobMeta::new(11)), options)
+        .await;
+    sleep(Duration::from_millis(1)).await;
+
+    let err = result.err().unwrap();
+    assert!(matches!(err.kind(), Kind::ConditionPartiallyApplied(msg) if msg.contains("\"0\"")));
+    assert_eq!(1, calls[1].1.put_count());
+
+    let get = backend
+        .get_local(BobKey::from(5), Operation::new_alien(0))
+        .await;
+    assert!(get.err().unwrap().is_key_not_found());
+}
+
+/// 2 nodes, 1 vdisk, 2 replicas in vdisk, quorum = 2
+/// both nodes reject conditional put => precondition failed
+#[tokio::test]
+async fn two_node_one_vdisk_cluster_all_nodes_rejected_put_err() {
+    test_utils::init_logger();
+    let (node, cluster) = prepare_configs(2, 1, 2, 2);
+    let actions: Vec<(&str, Call, Arc<CountCall>)> = vec![
+        create_ok_node("0", true, true),
+        create_rejecting_node("1"),
+    ];
+
+    let calls: Vec<_> = actions
+        .iter()
+        .map(|(name, _, call)| ((*name).to_string(), call.clone()))
+        .collect();
+    let (quorum, _) = create_cluster(&node, &cluster, &actions).await;
+
+    let data = BobData::new(vec![].into(), BobMeta::new(11));
+    let result = quorum.put(BobKey::from(5), &data, BobPutOptions::default()).await;
+    assert!(result.is_ok());
+
+    let options = BobPutOptions::default().with_condition(Some(BobWriteCondition::IfAbsent));
+    let result = quorum.put(BobKey::from(5), &data, options).await;
+    sleep(Duration::from_millis(1)).await;
+
+    assert!(result.err().unwrap().is_precondition_failed());
+    assert_eq!(2, calls[1].1.put_count());
+}
+
+/// 3 nodes, 1 vdisk, 3 replicas in vdisk, quorum = 2
+/// one node rejects conditional put => quorum => put ok, rejected replica gets put without
+/// condition
+#[tokio::test]
+async fn three_node_one_vdisk_cluster_one_node_rejected_put_ok() {
+    test_utils::init_logger();
+    let (node, cluster) = prepare_configs(3, 1, 3, 2);
+    let actions: Vec<(&str, Call, Arc<CountCall>)> = vec![
+        create_ok_node("0", true, true),
+        create_ok_node("1", true, true),
+        create_rejecting_node("2"),
+    ];
+
+    let calls: Vec<_> = actions
+        .iter()
+        .map(|(name, _, call)| ((*name).to_string(), call.clone()))
+        .collect();
+    let (quorum, _) = create_cluster(&node, &cluster, &actions).await;
+
+    let options = BobPutOptions::default().with_condition(Some(BobWriteCondition::IfAbsent));
+    let result = quorum
+        .put(BobKey::from(0), &BobData::new(vec![].into(), BobMeta::new(11)), options)
+        .await;
+    sleep(Duration::from_millis(100)).await;
+
+    assert!(result.is_ok());
+    assert_eq!(1, calls[1].1.put_count());
+    assert_eq!(2, calls[2].1.put_count());
+}
+
+/// 3 nodes, 1 vdisk, 3 replicas in vdisk, consistency level all
+/// one node rejects conditional put => conflict, replicas are not repaired
+#[tokio::test]
+async fn three_node_one_vdisk_cluster_one_node_rejected_put_all_err() {
+    test_utils::init_logger();
+    let (node, cluster) = prepare_configs(3, 1, 3, 2);
+    let actions: Vec<(&str, Call, Arc<CountCall>)> = vec![
+        create_ok_node("0", true, true),
+        create_ok_node("1", true, true),
+        create_rejecting_node("2"),
+    ];
+
+    let calls: Vec<_> = actions
+        .iter()
+        .map(|(name, _, call)| ((*name).to_string(), call.clone()))
+        .collect();
+    let (quorum, _) = create_cluster(&node, &cluster, &actions).await;
+
+    let options = BobPutOptions::default()
+        .with_condition(Some(BobWriteCondition::IfAbsent))
+        .with_consistency(Some(BobConsistencyLevel::All));
+    let result = quorum
+        .put(BobKey::from(0), &BobData::new(vec![].into(), BobMeta::new(11)), options)
+        .await;
+    sleep(Duration::from_millis(100)).await;
+
+    let err = result.err().unwrap();
+    assert!(matches!(err.kind(), Kind::ConditionPartiallyApplied(msg) if msg.contains("\"2\"")));
+    assert_eq!(1, calls[2].1.put_count());
+}
+
+/// 3 nodes, 1 vdisk, 3 replicas in vdisk, quorum = 2
+/// one node rejects conditional delete => quorum => delete ok, rejected replica gets delete
+/// without condition
+#[tokio::test]
+async fn three_node_one_vdisk_cluster_one_node_rejected_delete_ok() {
+    test_utils::init_logger();
+    let (node, cluster) = prepare_configs(3, 1, 3, 2);
+    let actions: Vec<(&str, Call, Arc<CountCall>)> = vec![
+        create_ok_node("0", true, true),
+        create_ok_node("1", true, true),
+        create_rejecting_node("2"),
+    ];
+
+    let calls: Vec<_> = actions
+        .iter()
+        .map(|(name, _, call)| ((*name).to_string(), call.clone()))
+        .collect();
+    let (quorum, _) = create_cluster(&node, &cluster, &actions).await;
+
+    let options = BobDeleteOptions::default().with_condition(Some(BobWriteCondition::IfNewerThan(11)));
+    let result = quorum.delete(BobKey::from(0), &BobMeta::new(11), options).await;
+    sleep(Duration::from_millis(100)).await;
+
+    assert!(result.is_ok());
+    // conditional delete, alien delete and repair
+    assert_eq!(2, calls[1].1.delete_count());
+    assert_eq!(3, calls[2].1.delete_count());
+}
+
 /// 2 nodes, 1 vdisk, 2 replicas in vdisk, quorum = 1
 /// one node failed => write one data local => quorum => put ok
 #[tokio::test]
@@ -353,7 +584,7 @@ async fn two_node_one_vdisk_cluster_one_node_failed_put_ok() {
     let (quorum, _) = create_cluster(&node, &cluster, &actions).await;
 
     let result = quorum
-        .put(BobKey::from(5), &BobData::new(vec![].into(), BobMeta::new(11)))
+        .put(BobKey::from(5), &BobData::new(vec![].into(), BobMeta::new(11)), BobPutOptions::default())
         .await;
     sleep(Duration::from_millis(1000)).await;
 
@@ -384,7 +615,7 @@ async fn three_node_two_vdisk_cluster_second_node_failed_put_ok() {
 
     sleep(Duration::from_millis(1)).await;
     let result = quorum
-        .put(BobKey::from(0), &BobData::new(vec![].into(), BobMeta::new(11)))
+        .put(BobKey::from(0), &BobData::new(vec![].into(), BobMeta::new(11)), BobPutOptions::default())
         .await;
     sleep(Duration::from_millis(1000)).await;
     assert!(result.is_ok());
@@ -415,7 +646,7 @@ async fn three_node_two_vdisk_cluster_one_node_failed_put_err() {
 
     info!("quorum put: 0");
     let result = quorum
-        .put(BobKey::from(0), &BobData::new(vec![].into(), BobMeta::new(11)))
+        .put(BobKey::from(0), &BobData::new(vec![].into(), BobMeta::new(11)), BobPutOptions::default())
         .await;
     sleep(Duration::from_millis(1000)).await;
 
@@ -451,7 +682,7 @@ async fn three_node_two_vdisk_cluster_one_node_failed_put_ok2() {
     let (quorum, backend) = create_cluster(&node, &cluster, &actions).await;
 
     let result = quorum
-        .put(BobKey::from(0), &BobData::new(vec![].into(), BobMeta::new(11)))
+        .put(BobKey::from(0), &BobData::new(vec![].into(), BobMeta::new(11)), BobPutOptions::default())
         .await;
     sleep(Duration::from_millis(1000)).await;
 
@@ -488,7 +719,7 @@ async fn three_node_one_vdisk_cluster_one_node_failed_put_ok() {
 
     info!("put local: 0");
     let result = quorum
-        .put(BobKey::from(0), &BobData::new(vec![].into(), BobMeta::new(11)))
+        .put(BobKey::from(0), &BobData::new(vec![].into(), BobMeta::new(11)), BobPutOptions::default())
         .await;
     assert!(result.is_ok());
     // assert_eq!(1, calls[0].1.put_count());
@@ -517,7 +748,7 @@ async fn simple_one_node_get_err() {
     info!("actions created");
     let (quorum, _) = create_cluster(&node, &cluster, &actions).await;
     info!("cluster created");
-    let result = quorum.get(BobKey::from(102)).await;
+    let result = quorum.get(BobKey::from(102), &BobGetOptions::new_all()).await;
     info!("request finished");
     assert!(result.is_err());
 }
@@ -536,7 +767,7 @@ async fn simple_two_node_get_ok() {
 
     let (quorum, _) = create_cluster(&node, &cluster, &actions).await;
 
-    let result = quorum.get(BobKey::from(110)).await;
+    let result = quorum.get(BobKey::from(110), &BobGetOptions::new_all()).await;
 
     assert!(result.is_ok());
     assert_eq!(1, result.unwrap().meta().timestamp());
@@ -559,10 +790,404 @@ async fn simple_two_node_read_sup_get_ok() {
         .collect();
     let (quorum, _) = create_cluster(&node, &cluster, &actions).await;
 
-    let result = quorum.get(BobKey::from(110)).await;
+    let result = quorum.get(BobKey::from(110), &BobGetOptions::new_all()).await;
     dbg!(&result);
 
     assert!(result.is_ok());
     assert_eq!(1, result.unwrap().meta().timestamp());
     assert_eq!(1, calls[1].1.get_count());
 }
+
+/// 2 nodes, 1 vdisk, 2 replicas in vdisk, quorum = 2
+/// one node failed, consistency level ONE => write one data local => put ok
+#[tokio::test]
+async fn two_node_one_vdisk_cluster_one_node_failed_put_one_ok() {
+    test_utils::init_logger();
+    let (node, cluster) = prepare_configs(2, 1, 2, 2);
+    let actions: Vec<(&str, Call, Arc<CountCall>)> = vec![
+        create_ok_node("0", true, true),
+        create_ok_node("1", false, true),
+    ];
+    let (quorum, backend) = create_cluster(&node, &cluster, &actions).await;
+
+    let options = BobPutOptions::default().with_consistency(Some(BobConsistencyLevel::One));
+    let result = quorum
+        .put(BobKey::from(5), &BobData::new(vec![].into(), BobMeta::new(11)), options)
+        .await;
+    sleep(Duration::from_millis(1)).await;
+
+    assert!(result.is_ok());
+    let get = backend
+        .get_local(BobKey::from(5), Operation::new_alien(0))
+        .await;
+    assert!(get.is_ok());
+}
+
+/// 2 nodes, 1 vdisk, 2 replicas in vdisk, quorum = 1
+/// one node failed, consistency level ALL => data goes to alien, but put err
+#[tokio::test]
+async fn two_node_one_vdisk_cluster_one_node_failed_put_all_err() {
+    test_utils::init_logger();
+    let (node, cluster) = prepare_configs(2, 1, 2, 1);
+    let actions: Vec<(&str, Call, Arc<CountCall>)> = vec![
+        create_ok_node("0", true, true),
+        create_ok_node("1", false, true),
+    ];
+    let (quorum, backend) = create_cluster(&node, &cluster, &actions).await;
+
+    let options = BobPutOptions::default().with_consistency(Some(BobConsistencyLevel::All));
+    let result = quorum
+        .put(BobKey::from(5), &BobData::new(vec![].into(), BobMeta::new(11)), options)
+        .await;
+
+    assert!(result.is_err());
+    let get = backend
+        .get_local(BobKey::from(5), Operation::new_alien(0))
+        .await;
+    assert!(get.is_ok());
+}
+
+/// 2 nodes, 1 vdisk, 2 replicas in vdisk, quorum = 2
+/// consistency level ALL => both replicas answered => newest data
+#[tokio::test]
+async fn simple_two_node_get_all_ok() {
+    let (node, cluster) = prepare_configs(2, 1, 2, 2);
+    let actions: Vec<(&str, Call, Arc<CountCall>)> = vec![
+        create_node("0", true, true, 0),
+        create_node("1", true, true, 1),
+    ];
+    let (quorum, backend) = create_cluster(&node, &cluster, &actions).await;
+    let key = BobKey::from(110);
+    backend
+        .put(key, &BobData::new(vec![].into(), BobMeta::new(5)), BobPutOptions::new_local())
+        .await
+        .unwrap();
+
+    let options = BobGetOptions::new_all().with_consistency(Some(BobConsistencyLevel::All));
+    let result = quorum.get(key, &options).await;
+
+    assert_eq!(5, result.unwrap().meta().timestamp());
+}
+
+/// 2 nodes, 1 vdisk, 2 replicas in vdisk, quorum = 1
+/// one node failed, consistency level ALL => get err
+#[tokio::test]
+async fn two_node_one_node_failed_get_all_err() {
+    let (node, cluster) = prepare_configs(2, 1, 2, 1);
+    let actions: Vec<(&str, Call, Arc<CountCall>)> = vec![
+        create_node("0", true, true, 0),
+        create_node("1", true, false, 0),
+    ];
+    let (quorum, _) = create_cluster(&node, &cluster, &actions).await;
+
+    let options = BobGetOptions::new_all().with_consistency(Some(BobConsistencyLevel::All));
+    let result = quorum.get(BobKey::from(110), &options).await;
+
+    assert!(result.is_err());
+}
+
+/// 3 nodes, 1 vdisk, 3 replicas in vdisk, quorum = 3
+/// one node failed, consistency level QUORUM => majority of replicas answered => get ok
+#[tokio::test]
+async fn consistency_level_quorum_is_majority_of_replicas() {
+    let (node, cluster) = prepare_configs(3, 1, 3, 3);
+    let actions: Vec<(&str, Call, Arc<CountCall>)> = vec![
+        create_node("0", true, true, 0),
+        create_node("1", true, true, 1),
+        create_node("2", true, false, 0),
+    ];
+    let (quorum, _) = create_cluster(&node, &cluster, &actions).await;
+
+    let options = BobGetOptions::new_all().with_consistency(Some(BobConsistencyLevel::Quorum));
+    let result = quorum.get(BobKey::from(110), &options).await;
+
+    assert_eq!(1, result.unwrap().meta().timestamp());
+}
+
+/// 3 nodes, 1 vdisk, 3 replicas in vdisk, quorum = 1
+/// two nodes failed, consistency level QUORUM => get err, level is not reached
+#[tokio::test]
+async fn consistency_level_quorum_not_reached_get_err() {
+    let (node, cluster) = prepare_configs(3, 1, 3, 1);
+    let actions: Vec<(&str, Call, Arc<CountCall>)> = vec![
+        create_node("0", true, true, 0),
+        create_node("1", true, false, 0),
+        create_node("2", true, false, 0),
+    ];
+    let (quorum, _) = create_cluster(&node, &cluster, &actions).await;
+
+    let options = BobGetOptions::new_all().with_consistency(Some(BobConsistencyLevel::Quorum));
+    let result = quorum.get(BobKey::from(110), &options).await;
+
+    assert!(matches!(
+        result.err().unwrap().kind(),
+        bob_common::error::Kind::ConsistencyLevelNotReached(_)
+    ));
+}
+
+/// 3 nodes, 1 vdisk, 3 replicas in vdisk, quorum = 1, hedged reads enabled
+/// no data local, first remote replica failed => next replica requested => get ok
+#[tokio::test]
+async fn three_node_hedged_get_first_remote_failed_ok() {
+    let (node, cluster) = prepare_configs(3, 1, 3, 1);
+    let actions: Vec<(&str, Call, Arc<CountCall>)> = vec![
+        create_node("0", true, true, 0),
+        create_node("1", true, false, 0),
+        create_node("2", true, true, 7),
+    ];
+    let calls: Vec<_> = actions.iter().map(|(_, _, call)| call.clone()).collect();
+    let (quorum, _) = create_cluster(&node, &cluster, &actions).await;
+    let config = hedged_read_config("1h", None);
+    let quorum = quorum.with_hedged_read(Some(&config));
+
+    let result = quorum.get(BobKey::from(110), &BobGetOptions::new_all()).await;
+
+    assert_eq!(7, result.unwrap().meta().timestamp());
+    assert_eq!(1, calls[1].get_count());
+    assert_eq!(1, calls[2].get_count());
+}
+
+/// 3 nodes, 1 vdisk, 3 replicas in vdisk, quorum = 1, racks: [0, 2], [1], local reads preferred
+/// no data local, replica in the same rack answered => other rack isn't requested
+#[tokio::test]
+async fn three_node_prefer_local_reads_same_rack_get_ok() {
+    let (node, cluster) = prepare_configs(3, 1, 3, 1);
+    let cluster = with_racks(cluster, &[("r1", &["0", "2"]), ("r2", &["1"])]);
+    let actions: Vec<(&str, Call, Arc<CountCall>)> = vec![
+        create_node("0", true, true, 0),
+        create_node("1", true, true, 5),
+        create_node("2", true, true, 7),
+    ];
+    let calls: Vec<_> = actions.iter().map(|(_, _, call)| call.clone()).collect();
+    let (quorum, _) = create_cluster(&node, &cluster, &actions).await;
+    let quorum = quorum.with_locality(&locality_config(None, true, false));
+
+    let result = quorum.get(BobKey::from(110), &BobGetOptions::new_all()).await;
+
+    assert_eq!(7, result.unwrap().meta().timestamp());
+    assert_eq!(0, calls[1].get_count());
+    assert_eq!(1, calls[2].get_count());
+}
+
+/// 3 nodes, 1 vdisk, 3 replicas in vdisk, quorum = 1, racks: [0, 2], [1], local reads preferred
+/// no data local, replica in the same rack failed => get from other rack
+#[tokio::test]
+async fn three_node_prefer_local_reads_same_rack_failed_get_ok() {
+    let (node, cluster) = prepare_configs(3, 1, 3, 1);
+    let cluster = with_racks(cluster, &[("r1", &["0", "2"]), ("r2", &["1"])]);
+    let actions: Vec<(&str, Call, Arc<CountCall>)> = vec![
+        create_node("0", true, true, 0),
+        create_node("1", true, true, 5),
+        create_node("2", true, false, 0),
+    ];
+    let calls: Vec<_> = actions.iter().map(|(_, _, call)| call.clone()).collect();
+    let (quorum, _) = create_cluster(&node, &cluster, &actions).await;
+    let quorum = quorum.with_locality(&locality_config(None, true, false));
+
+    let result = quorum.get(BobKey::from(110), &BobGetOptions::new_all()).await;
+
+    assert_eq!(5, result.unwrap().meta().timestamp());
+    assert_eq!(1, calls[1].get_count());
+    assert_eq!(1, calls[2].get_count());
+}
+
+/// 3 nodes, 1 vdisk, 3 replicas in vdisk, quorum = 1, racks: [1], [2], local node rack set
+/// in node config => same rack replica is requested
+#[tokio::test]
+async fn three_node_prefer_local_reads_rack_from_node_config_get_ok() {
+    let (node, cluster) = prepare_configs(3, 1, 3, 1);
+    let node = with_locality(node, locality_config(Some("r2"), true, false));
+    let cluster = with_racks(cluster, &[("r1", &["1"]), ("r2", &["2"])]);
+    let actions: Vec<(&str, Call, Arc<CountCall>)> = vec![
+        create_node("0", true, true, 0),
+        create_node("1", true, true, 5),
+        create_node("2", true, true, 7),
+    ];
+    let calls: Vec<_> = actions.iter().map(|(_, _, call)| call.clone()).collect();
+    let (quorum, _) = create_cluster(&node, &cluster, &actions).await;
+    let quorum = quorum.with_locality(node.locality());
+
+    let result = quorum.get(BobKey::from(110), &BobGetOptions::new_all()).await;
+
+    assert_eq!(7, result.unwrap().meta().timestamp());
+    assert_eq!(0, calls[1].get_count());
+}
+
+/// 4 nodes, 1 vdisk, 4 replicas in vdisk, quorum = 2, racks: [0, 1, 2], [3], spread writes
+/// => put returns only after replica in the other rack is written, replicas in the local rack
+/// are written in background, nothing goes to alien
+#[tokio::test]
+async fn four_node_spread_writes_across_racks_put_ok() {
+    let (node, cluster) = prepare_configs(4, 1, 4, 2);
+    let cluster = with_racks(cluster, &[("r1", &["0", "1", "2"]), ("r2", &["3"])]);
+    let actions: Vec<(&str, Call, Arc<CountCall>)> = vec![
+        create_ok_node("0", true, true),
+        create_ok_node("1", true, true),
+        create_ok_node("2", true, true),
+        create_ok_node("3", true, true),
+    ];
+    let calls: Vec<_> = actions.iter().map(|(_, _, call)| call.clone()).collect();
+    let (quorum, backend) = create_cluster(&node, &cluster, &actions).await;
+    let quorum = quorum.with_locality(&locality_config(None, false, true));
+
+    let result = quorum
+        .put(BobKey::from(0), &BobData::new(vec![].into(), BobMeta::new(11)), BobPutOptions::new_local())
+        .await;
+
+    assert!(result.is_ok());
+    assert_eq!(1, calls[3].put_count());
+    sleep(Duration::from_millis(1)).await;
+    assert_eq!(1, calls[1].put_count());
+    assert_eq!(1, calls[2].put_count());
+    assert_eq!(1, calls[3].put_count());
+    let get = backend
+        .get_local(BobKey::from(0), Operation::new_alien(0))
+        .await;
+    assert!(get.err().unwrap().is_key_not_found());
+}
+
+/// 3 nodes, 1 vdisk, 3 replicas in vdisk, quorum = 2, racks: [0, 1], [2], spread writes,
+/// other rack failed => put ok, because racks spread is preferred but not required
+#[tokio::test]
+async fn three_node_spread_writes_other_rack_failed_put_ok() {
+    let (node, cluster) = prepare_configs(3, 1, 3, 2);
+    let cluster = with_racks(cluster, &[("r1", &["0", "1"]), ("r2", &["2"])]);
+    let actions: Vec<(&str, Call, Arc<CountCall>)> = vec![
+        create_ok_node("0", true, true),
+        create_ok_node("1", true, true),
+        create_ok_node("2", false, true),
+    ];
+    let calls: Vec<_> = actions.iter().map(|(_, _, call)| call.clone()).collect();
+    let (quorum, _) = create_cluster(&node, &cluster, &actions).await;
+    let quorum = quorum.with_locality(&locality_config(None, false, true));
+
+    let result = quorum
+        .put(BobKey::from(0), &BobData::new(vec![].into(), BobMeta::new(11)), BobPutOptions::new_local())
+        .await;
+
+    assert!(result.is_ok());
+    assert_eq!(1, calls[1].put_count());
+    assert_eq!(1, calls[2].put_count());
+}
+
+/// 2 node, 1 vdisk, 2 replics in vdisk, quorum = 2, failure detector enabled
+/// circuit of node 1 is open => node 1 isn't requested, data written to local alien => put ok
+#[tokio::test]
+async fn two_node_one_vdisk_cluster_suspected_node_put_alien_ok() {
+    let (node, cluster) = prepare_configs(2, 1, 2, 2);
+    let node = with_failure_detector(node, 1);
+    let actions: Vec<(&str, Call, Arc<CountCall>)> = vec![
+        create_ok_node("0", true, true),
+        create_ok_node("1", true, true),
+    ];
+    let calls: Vec<_> = actions.iter().map(|(_, _, call)| call.clone()).collect();
+    let (quorum, backend) = create_cluster(&node, &cluster, &actions).await;
+    let suspected = backend.mapper().nodes()[1].clone();
+    suspected.record_request_result(Err(&Error::timeout()));
+    assert_eq!(Some(CircuitState::Open), suspected.circuit_state());
+
+    let result = quorum
+        .put(BobKey::from(5), &BobData::new(vec![].into(), BobMeta::new(11)), BobPutOptions::default())
+        .await;
+
+    assert!(result.is_ok());
+    assert_eq!(0, calls[1].put_count());
+    let get = backend
+        .get_local(BobKey::from(5), Operation::new_alien(0))
+        .await;
+    assert!(get.is_ok());
+}
+
+fn create_traced_node(name: &str, traceparents: Arc<Mutex<Vec<String>>>) -> (&str, Call, Arc<CountCall>) {
+    let call = move |client: &mut BobClient, n: Node, call: Arc<CountCall>| {
+        let traceparents = traceparents.clone();
+        let f = move |client: &mut BobClient, n: Node, c: Arc<CountCall>| {
+            ping_ok(client, n.clone());
+            let traceparents = traceparents.clone();
+            client.expect_put().returning(move |_key, _data, _options| {
+                c.put_inc();
+                // The same way as the real client sets context of the request
+                let mut metadata = MetadataMap::new();
+                bob_common::telemetry::inject_context(&mut metadata);
+                if let Some(traceparent) = metadata.get("traceparent") {
+                    traceparents.lock().expect("mutex").push(traceparent.to_str().unwrap().to_string());
+                }
+                test_utils::put_ok(n.name().to_owned())
+            });
+        };
+        f(client, n.clone(), call.clone());
+        client.expect_clone().returning(move || {
+            let mut cl = BobClient::default();
+            f(&mut cl, n.clone(), call.clone());
+            cl
+        });
+    };
+    let call = Box::new(call);
+    (name, call, Arc::new(CountCall::new()))
+}
+
+/// 2 node, 1 vdisk, 2 replics in vdisk, quorum = 2, tracing enabled
+/// put to node 1 carries context of the put span
+#[tokio::test]
+async fn two_node_put_propagates_trace_context() {
+    let provider = TracerProvider::builder().build();
+    let tracer = provider.tracer("test");
+    let subscriber = Registry::default().with(tracing_opentelemetry::layer().with_tracer(tracer));
+    let _guard = tracing::subscriber::set_default(subscriber);
+    let (node, cluster) = prepare_configs(2, 1, 2, 2);
+    let traceparents = Arc::new(Mutex::new(vec![]));
+    let actions: Vec<(&str, Call, Arc<CountCall>)> = vec![
+        create_ok_node("0", true, true),
+        create_traced_node("1", traceparents.clone()),
+    ];
+    let (quorum, _) = create_cluster(&node, &cluster, &actions).await;
+    let span = tracing::info_span!("put");
+    let trace_id = span.context().span().span_context().trace_id();
+
+    let result = quorum
+        .put(BobKey::from(3), &BobData::new(vec![].into(), BobMeta::new(11)), BobPutOptions::default())
+        .instrument(span)
+        .await;
+
+    assert!(result.is_ok());
+    let traceparents = traceparents.lock().expect("mutex");
+    assert_eq!(1, traceparents.len());
+    assert!(traceparents[0].contains(&format!("{:032x}", trace_id)));
+}
+
+#[test]
+fn hedged_read_delay_from_latency_percentile() {
+    let hedged_read = HedgedRead::new(&hedged_read_config("20ms", Some(50.0)));
+    for ms in 1..64 {
+        hedged_read.record_latency(Duration::from_millis(ms));
+    }
+    assert_eq!(Duration::from_millis(20), hedged_read.delay());
+
+    hedged_read.record_latency(Duration::from_millis(64));
+    assert_eq!(Duration::from_millis(32), hedged_read.delay());
+}
+
+/// 2 nodes, hedged reads with latency percentile, remote replica always fails
+/// => latency of failed requests isn't recorded, delay stays configured
+#[tokio::test]
+async fn hedged_read_delay_ignores_failed_requests() {
+    let (node, cluster) = prepare_configs(2, 1, 2, 1);
+    let actions: Vec<(&str, Call, Arc<CountCall>)> = vec![
+        create_node("0", true, true, 0),
+        create_node("1", true, false, 0),
+    ];
+    let calls: Vec<_> = actions.iter().map(|(_, _, call)| call.clone()).collect();
+    let (_, backend) = create_cluster(&node, &cluster, &actions).await;
+    let failed = backend.mapper().nodes()[1].clone();
+    let hedged_read = HedgedRead::new(&hedged_read_config("20ms", Some(50.0)));
+
+    for _ in 0..64 {
+        let result = hedged_read
+            .get_any(BobKey::from(110), vec![failed.clone()], GetOptions::new_all())
+            .await;
+        assert!(result.is_none());
+    }
+
+    assert_eq!(64, calls[1].get_count());
+    assert_eq!(Duration::from_millis(20), hedged_read.delay());
+}
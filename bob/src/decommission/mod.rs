@@ -0,0 +1,404 @@
+use crate::{link_manager::LinkManager, local_vdisk, prelude::*};
+use bob_backend::pearl::Group as PearlGroup;
+use bob_common::{configs::cluster::Cluster as ClusterConfig, data::StreamedRecord};
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Mutex,
+};
+
+#[cfg(test)]
+mod tests;
+
+const TRANSFER_CONCURRENCY: usize = 16;
+const TRANSFER_BATCH_SIZE: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DecommissionState {
+    #[default]
+    NotStarted,
+    Running,
+    Completed,
+    Failed,
+}
+
+/// Transfer of the local vdisk, or of aliens stored for other node, to its replacement replicas.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct VDiskTransfer {
+    pub vdisk_id: VDiskId,
+    /// Node the aliens are stored for, `None` for the local replica of the vdisk.
+    pub alien_node: Option<String>,
+    /// Nodes that hold the vdisk in the new cluster config and don't hold it now, owner of
+    /// the aliens if it holds the vdisk in the new cluster config.
+    pub targets: Vec<String>,
+    /// Records sent to every target: the newest version of every key of every local partition.
+    pub sent: u64,
+    /// Delete markers among sent records.
+    pub deleted: u64,
+    /// Keys with older version than sent on some target, checked after every batch.
+    pub not_verified: u64,
+    pub verified: bool,
+}
+
+impl VDiskTransfer {
+    fn new(vdisk_id: VDiskId, alien_node: Option<String>, targets: Vec<String>) -> Self {
+        Self {
+            vdisk_id,
+            alien_node,
+            targets,
+            sent: 0,
+            deleted: 0,
+            not_verified: 0,
+            verified: false,
+        }
+    }
+}
+
+/// Progress of the decommission, returned by `GET /decommission`.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DecommissionStatus {
+    pub state: DecommissionState,
+    pub vdisks: Vec<VDiskTransfer>,
+    pub error: Option<String>,
+}
+
+/// Finds replacement replicas for every local vdisk. Fails if local node is still in the new
+/// cluster config or some local vdisk is missing there.
+pub(crate) fn plan(mapper: &Virtual, new_cluster: &ClusterConfig) -> Result<Vec<VDiskTransfer>, String> {
+    let local_node = mapper.local_node_name();
+    if new_cluster.nodes().iter().any(|n| *local_node == n.name()) {
+        return Err(format!("node {} is still in the new cluster config", local_node));
+    }
+    let mut vdisk_ids = mapper
+        .vdisks()
+        .keys()
+        .copied()
+        .filter(|&id| mapper.is_vdisk_on_node(local_node.as_str(), id))
+        .collect::<Vec<_>>();
+    vdisk_ids.sort_unstable();
+    vdisk_ids
+        .into_iter()
+        .map(|id| {
+            let new_vdisk = new_cluster
+                .vdisks()
+                .iter()
+                .find(|vdisk| vdisk.id() == id)
+                .ok_or_else(|| format!("vdisk {} is missing in the new cluster config", id))?;
+            let old_replicas = mapper.get_vdisk(id).map_or(&[][..], |vdisk| vdisk.replicas());
+            let mut targets: Vec<String> = vec![];
+            for replica in new_vdisk.replicas() {
+                let node = replica.node();
+                let is_old = old_replicas.iter().any(|r| *r.node_name() == node);
+                if !is_old && !targets.iter().any(|t| t == node) {
+                    targets.push(node.to_owned());
+                }
+            }
+            Ok(VDiskTransfer::new(id, None, targets))
+        })
+        .collect()
+}
+
+/// Finds where local aliens go: to the node they are stored for if it holds their vdisk in the
+/// new cluster config, to all replicas of the vdisk otherwise. `aliens` are pairs of the owner
+/// node and the vdisk.
+pub(crate) fn plan_aliens(
+    aliens: &[(String, VDiskId)],
+    new_cluster: &ClusterConfig,
+) -> Result<Vec<VDiskTransfer>, String> {
+    let mut aliens = aliens.to_vec();
+    aliens.sort_unstable();
+    aliens.dedup();
+    aliens
+        .into_iter()
+        .map(|(node, id)| {
+            let new_vdisk = new_cluster
+                .vdisks()
+                .iter()
+                .find(|vdisk| vdisk.id() == id)
+                .ok_or_else(|| format!("vdisk {} of aliens is missing in the new cluster config", id))?;
+            let mut targets: Vec<String> = vec![];
+            for replica in new_vdisk.replicas() {
+                if !targets.iter().any(|t| t == replica.node()) {
+                    targets.push(replica.node().to_owned());
+                }
+            }
+            if targets.contains(&node) {
+                targets = vec![node.clone()];
+            }
+            Ok(VDiskTransfer::new(id, Some(node), targets))
+        })
+        .collect()
+}
+
+/// Collects target nodes of all transfers.
+pub(crate) fn targets(transfers: &[VDiskTransfer], new_cluster: &ClusterConfig) -> HashMap<String, Node> {
+    new_cluster
+        .nodes()
+        .iter()
+        .enumerate()
+        .filter(|(_, node)| transfers.iter().any(|t| t.targets.iter().any(|n| n == node.name())))
+        .map(|(index, node)| {
+            let target = Node::new(node.name().into(), node.address().to_owned(), index as u16, None);
+            (node.name().to_owned(), target)
+        })
+        .collect()
+}
+
+/// Retires the node: normal writes are rejected, local vdisks and aliens are copied to
+/// replacement replicas from the new cluster config and checked on them key by key.
+#[derive(Debug, Default)]
+pub(crate) struct Decommission {
+    started: AtomicBool,
+    status: Mutex<DecommissionStatus>,
+    client_factory: Mutex<Option<Factory>>,
+}
+
+impl Decommission {
+    pub(crate) fn set_client_factory(&self, factory: Factory) {
+        *self.client_factory.lock().expect("mutex") = Some(factory);
+    }
+
+    /// Normal writes are rejected with `NodeIsDecommissioning` from the start of the
+    /// decommission until the node is restarted.
+    pub(crate) fn check_write(&self) -> Result<(), Error> {
+        if self.started.load(Ordering::Acquire) {
+            Err(Error::node_is_decommissioning())
+        } else {
+            Ok(())
+        }
+    }
+
+    pub(crate) fn status(&self) -> DecommissionStatus {
+        self.status.lock().expect("mutex").clone()
+    }
+
+    pub(crate) fn is_running(&self) -> bool {
+        self.status().state == DecommissionState::Running
+    }
+
+    /// Spawns transfer of vdisks, failed or completed decommission may be started again.
+    pub(crate) fn start(
+        self: &Arc<Self>,
+        transfers: Vec<VDiskTransfer>,
+        targets: HashMap<String, Node>,
+        backend: Arc<Backend>,
+    ) -> Result<(), String> {
+        let factory = self
+            .client_factory
+            .lock()
+            .expect("mutex")
+            .clone()
+            .ok_or_else(|| "node is not started yet".to_owned())?;
+        {
+            let mut status = self.status.lock().expect("mutex");
+            if status.state == DecommissionState::Running {
+                return Err("decommission is already running".to_owned());
+            }
+            *status = DecommissionStatus {
+                state: DecommissionState::Running,
+                vdisks: transfers,
+                error: None,
+            };
+        }
+        self.started.store(true, Ordering::Release);
+        info!("decommission started, targets: {:?}", targets.keys());
+        let decommission = self.clone();
+        tokio::spawn(async move {
+            let result = decommission.run(&targets, &backend, &factory).await;
+            let mut status = decommission.status.lock().expect("mutex");
+            match result {
+                Ok(()) => {
+                    info!("decommission completed");
+                    status.state = DecommissionState::Completed;
+                }
+                Err(e) => {
+                    error!("decommission failed: {}", e);
+                    status.state = DecommissionState::Failed;
+                    status.error = Some(e);
+                }
+            }
+        });
+        Ok(())
+    }
+
+    async fn run(
+        &self,
+        targets: &HashMap<String, Node>,
+        backend: &Backend,
+        factory: &Factory,
+    ) -> Result<(), String> {
+        for target in targets.values() {
+            target
+                .check(factory)
+                .await
+                .map_err(|e| format!("can't connect to node {}: {}", target.name(), e))?;
+        }
+        let count = self.status.lock().expect("mutex").vdisks.len();
+        let mut not_verified = vec![];
+        for index in 0..count {
+            let (vdisk_id, alien_node, vdisk_targets) = {
+                let status = self.status.lock().expect("mutex");
+                let transfer = &status.vdisks[index];
+                let vdisk_targets = transfer
+                    .targets
+                    .iter()
+                    .map(|name| targets[name].clone())
+                    .collect::<Vec<_>>();
+                (transfer.vdisk_id, transfer.alien_node.clone(), vdisk_targets)
+            };
+            let groups = match &alien_node {
+                Some(node) => local_vdisk::alien_groups(backend)
+                    .await?
+                    .into_iter()
+                    .filter(|group| group.vdisk_id() == vdisk_id && *group.node_name() == node.as_str())
+                    .collect(),
+                None => local_vdisk::groups(backend, vdisk_id).await?,
+            };
+            self.transfer_vdisk(index, &groups, &vdisk_targets).await?;
+            let mut status = self.status.lock().expect("mutex");
+            let transfer = &mut status.vdisks[index];
+            transfer.verified = transfer.not_verified == 0;
+            if !transfer.verified {
+                not_verified.push(vdisk_id);
+            }
+        }
+        if not_verified.is_empty() {
+            Ok(())
+        } else {
+            Err(format!("targets don't have some of sent records of vdisks {:?}", not_verified))
+        }
+    }
+
+    // Records are sent by batches, only one partition is read into memory at a time
+    async fn transfer_vdisk(
+        &self,
+        index: usize,
+        groups: &[PearlGroup],
+        targets: &[Node],
+    ) -> Result<(), String> {
+        {
+            let mut status = self.status.lock().expect("mutex");
+            let transfer = &mut status.vdisks[index];
+            transfer.sent = 0;
+            transfer.deleted = 0;
+            transfer.not_verified = 0;
+        }
+        if targets.is_empty() {
+            return Ok(());
+        }
+        let mut batches = local_vdisk::records(groups, None)
+            .await
+            .chunks(TRANSFER_BATCH_SIZE);
+        while let Some(batch) = batches.next().await {
+            let batch = batch
+                .into_iter()
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| format!("can't read local records: {}", e))?;
+            let not_verified = Self::transfer_batch(&batch, targets).await?;
+            let mut status = self.status.lock().expect("mutex");
+            let transfer = &mut status.vdisks[index];
+            transfer.sent += batch.len() as u64;
+            transfer.deleted += batch.iter().filter(|r| r.deleted).count() as u64;
+            transfer.not_verified += not_verified;
+        }
+        Ok(())
+    }
+
+    // Sends records to targets and checks the newest version of every key on targets: it must
+    // be the sent one or a newer one written by clients while decommission runs. Returns count
+    // of keys with older version on some target
+    async fn transfer_batch(batch: &[StreamedRecord], targets: &[Node]) -> Result<u64, String> {
+        let mut results = futures::stream::iter(batch.iter().cloned())
+            .map(|record| Self::transfer_record(record, targets))
+            .buffer_unordered(TRANSFER_CONCURRENCY);
+        while let Some(result) = results.next().await {
+            result?;
+        }
+        // Partitions are streamed from old to new, so the last version of the key is the newest
+        let newest = batch
+            .iter()
+            .map(|record| (record.key(), record.clone()))
+            .collect::<HashMap<_, _>>()
+            .into_values()
+            .collect::<Vec<_>>();
+        let mut not_verified = 0;
+        for target in targets {
+            let mut results = futures::stream::iter(newest.iter().cloned())
+                .map(|record| Self::verify_record(record, target))
+                .buffer_unordered(TRANSFER_CONCURRENCY);
+            while let Some(verified) = results.next().await {
+                if !verified? {
+                    not_verified += 1;
+                }
+            }
+        }
+        Ok(not_verified)
+    }
+
+    // Missing key of the sent put is sent once more, if target still doesn't return it, newer
+    // delete marker hides it
+    async fn verify_record(record: StreamedRecord, target: &Node) -> Result<bool, String> {
+        let key = record.key();
+        let timestamp = record.data.meta().timestamp();
+        let verified = match Self::target_timestamp(key, target).await? {
+            Some(actual) if record.deleted => actual > timestamp,
+            Some(actual) => actual >= timestamp,
+            None if record.deleted => true,
+            None => {
+                Self::transfer_record(record, std::slice::from_ref(target)).await?;
+                Self::target_timestamp(key, target)
+                    .await?
+                    .is_none_or(|actual| actual >= timestamp)
+            }
+        };
+        if !verified {
+            warn!("{} is not verified on node {}", key, target.name());
+        }
+        Ok(verified)
+    }
+
+    // Timestamp of the newest version of the key on target, `None` if key is missing or deleted
+    async fn target_timestamp(key: BobKey, target: &Node) -> Result<Option<u64>, String> {
+        let options = BobGetOptions::new_local().to_grpc();
+        match LinkManager::call_node(target, |conn| conn.get(key, options).boxed()).await {
+            Ok(data) => Ok(Some(data.into_inner().meta().timestamp())),
+            Err(e) if e.inner().is_key_not_found() => Ok(None),
+            Err(e) => Err(format!("get {} on node {} failed: {}", key, target.name(), e.inner())),
+        }
+    }
+
+    async fn transfer_record(record: StreamedRecord, targets: &[Node]) -> Result<(), String> {
+        let key = record.key();
+        for target in targets {
+            let result = if record.deleted {
+                let meta = record.data.meta().clone();
+                let options = BobDeleteOptions::new_local().to_grpc();
+                LinkManager::call_node(target, |conn| conn.delete(key, meta, options).boxed())
+                    .await
+                    .map(|_| ())
+            } else {
+                let data = record.data.clone();
+                let options = BobPutOptions::new_local().to_grpc();
+                LinkManager::call_node(target, |conn| conn.put(key, data, options).boxed())
+                    .await
+                    .map(|_| ())
+            };
+            match result {
+                // Target already has newer or the same data
+                Err(e) if e.inner().is_precondition_failed() || e.inner().is_duplicate() => {}
+                Err(e) => {
+                    let operation = if record.deleted { "delete" } else { "put" };
+                    return Err(format!(
+                        "{} {} on node {} failed: {}",
+                        operation,
+                        key,
+                        target.name(),
+                        e.inner()
+                    ));
+                }
+                Ok(()) => {}
+            }
+        }
+        Ok(())
+    }
+}
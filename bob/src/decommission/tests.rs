@@ -0,0 +1,237 @@
+use crate::{prelude::*, test_utils};
+use bob_common::{
+    configs::{
+        cluster::{tests::cluster_config, Cluster as ClusterConfig},
+        node::tests::node_config,
+    },
+    data::{StreamedRecord, VDiskPosition},
+    error::Kind,
+};
+use std::sync::Mutex;
+
+use super::{plan, plan_aliens, targets, Decommission, DecommissionState};
+
+// Node "0" holds vdisks 0 and 2, node "3" replaces it
+const NEW_CLUSTER: &str = "
+nodes:
+  - name: '1'
+    address: 10.0.0.1:20000
+    disks:
+      - name: '1'
+        path: /tmp/1
+  - name: '2'
+    address: 10.0.0.2:20000
+    disks:
+      - name: '2'
+        path: /tmp/2
+  - name: '3'
+    address: 10.0.0.3:20000
+    disks:
+      - name: '3'
+        path: /tmp/3
+vdisks:
+  - id: 0
+    replicas:
+      - node: '3'
+        disk: '3'
+      - node: '1'
+        disk: '1'
+  - id: 1
+    replicas:
+      - node: '1'
+        disk: '1'
+      - node: '2'
+        disk: '2'
+  - id: 2
+    replicas:
+      - node: '2'
+        disk: '2'
+      - node: '3'
+        disk: '3'
+";
+
+fn mapper() -> Virtual {
+    Virtual::new(&node_config("0", 1), &cluster_config(3, 3, 2))
+}
+
+#[test]
+fn plan_sends_local_vdisks_to_replacement_replicas() {
+    let new_cluster = ClusterConfig::get_from_string(NEW_CLUSTER).unwrap();
+
+    let transfers = plan(&mapper(), &new_cluster).unwrap();
+
+    let vdisks = transfers
+        .iter()
+        .map(|t| (t.vdisk_id, t.targets.clone()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        vec![(0, vec!["3".to_owned()]), (2, vec!["3".to_owned()])],
+        vdisks
+    );
+    assert!(transfers.iter().all(|t| t.alien_node.is_none()));
+    let targets = targets(&transfers, &new_cluster);
+    assert_eq!(1, targets.len());
+    assert_eq!("10.0.0.3:20000", targets["3"].address());
+}
+
+#[test]
+fn plan_sends_aliens_to_owner_or_vdisk_replicas() {
+    let new_cluster = ClusterConfig::get_from_string(NEW_CLUSTER).unwrap();
+    let aliens = vec![
+        ("1".to_owned(), 1),
+        ("0".to_owned(), 2),
+        ("1".to_owned(), 1),
+    ];
+
+    let transfers = plan_aliens(&aliens, &new_cluster).unwrap();
+
+    let vdisks = transfers
+        .iter()
+        .map(|t| (t.alien_node.clone().unwrap(), t.vdisk_id, t.targets.clone()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        vec![
+            ("0".to_owned(), 2, vec!["2".to_owned(), "3".to_owned()]),
+            ("1".to_owned(), 1, vec!["1".to_owned()]),
+        ],
+        vdisks
+    );
+    let error = plan_aliens(&[("1".to_owned(), 5)], &new_cluster).unwrap_err();
+    assert!(error.contains("vdisk 5"), "{}", error);
+}
+
+#[test]
+fn plan_fails_if_node_is_in_new_cluster() {
+    let new_cluster = cluster_config(4, 3, 2);
+
+    let error = plan(&mapper(), &new_cluster).unwrap_err();
+
+    assert!(error.contains("still in the new cluster config"), "{}", error);
+}
+
+#[test]
+fn plan_fails_if_local_vdisk_is_missing() {
+    let without_vdisk_2 = NEW_CLUSTER.split("  - id: 2").next().unwrap();
+    let new_cluster = ClusterConfig::get_from_string(without_vdisk_2).unwrap();
+
+    let error = plan(&mapper(), &new_cluster).unwrap_err();
+
+    assert!(error.contains("vdisk 2 is missing"), "{}", error);
+}
+
+#[tokio::test]
+async fn writes_are_allowed_until_decommission_started() {
+    let decommission = Arc::new(Decommission::default());
+    let backend = Arc::new(Backend::new(Arc::new(mapper()), &node_config("0", 1)).await);
+
+    assert!(decommission.check_write().is_ok());
+    // Client factory is set when node starts
+    let error = decommission
+        .start(vec![], HashMap::new(), backend)
+        .unwrap_err();
+
+    assert_eq!("node is not started yet", error);
+    assert!(decommission.check_write().is_ok());
+    assert_eq!(DecommissionState::NotStarted, decommission.status().state);
+}
+
+#[test]
+fn decommission_error_is_unavailable() {
+    let status: Status = Error::node_is_decommissioning().into();
+    assert_eq!(Code::Unavailable, status.code());
+    let error: Error = status.into();
+    assert_eq!(&Kind::NodeIsDecommissioning, error.kind());
+}
+
+fn record(partition: u64, key: u64, deleted: bool) -> StreamedRecord {
+    StreamedRecord {
+        position: VDiskPosition {
+            partition,
+            key: key.into(),
+        },
+        data: BobData::new(vec![1].into(), BobMeta::new(partition + 1)),
+        deleted,
+    }
+}
+
+type Stored = Arc<Mutex<HashMap<BobKey, (u64, bool)>>>;
+
+// Target keeps timestamp and delete flag of the newest version of keys in `stored`, put of
+// `lost` key succeeds without storing it
+fn target(stored: Stored, lost: BobKey) -> Node {
+    fn store(stored: &Stored, key: BobKey, timestamp: u64, deleted: bool) {
+        let mut stored = stored.lock().unwrap();
+        if stored.get(&key).is_none_or(|(ts, _)| *ts < timestamp) {
+            stored.insert(key, (timestamp, deleted));
+        }
+    }
+    let node = Node::new("3".into(), "10.0.0.3:20000".to_owned(), 0, None);
+    let mut client = BobClient::new();
+    let put_stored = stored.clone();
+    client.expect_put().returning(move |key, data, _| {
+        if key != lost {
+            store(&put_stored, key, data.meta().timestamp(), false);
+        }
+        test_utils::put_ok("3".into())
+    });
+    let delete_stored = stored.clone();
+    client.expect_delete().returning(move |key, meta, _| {
+        store(&delete_stored, key, meta.timestamp(), true);
+        Ok(NodeOutput::new("3".into(), ()))
+    });
+    client.expect_get().returning(move |key, _| match stored.lock().unwrap().get(&key) {
+        Some((timestamp, false)) => test_utils::get_ok("3".into(), *timestamp),
+        _ => Err(NodeOutput::new("3".into(), Error::key_not_found(key))),
+    });
+    node.set_connection(client);
+    node
+}
+
+#[tokio::test]
+async fn transfer_sends_delete_markers_and_verifies_keys() {
+    let stored = Arc::default();
+    let targets = vec![target(Arc::clone(&stored), 4.into())];
+    // Key 2 is deleted in the next partition
+    let batch = vec![
+        record(0, 1, false),
+        record(0, 2, false),
+        record(1, 2, true),
+        record(1, 3, true),
+    ];
+
+    let not_verified = Decommission::transfer_batch(&batch, &targets).await.unwrap();
+
+    assert_eq!(0, not_verified);
+    let stored = stored.lock().unwrap().clone();
+    let expected = vec![(1.into(), (1, false)), (2.into(), (2, true)), (3.into(), (2, true))]
+        .into_iter()
+        .collect::<HashMap<BobKey, (u64, bool)>>();
+    assert_eq!(expected, stored);
+}
+
+#[tokio::test]
+async fn transfer_verifies_keys_overwritten_by_clients() {
+    // Clients deleted key 1 and wrote key 2 on target after they were read
+    let stored: Stored = Arc::new(Mutex::new(
+        vec![(1.into(), (10, true)), (2.into(), (10, false))]
+            .into_iter()
+            .collect(),
+    ));
+    let targets = vec![target(Arc::clone(&stored), 4.into())];
+    let batch = vec![record(0, 1, false), record(0, 2, true)];
+
+    let not_verified = Decommission::transfer_batch(&batch, &targets).await.unwrap();
+
+    assert_eq!(0, not_verified);
+}
+
+#[tokio::test]
+async fn transfer_counts_keys_with_older_version_on_target() {
+    let stored: Stored = Arc::new(Mutex::new(vec![(4.into(), (0, false))].into_iter().collect()));
+    let targets = vec![target(stored, 4.into())];
+    let batch = vec![record(0, 1, false), record(0, 4, false)];
+
+    let not_verified = Decommission::transfer_batch(&batch, &targets).await.unwrap();
+
+    assert_eq!(1, not_verified);
+}
@@ -4,8 +4,13 @@ use crate::{
     cleaner::Cleaner,
     cluster::{get_cluster, Cluster},
     counter::Counter as BlobsCounter,
+    decommission::Decommission,
+    handoff::HandoffQueue,
+    health::NodeHealth,
     hw_metrics_collector::HWMetricsCollector,
     link_manager::LinkManager,
+    rebuild::Rebuilds,
+    scrubber::Scrubber,
 };
 
 use bob_common::metrics::{
@@ -19,17 +24,35 @@ pub struct Grinder {
     backend: Arc<Backend>,
     link_manager: Arc<LinkManager>,
     cluster: Arc<dyn Cluster + Send + Sync>,
+    handoff: Option<Arc<HandoffQueue>>,
     cleaner: Arc<Cleaner>,
     counter: Arc<BlobsCounter>,
     node_config: NodeConfig,
     hw_counter: Arc<HWMetricsCollector>,
+    health: Arc<NodeHealth>,
+    decommission: Arc<Decommission>,
+    rebuilds: Arc<Rebuilds>,
+    scrubber: Option<Arc<Scrubber>>,
 }
 
 impl Grinder {
     /// Creates new instance of the Grinder
     pub async fn new(mapper: Virtual, config: &NodeConfig) -> Grinder {
         let nodes = mapper.nodes().iter().cloned().collect::<Vec<_>>();
-        let link_manager = Arc::new(LinkManager::new(nodes.as_slice(), config.check_interval()));
+        let handoff = match config.handoff() {
+            Some(handoff_config) => {
+                let handoff = HandoffQueue::new(handoff_config, &nodes, mapper.local_node_name())
+                    .await
+                    .expect("create handoff queue");
+                Some(Arc::new(handoff))
+            }
+            None => None,
+        };
+        let link_manager = Arc::new(LinkManager::new(
+            nodes.as_slice(),
+            config.check_interval(),
+            handoff.clone(),
+        ));
         let mapper = Arc::new(mapper);
         let backend = Arc::new(Backend::new(mapper.clone(), config).await);
         let cleaner = Cleaner::new(
@@ -50,11 +73,16 @@ impl Grinder {
         Grinder {
             backend: backend.clone(),
             link_manager,
-            cluster: get_cluster(mapper, config, backend),
+            cluster: get_cluster(mapper, config, backend, handoff.clone()),
+            handoff,
             cleaner,
             counter,
             node_config: config.clone(),
             hw_counter,
+            health: Arc::default(),
+            decommission: Arc::default(),
+            rebuilds: Arc::new(Rebuilds::new(config.rebuild().records_per_sec())),
+            scrubber: config.scrubber().map(|c| Arc::new(Scrubber::new(c))),
         }
     }
 
@@ -63,7 +91,29 @@ impl Grinder {
     }
 
     pub(crate) async fn run_backend(&self) -> Result<()> {
-        self.backend.run_backend().await
+        self.backend.run_backend().await?;
+        self.health.set_backend_started();
+        Ok(())
+    }
+
+    pub(crate) fn health(&self) -> &NodeHealth {
+        &self.health
+    }
+
+    pub(crate) fn decommission(&self) -> &Arc<Decommission> {
+        &self.decommission
+    }
+
+    pub(crate) fn rebuilds(&self) -> &Arc<Rebuilds> {
+        &self.rebuilds
+    }
+
+    pub(crate) fn scrubber(&self) -> Option<&Scrubber> {
+        self.scrubber.as_deref()
+    }
+
+    pub(crate) fn handoff(&self) -> Option<&HandoffQueue> {
+        self.handoff.as_deref()
     }
 
     pub(crate) fn node_config(&self) -> &NodeConfig {
@@ -74,12 +124,18 @@ impl Grinder {
         &self.hw_counter
     }
 
+    #[tracing::instrument(name = "grinder.put", skip_all, fields(key = %key))]
     pub(crate) async fn put(
         &self,
         key: BobKey,
         data: &BobData,
         opts: BobPutOptions,
+        from_node: bool,
     ) -> Result<(), Error> {
+        let _request = self.health.start_request(from_node)?;
+        if !opts.to_alien() {
+            self.decommission.check_write()?;
+        }
         let sw = Stopwatch::start_new();
         if opts.force_node() {
             trace!(">>>- - - - - GRINDER PUT START - - - - -");
@@ -107,7 +163,7 @@ impl Grinder {
             counter!(GRINDER_PUT_COUNTER, 1);
             let time = Instant::now();
 
-            let result = self.cluster.put(key, data).await;
+            let result = self.cluster.put(key, data, opts).await;
             if result.is_err() {
                 counter!(GRINDER_PUT_ERROR_COUNT_COUNTER, 1);
             }
@@ -118,7 +174,14 @@ impl Grinder {
         }
     }
 
-    pub(crate) async fn get(&self, key: BobKey, opts: &BobGetOptions) -> Result<BobData, Error> {
+    #[tracing::instrument(name = "grinder.get", skip_all, fields(key = %key))]
+    pub(crate) async fn get(
+        &self,
+        key: BobKey,
+        opts: &BobGetOptions,
+        from_node: bool,
+    ) -> Result<BobData, Error> {
+        let _request = self.health.start_request(from_node)?;
         trace!(">>>- - - - - GRINDER GET START - - - - -");
         let sw = Stopwatch::start_new();
         if opts.force_node() {
@@ -153,7 +216,7 @@ impl Grinder {
             counter!(GRINDER_GET_COUNTER, 1);
             let time = Instant::now();
             debug!("GET[{}] will route to cluster", key);
-            let result = self.cluster.get(key).await;
+            let result = self.cluster.get(key, opts).await;
             trace!(
                 "cluster processed get, /{:.3}ms/",
                 sw.elapsed().as_secs_f64() * 1000.0
@@ -167,11 +230,14 @@ impl Grinder {
         }
     }
 
+    #[tracing::instrument(name = "grinder.exist", skip_all, fields(keys = keys.len()))]
     pub(crate) async fn exist(
         &self,
         keys: &[BobKey],
         opts: &BobGetOptions,
+        from_node: bool,
     ) -> Result<Vec<bool>, Error> {
+        let _request = self.health.start_request(from_node)?;
         let sw = Stopwatch::start_new();
         if opts.force_node() {
             counter!(CLIENT_EXIST_COUNTER, 1);
@@ -208,19 +274,29 @@ impl Grinder {
 
     #[inline]
     pub(crate) fn run_periodic_tasks(&self, client_factory: Factory) {
+        self.decommission.set_client_factory(client_factory.clone());
         self.link_manager.spawn_checker(client_factory);
         self.cleaner
             .spawn_task(self.cleaner.clone(), self.backend.clone());
         self.counter.spawn_task(self.backend.clone());
         self.hw_counter.spawn_task();
+        if let Some(scrubber) = &self.scrubber {
+            scrubber.spawn_task(self.backend.clone());
+        }
     }
 
+    #[tracing::instrument(name = "grinder.delete", skip_all, fields(key = %key))]
     pub(crate) async fn delete(
         &self,
         key: BobKey,
         meta: &BobMeta,
         options: BobDeleteOptions,
+        from_node: bool,
     ) -> Result<(), Error> {
+        let _request = self.health.start_request(from_node)?;
+        if !options.to_alien() {
+            self.decommission.check_write()?;
+        }
         trace!(">>>- - - - - GRINDER DELETE START - - - - -");
         let result = if options.force_node() {
             counter!(CLIENT_DELETE_COUNTER, 1);
@@ -242,7 +318,7 @@ impl Grinder {
         } else {
             counter!(GRINDER_DELETE_COUNTER, 1);
             let sw = Stopwatch::start_new();
-            let result = self.cluster.delete(key, meta).await;
+            let result = self.cluster.delete(key, meta, options).await;
             trace!(
                 "cluster processed delete, /{:.3}ms/",
                 sw.elapsed().as_secs_f64() * 1000.0
@@ -269,6 +345,7 @@ impl Debug for Grinder {
             .field("backend", &self.backend)
             .field("link_manager", &self.link_manager)
             .field("cluster", &"..")
+            .field("handoff", &self.handoff)
             .finish()
     }
 }
@@ -0,0 +1,328 @@
+use crate::{link_manager::LinkManager, prelude::*};
+use bob_common::{
+    configs::node::HandoffConfig,
+    data::BOB_KEY_SIZE,
+    metrics::{HANDOFF_DROPPED_COUNTER, HANDOFF_QUEUE_DEPTH_PREFIX, HANDOFF_REPLAYED_COUNTER},
+};
+use std::{
+    convert::TryInto,
+    io::{Error as IOError, ErrorKind, Result as IOResult, SeekFrom},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+};
+use tokio::{
+    fs::{self, File, OpenOptions},
+    io::{self, AsyncRead, AsyncReadExt, AsyncSeekExt, AsyncWriteExt, BufReader},
+    sync::{Mutex, OwnedSemaphorePermit, Semaphore},
+};
+
+#[cfg(test)]
+mod tests;
+
+const HINTS_FILE_EXTENSION: &str = "hints";
+const HINT_PUT: u8 = 0;
+const HINT_DELETE: u8 = 1;
+const TIMESTAMP_LEN: usize = 8;
+const HINT_HEADER_LEN: usize = 1 + BOB_KEY_SIZE + 4;
+
+/// Write that wasn't delivered to the target node.
+#[derive(Debug, Clone)]
+pub(crate) enum Hint {
+    Put(BobKey, BobData),
+    Delete(BobKey, BobMeta),
+}
+
+impl Hint {
+    pub(crate) fn key(&self) -> BobKey {
+        match self {
+            Hint::Put(key, _) | Hint::Delete(key, _) => *key,
+        }
+    }
+
+    // Layout: [kind: u8][key][payload length: u32][payload]
+    fn to_bytes(&self) -> Vec<u8> {
+        let (kind, key, payload) = match self {
+            Hint::Put(key, data) => (HINT_PUT, key, data.to_serialized_bytes().to_vec()),
+            Hint::Delete(key, meta) => (HINT_DELETE, key, meta.timestamp().to_be_bytes().to_vec()),
+        };
+        let mut buf = Vec::with_capacity(HINT_HEADER_LEN + payload.len());
+        buf.push(kind);
+        buf.extend_from_slice(key.as_slice());
+        buf.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+        buf.extend_from_slice(&payload);
+        buf
+    }
+
+    // Returns hint with its length in file, `None` at the end of file. Payload can't be longer
+    // than `remaining` bytes of file from the start of the hint, otherwise the tail is corrupted
+    async fn read(
+        reader: &mut (impl AsyncRead + Unpin),
+        remaining: u64,
+    ) -> IOResult<Option<(Self, usize)>> {
+        let kind = match reader.read_u8().await {
+            Ok(kind) => kind,
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        let mut key = [0; BOB_KEY_SIZE];
+        reader.read_exact(&mut key).await?;
+        let key = BobKey::from(&key[..]);
+        let payload_len = reader.read_u32().await? as usize;
+        if (HINT_HEADER_LEN + payload_len) as u64 > remaining {
+            return Err(Self::invalid_data("payload length exceeds file length"));
+        }
+        let mut payload = vec![0; payload_len];
+        reader.read_exact(&mut payload).await?;
+        if payload_len < TIMESTAMP_LEN {
+            return Err(Self::invalid_data("payload is too short"));
+        }
+        let hint = match kind {
+            HINT_PUT => {
+                let data = BobData::from_serialized_bytes(payload.into())
+                    .map_err(|e| Self::invalid_data(&e.to_string()))?;
+                Hint::Put(key, data)
+            }
+            HINT_DELETE => {
+                let timestamp = payload[..].try_into().map_err(|_| Self::invalid_data("wrong timestamp length"))?;
+                Hint::Delete(key, BobMeta::new(u64::from_be_bytes(timestamp)))
+            }
+            kind => return Err(Self::invalid_data(&format!("unknown hint kind {}", kind))),
+        };
+        Ok(Some((hint, HINT_HEADER_LEN + payload_len)))
+    }
+
+    fn invalid_data(msg: &str) -> IOError {
+        IOError::new(ErrorKind::InvalidData, msg)
+    }
+}
+
+#[derive(Debug)]
+struct NodeQueue {
+    path: PathBuf,
+    len: AtomicUsize,
+    replaying: AtomicBool,
+    // Guards file, replay holds it only to take snapshot and to cut replayed hints
+    file_lock: Mutex<()>,
+    depth_gauge: String,
+}
+
+/// Per node on-disk queues of writes that failed both on the node and on aliens.
+/// Hints are replayed when `LinkManager` reaches the node again.
+#[derive(Debug)]
+pub(crate) struct HandoffQueue {
+    max_hints_per_node: usize,
+    queues: HashMap<NodeName, NodeQueue>,
+    background_puts: Arc<Semaphore>,
+}
+
+impl HandoffQueue {
+    pub(crate) async fn new(
+        config: &HandoffConfig,
+        nodes: &[Node],
+        local_node_name: &NodeName,
+    ) -> IOResult<Self> {
+        let dir = Path::new(config.path());
+        fs::create_dir_all(dir).await?;
+        let mut queues = HashMap::new();
+        for node in nodes.iter().filter(|node| node.name() != local_node_name) {
+            let path = dir.join(format!("{}.{}", node.name(), HINTS_FILE_EXTENSION));
+            let len = Self::restore(&path).await?;
+            if len > 0 {
+                info!("handoff queue for node {} restored: {} hints", node.name(), len);
+            }
+            let depth_gauge = format!("{}.{}", HANDOFF_QUEUE_DEPTH_PREFIX, node.name());
+            gauge!(depth_gauge.clone(), len as f64);
+            let queue = NodeQueue {
+                path,
+                len: AtomicUsize::new(len),
+                replaying: AtomicBool::new(false),
+                file_lock: Mutex::new(()),
+                depth_gauge,
+            };
+            queues.insert(node.name().clone(), queue);
+        }
+        Ok(Self {
+            max_hints_per_node: config.max_hints_per_node(),
+            queues,
+            background_puts: Arc::new(Semaphore::new(config.max_background_puts())),
+        })
+    }
+
+    // Counts hints in file and cuts hint that was partially written before restart
+    async fn restore(path: &Path) -> IOResult<usize> {
+        let file = match File::open(path).await {
+            Ok(file) => file,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e),
+        };
+        let file_len = file.metadata().await?.len();
+        let mut reader = BufReader::new(file);
+        let mut count = 0;
+        let mut valid_len = 0;
+        loop {
+            match Hint::read(&mut reader, file_len - valid_len).await {
+                Ok(Some((_, len))) => {
+                    count += 1;
+                    valid_len += len as u64;
+                }
+                Ok(None) => break,
+                Err(e) => {
+                    warn!("handoff file {:?} is cut after {} hints: {}", path, count, e);
+                    let file = OpenOptions::new().write(true).open(path).await?;
+                    file.set_len(valid_len).await?;
+                    break;
+                }
+            }
+        }
+        Ok(count)
+    }
+
+    /// Count of pending hints for the node, `None` if there is no queue for it.
+    pub(crate) fn depth(&self, node_name: &NodeName) -> Option<usize> {
+        self.queues
+            .get(node_name)
+            .map(|queue| queue.len.load(Ordering::Acquire))
+    }
+
+    /// Returns `None` if max count of background puts are in progress.
+    pub(crate) fn try_acquire_background_put(&self) -> Option<OwnedSemaphorePermit> {
+        self.background_puts.clone().try_acquire_owned().ok()
+    }
+
+    pub(crate) async fn push(&self, node_name: &NodeName, hint: &Hint) -> Result<(), Error> {
+        let queue = self.queues.get(node_name).ok_or_else(|| {
+            Error::failed(format!("no handoff queue for node {}", node_name))
+        })?;
+        let _guard = queue.file_lock.lock().await;
+        if queue.len.load(Ordering::Acquire) >= self.max_hints_per_node {
+            counter!(HANDOFF_DROPPED_COUNTER, 1);
+            return Err(Error::failed(format!("handoff queue for node {} is full", node_name)));
+        }
+        if let Err(e) = Self::append(&queue.path, &hint.to_bytes()).await {
+            counter!(HANDOFF_DROPPED_COUNTER, 1);
+            return Err(Error::failed(format!(
+                "failed to write handoff queue for node {}: {}",
+                node_name, e
+            )));
+        }
+        let len = queue.len.fetch_add(1, Ordering::AcqRel) + 1;
+        gauge!(queue.depth_gauge.clone(), len as f64);
+        Ok(())
+    }
+
+    /// Saves hint for every node, errors are logged.
+    pub(crate) async fn push_for_nodes(&self, node_names: &[NodeName], hint: Hint) {
+        for node_name in node_names {
+            match self.push(node_name, &hint).await {
+                Ok(()) => debug!("[{}] hint saved to handoff queue of node {}", hint.key(), node_name),
+                Err(e) => error!("[{}] hint for node {} dropped: {}", hint.key(), node_name, e),
+            }
+        }
+    }
+
+    async fn append(path: &Path, bytes: &[u8]) -> IOResult<()> {
+        let mut file = OpenOptions::new().create(true).append(true).open(path).await?;
+        file.write_all(bytes).await?;
+        file.sync_data().await
+    }
+
+    /// Spawns replay of node queue if it has hints and isn't replayed already.
+    pub(crate) fn schedule_replay(self: &Arc<Self>, node: &Node) {
+        if let Some(queue) = self.queues.get(node.name()) {
+            if queue.len.load(Ordering::Acquire) > 0 && !queue.replaying.swap(true, Ordering::AcqRel) {
+                let handoff = self.clone();
+                let node = node.clone();
+                tokio::spawn(async move { handoff.replay(&node).await });
+            }
+        }
+    }
+
+    async fn replay(&self, node: &Node) {
+        let queue = self.queues.get(node.name()).expect("handoff queue for node");
+        match Self::replay_queue(queue, node).await {
+            Ok((replayed, kept)) => {
+                gauge!(queue.depth_gauge.clone(), kept as f64);
+                counter!(HANDOFF_REPLAYED_COUNTER, replayed as u64);
+                info!(
+                    "handoff queue replayed to node {}: {} hints, {} hints left",
+                    node.name(),
+                    replayed,
+                    kept
+                );
+            }
+            Err(e) => error!("failed to replay handoff queue to node {}: {}", node.name(), e),
+        }
+        queue.replaying.store(false, Ordering::Release);
+    }
+
+    // Hints in file at the start of replay are sent without lock, so writes to the queue don't
+    // wait for the node. Returns count of replayed and kept hints
+    async fn replay_queue(queue: &NodeQueue, node: &Node) -> IOResult<(usize, usize)> {
+        let snapshot_len = {
+            let _guard = queue.file_lock.lock().await;
+            match fs::metadata(&queue.path).await {
+                Ok(metadata) => metadata.len(),
+                Err(e) if e.kind() == ErrorKind::NotFound => return Ok((0, 0)),
+                Err(e) => return Err(e),
+            }
+        };
+        let (replayed, replayed_len) = Self::send_hints(&queue.path, snapshot_len, node).await?;
+        let _guard = queue.file_lock.lock().await;
+        if replayed_len > 0 {
+            Self::cut_replayed(&queue.path, replayed_len).await?;
+        }
+        let kept = queue.len.fetch_sub(replayed, Ordering::AcqRel) - replayed;
+        Ok((replayed, kept))
+    }
+
+    // Sends hints from the first `len` bytes of file until the first failure.
+    // Returns count and length in file of replayed hints
+    async fn send_hints(path: &Path, len: u64, node: &Node) -> IOResult<(usize, u64)> {
+        // Only replay removes hints from file, others append them after the snapshot
+        let mut reader = BufReader::new(File::open(path).await?).take(len);
+        let mut replayed = 0;
+        let mut replayed_len = 0;
+        while let Some((hint, hint_len)) = Hint::read(&mut reader, len - replayed_len).await? {
+            if let Err(e) = Self::send(node, &hint).await {
+                warn!("[{}] replay to node {} stopped: {:?}", hint.key(), node.name(), e);
+                break;
+            }
+            replayed += 1;
+            replayed_len += hint_len as u64;
+        }
+        Ok((replayed, replayed_len))
+    }
+
+    // Removes replayed hints from the beginning of file, must be called under file lock
+    async fn cut_replayed(path: &Path, replayed_len: u64) -> IOResult<()> {
+        let mut file = File::open(path).await?;
+        if file.metadata().await?.len() == replayed_len {
+            return fs::remove_file(path).await;
+        }
+        file.seek(SeekFrom::Start(replayed_len)).await?;
+        let tmp_path = path.with_extension("tmp");
+        let mut tmp = File::create(&tmp_path).await?;
+        io::copy(&mut file, &mut tmp).await?;
+        tmp.sync_data().await?;
+        fs::rename(&tmp_path, path).await
+    }
+
+    async fn send(node: &Node, hint: &Hint) -> Result<(), NodeOutput<Error>> {
+        let result = match hint.clone() {
+            Hint::Put(key, data) => {
+                let options = BobPutOptions::new_local().to_grpc();
+                LinkManager::call_node(node, |conn| conn.put(key, data, options).boxed()).await
+            }
+            Hint::Delete(key, meta) => {
+                let options = BobDeleteOptions::new_local().to_grpc();
+                LinkManager::call_node(node, |conn| conn.delete(key, meta, options).boxed()).await
+            }
+        };
+        match result {
+            // Node already has newer or the same data
+            Err(e) if e.inner().is_precondition_failed() || e.inner().is_duplicate() => Ok(()),
+            Err(e) => Err(e),
+            Ok(_) => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,194 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::{prelude::*, test_utils};
+
+use bob_common::{
+    bob_client::b_client::MockBobClient as BobClient,
+    configs::node::tests::{node_config, with_handoff},
+    data::BOB_KEY_SIZE,
+};
+use std::path::{Path, PathBuf};
+use tokio::{fs::OpenOptions, io::AsyncWriteExt};
+
+use super::{HandoffQueue, Hint};
+
+static DIR_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+fn handoff_dir() -> PathBuf {
+    let id = DIR_COUNTER.fetch_add(1, Ordering::SeqCst);
+    std::env::temp_dir().join(format!("bob_handoff_test_{}_{}", std::process::id(), id))
+}
+
+fn nodes() -> Vec<Node> {
+    vec![
+        Node::new("0".into(), "0.0.0.0:0".to_string(), 0, None),
+        Node::new("1".into(), "0.0.0.0:1".to_string(), 1, None),
+    ]
+}
+
+async fn create_handoff(dir: &Path, max_hints_per_node: usize) -> HandoffQueue {
+    let config = with_handoff(node_config("0", 1), dir.to_str().unwrap(), max_hints_per_node);
+    HandoffQueue::new(config.handoff().unwrap(), &nodes(), &"0".into())
+        .await
+        .expect("create handoff queue")
+}
+
+fn put_hint(key: u64, timestamp: u64) -> Hint {
+    Hint::Put(key.into(), BobData::new(vec![1, 2, 3].into(), BobMeta::new(timestamp)))
+}
+
+async fn push_hints(handoff: &HandoffQueue) {
+    let node_name = "1".into();
+    handoff.push(&node_name, &put_hint(1, 10)).await.unwrap();
+    handoff.push(&node_name, &Hint::Delete(2.into(), BobMeta::new(11))).await.unwrap();
+    handoff.push(&node_name, &put_hint(3, 12)).await.unwrap();
+}
+
+fn connect(node: &Node, put_ok: bool, puts: Arc<AtomicU64>, deletes: Arc<AtomicU64>) {
+    let mut client = BobClient::new();
+    let name = node.name().clone();
+    client.expect_put().returning(move |_key, _data, _options| {
+        puts.fetch_add(1, Ordering::SeqCst);
+        if put_ok {
+            test_utils::put_ok(name.clone())
+        } else {
+            test_utils::put_err(name.clone())
+        }
+    });
+    let name = node.name().clone();
+    client.expect_delete().returning(move |_key, _meta, _options| {
+        deletes.fetch_add(1, Ordering::SeqCst);
+        Ok(NodeOutput::new(name.clone(), ()))
+    });
+    node.set_connection(client.into());
+}
+
+#[tokio::test]
+async fn handoff_queue_restored_after_restart() {
+    let dir = handoff_dir();
+    let handoff = create_handoff(&dir, 10).await;
+    assert_eq!(Some(0), handoff.depth(&"1".into()));
+    assert_eq!(None, handoff.depth(&"0".into()));
+    push_hints(&handoff).await;
+    drop(handoff);
+
+    let handoff = create_handoff(&dir, 10).await;
+
+    assert_eq!(Some(3), handoff.depth(&"1".into()));
+}
+
+#[tokio::test]
+async fn handoff_queue_cuts_partially_written_hint() {
+    let dir = handoff_dir();
+    let handoff = create_handoff(&dir, 10).await;
+    push_hints(&handoff).await;
+    drop(handoff);
+    let mut file = OpenOptions::new()
+        .append(true)
+        .open(dir.join("1.hints"))
+        .await
+        .unwrap();
+    file.write_all(&[0, 1, 2]).await.unwrap();
+
+    let handoff = create_handoff(&dir, 10).await;
+    handoff.push(&"1".into(), &put_hint(4, 13)).await.unwrap();
+    drop(handoff);
+    let handoff = create_handoff(&dir, 10).await;
+
+    assert_eq!(Some(4), handoff.depth(&"1".into()));
+}
+
+#[tokio::test]
+async fn handoff_queue_cuts_hint_with_corrupted_length() {
+    let dir = handoff_dir();
+    let handoff = create_handoff(&dir, 10).await;
+    push_hints(&handoff).await;
+    drop(handoff);
+    let mut file = OpenOptions::new()
+        .append(true)
+        .open(dir.join("1.hints"))
+        .await
+        .unwrap();
+    let mut hint = put_hint(4, 13).to_bytes();
+    hint[1 + BOB_KEY_SIZE..1 + BOB_KEY_SIZE + 4].copy_from_slice(&u32::MAX.to_be_bytes());
+    file.write_all(&hint).await.unwrap();
+
+    let handoff = create_handoff(&dir, 10).await;
+
+    assert_eq!(Some(3), handoff.depth(&"1".into()));
+}
+
+#[tokio::test]
+async fn handoff_queue_is_bounded() {
+    let handoff = create_handoff(&handoff_dir(), 2).await;
+    let node_name = "1".into();
+
+    assert!(handoff.push(&node_name, &put_hint(1, 10)).await.is_ok());
+    assert!(handoff.push(&node_name, &put_hint(2, 10)).await.is_ok());
+    assert!(handoff.push(&node_name, &put_hint(3, 10)).await.is_err());
+    assert_eq!(Some(2), handoff.depth(&node_name));
+}
+
+#[tokio::test]
+async fn handoff_queue_replayed_to_node() {
+    let dir = handoff_dir();
+    let handoff = create_handoff(&dir, 10).await;
+    push_hints(&handoff).await;
+    let node = nodes().remove(1);
+    let (puts, deletes) = (Arc::new(AtomicU64::new(0)), Arc::new(AtomicU64::new(0)));
+    connect(&node, true, puts.clone(), deletes.clone());
+
+    handoff.replay(&node).await;
+
+    assert_eq!(2, puts.load(Ordering::SeqCst));
+    assert_eq!(1, deletes.load(Ordering::SeqCst));
+    assert_eq!(Some(0), handoff.depth(node.name()));
+    assert!(!dir.join("1.hints").exists());
+}
+
+#[tokio::test]
+async fn handoff_queue_kept_if_node_failed() {
+    let dir = handoff_dir();
+    let handoff = create_handoff(&dir, 10).await;
+    push_hints(&handoff).await;
+    let node = nodes().remove(1);
+    let (puts, deletes) = (Arc::new(AtomicU64::new(0)), Arc::new(AtomicU64::new(0)));
+    connect(&node, false, puts.clone(), deletes.clone());
+
+    handoff.replay(&node).await;
+
+    assert_eq!(1, puts.load(Ordering::SeqCst));
+    assert_eq!(0, deletes.load(Ordering::SeqCst));
+    assert_eq!(Some(3), handoff.depth(node.name()));
+    drop(handoff);
+    assert_eq!(Some(3), create_handoff(&dir, 10).await.depth(node.name()));
+}
+
+#[tokio::test]
+async fn handoff_queue_accepts_hints_during_replay() {
+    let dir = handoff_dir();
+    let handoff = Arc::new(create_handoff(&dir, 10).await);
+    push_hints(&handoff).await;
+    let node = nodes().remove(1);
+    let mut client = BobClient::new();
+    let (name, queue, next_key) = (node.name().clone(), handoff.clone(), Arc::new(AtomicU64::new(10)));
+    client.expect_put().returning(move |_key, _data, _options| {
+        // Writes to the queue don't wait for replay to the node
+        assert!(queue.queues[&name].file_lock.try_lock().is_ok());
+        let hint = put_hint(next_key.fetch_add(1, Ordering::SeqCst), 20);
+        futures::executor::block_on(queue.push(&name, &hint)).unwrap();
+        test_utils::put_ok(name.clone())
+    });
+    let name = node.name().clone();
+    client
+        .expect_delete()
+        .returning(move |_key, _meta, _options| Ok(NodeOutput::new(name.clone(), ())));
+    node.set_connection(client.into());
+
+    handoff.replay(&node).await;
+
+    assert_eq!(Some(2), handoff.depth(node.name()));
+    node.clear_connection();
+    drop(handoff);
+    assert_eq!(Some(2), create_handoff(&dir, 10).await.depth(node.name()));
+}
@@ -0,0 +1,230 @@
+use crate::{prelude::*, server::Server};
+use bob_access::Authenticator;
+use bob_grpc::health::{
+    health_check_response::ServingStatus, health_server::Health, HealthCheckRequest,
+    HealthCheckResponse,
+};
+use futures::Stream;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use tokio::sync::Notify;
+
+#[cfg(test)]
+mod tests;
+
+/// Name of `BobApi` in health checks, empty name means the whole node.
+pub const BOB_API_SERVICE: &str = "bob_storage.BobApi";
+
+const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Tracks data requests in progress so the node can be drained before shutdown.
+#[derive(Debug, Default)]
+pub(crate) struct NodeHealth {
+    backend_started: AtomicBool,
+    draining: AtomicBool,
+    shutting_down: AtomicBool,
+    in_flight: AtomicUsize,
+    idle: Notify,
+}
+
+/// Data request in progress, drain waits until all of them are dropped.
+#[derive(Debug)]
+pub(crate) struct RequestGuard {
+    health: Arc<NodeHealth>,
+}
+
+impl NodeHealth {
+    pub(crate) fn set_backend_started(&self) {
+        self.backend_started.store(true, Ordering::Release);
+    }
+
+    pub(crate) fn backend_started(&self) -> bool {
+        self.backend_started.load(Ordering::Acquire)
+    }
+
+    pub(crate) fn is_draining(&self) -> bool {
+        self.draining.load(Ordering::SeqCst)
+    }
+
+    pub(crate) fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::SeqCst)
+    }
+
+    /// Fails with `NodeIsDraining` after drain started. Drain applies to client requests only,
+    /// requests from other nodes keep replication and handoff of the cluster going until the
+    /// node shuts down.
+    pub(crate) fn start_request(self: &Arc<Self>, from_node: bool) -> Result<RequestGuard, Error> {
+        // Counted before the check, so drain can't see zero requests and miss this one
+        self.in_flight.fetch_add(1, Ordering::SeqCst);
+        let guard = RequestGuard {
+            health: self.clone(),
+        };
+        let rejected = if from_node {
+            self.shutting_down.load(Ordering::SeqCst)
+        } else {
+            self.is_draining()
+        };
+        if rejected {
+            Err(Error::node_is_draining())
+        } else {
+            Ok(guard)
+        }
+    }
+
+    /// New client requests are rejected after this call, readiness checks fail.
+    pub(crate) fn start_drain(&self) {
+        if !self.draining.swap(true, Ordering::SeqCst) {
+            info!("drain started, requests in progress: {}", self.in_flight());
+        }
+    }
+
+    /// Returns `false` if the node is shutting down, drain can't be stopped then.
+    pub(crate) fn stop_drain(&self) -> bool {
+        if self.shutting_down.load(Ordering::SeqCst) {
+            return false;
+        }
+        if self.draining.swap(false, Ordering::SeqCst) {
+            info!("drain stopped");
+        }
+        true
+    }
+
+    /// Drain before shutdown, requests from other nodes are rejected too.
+    pub(crate) fn start_shutdown(&self) {
+        self.shutting_down.store(true, Ordering::SeqCst);
+        self.start_drain();
+    }
+
+    /// Returns `false` if some requests are still in progress after `timeout`.
+    pub(crate) async fn wait_idle(&self, timeout: Duration) -> bool {
+        let idle = async {
+            loop {
+                let notified = self.idle.notified();
+                if self.in_flight() == 0 {
+                    break;
+                }
+                notified.await;
+            }
+        };
+        tokio::time::timeout(timeout, idle).await.is_ok()
+    }
+}
+
+impl Drop for RequestGuard {
+    fn drop(&mut self) {
+        if self.health.in_flight.fetch_sub(1, Ordering::SeqCst) == 1 && self.health.is_draining() {
+            self.health.idle.notify_waiters();
+        }
+    }
+}
+
+/// Readiness of the node, returned by `/health/ready`.
+#[derive(Debug, Serialize)]
+pub struct Readiness {
+    pub ready: bool,
+    pub backend_started: bool,
+    pub draining: bool,
+    pub in_flight: usize,
+    pub not_ready_disks: Vec<String>,
+}
+
+impl Readiness {
+    /// Node is ready when backend is started, node is not draining and at least one local disk
+    /// serves requests. Single failed disk doesn't take the node out of service, other replicas
+    /// cover its vdisks.
+    pub(crate) async fn check(health: &NodeHealth, backend: &Backend) -> Self {
+        let mut not_ready_disks = vec![];
+        let mut ready_disks = 0;
+        let mut disks = 0;
+        if let Some((dcs, alien_dc)) = backend.inner().disk_controllers() {
+            for dc in dcs {
+                disks += 1;
+                if dc.is_ready().await {
+                    ready_disks += 1;
+                } else {
+                    not_ready_disks.push(dc.disk().name().to_string());
+                }
+            }
+            if !alien_dc.is_ready().await {
+                not_ready_disks.push(alien_dc.disk().name().to_string());
+            }
+        }
+        let backend_started = health.backend_started();
+        let draining = health.is_draining();
+        Self {
+            ready: backend_started && !draining && (disks == 0 || ready_disks > 0),
+            backend_started,
+            draining,
+            in_flight: health.in_flight(),
+            not_ready_disks,
+        }
+    }
+}
+
+/// Standard `grpc.health.v1.Health` service.
+#[derive(Debug)]
+pub struct HealthService<A: Authenticator> {
+    bob: Server<A>,
+}
+
+impl<A: Authenticator> HealthService<A> {
+    pub fn new(bob: Server<A>) -> Self {
+        Self { bob }
+    }
+
+    async fn status(bob: &Server<A>, service: &str) -> ServingStatus {
+        match service {
+            "" | BOB_API_SERVICE => {
+                if bob.readiness().await.ready {
+                    ServingStatus::Serving
+                } else {
+                    ServingStatus::NotServing
+                }
+            }
+            _ => ServingStatus::ServiceUnknown,
+        }
+    }
+}
+
+fn response(status: ServingStatus) -> HealthCheckResponse {
+    HealthCheckResponse {
+        status: status as i32,
+    }
+}
+
+#[tonic::async_trait]
+impl<A: Authenticator> Health for HealthService<A> {
+    async fn check(
+        &self,
+        req: Request<HealthCheckRequest>,
+    ) -> Result<Response<HealthCheckResponse>, Status> {
+        let service = req.into_inner().service;
+        match Self::status(&self.bob, &service).await {
+            ServingStatus::ServiceUnknown => {
+                Err(Status::not_found(format!("unknown service {}", service)))
+            }
+            status => Ok(Response::new(response(status))),
+        }
+    }
+
+    type WatchStream =
+        Pin<Box<dyn Stream<Item = Result<HealthCheckResponse, Status>> + Send + 'static>>;
+
+    /// Sends current status and then every change of it.
+    async fn watch(
+        &self,
+        req: Request<HealthCheckRequest>,
+    ) -> Result<Response<Self::WatchStream>, Status> {
+        let service = req.into_inner().service;
+        let state = (self.bob.clone(), service, None);
+        let stream = futures::stream::unfold(state, |(bob, service, last)| async move {
+            loop {
+                let status = Self::status(&bob, &service).await;
+                if last != Some(status) {
+                    return Some((Ok(response(status)), (bob, service, Some(status))));
+                }
+                tokio::time::sleep(WATCH_INTERVAL).await;
+            }
+        });
+        Ok(Response::new(Box::pin(stream)))
+    }
+}
@@ -0,0 +1,73 @@
+use crate::prelude::*;
+use bob_common::error::Kind;
+
+use super::NodeHealth;
+
+#[tokio::test]
+async fn drain_rejects_new_requests() {
+    let health = Arc::new(NodeHealth::default());
+    let request = health.start_request(false).unwrap();
+
+    health.start_drain();
+
+    let error = health.start_request(false).unwrap_err();
+    assert_eq!(&Kind::NodeIsDraining, error.kind());
+    assert_eq!(1, health.in_flight());
+    drop(request);
+    assert_eq!(0, health.in_flight());
+}
+
+#[tokio::test]
+async fn drain_waits_for_requests_in_progress() {
+    let health = Arc::new(NodeHealth::default());
+    let request = health.start_request(false).unwrap();
+    health.start_drain();
+
+    assert!(!health.wait_idle(Duration::from_millis(50)).await);
+
+    let waiter = {
+        let health = health.clone();
+        tokio::spawn(async move { health.wait_idle(Duration::from_secs(5)).await })
+    };
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    drop(request);
+    assert!(waiter.await.unwrap());
+}
+
+#[tokio::test]
+async fn idle_node_is_drained_immediately() {
+    let health = NodeHealth::default();
+    health.start_drain();
+
+    assert!(health.wait_idle(Duration::from_millis(1)).await);
+    assert!(health.is_draining());
+    assert!(!health.backend_started());
+}
+
+#[tokio::test]
+async fn drain_serves_requests_from_nodes() {
+    let health = Arc::new(NodeHealth::default());
+    health.start_drain();
+
+    let request = health.start_request(true).unwrap();
+    assert_eq!(1, health.in_flight());
+    drop(request);
+
+    health.start_shutdown();
+    let error = health.start_request(true).unwrap_err();
+    assert_eq!(&Kind::NodeIsDraining, error.kind());
+}
+
+#[tokio::test]
+async fn undrain_accepts_requests_until_shutdown() {
+    let health = Arc::new(NodeHealth::default());
+    health.start_drain();
+
+    assert!(health.stop_drain());
+    assert!(!health.is_draining());
+    assert!(health.start_request(false).is_ok());
+
+    health.start_shutdown();
+    assert!(!health.stop_drain());
+    assert!(health.is_draining());
+}
@@ -20,12 +20,21 @@ pub mod build_info;
 pub mod cleaner;
 pub mod cluster;
 pub mod counter;
+pub mod decommission;
 pub mod grinder;
+pub mod handoff;
+pub mod health;
 pub mod hw_metrics_collector;
 pub mod link_manager;
+pub(crate) mod local_vdisk;
+pub mod rebuild;
+pub mod scrubber;
 pub mod server;
+#[cfg(test)]
+pub(crate) mod sim;
+pub mod snapshot;
 
-pub use crate::{grinder::Grinder, server::Server as BobServer};
+pub use crate::{grinder::Grinder, health::HealthService, server::Server as BobServer};
 pub use bob_backend::pearl::Key as PearlKey;
 pub use bob_common::{
     bob_client::{Factory, FactoryTlsConfig},
@@ -41,7 +50,8 @@ pub use bob_common::{
 pub use bob_grpc::{
     bob_api_client::BobApiClient, bob_api_server::BobApiServer, Blob, BlobKey, BlobMeta,
     DeleteOptions, DeleteRequest, ExistRequest, GetOptions, GetRequest, GetSource, PutOptions,
-    PutRequest,
+    PutRequest, StreamVDiskRequest, VDiskRecord, WriteCondition, ConsistencyLevel,
+    health::health_server::HealthServer, FILE_DESCRIPTOR_SET,
 };
 
 mod prelude {
@@ -51,29 +61,35 @@ mod prelude {
         bob_client::{BobClient, Factory},
         configs::node::Node as NodeConfig,
         data::{BobData, BobKey, BobMeta},
-        operation_options::{BobPutOptions, BobGetOptions, BobDeleteOptions},
+        operation_options::{
+            BobConsistencyLevel, BobDeleteOptions, BobGetOptions, BobPutOptions, BobWriteCondition,
+        },
         core_types::{DiskPath, VDiskId},
         error::Error,
         mapper::Virtual,
         metrics::{
-            ALIEN_BLOBS_COUNT, AVAILABLE_NODES_COUNT, BLOBS_COUNT, CLIENT_EXIST_COUNTER,
+            ALIEN_BLOBS_COUNT, AVAILABLE_NODES_COUNT, BLOBS_COUNT, CIRCUIT_OPEN_REJECTED_COUNTER,
+            CLIENT_EXIST_COUNTER,
             CLIENT_EXIST_ERROR_COUNT_COUNTER, CLIENT_EXIST_TIMER, CLIENT_GET_COUNTER,
             CLIENT_EXIST_ERROR_KEYS_COUNT_COUNTER, CLIENT_EXIST_KEYS_COUNT_COUNTER,
             CLIENT_GET_ERROR_COUNT_COUNTER, CLIENT_GET_TIMER, CLIENT_PUT_COUNTER,
             CLIENT_PUT_ERROR_COUNT_COUNTER, CLIENT_PUT_TIMER, GRINDER_EXIST_COUNTER,
             GRINDER_EXIST_KEYS_COUNT_COUNTER, GRINDER_EXIST_ERROR_KEYS_COUNT_COUNTER,
             GRINDER_EXIST_ERROR_COUNT_COUNTER, GRINDER_EXIST_TIMER, GRINDER_GET_COUNTER,
-            GRINDER_GET_ERROR_COUNT_COUNTER, GRINDER_GET_TIMER, GRINDER_PUT_COUNTER,
-            GRINDER_PUT_ERROR_COUNT_COUNTER, GRINDER_PUT_TIMER, INDEX_MEMORY,
+            GRINDER_GET_ERROR_COUNT_COUNTER, GRINDER_GET_HEDGED_COUNTER, GRINDER_GET_HEDGED_WON_COUNTER,
+            GRINDER_GET_OTHER_RACK_COUNTER, GRINDER_GET_SAME_RACK_COUNTER, GRINDER_GET_TIMER,
+            GRINDER_PUT_COUNTER, GRINDER_PUT_ERROR_COUNT_COUNTER, GRINDER_PUT_RACK_SPREAD_WAIT_COUNTER,
+            GRINDER_PUT_TIMER, INDEX_MEMORY, SUSPECTED_NODES_COUNT,
         },
         node::{Node, NodeName, Output as NodeOutput},
         stopwatch::Stopwatch,
     };
     pub use bob_grpc::{
-        bob_api_server::BobApi, Blob, BlobMeta, DeleteOptions, DeleteRequest, ExistRequest,
+        bob_api_server::BobApi, Blob, BlobKey, BlobMeta, DeleteOptions, DeleteRequest, ExistRequest,
         ExistResponse, GetOptions, GetRequest, Null, OpStatus, PutOptions, PutRequest,
+        StreamVDiskRequest, VDiskRecord,
     };
-    pub use futures::{future, stream::FuturesUnordered, Future, FutureExt, StreamExt};
+    pub use futures::{future, stream::FuturesUnordered, Future, FutureExt, Stream, StreamExt};
     pub use std::{
         collections::HashMap, collections::HashSet,
         fmt::{Debug, Formatter, Result as FmtResult},
@@ -93,7 +109,7 @@ mod prelude {
 #[allow(dead_code)]
 pub(crate) mod test_utils {
     use bob_common::{
-        bob_client::{GetResult, PingResult, PutResult},
+        bob_client::{DeleteResult, GetResult, PingResult, PutResult},
         data::BobMeta,
     };
     use chrono::Local;
@@ -116,6 +132,20 @@ pub(crate) mod test_utils {
         Err(NodeOutput::new(node_name, Error::internal()))
     }
 
+    pub(crate) fn put_precondition_failed(node_name: NodeName) -> PutResult {
+        debug!("return precondition failed error on PUT");
+        Err(NodeOutput::new(node_name, Error::precondition_failed()))
+    }
+
+    pub(crate) fn delete_ok(node_name: NodeName) -> DeleteResult {
+        Ok(NodeOutput::new(node_name, ()))
+    }
+
+    pub(crate) fn delete_precondition_failed(node_name: NodeName) -> DeleteResult {
+        debug!("return precondition failed error on DELETE");
+        Err(NodeOutput::new(node_name, Error::precondition_failed()))
+    }
+
     pub(crate) fn get_ok(node_name: NodeName, timestamp: u64) -> GetResult {
         let inner = BobData::new(vec![].into(), BobMeta::new(timestamp));
         Ok(NodeOutput::new(node_name, inner))
@@ -1,7 +1,7 @@
 use tokio::sync::mpsc::{channel, Receiver, Sender, error::TrySendError};
 use std::sync::RwLock;
 
-use crate::prelude::*;
+use crate::{handoff::HandoffQueue, prelude::*};
 use std::time::Instant;
 
 const FAST_PING_PERIOD_MS: u64 = 100;
@@ -12,6 +12,7 @@ pub(crate) struct LinkManager {
     nodes: Arc<[Node]>,
     check_interval: Duration,
     node_check_queue: Arc<RwLock<Option<Sender<String>>>>,
+    handoff: Option<Arc<HandoffQueue>>,
 }
 
 pub(crate) type ClusterCallOutput<T> = Result<NodeOutput<T>, NodeOutput<Error>>;
@@ -19,15 +20,25 @@ pub(crate) type ClusterCallFuture<'a, T> =
     Pin<Box<dyn Future<Output = ClusterCallOutput<T>> + Send + 'a>>;
 
 impl LinkManager {
-    pub(crate) fn new(nodes: &[Node], check_interval: Duration) -> LinkManager {
+    pub(crate) fn new(
+        nodes: &[Node],
+        check_interval: Duration,
+        handoff: Option<Arc<HandoffQueue>>,
+    ) -> LinkManager {
         LinkManager {
             nodes: Arc::from(nodes),
             check_interval,
             node_check_queue: Arc::new(RwLock::new(None)),
+            handoff,
         }
     }
 
-    async fn checker_task(factory: Factory, nodes: Arc<[Node]>, period: Duration) {
+    async fn checker_task(
+        factory: Factory,
+        nodes: Arc<[Node]>,
+        period: Duration,
+        handoff: Option<Arc<HandoffQueue>>,
+    ) {
         let start = Instant::now();
         let fast_log_iteration_div = 
             (period.as_millis() as usize / FAST_PING_PERIOD_MS as usize).max(1);
@@ -37,9 +48,10 @@ impl LinkManager {
             Duration::from_millis(FAST_PING_PERIOD_MS).min(period),
             || start.elapsed().as_secs() > FAST_PING_DURATION_SEC,
             fast_log_iteration_div,
+            handoff.as_ref(),
         )
         .await;
-        Self::checker(&factory, &nodes, period, || false, 1).await;
+        Self::checker(&factory, &nodes, period, || false, 1, handoff.as_ref()).await;
     }
 
     async fn checker(
@@ -48,6 +60,7 @@ impl LinkManager {
         period: Duration,
         should_stop: impl Fn() -> bool,
         log_iteration_div: usize,
+        handoff: Option<&Arc<HandoffQueue>>,
     ) {
         let mut interval = interval(period);
         let mut i: usize = 1;
@@ -73,6 +86,9 @@ impl LinkManager {
                     if log_in_this_iter {
                         status += &format!("[+]{:<10} ", node.name());
                     }
+                    if let Some(handoff) = handoff {
+                        handoff.schedule_replay(node);
+                    }
                 }
             }
             if log_in_this_iter {
@@ -80,6 +96,8 @@ impl LinkManager {
             }
             let cnt = nodes.len() - err_cnt;
             gauge!(AVAILABLE_NODES_COUNT, cnt as f64);
+            let suspected_cnt = nodes.iter().filter(|node| node.is_suspected()).count();
+            gauge!(SUSPECTED_NODES_COUNT, suspected_cnt as f64);
         }
     }
 
@@ -87,6 +105,7 @@ impl LinkManager {
         nodes: Arc<[Node]>,
         factory: Factory,
         mut node_check_queue: Receiver<String>,
+        handoff: Option<Arc<HandoffQueue>>,
     ) {
         while let Some(name) = node_check_queue.recv().await {
             if let Some(node) = nodes.iter().find(|n| *n.name() == name) {
@@ -100,6 +119,9 @@ impl LinkManager {
                         );
                     } else {
                         debug!("Create connection in response to ping from {}", node.name());
+                        if let Some(handoff) = &handoff {
+                            handoff.schedule_replay(node);
+                        }
                     }
                 }
             }
@@ -113,11 +135,13 @@ impl LinkManager {
             factory.clone(),
             self.nodes.clone(),
             self.check_interval,
+            self.handoff.clone(),
         ));
         tokio::spawn(Self::priority_nodes_checker(
             self.nodes.clone(),
             factory,
             receiver,
+            self.handoff.clone(),
         ));
     }
 
@@ -134,17 +158,32 @@ impl LinkManager {
         futures.collect().await
     }
 
+    #[tracing::instrument(name = "link_manager.call_node", skip_all, fields(node = %node.name()))]
     pub(crate) async fn call_node<F, T>(node: &Node, f: F) -> ClusterCallOutput<T>
     where
         F: FnOnce(&'_ BobClient) -> ClusterCallFuture<'_, T> + Send + Clone,
         T: Send,
     {
-        match node.get_connection() {
-            Some(conn) => f(&conn).await,
-            None => Err(NodeOutput::new(
+        let permit = if let Some(permit) = node.try_acquire_request() {
+            permit
+        } else {
+            counter!(CIRCUIT_OPEN_REJECTED_COUNTER, 1);
+            return Err(NodeOutput::new(
                 node.name().to_owned(),
-                Error::failed(format!("No active connection {:?}", node)),
-            )),
+                Error::failed(format!("Circuit is open for node {:?}", node)),
+            ));
+        };
+        match node.get_connection() {
+            Some(conn) => {
+                let result = f(&conn).await;
+                permit.record(result.as_ref().map(|_| ()).map_err(|e| e.inner()));
+                result
+            }
+            None => {
+                let error = Error::transport(format!("No active connection {:?}", node));
+                permit.record(Err(&error));
+                Err(NodeOutput::new(node.name().to_owned(), error))
+            }
         }
     }
 
@@ -0,0 +1,133 @@
+//! Replicas of vdisk on local disks, read when vdisk is copied to other nodes.
+
+use crate::prelude::*;
+use bob_backend::pearl::{Group as PearlGroup, Holder};
+use bob_common::data::{StreamedRecord, VDiskPosition};
+use bytes::Bytes;
+use futures::stream::{self, BoxStream, TryStreamExt};
+use pearl::ReadResult;
+use std::collections::BTreeMap;
+
+// Keys are read from indexes, indexes of active blobs are dumped in background
+pub(crate) const INDEX_DUMP_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Normal groups of the vdisk on all local disks.
+pub(crate) async fn groups(backend: &Backend, vdisk_id: VDiskId) -> Result<Vec<PearlGroup>, String> {
+    let (dcs, _) = backend
+        .inner()
+        .disk_controllers()
+        .ok_or_else(|| "vdisk records can be read only from pearl backend".to_owned())?;
+    let mut groups = vec![];
+    for dc in dcs.iter().filter(|dc| dc.vdisks().contains(&vdisk_id)) {
+        let group = dc
+            .vdisk_group(vdisk_id)
+            .await
+            .map_err(|e| format!("vdisk {} on disk {}: {}", vdisk_id, dc.disk().name(), e))?;
+        groups.push(group);
+    }
+    Ok(groups)
+}
+
+/// Groups of aliens stored for other nodes.
+pub(crate) async fn alien_groups(backend: &Backend) -> Result<Vec<PearlGroup>, String> {
+    let (_, alien_dc) = backend
+        .inner()
+        .disk_controllers()
+        .ok_or_else(|| "aliens can be read only from pearl backend".to_owned())?;
+    let groups = alien_dc.groups();
+    let groups = groups.read().await;
+    Ok(groups.clone())
+}
+
+/// Newest version of every key of every partition, delete markers included. Partitions are
+/// read in order of their start timestamps, only keys of the current partition are in memory.
+pub(crate) async fn records(
+    groups: &[PearlGroup],
+    start_after: Option<VDiskPosition>,
+) -> BoxStream<'static, Result<StreamedRecord, Error>> {
+    let partitions = partitions(groups)
+        .await
+        .into_iter()
+        .filter(move |(partition, _)| start_after.is_none_or(|p| *partition >= p.partition));
+    stream::iter(partitions)
+        .then(move |(partition, holders)| async move {
+            let start_key = start_after
+                .filter(|p| p.partition == partition)
+                .map(|p| p.key);
+            let keys = partition_keys(&holders, start_key).await?;
+            trace!("partition {}: {} keys", partition, keys.len());
+            let records = stream::iter(keys)
+                .then(move |key| {
+                    let holders = holders.clone();
+                    async move { read_partition(&holders, partition, key).await }
+                })
+                .filter_map(|record| future::ready(record.transpose()));
+            Ok::<_, Error>(records)
+        })
+        .try_flatten()
+        .boxed()
+}
+
+// Holders of the same partition on different disks are read together
+async fn partitions(groups: &[PearlGroup]) -> BTreeMap<u64, Vec<Holder>> {
+    let mut partitions = BTreeMap::<_, Vec<_>>::new();
+    for group in groups {
+        let holders = group.holders();
+        let holders = holders.read().await;
+        for holder in holders.iter() {
+            partitions
+                .entry(holder.start_timestamp())
+                .or_default()
+                .push(holder.clone());
+        }
+    }
+    partitions
+}
+
+async fn partition_keys(
+    holders: &[Holder],
+    start_after: Option<BobKey>,
+) -> Result<Vec<BobKey>, Error> {
+    let mut keys = vec![];
+    for holder in holders {
+        let holder_keys = holder.keys(INDEX_DUMP_TIMEOUT).await.map_err(|e| {
+            Error::failed(format!("can't read keys of partition {}: {}", holder.start_timestamp(), e))
+        })?;
+        keys.extend(holder_keys);
+    }
+    keys.sort_unstable_by(|a, b| a.as_slice().cmp(b.as_slice()));
+    keys.dedup();
+    if let Some(start_after) = start_after {
+        keys.retain(|key| key.as_slice() > start_after.as_slice());
+    }
+    Ok(keys)
+}
+
+async fn read_partition(
+    holders: &[Holder],
+    partition: u64,
+    key: BobKey,
+) -> Result<Option<StreamedRecord>, Error> {
+    let mut newest: Option<StreamedRecord> = None;
+    for holder in holders {
+        let (data, deleted) = match holder.read(key).await? {
+            ReadResult::Found(data) => (data, false),
+            ReadResult::Deleted(ts) => (BobData::new(Bytes::new(), BobMeta::new(ts.into())), true),
+            ReadResult::NotFound => continue,
+        };
+        // Delete marker wins over the record with the same timestamp
+        let timestamp = data.meta().timestamp();
+        let is_newer = newest.as_ref().is_none_or(|n| {
+            let newest_timestamp = n.data.meta().timestamp();
+            newest_timestamp < timestamp || (newest_timestamp == timestamp && deleted)
+        });
+        if is_newer {
+            newest = Some(StreamedRecord {
+                position: VDiskPosition { partition, key },
+                data,
+                deleted,
+            });
+        }
+    }
+    Ok(newest)
+}
@@ -0,0 +1,410 @@
+use crate::{link_manager::LinkManager, prelude::*};
+use bob_common::{
+    core_types::DiskName,
+    data::{VDiskPosition, BOB_KEY_SIZE},
+};
+use std::{collections::BTreeMap, path::PathBuf, sync::Mutex};
+
+#[cfg(test)]
+mod tests;
+
+const PROGRESS_FILE_NAME: &str = "rebuild.progress";
+// Progress is saved after this count of records and at the end of every vdisk
+const PROGRESS_SAVE_INTERVAL: u64 = 1000;
+// Shorter delays are accumulated to avoid sleeping on every record
+const MIN_THROTTLE_DELAY: Duration = Duration::from_millis(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RebuildState {
+    #[default]
+    NotStarted,
+    Running,
+    Completed,
+    Failed,
+}
+
+/// Rebuild of one vdisk of the disk.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct VDiskRebuild {
+    pub vdisk_id: VDiskId,
+    /// Remote replicas of the vdisk, the next one is used if the current fails.
+    pub sources: Vec<String>,
+    /// Replica records are pulled from now.
+    pub source: Option<String>,
+    /// Records written to the disk since the start of the rebuild.
+    pub records: u64,
+    /// Records are pulled partition by partition in order of start timestamps, records of the
+    /// partition in order of key bytes. Rebuild is resumed after `last_key` of `last_partition`.
+    pub last_partition: Option<u64>,
+    pub last_key: Option<String>,
+    pub completed: bool,
+}
+
+impl VDiskRebuild {
+    fn new(vdisk_id: VDiskId, sources: Vec<String>) -> Self {
+        Self {
+            vdisk_id,
+            sources,
+            source: None,
+            records: 0,
+            last_partition: None,
+            last_key: None,
+            completed: false,
+        }
+    }
+}
+
+/// Progress of the disk rebuild, returned by `GET /disks/:disk_name/rebuild`.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RebuildStatus {
+    pub disk: String,
+    pub state: RebuildState,
+    pub vdisks: Vec<VDiskRebuild>,
+    pub error: Option<String>,
+}
+
+/// Finds remote replicas of every vdisk of the local disk.
+pub(crate) fn plan(mapper: &Virtual, disk_name: &DiskName) -> Result<Vec<VDiskRebuild>, String> {
+    let local_node = mapper.local_node_name();
+    let mut vdisk_ids = mapper.get_vdisks_by_disk(disk_name);
+    if vdisk_ids.is_empty() {
+        return Err(format!("disk {} has no vdisks", disk_name));
+    }
+    vdisk_ids.sort_unstable();
+    vdisk_ids
+        .into_iter()
+        .map(|id| {
+            let replicas = mapper.get_vdisk(id).map_or(&[][..], |vdisk| vdisk.replicas());
+            let mut sources: Vec<String> = vec![];
+            for replica in replicas {
+                let node = replica.node_name();
+                if node != local_node && !sources.iter().any(|s| *node == s.as_str()) {
+                    sources.push(node.to_string());
+                }
+            }
+            if sources.is_empty() {
+                Err(format!("vdisk {} has no replicas on other nodes", id))
+            } else {
+                Ok(VDiskRebuild::new(id, sources))
+            }
+        })
+        .collect()
+}
+
+/// Position of the vdisk in the interrupted rebuild.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum VDiskProgress {
+    After(VDiskPosition),
+    Completed,
+}
+
+/// Rebuild progress of the disk, saved to the file on the disk itself, so the rebuild can
+/// be resumed after the node restart. One line per vdisk:
+/// `<vdisk id> <last partition> <last key>` or `<vdisk id> completed`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct Progress(BTreeMap<VDiskId, VDiskProgress>);
+
+impl Progress {
+    pub(crate) fn parse(content: &str) -> Result<Self, String> {
+        let mut vdisks = BTreeMap::new();
+        for line in content.lines().filter(|line| !line.trim().is_empty()) {
+            let (id, position) = line
+                .trim()
+                .split_once(' ')
+                .ok_or_else(|| format!("invalid progress line '{}'", line))?;
+            let id = id
+                .parse()
+                .map_err(|_| format!("invalid vdisk id in progress line '{}'", line))?;
+            let position = match position {
+                "completed" => VDiskProgress::Completed,
+                position => {
+                    let (partition, key) = position
+                        .split_once(' ')
+                        .ok_or_else(|| format!("invalid position in progress line '{}'", line))?;
+                    let partition = partition
+                        .parse()
+                        .map_err(|_| format!("invalid partition in progress line '{}'", line))?;
+                    if key.len() != BOB_KEY_SIZE * 2 || !key.bytes().all(|b| b.is_ascii_hexdigit()) {
+                        return Err(format!("invalid key in progress line '{}'", line));
+                    }
+                    let key = key.parse().expect("parse hex key");
+                    VDiskProgress::After(VDiskPosition { partition, key })
+                }
+            };
+            vdisks.insert(id, position);
+        }
+        Ok(Self(vdisks))
+    }
+
+    pub(crate) fn get(&self, vdisk_id: VDiskId) -> Option<VDiskProgress> {
+        self.0.get(&vdisk_id).copied()
+    }
+
+    pub(crate) fn set(&mut self, vdisk_id: VDiskId, progress: VDiskProgress) {
+        self.0.insert(vdisk_id, progress);
+    }
+
+    fn path(disk: &DiskPath) -> PathBuf {
+        PathBuf::from(disk.path()).join(PROGRESS_FILE_NAME)
+    }
+
+    async fn load(disk: &DiskPath) -> Result<Self, String> {
+        let path = Self::path(disk);
+        match tokio::fs::read_to_string(&path).await {
+            Ok(content) => Self::parse(&content),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(format!("can't read {}: {}", path.display(), e)),
+        }
+    }
+
+    async fn save(&self, disk: &DiskPath) -> Result<(), String> {
+        let path = Self::path(disk);
+        let tmp_path = path.with_extension("progress.tmp");
+        tokio::fs::write(&tmp_path, self.to_string())
+            .await
+            .and(tokio::fs::rename(&tmp_path, &path).await)
+            .map_err(|e| format!("can't save {}: {}", path.display(), e))
+    }
+
+    async fn remove(disk: &DiskPath) -> Result<(), String> {
+        let path = Self::path(disk);
+        match tokio::fs::remove_file(&path).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                Err(format!("can't remove {}: {}", path.display(), e))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl std::fmt::Display for Progress {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        for (id, progress) in &self.0 {
+            match progress {
+                VDiskProgress::After(position) => {
+                    writeln!(f, "{} {} {}", id, position.partition, position.key)?
+                }
+                VDiskProgress::Completed => writeln!(f, "{} completed", id)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Limits the rate of written records.
+#[derive(Debug)]
+pub(crate) struct Throttle {
+    records_per_sec: u64,
+    started: Instant,
+    records: u64,
+}
+
+impl Throttle {
+    pub(crate) fn new(records_per_sec: u64) -> Self {
+        Self {
+            records_per_sec,
+            started: Instant::now(),
+            records: 0,
+        }
+    }
+
+    /// Counts the record, returns the time to wait if records are written faster than allowed.
+    pub(crate) fn record(&mut self, now: Instant) -> Option<Duration> {
+        self.records += 1;
+        let expected = Duration::from_secs_f64(self.records as f64 / self.records_per_sec as f64);
+        let elapsed = now.saturating_duration_since(self.started);
+        expected
+            .checked_sub(elapsed)
+            .filter(|delay| *delay >= MIN_THROTTLE_DELAY)
+    }
+}
+
+/// Rebuilds of replaced disks: records of every vdisk of the disk are pulled from remote
+/// replicas with `StreamVDisk` RPC and written to the disk.
+#[derive(Debug)]
+pub(crate) struct Rebuilds {
+    records_per_sec: u64,
+    statuses: Mutex<HashMap<String, RebuildStatus>>,
+}
+
+impl Rebuilds {
+    pub(crate) fn new(records_per_sec: u64) -> Self {
+        Self {
+            records_per_sec,
+            statuses: Mutex::default(),
+        }
+    }
+
+    pub(crate) fn status(&self, disk_name: &str) -> Option<RebuildStatus> {
+        self.statuses.lock().expect("mutex").get(disk_name).cloned()
+    }
+
+    pub(crate) fn is_running(&self, disk_name: &str) -> bool {
+        matches!(self.status(disk_name), Some(status) if status.state == RebuildState::Running)
+    }
+
+    /// Spawns rebuild of the disk. Vdisks completed by the interrupted rebuild are skipped,
+    /// the others are resumed after the last saved key.
+    pub(crate) fn start(
+        self: &Arc<Self>,
+        disk: DiskPath,
+        vdisks: Vec<VDiskRebuild>,
+        backend: Arc<Backend>,
+    ) -> Result<(), String> {
+        let disk_name = disk.name().to_string();
+        {
+            let mut statuses = self.statuses.lock().expect("mutex");
+            if matches!(statuses.get(&disk_name), Some(status) if status.state == RebuildState::Running) {
+                return Err(format!("rebuild of disk {} is already running", disk_name));
+            }
+            let status = RebuildStatus {
+                disk: disk_name.clone(),
+                state: RebuildState::Running,
+                vdisks,
+                error: None,
+            };
+            statuses.insert(disk_name.clone(), status);
+        }
+        info!("rebuild of disk {} started", disk_name);
+        let rebuilds = self.clone();
+        tokio::spawn(async move {
+            let result = rebuilds.run(&disk, &backend).await;
+            rebuilds.update(&disk_name, |status| match result {
+                Ok(()) => {
+                    info!("rebuild of disk {} completed", disk_name);
+                    status.state = RebuildState::Completed;
+                }
+                Err(e) => {
+                    error!("rebuild of disk {} failed: {}", disk_name, e);
+                    status.state = RebuildState::Failed;
+                    status.error = Some(e);
+                }
+            });
+        });
+        Ok(())
+    }
+
+    fn update(&self, disk_name: &str, f: impl FnOnce(&mut RebuildStatus)) {
+        if let Some(status) = self.statuses.lock().expect("mutex").get_mut(disk_name) {
+            f(status);
+        }
+    }
+
+    fn update_vdisk(&self, disk_name: &str, index: usize, f: impl FnOnce(&mut VDiskRebuild)) {
+        self.update(disk_name, |status| f(&mut status.vdisks[index]));
+    }
+
+    async fn run(&self, disk: &DiskPath, backend: &Backend) -> Result<(), String> {
+        let disk_name = disk.name().to_string();
+        let vdisks = self
+            .status(&disk_name)
+            .map(|status| status.vdisks)
+            .unwrap_or_default();
+        let mut progress = Progress::load(disk).await?;
+        let mut throttle = Throttle::new(self.records_per_sec);
+        for (index, vdisk) in vdisks.iter().enumerate() {
+            let mut start_after = match progress.get(vdisk.vdisk_id) {
+                Some(VDiskProgress::Completed) => {
+                    self.update_vdisk(&disk_name, index, |v| v.completed = true);
+                    continue;
+                }
+                Some(VDiskProgress::After(position)) => Some(position),
+                None => None,
+            };
+            let mut errors = vec![];
+            for source in &vdisk.sources {
+                self.update_vdisk(&disk_name, index, |v| v.source = Some(source.clone()));
+                let result = self
+                    .pull(disk, backend, index, source, &mut start_after, &mut progress, &mut throttle)
+                    .await;
+                match result {
+                    Ok(()) => break,
+                    Err(e) => {
+                        warn!("rebuild of vdisk {} from node {} failed: {}", vdisk.vdisk_id, source, e);
+                        errors.push(format!("{}: {}", source, e));
+                    }
+                }
+            }
+            if errors.len() == vdisk.sources.len() {
+                if let Some(position) = start_after {
+                    progress.set(vdisk.vdisk_id, VDiskProgress::After(position));
+                    progress.save(disk).await?;
+                }
+                return Err(format!(
+                    "vdisk {} can't be rebuilt from any replica: {}",
+                    vdisk.vdisk_id,
+                    errors.join("; ")
+                ));
+            }
+            progress.set(vdisk.vdisk_id, VDiskProgress::Completed);
+            progress.save(disk).await?;
+            self.update_vdisk(&disk_name, index, |v| v.completed = true);
+        }
+        Progress::remove(disk).await
+    }
+
+    // Writes records and delete markers of the vdisk from the source starting after
+    // `start_after`, which is moved forward with every written record
+    #[allow(clippy::too_many_arguments)]
+    async fn pull(
+        &self,
+        disk: &DiskPath,
+        backend: &Backend,
+        index: usize,
+        source: &str,
+        start_after: &mut Option<VDiskPosition>,
+        progress: &mut Progress,
+        throttle: &mut Throttle,
+    ) -> Result<(), String> {
+        let disk_name = disk.name().to_string();
+        let vdisk_id = self
+            .status(&disk_name)
+            .map(|status| status.vdisks[index].vdisk_id)
+            .ok_or_else(|| format!("rebuild of disk {} is not found", disk_name))?;
+        let node = backend
+            .mapper()
+            .nodes()
+            .iter()
+            .find(|node| *node.name() == source)
+            .ok_or_else(|| format!("node {} is not in cluster config", source))?;
+        let from = *start_after;
+        let mut records = LinkManager::call_node(node, |conn| conn.stream_vdisk(vdisk_id, from).boxed())
+            .await
+            .map_err(|e| e.into_inner().to_string())?
+            .into_inner();
+        while let Some(record) = records.next().await {
+            let record = record.map_err(|e| e.to_string())?;
+            let key = record.key();
+            let op = Operation::new_local(vdisk_id, disk.clone());
+            let result = if record.deleted {
+                backend.inner().delete(op, key, record.data.meta()).await.map(|_| ())
+            } else {
+                backend.inner().put(op, key, &record.data).await
+            };
+            match result {
+                // Disk already has the record
+                Err(e) if e.is_duplicate() => {}
+                Err(e) => return Err(format!("can't write {}: {}", key, e)),
+                Ok(()) => {}
+            }
+            let position = record.position;
+            *start_after = Some(position);
+            let mut records = 0;
+            self.update_vdisk(&disk_name, index, |v| {
+                v.records += 1;
+                v.last_partition = Some(position.partition);
+                v.last_key = Some(key.to_string());
+                records = v.records;
+            });
+            if records % PROGRESS_SAVE_INTERVAL == 0 {
+                progress.set(vdisk_id, VDiskProgress::After(position));
+                progress.save(disk).await?;
+            }
+            if let Some(delay) = throttle.record(Instant::now()) {
+                tokio::time::sleep(delay).await;
+            }
+        }
+        Ok(())
+    }
+}
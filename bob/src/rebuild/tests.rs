@@ -0,0 +1,222 @@
+use crate::prelude::*;
+use bob_common::{
+    configs::{cluster::tests::cluster_config, node::tests::node_config},
+    data::{StreamedRecord, VDiskPosition},
+};
+use futures::stream;
+
+use super::{plan, Progress, RebuildState, Rebuilds, Throttle, VDiskProgress};
+
+const RECORDS_PER_VDISK: u64 = 3;
+
+fn key(vdisk_id: VDiskId, index: u64) -> BobKey {
+    (u64::from(vdisk_id) * 100 + index).into()
+}
+
+fn position(vdisk_id: VDiskId, index: u64) -> VDiskPosition {
+    VDiskPosition {
+        partition: 0,
+        key: key(vdisk_id, index),
+    }
+}
+
+fn record(position: VDiskPosition, timestamp: u64, deleted: bool) -> StreamedRecord {
+    let data = if deleted { vec![] } else { vec![1] };
+    StreamedRecord {
+        position,
+        data: BobData::new(data.into(), BobMeta::new(timestamp)),
+        deleted,
+    }
+}
+
+// `RECORDS_PER_VDISK` records in the first partition
+fn vdisk_records(vdisk_id: VDiskId) -> Vec<StreamedRecord> {
+    (0..RECORDS_PER_VDISK)
+        .map(|i| record(position(vdisk_id, i), 1, false))
+        .collect()
+}
+
+type Streamed = Arc<std::sync::Mutex<Vec<(VDiskId, Option<VDiskPosition>)>>>;
+
+// Node "1" is down, node "2" streams `RECORDS_PER_VDISK` records of every vdisk
+async fn backend(path: &str, streamed: Streamed) -> Arc<Backend> {
+    backend_with_records(path, streamed, vdisk_records).await
+}
+
+async fn backend_with_records(
+    path: &str,
+    streamed: Streamed,
+    vdisk_records: fn(VDiskId) -> Vec<StreamedRecord>,
+) -> Arc<Backend> {
+    let node = node_config("0", 1);
+    let cluster = cluster_config(3, 3, 3);
+    cluster.check(&node).expect("check node config");
+    let mapper = Arc::new(Virtual::new(&node, &cluster));
+    for remote in mapper.nodes() {
+        let mut client = BobClient::new();
+        let name = remote.name().clone();
+        let streamed = streamed.clone();
+        client.expect_stream_vdisk().returning(move |vdisk_id, start_after| {
+            if name == "1" {
+                return Err(NodeOutput::new(name.clone(), Error::failed("node is down")));
+            }
+            streamed.lock().expect("mutex").push((vdisk_id, start_after));
+            let records = vdisk_records(vdisk_id)
+                .into_iter()
+                .filter(|record| match start_after {
+                    Some(after) => {
+                        (record.position.partition, record.position.key.as_slice())
+                            > (after.partition, after.key.as_slice())
+                    }
+                    None => true,
+                })
+                .map(Ok)
+                .collect::<Vec<_>>();
+            Ok(NodeOutput::new(name.clone(), stream::iter(records).boxed()))
+        });
+        remote.set_connection(client);
+    }
+    std::fs::create_dir_all(path).expect("create disk dir");
+    Arc::new(Backend::new(mapper, &node).await)
+}
+
+async fn wait(rebuilds: &Rebuilds) -> RebuildState {
+    for _ in 0..100 {
+        let state = rebuilds.status("0").expect("rebuild status").state;
+        if state != RebuildState::Running {
+            return state;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    RebuildState::Running
+}
+
+#[test]
+fn plan_finds_replicas_on_other_nodes() {
+    let mapper = Virtual::new(&node_config("0", 1), &cluster_config(3, 3, 2));
+
+    let vdisks = plan(&mapper, &"0".into()).unwrap();
+
+    let sources = vdisks
+        .iter()
+        .map(|v| (v.vdisk_id, v.sources.clone()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        vec![(0, vec!["1".to_owned()]), (2, vec!["2".to_owned()])],
+        sources
+    );
+}
+
+#[test]
+fn progress_roundtrip() {
+    let mut progress = Progress::default();
+    progress.set(2, VDiskProgress::After(VDiskPosition { partition: 7, key: key(2, 1) }));
+    progress.set(0, VDiskProgress::Completed);
+
+    let parsed = Progress::parse(&progress.to_string()).unwrap();
+
+    assert_eq!(progress, parsed);
+    assert_eq!(Some(VDiskProgress::Completed), parsed.get(0));
+    assert_eq!(None, parsed.get(1));
+    assert!(Progress::parse("1 not_a_key").is_err());
+    assert!(Progress::parse(&format!("1 {}", key(1, 0))).is_err());
+}
+
+#[test]
+fn throttle_delays_records_over_rate() {
+    let mut throttle = Throttle::new(100);
+    let started = throttle.started;
+
+    assert_eq!(Some(Duration::from_millis(10)), throttle.record(started));
+    assert_eq!(None, throttle.record(started + Duration::from_millis(20)));
+    assert!(throttle.record(started).unwrap() >= Duration::from_millis(30));
+}
+
+#[tokio::test]
+async fn rebuild_uses_next_replica_if_source_fails() {
+    let path = "/tmp/bob_rebuild_next_replica";
+    let _ = std::fs::remove_file(format!("{}/rebuild.progress", path));
+    let streamed = Arc::default();
+    let backend = backend(path, streamed).await;
+    let disk = DiskPath::new("0".into(), path);
+    let vdisks = plan(backend.mapper(), disk.name()).unwrap();
+    let rebuilds = Arc::new(Rebuilds::new(1_000_000));
+
+    rebuilds.start(disk.clone(), vdisks, backend.clone()).unwrap();
+
+    assert_eq!(RebuildState::Completed, wait(&rebuilds).await);
+    let status = rebuilds.status("0").unwrap();
+    for vdisk in &status.vdisks {
+        assert!(vdisk.completed);
+        assert_eq!(Some("2".to_owned()), vdisk.source);
+        assert_eq!(RECORDS_PER_VDISK, vdisk.records);
+        for i in 0..RECORDS_PER_VDISK {
+            let op = Operation::new_local(vdisk.vdisk_id, disk.clone());
+            assert!(backend.inner().get(op, key(vdisk.vdisk_id, i)).await.is_ok());
+        }
+    }
+    assert!(!std::path::Path::new(path).join("rebuild.progress").exists());
+}
+
+#[tokio::test]
+async fn rebuild_resumes_from_saved_progress() {
+    let path = "/tmp/bob_rebuild_resume";
+    let streamed = Arc::default();
+    let backend = backend(path, Arc::clone(&streamed)).await;
+    let mut progress = Progress::default();
+    progress.set(0, VDiskProgress::Completed);
+    progress.set(1, VDiskProgress::After(position(1, 0)));
+    std::fs::write(format!("{}/rebuild.progress", path), progress.to_string()).unwrap();
+    let disk = DiskPath::new("0".into(), path);
+    let vdisks = plan(backend.mapper(), disk.name()).unwrap();
+    let rebuilds = Arc::new(Rebuilds::new(1_000_000));
+
+    rebuilds.start(disk, vdisks, backend).unwrap();
+
+    assert_eq!(RebuildState::Completed, wait(&rebuilds).await);
+    assert_eq!(
+        vec![(1, Some(position(1, 0))), (2, None)],
+        *streamed.lock().unwrap()
+    );
+    let records = rebuilds
+        .status("0")
+        .unwrap()
+        .vdisks
+        .iter()
+        .map(|v| v.records)
+        .collect::<Vec<_>>();
+    assert_eq!(vec![0, RECORDS_PER_VDISK - 1, RECORDS_PER_VDISK], records);
+}
+
+// The first record is deleted in the next partition
+fn vdisk_records_with_delete(vdisk_id: VDiskId) -> Vec<StreamedRecord> {
+    let mut records = vdisk_records(vdisk_id);
+    let deleted = VDiskPosition {
+        partition: 1,
+        key: key(vdisk_id, 0),
+    };
+    records.push(record(deleted, 2, true));
+    records
+}
+
+#[tokio::test]
+async fn rebuild_applies_delete_markers() {
+    let path = "/tmp/bob_rebuild_delete_markers";
+    let _ = std::fs::remove_dir_all(path);
+    let backend = backend_with_records(path, Arc::default(), vdisk_records_with_delete).await;
+    let disk = DiskPath::new("0".into(), path);
+    let vdisks = plan(backend.mapper(), disk.name()).unwrap();
+    let rebuilds = Arc::new(Rebuilds::new(1_000_000));
+
+    rebuilds.start(disk.clone(), vdisks, backend.clone()).unwrap();
+
+    assert_eq!(RebuildState::Completed, wait(&rebuilds).await);
+    for vdisk in &rebuilds.status("0").unwrap().vdisks {
+        assert_eq!(RECORDS_PER_VDISK + 1, vdisk.records);
+        assert_eq!(Some(1), vdisk.last_partition);
+        let op = Operation::new_local(vdisk.vdisk_id, disk.clone());
+        let err = backend.inner().get(op.clone(), key(vdisk.vdisk_id, 0)).await.unwrap_err();
+        assert!(err.is_key_not_found());
+        assert!(backend.inner().get(op, key(vdisk.vdisk_id, 1)).await.is_ok());
+    }
+}
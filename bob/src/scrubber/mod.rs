@@ -0,0 +1,273 @@
+use crate::{link_manager::LinkManager, prelude::*};
+use bob_backend::pearl::{CorruptedBlob, DiskController, Holder};
+use bob_common::configs::node::ScrubberConfig;
+use pearl::ReadResult;
+use std::{
+    path::PathBuf,
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+#[cfg(test)]
+mod tests;
+
+// Only the latest errors are kept in the status
+const MAX_ERRORS: usize = 100;
+
+/// Scrub progress and results of the disk, returned by `GET /disks/:disk_name/scrub`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct DiskScrubStatus {
+    pub disk: String,
+    pub enabled: bool,
+    pub running: bool,
+    /// Closed blobs checked by the current or the last pass.
+    pub blobs_checked: u64,
+    pub blobs_total: u64,
+    /// Unix timestamps of the current or the last pass.
+    pub last_scrub_started: Option<u64>,
+    pub last_scrub_finished: Option<u64>,
+    /// Counters since the node start.
+    pub corrupted_blobs: u64,
+    /// Keys of corrupted blobs restored from other replicas or from local delete markers.
+    pub keys_restored: u64,
+    /// Keys of corrupted blobs not found on other replicas: lost or deleted.
+    pub keys_not_restored: u64,
+    pub errors: Vec<String>,
+}
+
+impl DiskScrubStatus {
+    fn new(disk: &str) -> Self {
+        Self {
+            disk: disk.to_owned(),
+            enabled: true,
+            ..Default::default()
+        }
+    }
+
+    fn push_error(&mut self, error: String) {
+        if self.errors.len() == MAX_ERRORS {
+            self.errors.remove(0);
+        }
+        self.errors.push(error);
+    }
+}
+
+/// Time to read `bytes` at `bytes_per_sec` rate minus the time already spent.
+pub(crate) fn throttle_delay(bytes: u64, bytes_per_sec: u64, elapsed: Duration) -> Option<Duration> {
+    Duration::from_secs_f64(bytes as f64 / bytes_per_sec as f64)
+        .checked_sub(elapsed)
+        .filter(|delay| !delay.is_zero())
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/// Low-priority background task which validates record checksums of closed blobs on local
+/// disks. Keys of corrupted blobs are re-fetched from other replicas, then blobs are quarantined.
+#[derive(Debug)]
+pub(crate) struct Scrubber {
+    interval: Duration,
+    max_read_per_sec: u64,
+    statuses: Mutex<HashMap<String, DiskScrubStatus>>,
+}
+
+impl Scrubber {
+    pub(crate) fn new(config: &ScrubberConfig) -> Self {
+        Self {
+            interval: config.interval(),
+            max_read_per_sec: config.max_read_per_sec(),
+            statuses: Mutex::default(),
+        }
+    }
+
+    pub(crate) fn status(&self, disk_name: &str) -> DiskScrubStatus {
+        self.statuses
+            .lock()
+            .expect("mutex")
+            .get(disk_name)
+            .cloned()
+            .unwrap_or_else(|| DiskScrubStatus::new(disk_name))
+    }
+
+    fn update(&self, disk_name: &str, f: impl FnOnce(&mut DiskScrubStatus)) {
+        let mut statuses = self.statuses.lock().expect("mutex");
+        f(statuses
+            .entry(disk_name.to_owned())
+            .or_insert_with(|| DiskScrubStatus::new(disk_name)));
+    }
+
+    pub(crate) fn spawn_task(self: &Arc<Self>, backend: Arc<Backend>) {
+        let scrubber = self.clone();
+        tokio::spawn(async move {
+            // The first pass waits for the interval too, restarts don't rescrub all disks
+            let start = tokio::time::Instant::now() + scrubber.interval;
+            let mut interval = tokio::time::interval_at(start, scrubber.interval);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                interval.tick().await;
+                scrubber.scrub(&backend).await;
+            }
+        });
+    }
+
+    /// One pass over all ready local disks, disks are scrubbed one by one.
+    async fn scrub(&self, backend: &Backend) {
+        let dcs = match backend.inner().disk_controllers() {
+            Some((dcs, _)) => dcs,
+            None => return,
+        };
+        for dc in dcs.iter() {
+            if dc.is_ready().await {
+                self.scrub_disk(dc, backend).await;
+            } else {
+                debug!("disk {} is not ready, scrub skipped", dc.disk().name());
+            }
+        }
+    }
+
+    async fn scrub_disk(&self, dc: &DiskController, backend: &Backend) {
+        let disk_name = dc.disk().name().to_string();
+        let mut blobs: Vec<(VDiskId, Holder, PathBuf)> = vec![];
+        for &vdisk_id in dc.vdisks() {
+            let group = match dc.vdisk_group(vdisk_id).await {
+                Ok(group) => group,
+                Err(e) => {
+                    self.update(&disk_name, |s| s.push_error(format!("vdisk {}: {}", vdisk_id, e)));
+                    continue;
+                }
+            };
+            let holders = group.holders();
+            let holders = holders.read().await;
+            for holder in holders.iter() {
+                for blob in holder.closed_blobs().await {
+                    blobs.push((vdisk_id, holder.clone(), blob));
+                }
+            }
+        }
+        info!("scrub of disk {} started, {} closed blobs", disk_name, blobs.len());
+        self.update(&disk_name, |s| {
+            s.running = true;
+            s.blobs_checked = 0;
+            s.blobs_total = blobs.len() as u64;
+            s.last_scrub_started = Some(unix_now());
+            s.last_scrub_finished = None;
+        });
+        for (vdisk_id, holder, blob) in blobs {
+            let started = Instant::now();
+            let size = tokio::fs::metadata(&blob).await.map_or(0, |m| m.len());
+            if let Some(corrupted) = Holder::check_blob(&blob).await {
+                error!("scrub: blob {} is corrupted: {}", blob.display(), corrupted.error);
+                self.update(&disk_name, |s| {
+                    s.corrupted_blobs += 1;
+                    s.push_error(format!("{}: {}", blob.display(), corrupted.error));
+                });
+                match repair_blob(backend, vdisk_id, dc.disk(), &holder, corrupted).await {
+                    Ok((restored, not_restored)) => {
+                        self.update(&disk_name, |s| {
+                            s.keys_restored += restored;
+                            s.keys_not_restored += not_restored;
+                        });
+                    }
+                    Err(e) => self.update(&disk_name, |s| s.push_error(e.to_string())),
+                }
+            }
+            self.update(&disk_name, |s| s.blobs_checked += 1);
+            if let Some(delay) = throttle_delay(size, self.max_read_per_sec, started.elapsed()) {
+                tokio::time::sleep(delay).await;
+            }
+        }
+        self.update(&disk_name, |s| {
+            s.running = false;
+            s.last_scrub_finished = Some(unix_now());
+        });
+        info!("scrub of disk {} finished", disk_name);
+    }
+}
+
+/// Restores keys of the corrupted blob while the holder still serves the rest of its records,
+/// then moves the blob to quarantine. Delete markers can't be fetched from replicas, they are
+/// copied from the holder before the blob is moved. Returns counts of restored and lost keys.
+async fn repair_blob(
+    backend: &Backend,
+    vdisk_id: VDiskId,
+    disk: &DiskPath,
+    holder: &Holder,
+    corrupted: CorruptedBlob,
+) -> Result<(u64, u64), Error> {
+    let mut markers = vec![];
+    let mut keys = vec![];
+    for key in corrupted.keys {
+        match holder.exist(key).await? {
+            ReadResult::Deleted(ts) => markers.push((key, BobMeta::new(ts.into()))),
+            _ => keys.push(key),
+        }
+    }
+    restore_keys(backend, vdisk_id, disk, &keys).await;
+    holder.quarantine(&[corrupted.path]).await?;
+    let op = Operation::new_local(vdisk_id, disk.clone());
+    for (key, meta) in &markers {
+        backend.inner().delete(op.clone(), *key, meta).await?;
+    }
+    // Storage without duplicates skips writes of keys found in the corrupted blob,
+    // they are restored again after the blob is moved
+    let exist = backend.inner().exist(op, &keys).await?;
+    let lost = keys
+        .iter()
+        .zip(exist)
+        .filter_map(|(key, exist)| (!exist).then_some(*key))
+        .collect::<Vec<_>>();
+    let (_, not_restored) = restore_keys(backend, vdisk_id, disk, &lost).await;
+    let restored = (keys.len() + markers.len()) as u64 - not_restored;
+    Ok((restored, not_restored))
+}
+
+/// Writes the newest version of every key found on remote replicas of the vdisk to the disk.
+/// Returns counts of restored and not found keys.
+pub(crate) async fn restore_keys(
+    backend: &Backend,
+    vdisk_id: VDiskId,
+    disk: &DiskPath,
+    keys: &[BobKey],
+) -> (u64, u64) {
+    let mapper = backend.mapper();
+    let local_node = mapper.local_node_name();
+    let replicas = mapper.get_vdisk(vdisk_id).map_or(&[][..], |vdisk| vdisk.replicas());
+    let nodes = mapper
+        .nodes()
+        .iter()
+        .filter(|node| {
+            node.name() != local_node && replicas.iter().any(|r| r.node_name() == node.name())
+        })
+        .collect::<Vec<_>>();
+    let (mut restored, mut not_restored) = (0, 0);
+    for &key in keys {
+        let results = LinkManager::call_nodes(nodes.iter().copied(), |conn| {
+            conn.get(key, GetOptions::new_local()).boxed()
+        })
+        .await;
+        let newest = results
+            .into_iter()
+            .filter_map(|result| result.ok().map(|output| output.into_inner()))
+            .max_by_key(|data| data.meta().timestamp());
+        let data = match newest {
+            Some(data) => data,
+            None => {
+                warn!("scrub: key {} of vdisk {} is not found on replicas", key, vdisk_id);
+                not_restored += 1;
+                continue;
+            }
+        };
+        let op = Operation::new_local(vdisk_id, disk.clone());
+        match backend.inner().put(op, key, &data).await {
+            Err(e) if !e.is_duplicate() => {
+                error!("scrub: can't restore key {}: {}", key, e);
+                not_restored += 1;
+            }
+            _ => restored += 1,
+        }
+    }
+    (restored, not_restored)
+}
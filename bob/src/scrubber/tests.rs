@@ -0,0 +1,59 @@
+use crate::{prelude::*, test_utils};
+use bob_common::configs::{cluster::tests::cluster_config, node::tests::node_config};
+
+use super::{restore_keys, throttle_delay, DiskScrubStatus, MAX_ERRORS};
+
+#[test]
+fn throttle_delay_keeps_read_rate() {
+    let mib = 1024 * 1024;
+
+    assert_eq!(
+        Some(Duration::from_millis(1500)),
+        throttle_delay(2 * mib, mib, Duration::from_millis(500))
+    );
+    assert_eq!(None, throttle_delay(mib, mib, Duration::from_secs(2)));
+    assert_eq!(None, throttle_delay(0, mib, Duration::ZERO));
+}
+
+#[test]
+fn status_keeps_latest_errors() {
+    let mut status = DiskScrubStatus::new("0");
+    for i in 0..=MAX_ERRORS {
+        status.push_error(i.to_string());
+    }
+
+    assert_eq!(MAX_ERRORS, status.errors.len());
+    assert_eq!("1", status.errors[0]);
+    assert_eq!(MAX_ERRORS.to_string(), status.errors[MAX_ERRORS - 1]);
+}
+
+// Node "1" has the key with timestamp 2, node "2" with timestamp 1, no node has the other key
+#[tokio::test]
+async fn restore_keys_writes_newest_replica() {
+    let path = "/tmp/bob_scrub_restore";
+    let node = node_config("0", 1);
+    let cluster = cluster_config(3, 3, 3);
+    cluster.check(&node).expect("check node config");
+    let mapper = Arc::new(Virtual::new(&node, &cluster));
+    let found: BobKey = 1.into();
+    for remote in mapper.nodes() {
+        let mut client = BobClient::new();
+        let name = remote.name().clone();
+        client.expect_get().returning(move |key, _| match (key == found, name.as_str()) {
+            (true, "1") => test_utils::get_ok(name.clone(), 2),
+            (true, "2") => test_utils::get_ok(name.clone(), 1),
+            _ => test_utils::get_err(name.clone()),
+        });
+        remote.set_connection(client);
+    }
+    std::fs::create_dir_all(path).expect("create disk dir");
+    let backend = Backend::new(mapper, &node).await;
+    let disk = DiskPath::new("0".into(), path);
+
+    let result = restore_keys(&backend, 0, &disk, &[found, 2.into()]).await;
+
+    assert_eq!((1, 1), result);
+    let op = Operation::new_local(0, disk);
+    let data = backend.inner().get(op, found).await.expect("restored key");
+    assert_eq!(2, data.meta().timestamp());
+}
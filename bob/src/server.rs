@@ -1,13 +1,30 @@
+#[cfg(test)]
+mod tests;
+
 use std::net::IpAddr;
 
-use bob_access::{Authenticator, CredentialsHolder};
+use bob_access::{Authenticator, CredentialsHolder, CredentialsKind, RequestCredentials};
 use bytes::Bytes;
 use tokio::{runtime::Handle, task::block_in_place};
+use tracing::{info_span, Instrument};
 
 use crate::prelude::*;
 
-use super::grinder::Grinder;
-use bob_common::{configs::node::TLSConfig, metrics::SharedMetricsSnapshot};
+use super::{
+    decommission::{self, DecommissionStatus},
+    grinder::Grinder,
+    health::Readiness,
+    local_vdisk,
+    rebuild::{self, RebuildStatus},
+    scrubber::DiskScrubStatus,
+};
+use bob_common::{
+    configs::{cluster::Cluster as ClusterConfig, node::TLSConfig},
+    data::VDiskPosition,
+    metrics::SharedMetricsSnapshot,
+    telemetry,
+};
+use futures::TryStreamExt;
 
 /// Struct contains `Grinder` and receives incomming GRPC requests
 #[derive(Clone, Debug)]
@@ -42,6 +59,25 @@ where
         block_in_place(|| self.handle.block_on(f))
     }
 
+    /// Internode requests name another node of the cluster in `node_name` metadata, confirmed
+    /// by internode credentials when authentication is enabled. `force_node` can't tell them
+    /// apart, clients may set it too.
+    fn is_node_request<T>(&self, req: &Request<T>, creds: &RequestCredentials) -> bool {
+        let node_name = match req.metadata().get("node_name").map(|name| name.to_str()) {
+            Some(Ok(name)) => name,
+            _ => return false,
+        };
+        let mapper = self.grinder.backend().mapper();
+        let is_remote_node = mapper.local_node_name().as_str() != node_name
+            && mapper.nodes().iter().any(|node| node.name().as_str() == node_name);
+        is_remote_node
+            && match creds.kind() {
+                None => true,
+                Some(CredentialsKind::InterNode(name)) => name == node_name,
+                Some(_) => false,
+            }
+    }
+
     pub(crate) fn grinder(&self) -> &Grinder {
         self.grinder.as_ref()
     }
@@ -72,12 +108,76 @@ where
         self.grinder.run_periodic_tasks(client_factory);
     }
 
-    /// Gracefully shutdowns bob
+    /// Gracefully shutdowns bob: new requests are rejected, requests in progress are given
+    /// `drain_timeout` to finish, then backend is stopped
     pub async fn shutdown(&self) {
+        let health = self.grinder.health();
+        health.start_shutdown();
+        if !health.wait_idle(self.grinder.node_config().drain_timeout()).await {
+            warn!("drain timeout, {} requests are aborted", health.in_flight());
+        }
         let backend = self.grinder.backend().clone();
         backend.shutdown().await;
     }
 
+    /// Stops accepting data requests from clients, node stays up and reports that it's not
+    /// ready. Requests from other nodes are still served
+    pub fn drain(&self) {
+        self.grinder.health().start_drain();
+    }
+
+    /// Accepts client requests again after `drain`, returns `false` if node is shutting down
+    pub fn undrain(&self) -> bool {
+        self.grinder.health().stop_drain()
+    }
+
+    /// Starts decommission: normal writes are rejected, local vdisks are copied to replicas that
+    /// replace this node in `new_cluster`.
+    pub(crate) async fn start_decommission(&self, new_cluster: &ClusterConfig) -> Result<(), String> {
+        let backend = self.grinder.backend();
+        let mut transfers = decommission::plan(backend.mapper(), new_cluster)?;
+        let aliens = local_vdisk::alien_groups(backend)
+            .await?
+            .iter()
+            .map(|group| (group.node_name().to_string(), group.vdisk_id()))
+            .collect::<Vec<_>>();
+        transfers.extend(decommission::plan_aliens(&aliens, new_cluster)?);
+        let targets = decommission::targets(&transfers, new_cluster);
+        self.grinder
+            .decommission()
+            .start(transfers, targets, backend.clone())
+    }
+
+    pub fn decommission_status(&self) -> DecommissionStatus {
+        self.grinder.decommission().status()
+    }
+
+    /// Starts rebuild of the local disk: records of its vdisks are pulled from replicas on
+    /// other nodes. The disk is expected to be replaced and started.
+    pub(crate) fn start_rebuild(&self, disk: DiskPath) -> Result<(), String> {
+        let backend = self.grinder.backend();
+        let vdisks = rebuild::plan(backend.mapper(), disk.name())?;
+        self.grinder.rebuilds().start(disk, vdisks, backend.clone())
+    }
+
+    pub fn rebuild_status(&self, disk_name: &str) -> Option<RebuildStatus> {
+        self.grinder.rebuilds().status(disk_name)
+    }
+
+    pub fn scrub_status(&self, disk_name: &str) -> DiskScrubStatus {
+        match self.grinder.scrubber() {
+            Some(scrubber) => scrubber.status(disk_name),
+            None => DiskScrubStatus {
+                disk: disk_name.to_owned(),
+                ..Default::default()
+            },
+        }
+    }
+
+    pub async fn readiness(&self) -> Readiness {
+        Readiness::check(self.grinder.health(), self.grinder.backend()).await
+    }
+
     pub fn auth(&self) -> &A {
         &self.auth
     }
@@ -106,16 +206,23 @@ fn delete_extract(req: DeleteRequest) -> Option<(BobKey, u64, Option<DeleteOptio
 
 type ApiResult<T> = Result<Response<T>, Status>;
 
+type VDiskRecordsStream = Pin<Box<dyn Stream<Item = Result<VDiskRecord, Status>> + Send>>;
+
 #[tonic::async_trait]
 impl<A> BobApi for Server<A>
 where
     A: Authenticator,
 {
+    type StreamVDiskStream = VDiskRecordsStream;
+
     async fn put(&self, req: Request<PutRequest>) -> ApiResult<OpStatus> {
-        let creds: CredentialsHolder<A> = (&req).into();
-        if !self.auth.check_credentials_grpc(creds.into())?.has_write() {
+        let creds: RequestCredentials = CredentialsHolder::<A>::from(&req).into();
+        let from_node = self.is_node_request(&req, &creds);
+        if !self.auth.check_credentials_grpc(creds)?.has_write() {
             return Err(Status::permission_denied("WRITE permission required"));
         }
+        let span = info_span!("grpc.put");
+        telemetry::set_grpc_parent(&span, req.metadata());
         trace!("- - - - - SERVER PUT START - - - - -");
         let sw = Stopwatch::start_new();
         trace!(
@@ -133,6 +240,7 @@ where
                 "extract params from request, /{:.3}ms/",
                 sw.elapsed().as_secs_f64() * 1000.0
             );
+            let options = BobPutOptions::from_grpc(options)?;
             let meta = BobMeta::new(timestamp);
             let data = BobData::new(inner, meta);
 
@@ -144,7 +252,8 @@ where
             );
             let put_result = self
                 .grinder
-                .put(key, &data, BobPutOptions::from_grpc(options))
+                .put(key, &data, options, from_node)
+                .instrument(span)
                 .await;
             trace!(
                 "grinder processed put request, /{:.3}ms/",
@@ -174,10 +283,13 @@ where
     }
 
     async fn get(&self, req: Request<GetRequest>) -> ApiResult<Blob> {
-        let creds: CredentialsHolder<A> = (&req).into();
-        if !self.auth.check_credentials_grpc(creds.into())?.has_read() {
+        let creds: RequestCredentials = CredentialsHolder::<A>::from(&req).into();
+        let from_node = self.is_node_request(&req, &creds);
+        if !self.auth.check_credentials_grpc(creds)?.has_read() {
             return Err(Status::permission_denied("READ permission required"));
         }
+        let span = info_span!("grpc.get");
+        telemetry::set_grpc_parent(&span, req.metadata());
         trace!("- - - - - SERVER GET START - - - - -");
         let sw = Stopwatch::start_new();
         trace!(
@@ -194,14 +306,15 @@ where
                 "create new bob options /{:.3}ms/",
                 sw.elapsed().as_secs_f64() * 1000.0
             );
-            let options = BobGetOptions::from_grpc(options);
+            let options = BobGetOptions::from_grpc(options)?;
             trace!(
                 "pass request to grinder /{:.3}ms/",
                 sw.elapsed().as_secs_f64() * 1000.0
             );
             let get_res = self
                 .grinder
-                .get(key, &options)
+                .get(key, &options, from_node)
+                .instrument(span)
                 .await
                 .map_err::<Status, _>(|e| e.into())?;
             trace!(
@@ -234,18 +347,22 @@ where
     }
 
     async fn exist(&self, req: Request<ExistRequest>) -> ApiResult<ExistResponse> {
-        let creds: CredentialsHolder<A> = (&req).into();
-        if !self.auth.check_credentials_grpc(creds.into())?.has_read() {
+        let creds: RequestCredentials = CredentialsHolder::<A>::from(&req).into();
+        let from_node = self.is_node_request(&req, &creds);
+        if !self.auth.check_credentials_grpc(creds)?.has_read() {
             return Err(Status::permission_denied("READ permission required"));
         }
+        let span = info_span!("grpc.exist");
+        telemetry::set_grpc_parent(&span, req.metadata());
         let sw = Stopwatch::start_new();
         let req = req.into_inner();
         let ExistRequest { keys, options } = req;
         let keys = keys.into_iter().map(|k| k.key.into()).collect::<Vec<_>>();
-        let options = BobGetOptions::from_grpc(options);
+        let options = BobGetOptions::from_grpc(options)?;
         let exist = self
             .grinder
-            .exist(&keys, &options)
+            .exist(&keys, &options, from_node)
+            .instrument(span)
             .await
             .map_err::<Status, _>(|e| e.into())?;
         debug!("EXISTS-OK dt: {:?}", sw.elapsed());
@@ -255,21 +372,23 @@ where
     }
 
     async fn delete(&self, req: Request<DeleteRequest>) -> ApiResult<OpStatus> {
-        let creds: CredentialsHolder<A> = (&req).into();
-        if !self.auth.check_credentials_grpc(creds.into())?.has_write() {
+        let creds: RequestCredentials = CredentialsHolder::<A>::from(&req).into();
+        let from_node = self.is_node_request(&req, &creds);
+        if !self.auth.check_credentials_grpc(creds)?.has_write() {
             return Err(Status::permission_denied("WRITE permission required"));
         }
+        let span = info_span!("grpc.delete");
+        telemetry::set_grpc_parent(&span, req.metadata());
 
         let req = req.into_inner();
         if let Some((key, timestamp, options)) = delete_extract(req) {
             trace!("DELETE[{}] request processing started", key);
+            let options = BobDeleteOptions::from_grpc(options)?;
             let sw = Stopwatch::start_new();
             let delete_result = self.grinder
-                .delete(
-                    key,
-                    &BobMeta::new(timestamp),
-                    BobDeleteOptions::from_grpc(options),
-                ).await;
+                .delete(key, &BobMeta::new(timestamp), options, from_node)
+                .instrument(span)
+                .await;
 
             delete_result
                 .map(|_| {
@@ -287,4 +406,41 @@ where
             ))
         }
     }
+
+    async fn stream_v_disk(&self, req: Request<StreamVDiskRequest>) -> ApiResult<VDiskRecordsStream> {
+        let creds: CredentialsHolder<A> = (&req).into();
+        if !self.auth.check_credentials_grpc(creds.into())?.has_read() {
+            return Err(Status::permission_denied("READ permission required"));
+        }
+        let StreamVDiskRequest {
+            vdisk_id,
+            start_after,
+            start_partition,
+        } = req.into_inner();
+        let start_after = start_after.map(|k| VDiskPosition {
+            partition: start_partition,
+            key: BobKey::from(k.key),
+        });
+        let groups = local_vdisk::groups(self.grinder.backend(), vdisk_id)
+            .await
+            .map_err(Status::failed_precondition)?;
+        debug!("STREAM VDISK[{}] after {:?}", vdisk_id, start_after);
+        let records = local_vdisk::records(&groups, start_after)
+            .await
+            .map_ok(|record| VDiskRecord {
+                key: Some(BlobKey {
+                    key: record.key().into(),
+                }),
+                data: Some(Blob {
+                    meta: Some(BlobMeta {
+                        timestamp: record.data.meta().timestamp(),
+                    }),
+                    data: record.data.into_inner(),
+                }),
+                deleted: record.deleted,
+                partition: record.position.partition,
+            })
+            .map_err(Status::from);
+        Ok(Response::new(Box::pin(records)))
+    }
 }
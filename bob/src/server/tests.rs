@@ -0,0 +1,71 @@
+use crate::{grinder::Grinder, server::Server as BobServer};
+use bob_access::{RequestCredentials, StubAuthenticator};
+use bob_common::{
+    configs::{
+        cluster::tests::{cluster_config, with_disks_root},
+        node::tests::node_config,
+    },
+    error::{Error, Kind},
+    mapper::Virtual,
+};
+use bob_grpc::{bob_api_server::BobApi, ExistRequest, GetOptions, GetSource};
+use tokio::runtime::Handle;
+use tonic::Request;
+
+async fn bob() -> BobServer<StubAuthenticator> {
+    let config = node_config("0", 1);
+    let root = std::env::temp_dir().join(format!("bob_server_{}", std::process::id()));
+    std::fs::create_dir_all(root.join("0")).expect("create disk directory");
+    let cluster = with_disks_root(cluster_config(2, 1, 2), root.to_str().unwrap());
+    cluster.check(&config).expect("check node config");
+    let mapper = Virtual::new(&config, &cluster);
+    let grinder = Grinder::new(mapper, &config).await;
+    grinder.run_backend().await.expect("run backend");
+    BobServer::new(grinder, Handle::current(), Default::default(), StubAuthenticator::new())
+}
+
+fn from(node_name: Option<&str>) -> Request<ExistRequest> {
+    let options = GetOptions {
+        force_node: true,
+        source: GetSource::Normal as i32,
+        ..Default::default()
+    };
+    let mut request = Request::new(ExistRequest {
+        keys: vec![],
+        options: Some(options),
+    });
+    if let Some(name) = node_name {
+        request.metadata_mut().insert("node_name", name.parse().unwrap());
+    }
+    request
+}
+
+#[tokio::test]
+async fn node_request_needs_name_of_other_cluster_node() {
+    let bob = bob().await;
+    let creds = RequestCredentials::default();
+
+    assert!(bob.is_node_request(&from(Some("1")), &creds));
+    assert!(!bob.is_node_request(&from(None), &creds));
+    assert!(!bob.is_node_request(&from(Some("0")), &creds));
+    assert!(!bob.is_node_request(&from(Some("unknown")), &creds));
+
+    let internode = RequestCredentials::builder().with_nodename("1").build();
+    assert!(bob.is_node_request(&from(Some("1")), &internode));
+    let other_node = RequestCredentials::builder().with_nodename("0").build();
+    assert!(!bob.is_node_request(&from(Some("1")), &other_node));
+    let user = RequestCredentials::builder()
+        .with_username_password("admin", "admin")
+        .build();
+    assert!(!bob.is_node_request(&from(Some("1")), &user));
+}
+
+#[tokio::test]
+async fn drain_rejects_client_requests_with_force_node() {
+    let bob = bob().await;
+    bob.grinder().health().start_drain();
+
+    let status = bob.exist(from(None)).await.unwrap_err();
+    assert_eq!(Kind::NodeIsDraining, *Error::from(status).kind());
+    assert!(bob.exist(from(Some("1"))).await.is_ok());
+}
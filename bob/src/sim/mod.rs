@@ -0,0 +1,321 @@
+//! Cluster simulation for tests: every node runs its own [`Grinder`] in the current runtime and
+//! nodes talk through in-memory network instead of gRPC. The network adds latency to every
+//! message, loses messages between partitioned nodes and refuses connections to crashed ones.
+//! Latencies are drawn from the generator seeded by the test, so under paused tokio time
+//! (`#[tokio::test(start_paused = true)]`) the same seed replays the same scenario.
+
+use crate::{grinder::Grinder, local_vdisk, prelude::*};
+use bob_common::{
+    bob_client::Transport,
+    configs::cluster::Cluster as ClusterConfig,
+    data::{StreamedRecord, VDiskPosition},
+};
+use futures::stream::BoxStream;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::{
+    ops::RangeInclusive,
+    sync::{Mutex, MutexGuard, RwLock, Weak},
+};
+
+mod tests;
+
+const DEFAULT_LATENCY: RangeInclusive<Duration> =
+    Duration::from_millis(1)..=Duration::from_millis(10);
+
+type Latency = RangeInclusive<Duration>;
+
+/// Nodes of the simulated cluster. Dropping it stops the network, requests in flight fail.
+pub(crate) struct SimCluster {
+    network: Arc<Network>,
+}
+
+struct Network {
+    nodes: RwLock<HashMap<NodeName, Arc<Grinder>>>,
+    state: Mutex<NetworkState>,
+}
+
+struct NetworkState {
+    rng: StdRng,
+    latency: Latency,
+    link_latency: HashMap<(NodeName, NodeName), Latency>,
+    // Links are symmetric, both directions are stored
+    partitions: HashSet<(NodeName, NodeName)>,
+    crashed: HashSet<NodeName>,
+}
+
+enum Route {
+    Deliver(Duration),
+    Lost,
+    Refused,
+}
+
+/// Connection of one node to another, sends requests through the network.
+struct Link {
+    from: NodeName,
+    to: NodeName,
+    timeout: Duration,
+    network: Weak<Network>,
+}
+
+impl SimCluster {
+    /// Starts all nodes of `cluster`, config of every node is made by `node_config` from its name.
+    /// Disk directories are created if they don't exist.
+    pub(crate) async fn start(
+        cluster: &ClusterConfig,
+        seed: u64,
+        node_config: impl Fn(&str) -> NodeConfig,
+    ) -> Self {
+        let network = Arc::new(Network::new(seed));
+        for node in cluster.nodes() {
+            for disk in node.disks() {
+                std::fs::create_dir_all(disk.path()).expect("create disk dir");
+            }
+            let config = node_config(node.name());
+            cluster.check(&config).expect("check node config");
+            let mapper = Virtual::new(&config, cluster);
+            let local = mapper.local_node_name().clone();
+            for remote in mapper.nodes() {
+                let link = Link {
+                    from: local.clone(),
+                    to: remote.name().clone(),
+                    timeout: config.operation_timeout(),
+                    network: Arc::downgrade(&network),
+                };
+                remote.set_connection(BobClient::with_transport(
+                    remote.name().clone(),
+                    Arc::new(link),
+                ));
+            }
+            let grinder = Grinder::new(mapper, &config).await;
+            grinder.run_backend().await.expect("run backend");
+            network
+                .nodes
+                .write()
+                .expect("rwlock")
+                .insert(local, Arc::new(grinder));
+        }
+        Self { network }
+    }
+
+    pub(crate) fn node(&self, name: &str) -> Arc<Grinder> {
+        self.network
+            .grinder(&name.into())
+            .unwrap_or_else(|| panic!("node {} is not in the cluster", name))
+    }
+
+    /// One way delay of every message, drawn uniformly from the range.
+    pub(crate) fn set_latency(&self, latency: Latency) {
+        self.network.state().latency = latency;
+    }
+
+    /// Overrides latency of messages from `from` to `to`.
+    pub(crate) fn set_link_latency(&self, from: &str, to: &str, latency: Latency) {
+        self.network
+            .state()
+            .link_latency
+            .insert((from.into(), to.into()), latency);
+    }
+
+    /// Messages between any node of `left` and any node of `right` are lost, requests wait for
+    /// operation timeout.
+    pub(crate) fn partition(&self, left: &[&str], right: &[&str]) {
+        let mut state = self.network.state();
+        for a in left {
+            for b in right {
+                state.partitions.insert(((*a).into(), (*b).into()));
+                state.partitions.insert(((*b).into(), (*a).into()));
+            }
+        }
+    }
+
+    pub(crate) fn heal_partitions(&self) {
+        self.network.state().partitions.clear();
+    }
+
+    /// Node stops answering, connections to it are refused. Stored data survives the crash.
+    pub(crate) fn crash(&self, name: &str) {
+        self.network.state().crashed.insert(name.into());
+    }
+
+    pub(crate) fn restart(&self, name: &str) {
+        self.network.state().crashed.remove(&NodeName::from(name));
+    }
+
+    /// Sends client request to the node, like it came through gRPC API without options.
+    pub(crate) async fn put(&self, node: &str, key: u64, data: &BobData) -> Result<(), Error> {
+        let grinder = self.client_node(node)?;
+        grinder
+            .put(key.into(), data, BobPutOptions::default(), false)
+            .await
+    }
+
+    pub(crate) async fn get(&self, node: &str, key: u64) -> Result<BobData, Error> {
+        let grinder = self.client_node(node)?;
+        grinder
+            .get(key.into(), &BobGetOptions::default(), false)
+            .await
+    }
+
+    pub(crate) async fn exist(&self, node: &str, keys: &[u64]) -> Result<Vec<bool>, Error> {
+        let grinder = self.client_node(node)?;
+        let keys = keys.iter().map(|key| (*key).into()).collect::<Vec<_>>();
+        grinder.exist(&keys, &BobGetOptions::default(), false).await
+    }
+
+    pub(crate) async fn delete(&self, node: &str, key: u64, timestamp: u64) -> Result<(), Error> {
+        let grinder = self.client_node(node)?;
+        grinder
+            .delete(
+                key.into(),
+                &BobMeta::new(timestamp),
+                BobDeleteOptions::default(),
+                false,
+            )
+            .await
+    }
+
+    fn client_node(&self, name: &str) -> Result<Arc<Grinder>, Error> {
+        let name = NodeName::from(name);
+        if self.network.state().crashed.contains(&name) {
+            return Err(Error::failed(format!("node {} is down", name)));
+        }
+        Ok(self.node(name.as_str()))
+    }
+}
+
+impl Network {
+    fn new(seed: u64) -> Self {
+        Self {
+            nodes: RwLock::default(),
+            state: Mutex::new(NetworkState {
+                rng: StdRng::seed_from_u64(seed),
+                latency: DEFAULT_LATENCY,
+                link_latency: HashMap::new(),
+                partitions: HashSet::new(),
+                crashed: HashSet::new(),
+            }),
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, NetworkState> {
+        self.state.lock().expect("mutex")
+    }
+
+    fn grinder(&self, name: &NodeName) -> Option<Arc<Grinder>> {
+        self.nodes.read().expect("rwlock").get(name).cloned()
+    }
+
+    fn route(&self, from: &NodeName, to: &NodeName) -> Route {
+        let mut state = self.state();
+        if state.crashed.contains(from) || state.crashed.contains(to) {
+            return Route::Refused;
+        }
+        let link = (from.clone(), to.clone());
+        if state.partitions.contains(&link) {
+            return Route::Lost;
+        }
+        let latency = state
+            .link_latency
+            .get(&link)
+            .unwrap_or(&state.latency)
+            .clone();
+        Route::Deliver(state.rng.gen_range(latency))
+    }
+
+    /// Waits until the message is delivered, lost message is never delivered.
+    async fn transmit(&self, from: &NodeName, to: &NodeName) -> Result<(), Error> {
+        match self.route(from, to) {
+            Route::Deliver(latency) => {
+                tokio::time::sleep(latency).await;
+                Ok(())
+            }
+            Route::Lost => future::pending().await,
+            Route::Refused => Err(Error::failed(format!(
+                "connection from {} to {} is refused",
+                from, to
+            ))),
+        }
+    }
+}
+
+impl Link {
+    /// Delivers request to the target node and its response back within operation timeout.
+    async fn call<T, F, Fut>(&self, f: F) -> Result<T, Error>
+    where
+        F: FnOnce(Arc<Grinder>) -> Fut,
+        Fut: Future<Output = Result<T, Error>>,
+    {
+        let exchange = async {
+            let network = self
+                .network
+                .upgrade()
+                .ok_or_else(|| Error::failed("simulation is stopped"))?;
+            network.transmit(&self.from, &self.to).await?;
+            let grinder = network
+                .grinder(&self.to)
+                .ok_or_else(|| Error::failed(format!("node {} is not started", self.to)))?;
+            let result = f(grinder).await;
+            network.transmit(&self.to, &self.from).await?;
+            result
+        };
+        tokio::time::timeout(self.timeout, exchange)
+            .await
+            .unwrap_or_else(|_| Err(Error::timeout()))
+    }
+}
+
+#[async_trait]
+impl Transport for Link {
+    async fn put(&self, key: BobKey, data: BobData, options: PutOptions) -> Result<(), Error> {
+        let options = BobPutOptions::from_grpc(Some(options))?;
+        self.call(|grinder| async move { grinder.put(key, &data, options, true).await })
+            .await
+    }
+
+    async fn get(&self, key: BobKey, options: GetOptions) -> Result<BobData, Error> {
+        let options = BobGetOptions::from_grpc(Some(options))?;
+        self.call(|grinder| async move { grinder.get(key, &options, true).await })
+            .await
+    }
+
+    async fn ping(&self) -> Result<(), Error> {
+        let from = self.from.clone();
+        self.call(|grinder| async move {
+            grinder.update_node_connection(from.as_str());
+            Ok(())
+        })
+        .await
+    }
+
+    async fn exist(&self, keys: Vec<BobKey>, options: GetOptions) -> Result<Vec<bool>, Error> {
+        let options = BobGetOptions::from_grpc(Some(options))?;
+        self.call(|grinder| async move { grinder.exist(&keys, &options, true).await })
+            .await
+    }
+
+    async fn delete(
+        &self,
+        key: BobKey,
+        meta: BobMeta,
+        options: DeleteOptions,
+    ) -> Result<(), Error> {
+        let options = BobDeleteOptions::from_grpc(Some(options))?;
+        self.call(|grinder| async move { grinder.delete(key, &meta, options, true).await })
+            .await
+    }
+
+    async fn stream_vdisk(
+        &self,
+        vdisk_id: u32,
+        start_after: Option<VDiskPosition>,
+    ) -> Result<BoxStream<'static, Result<StreamedRecord, Error>>, Error> {
+        self.call(|grinder| async move {
+            let groups = local_vdisk::groups(grinder.backend(), vdisk_id)
+                .await
+                .map_err(Error::failed)?;
+            let records = local_vdisk::records(&groups, start_after).await;
+            Ok(records)
+        })
+        .await
+    }
+}
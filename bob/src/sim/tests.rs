@@ -0,0 +1,145 @@
+use crate::{prelude::*, test_utils};
+use bob_common::configs::{
+    cluster::tests::{cluster_config, with_disks_root},
+    node::tests::node_config,
+};
+use tokio::time::Instant;
+
+use super::SimCluster;
+
+fn data(timestamp: u64) -> BobData {
+    BobData::new(vec![1, 2, 3].into(), BobMeta::new(timestamp))
+}
+
+// 3 nodes, vdisk `i` is stored on nodes `i` and `i + 1`
+async fn start(seed: u64, quorum: usize) -> SimCluster {
+    test_utils::init_logger();
+    let root = std::env::temp_dir().join(format!("bob_sim_{}", std::process::id()));
+    let cluster = with_disks_root(cluster_config(3, 3, 2), root.to_str().unwrap());
+    SimCluster::start(&cluster, seed, |name| node_config(name, quorum)).await
+}
+
+async fn has_alien(sim: &SimCluster, node: &str, key: u64) -> bool {
+    let vdisk_id = sim
+        .node(node)
+        .backend()
+        .mapper()
+        .vdisk_id_from_key(key.into());
+    sim.node(node)
+        .backend()
+        .get_local(key.into(), Operation::new_alien(vdisk_id))
+        .await
+        .is_ok()
+}
+
+async fn has_local(sim: &SimCluster, node: &str, key: u64) -> bool {
+    sim.node(node)
+        .backend()
+        .get(key.into(), &BobGetOptions::new_local())
+        .await
+        .is_ok()
+}
+
+#[tokio::test(start_paused = true)]
+async fn put_get_delete_through_any_node() {
+    let sim = start(1, 2).await;
+
+    sim.put("2", 0, &data(10)).await.unwrap();
+
+    assert!(has_local(&sim, "0", 0).await);
+    assert!(has_local(&sim, "1", 0).await);
+    assert!(!has_local(&sim, "2", 0).await);
+    for node in ["0", "1", "2"] {
+        assert_eq!(10, sim.get(node, 0).await.unwrap().meta().timestamp());
+    }
+    assert_eq!(vec![true, false], sim.exist("1", &[0, 3]).await.unwrap());
+
+    sim.delete("0", 0, 11).await.unwrap();
+
+    for node in ["0", "1", "2"] {
+        assert!(sim.get(node, 0).await.unwrap_err().is_key_not_found());
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn crashed_replica_is_replaced_by_alien() {
+    let sim = start(2, 2).await;
+    sim.crash("1");
+
+    sim.put("0", 0, &data(10)).await.unwrap();
+
+    assert!(has_local(&sim, "0", 0).await);
+    assert!(has_alien(&sim, "2", 0).await);
+    assert!(sim.get("1", 0).await.is_err());
+    sim.restart("1");
+    assert!(!has_local(&sim, "1", 0).await);
+    assert_eq!(10, sim.get("1", 0).await.unwrap().meta().timestamp());
+}
+
+#[tokio::test(start_paused = true)]
+async fn partitioned_replica_fails_after_operation_timeout() {
+    let sim = start(3, 2).await;
+    sim.partition(&["0"], &["1"]);
+
+    let started = Instant::now();
+    sim.put("0", 0, &data(10)).await.unwrap();
+
+    assert!(started.elapsed() >= Duration::from_secs(3));
+    assert!(has_alien(&sim, "2", 0).await);
+    assert!(!has_local(&sim, "1", 0).await);
+    sim.heal_partitions();
+    sim.put("0", 1, &data(10)).await.unwrap();
+    assert!(has_local(&sim, "1", 1).await);
+}
+
+#[tokio::test(start_paused = true)]
+async fn partition_doesnt_affect_other_links() {
+    let sim = start(4, 2).await;
+    sim.partition(&["0"], &["1"]);
+
+    let started = Instant::now();
+    sim.put("2", 0, &data(10)).await.unwrap();
+
+    assert!(started.elapsed() < Duration::from_secs(1));
+    assert!(has_local(&sim, "0", 0).await);
+    assert!(has_local(&sim, "1", 0).await);
+}
+
+#[tokio::test(start_paused = true)]
+async fn link_latency_delays_requests() {
+    let sim = start(5, 2).await;
+    sim.set_latency(Duration::ZERO..=Duration::ZERO);
+    sim.set_link_latency(
+        "2",
+        "1",
+        Duration::from_millis(500)..=Duration::from_millis(500),
+    );
+
+    let started = Instant::now();
+    sim.put("2", 0, &data(10)).await.unwrap();
+
+    assert_eq!(Duration::from_millis(500), started.elapsed());
+}
+
+async fn run_scenario(seed: u64) -> Vec<Duration> {
+    let sim = start(seed, 2).await;
+    sim.set_latency(Duration::from_millis(1)..=Duration::from_millis(100));
+    let mut elapsed = vec![];
+    for key in 0..10 {
+        let started = Instant::now();
+        sim.put(&(key % 3).to_string(), key, &data(key))
+            .await
+            .unwrap();
+        sim.get(&((key + 1) % 3).to_string(), key).await.unwrap();
+        elapsed.push(started.elapsed());
+    }
+    elapsed
+}
+
+#[tokio::test(start_paused = true)]
+async fn same_seed_replays_same_scenario() {
+    let first = run_scenario(42).await;
+
+    assert_eq!(first, run_scenario(42).await);
+    assert_ne!(first, run_scenario(43).await);
+}
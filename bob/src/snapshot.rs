@@ -0,0 +1,179 @@
+//! Point-in-time snapshots of vdisks. Active blobs are closed and partition files are hard
+//! linked to `<disk path>/snapshots/<vdisk id>/<snapshot name>` next to `manifest.json`.
+
+use crate::{local_vdisk::INDEX_DUMP_TIMEOUT, prelude::*};
+use bob_backend::pearl::{Group as PearlGroup, PartitionSnapshot};
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+#[cfg(test)]
+mod tests;
+
+const SNAPSHOTS_DIR_NAME: &str = "snapshots";
+const MANIFEST_FILE_NAME: &str = "manifest.json";
+
+// Unix milliseconds of the last snapshot name, names of the same millisecond are moved forward
+static LAST_NAME: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PartitionManifest {
+    pub name: String,
+    pub start_timestamp: u64,
+    pub blob_prefix: String,
+    pub files: Vec<String>,
+}
+
+impl From<PartitionSnapshot> for PartitionManifest {
+    fn from(partition: PartitionSnapshot) -> Self {
+        Self {
+            name: partition.name,
+            start_timestamp: partition.start_timestamp,
+            blob_prefix: partition.blob_prefix,
+            files: partition.files,
+        }
+    }
+}
+
+impl From<PartitionManifest> for PartitionSnapshot {
+    fn from(partition: PartitionManifest) -> Self {
+        Self {
+            name: partition.name,
+            start_timestamp: partition.start_timestamp,
+            blob_prefix: partition.blob_prefix,
+            files: partition.files,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotManifest {
+    pub name: String,
+    pub node_name: String,
+    pub disk_name: String,
+    pub vdisk_id: VDiskId,
+    /// Unix timestamp of the snapshot.
+    pub created: u64,
+    pub partitions: Vec<PartitionManifest>,
+}
+
+fn vdisk_dir(disk: &DiskPath, vdisk_id: VDiskId) -> PathBuf {
+    Path::new(disk.path())
+        .join(SNAPSHOTS_DIR_NAME)
+        .join(vdisk_id.to_string())
+}
+
+fn storage_error(action: &str, path: &Path, e: impl Display) -> Error {
+    Error::storage(format!("can't {} {}: {}", action, path.display(), e))
+}
+
+async fn read_manifest(dir: &Path) -> Result<SnapshotManifest, Error> {
+    let path = dir.join(MANIFEST_FILE_NAME);
+    let content = tokio::fs::read(&path)
+        .await
+        .map_err(|e| storage_error("read", &path, e))?;
+    serde_json::from_slice(&content).map_err(|e| storage_error("parse", &path, e))
+}
+
+/// Unique within the process and increasing, so names sort in creation order.
+fn next_name() -> u64 {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u64);
+    let mut name = now;
+    let _ = LAST_NAME.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |last| {
+        name = now.max(last + 1);
+        Some(name)
+    });
+    name
+}
+
+/// Snapshots all partitions of the group, the snapshot is named by its unix timestamp in
+/// milliseconds.
+pub(crate) async fn create(group: &PearlGroup, disk: &DiskPath) -> Result<SnapshotManifest, Error> {
+    let name_millis = next_name();
+    let created = name_millis / 1000;
+    let name = name_millis.to_string();
+    let vdisk_dir = vdisk_dir(disk, group.vdisk_id());
+    tokio::fs::create_dir_all(&vdisk_dir)
+        .await
+        .map_err(|e| storage_error("create", &vdisk_dir, e))?;
+    let dir = vdisk_dir.join(&name);
+    // Fails if the name is taken, e.g. by a snapshot of the previous run with a clock behind
+    tokio::fs::create_dir(&dir).await.map_err(|e| {
+        if e.kind() == std::io::ErrorKind::AlreadyExists {
+            Error::failed(format!("snapshot {} already exists", name))
+        } else {
+            storage_error("create", &dir, e)
+        }
+    })?;
+    let result = async {
+        let partitions = group.snapshot(&dir, INDEX_DUMP_TIMEOUT).await?;
+        let manifest = SnapshotManifest {
+            name: name.clone(),
+            node_name: group.node_name().to_string(),
+            disk_name: group.disk_name().to_string(),
+            vdisk_id: group.vdisk_id(),
+            created,
+            partitions: partitions.into_iter().map(Into::into).collect(),
+        };
+        let path = dir.join(MANIFEST_FILE_NAME);
+        let content =
+            serde_json::to_vec_pretty(&manifest).map_err(|e| storage_error("write", &path, e))?;
+        tokio::fs::write(&path, content)
+            .await
+            .map_err(|e| storage_error("write", &path, e))?;
+        Ok(manifest)
+    }
+    .await;
+    if result.is_err() {
+        // Partly linked snapshot can't be restored
+        let _ = tokio::fs::remove_dir_all(&dir).await;
+    }
+    result
+}
+
+/// Snapshots of the vdisk on the disk ordered by creation time.
+pub(crate) async fn list(disk: &DiskPath, vdisk_id: VDiskId) -> Result<Vec<SnapshotManifest>, Error> {
+    let dir = vdisk_dir(disk, vdisk_id);
+    if !dir.exists() {
+        return Ok(vec![]);
+    }
+    let read_error = |e| storage_error("read", &dir, e);
+    let mut entries = tokio::fs::read_dir(&dir).await.map_err(read_error)?;
+    let mut snapshots = vec![];
+    while let Some(entry) = entries.next_entry().await.map_err(read_error)? {
+        match read_manifest(&entry.path()).await {
+            Ok(manifest) => snapshots.push(manifest),
+            Err(e) => warn!("snapshot {} is skipped: {}", entry.path().display(), e),
+        }
+    }
+    snapshots.sort_by(|a, b| (a.created, &a.name).cmp(&(b.created, &b.name)));
+    Ok(snapshots)
+}
+
+/// Copies partitions of the snapshot to the vdisk and attaches them to the group.
+pub(crate) async fn restore(
+    group: &PearlGroup,
+    disk: &DiskPath,
+    snapshot: &SnapshotManifest,
+) -> Result<(), Error> {
+    let dir = vdisk_dir(disk, group.vdisk_id()).join(&snapshot.name);
+    let partitions = snapshot
+        .partitions
+        .iter()
+        .cloned()
+        .map(Into::into)
+        .collect::<Vec<_>>();
+    group.restore(&dir, &partitions).await
+}
+
+pub(crate) async fn delete(disk: &DiskPath, snapshot: &SnapshotManifest) -> Result<(), Error> {
+    let dir = vdisk_dir(disk, snapshot.vdisk_id).join(&snapshot.name);
+    tokio::fs::remove_dir_all(&dir)
+        .await
+        .map_err(|e| storage_error("delete", &dir, e))
+}
@@ -0,0 +1,7 @@
+use super::next_name;
+
+#[test]
+fn names_of_snapshots_created_at_once_increase() {
+    let names = (0..1000).map(|_| next_name()).collect::<Vec<_>>();
+    assert!(names.windows(2).all(|pair| pair[0] < pair[1]));
+}